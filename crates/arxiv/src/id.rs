@@ -0,0 +1,160 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// An arXiv identifier without its version, e.g. `2401.01234` or the older
+/// `hep-th/9901001`, so `2401.01234v2` and an abstract URL compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArxivId(String);
+
+impl ArxivId {
+    /// Parses a bare id, an `arXiv:` reference or an arxiv.org abs/pdf/html
+    /// URL, dropping any version suffix.
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut id = input.trim();
+        for marker in ["/abs/", "/pdf/", "/html/"] {
+            if let Some((_, rest)) = id.split_once(marker) {
+                id = rest;
+            }
+        }
+        if let Some(rest) = id
+            .strip_prefix("arXiv:")
+            .or_else(|| id.strip_prefix("arxiv:"))
+        {
+            id = rest;
+        }
+        let id = id.split(['?', '#']).next().unwrap_or_default();
+        let id = id.trim_end_matches('/').trim_end_matches(".pdf");
+        let id = strip_version(id);
+        (is_new_style(id) || is_old_style(id)).then(|| Self(id.to_string()))
+    }
+
+    /// Every distinct id mentioned in `text`, in order of appearance.
+    pub fn find_all(text: &str) -> Vec<Self> {
+        let mut ids: Vec<Self> = Vec::new();
+        for token in text.split(|c: char| c.is_whitespace() || "()[]<>\"',;|*".contains(c)) {
+            if let Some(id) = Self::parse(token.trim_end_matches(['.', ':'])) {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        ids
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn abs_url(&self) -> String {
+        format!("https://arxiv.org/abs/{}", self.0)
+    }
+}
+
+fn strip_version(id: &str) -> &str {
+    match id.rfind('v') {
+        Some(i) if i > 0 && i + 1 < id.len() && id[i + 1..].bytes().all(|b| b.is_ascii_digit()) => {
+            &id[..i]
+        }
+        _ => id,
+    }
+}
+
+/// `YYMM.NNNN` (before 2015) or `YYMM.NNNNN`.
+fn is_new_style(id: &str) -> bool {
+    let Some((yymm, number)) = id.split_once('.') else {
+        return false;
+    };
+    yymm.len() == 4
+        && yymm.bytes().all(|b| b.is_ascii_digit())
+        && matches!(
+            &yymm[2..],
+            "01" | "02" | "03" | "04" | "05" | "06" | "07" | "08" | "09" | "10" | "11" | "12"
+        )
+        && (4..=5).contains(&number.len())
+        && number.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// `archive/YYMMNNN` or `archive.XX/YYMMNNN`, e.g. `math.GT/0309136`.
+fn is_old_style(id: &str) -> bool {
+    let Some((archive, number)) = id.split_once('/') else {
+        return false;
+    };
+    let subject = archive.split('.').next().unwrap_or_default();
+    !subject.is_empty()
+        && subject.bytes().all(|b| b.is_ascii_lowercase() || b == b'-')
+        && number.len() == 7
+        && number.bytes().all(|b| b.is_ascii_digit())
+}
+
+impl fmt::Display for ArxivId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for ArxivId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ArxivId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        Self::parse(&raw)
+            .ok_or_else(|| serde::de::Error::custom(format!("not an arXiv id: '{}'", raw)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_references() {
+        for input in [
+            "2302.13971",
+            "2302.13971v3",
+            "arXiv:2302.13971v1",
+            "https://arxiv.org/abs/2302.13971",
+            "https://arxiv.org/pdf/2302.13971v2.pdf",
+            "https://arxiv.org/html/2302.13971v1/",
+        ] {
+            assert_eq!(
+                ArxivId::parse(input).unwrap().as_str(),
+                "2302.13971",
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            ArxivId::parse("hep-th/9901001v2").unwrap().as_str(),
+            "hep-th/9901001"
+        );
+        assert_eq!(
+            ArxivId::parse("math.GT/0309136").unwrap().as_str(),
+            "math.GT/0309136"
+        );
+        for invalid in [
+            "",
+            "1.2.3",
+            "2313.00001",
+            "2302.139",
+            "github.com/org/repo",
+            "v1",
+        ] {
+            assert_eq!(ArxivId::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn finds_ids_in_markdown() {
+        let markdown = "# Papers\n\n[LLaMA](https://arxiv.org/abs/2302.13971v1), see also \
+                        arXiv:1706.03762. Again: (2302.13971) and version 1.2 of the code.";
+        let ids: Vec<String> = ArxivId::find_all(markdown)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(ids, ["2302.13971", "1706.03762"]);
+    }
+}
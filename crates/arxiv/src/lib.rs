@@ -1,37 +1,225 @@
+mod id;
+
 use anyhow::Result;
-use reqwest::Client;
-use scraper::{Html, Selector};
+use async_trait::async_trait;
+use common::html::{self, TextOptions};
+use common::ranking::Scored;
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerError, CrawlerResult,
+    HttpClient, OutputFormat, Publisher, RateLimitedClient, RateLimiter,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+pub use id::ArxivId;
+
+/// arXiv asks automated clients for at most one request every three seconds.
+const ARXIV_REQUEST_INTERVAL: Duration = Duration::from_secs(3);
+/// Papers requested at once; the rate limit still spaces out the requests.
+const PAPER_CONCURRENCY: usize = 4;
 
 #[derive(Clone)]
 pub struct ArxivClient {
-    client: Client,
+    client: RateLimitedClient,
 }
 
 impl ArxivClient {
-    pub fn new() -> Self {
-        Self { client: Client::new() }
+    pub fn new() -> CrawlerResult<Self> {
+        Ok(Self::with_client(HttpClient::new()?))
+    }
+
+    pub fn with_client(client: HttpClient) -> Self {
+        Self {
+            client: RateLimitedClient::new(client, RateLimiter::every(ARXIV_REQUEST_INTERVAL)),
+        }
     }
 
     pub async fn fetch_html(&self, arxiv_id: &str) -> Result<String> {
         let url = format!("https://arxiv.org/html/{}", arxiv_id);
-        let resp = self.client.get(&url).send().await?;
-        Ok(resp.text().await?)
+        Ok(self.client.get_text(&url).await?)
     }
 
     pub async fn fetch_paper_body(&self, arxiv_id: &str) -> Result<String> {
         let html = self.fetch_html(arxiv_id).await?;
         Ok(extract_body_text(&html))
     }
+
+    /// A copy using the context's shared client, when it has one, keeping
+    /// this client's rate limit.
+    fn in_context(&self, ctx: &CrawlerContext) -> Self {
+        match &ctx.http {
+            Some(client) => Self {
+                client: RateLimitedClient::new(client.clone(), self.client.limiter().clone()),
+            },
+            None => self.clone(),
+        }
+    }
 }
 
-pub fn extract_body_text(html: &str) -> String {
-    let document = Html::parse_document(html);
-    let body_selector = Selector::parse("body").unwrap();
-    let body = document.select(&body_selector).next();
+/// A paper's body text, as published.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Paper {
+    pub id: ArxivId,
+    pub url: String,
+    pub body: String,
+}
+
+impl Paper {
+    pub fn to_markdown_string(&self) -> String {
+        let heading = markdown::link(&format!("arXiv:{}", self.id), &self.url);
+        format!("## {}\n\n{}", heading, self.body)
+    }
+}
+
+/// Publishes the body text of the `ARXIV_IDS` papers to `{date}/arxiv.md`,
+/// one section per paper in the order given.
+pub struct ArxivCrawler {
+    client: ArxivClient,
+    ids: Vec<ArxivId>,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+}
+
+impl ArxivCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        let mut ids: Vec<ArxivId> = Vec::new();
+        for raw in &config.arxiv_ids {
+            match ArxivId::parse(raw) {
+                Some(id) if !ids.contains(&id) => ids.push(id),
+                Some(_) => {}
+                None => warn!("Ignoring ARXIV_IDS entry '{}': not an arXiv id", raw),
+            }
+        }
+        Ok(Self {
+            client: ArxivClient::with_client(ClientFactory::from_config(config).build()?),
+            ids,
+            publisher: Publisher::for_crawler(config, "arxiv")?,
+            output_formats: config.output_formats_for("arxiv"),
+        })
+    }
 
-    let full_text = body
-        .map(|b| b.text().collect::<Vec<_>>().join("\n"))
-        .unwrap_or_default();
+    /// Fetches every paper, keeping the order of `ids`; failures are recorded
+    /// on the context and left out.
+    async fn fetch_papers(&self, ctx: &CrawlerContext) -> Vec<Paper> {
+        let client = self.client.in_context(ctx);
+        let semaphore = Arc::new(Semaphore::new(PAPER_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for (index, id) in self.ids.iter().cloned().enumerate() {
+            let client = client.clone();
+            let semaphore = semaphore.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let body = client.fetch_paper_body(&id.to_string()).await;
+                (index, id, body)
+            });
+        }
+
+        let mut papers = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            let Ok((index, id, body)) = result else {
+                continue;
+            };
+            match body {
+                Ok(body) if !body.trim().is_empty() => papers.push((
+                    index,
+                    Paper {
+                        url: format!("https://arxiv.org/abs/{}", id),
+                        id,
+                        body,
+                    },
+                )),
+                Ok(_) => {
+                    warn!("No body text found for arXiv:{}", id);
+                    ctx.item_failed(format!("arXiv:{}", id), "no body text found");
+                }
+                Err(e) => {
+                    warn!("Failed to fetch arXiv:{}: {}", id, e);
+                    ctx.item_failed(format!("arXiv:{}", id), &e);
+                }
+            }
+        }
+        papers.sort_by_key(|(index, _)| *index);
+        papers.into_iter().map(|(_, paper)| paper).collect()
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        if self.ids.is_empty() {
+            info!("No valid arXiv ids to fetch");
+            return Ok(());
+        }
+        let papers = self.fetch_papers(ctx).await;
+        if papers.is_empty() {
+            return Err(CrawlerError::Api(format!(
+                "none of the {} arXiv paper(s) could be read",
+                self.ids.len()
+            )));
+        }
+
+        let stem = format!("{}/arxiv", ctx.date);
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_papers_markdown(&papers),
+                &papers,
+            )
+            .await?;
+        info!(
+            "Uploaded {} arXiv papers to {}",
+            papers.len(),
+            written.join(", ")
+        );
+        Ok(())
+    }
+}
+
+fn format_papers_markdown(papers: &[Paper]) -> String {
+    let mut markdown = markdown::section(1, "arXiv");
+    for paper in papers {
+        markdown.push_str("\n\n");
+        markdown.push_str(&paper.to_markdown_string());
+    }
+    markdown
+}
+
+#[async_trait]
+impl Crawler for ArxivCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("arXiv crawler starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "arXiv"
+    }
+
+    fn slug(&self) -> &'static str {
+        "arxiv"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if config.arxiv_ids.is_empty() {
+            return Err("ARXIV_IDS not set".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(ArxivCrawler {
+    name: "arXiv crawler",
+    slug: "arxiv",
+    required_env: ["ARXIV_IDS"],
+    digest: ("arxiv", "arXiv"),
+    new: ArxivCrawler::new,
+});
+
+pub fn extract_body_text(html: &str) -> String {
+    let full_text = html::to_text(html, &TextOptions::document());
 
     let lines: Vec<_> = full_text.lines().collect();
     let mut start_index = 0;
@@ -61,23 +249,35 @@ pub fn extract_body_text(html: &str) -> String {
         .join("\n")
 }
 
-fn is_valid_body_line(line: &str, min_length: usize) -> bool {
-    if line.contains('@') {
-        return false;
-    }
+/// Words that mark a line as author affiliations rather than body text.
+const AFFILIATION_KEYWORDS: &[&str] = &[
+    "university",
+    "lab",
+    "department",
+    "institute",
+    "corresponding author",
+];
 
+/// How much `line` looks like an affiliation: one point per keyword and for
+/// an email address, with the matches as reasons.
+pub fn affiliation_score(line: &str) -> Scored<&str> {
     let lower = line.to_lowercase();
-    for kw in [
-        "university",
-        "lab",
-        "department",
-        "institute",
-        "corresponding author",
-    ] {
+    let mut scored = Scored::new(line, 0.0);
+    if line.contains('@') {
+        scored = scored.add(1.0, 1.0, "contains an email address");
+    }
+    for kw in AFFILIATION_KEYWORDS {
         if lower.contains(kw) {
-            return false;
+            scored = scored.add(1.0, 1.0, format!("mentions '{}'", kw));
         }
     }
+    scored
+}
+
+fn is_valid_body_line(line: &str, min_length: usize) -> bool {
+    if affiliation_score(line).score > 0.0 {
+        return false;
+    }
 
     if line.len() < min_length {
         return false;
@@ -85,3 +285,73 @@ fn is_valid_body_line(line: &str, min_length: usize) -> bool {
 
     line.contains('.')
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{fixture, FixtureDir, MockSupabase, TestConfig};
+
+    #[test]
+    fn affiliation_lines_score_above_zero() {
+        let scored =
+            affiliation_score("Department of Physics, Example University, jane@example.edu");
+        assert_eq!(scored.score, 3.0);
+        assert_eq!(
+            scored.reasons,
+            [
+                "contains an email address",
+                "mentions 'university'",
+                "mentions 'department'"
+            ]
+        );
+        assert_eq!(
+            affiliation_score("We propose a new method for sparse attention.").score,
+            0.0
+        );
+        assert!(!is_valid_body_line(scored.item, 10));
+    }
+
+    #[tokio::test]
+    async fn publishes_the_papers_that_could_be_read() {
+        let fixtures = FixtureDir::new();
+        fixtures.add(
+            "https://arxiv.org/html/2401.01234",
+            &fixture("arxiv/2401.01234.html"),
+        );
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| {
+                c.arxiv_ids = [
+                    "https://arxiv.org/abs/2401.01234v2",
+                    "2401.99999",
+                    "not-an-id",
+                ]
+                .map(String::from)
+                .to_vec()
+            })
+            .build();
+        let crawler = ArxivCrawler::new(&config).unwrap();
+        assert_eq!(crawler.ids.len(), 2, "invalid ids are dropped");
+
+        let date = time::macros::date!(2024 - 05 - 10);
+        let ctx = CrawlerContext::default().with_date(date);
+        crawler.run_with_context(&ctx).await.unwrap();
+
+        let failed: Vec<String> = ctx.item_errors.take().into_iter().map(|e| e.item).collect();
+        assert_eq!(failed, ["arXiv:2401.99999"]);
+        let markdown = supabase.uploaded("2024-05-10/arxiv.md").await;
+        assert!(markdown.starts_with(
+            "# arXiv\n\n## [arXiv:2401.01234](https://arxiv.org/abs/2401.01234)\n\nWe propose"
+        ));
+        assert!(!markdown.contains("jane@example.edu"));
+        assert_eq!(
+            ArxivId::find_all(&markdown),
+            [ArxivId::parse("2401.01234").unwrap()]
+        );
+        let papers: Vec<Paper> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/arxiv.json").await).unwrap();
+        assert_eq!(papers.len(), 1);
+    }
+}
@@ -0,0 +1,257 @@
+use async_trait::async_trait;
+use common::output::load_items;
+use common::{markdown, Config, Crawler, CrawlerContext, CrawlerResult, OutputFormat, Publisher};
+use github::Repository;
+use hacker_news::models::StoryData;
+use serde::Serialize;
+use tracing::{info, warn};
+
+/// Entries kept in the combined list.
+const MAX_ENTRIES: usize = 25;
+const HN_ITEM_URL_FORMAT: &str = "https://news.ycombinator.com/item?id={id}";
+
+/// One item in the combined ranking.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RankedEntry {
+    /// Slug of the crawler the item came from.
+    pub source: String,
+    pub title: String,
+    pub url: String,
+    /// The source's own metric: HN points or GitHub stars.
+    pub raw_score: f64,
+    /// `raw_score` normalized to the source's best item, times the source weight.
+    pub score: f64,
+}
+
+/// Weights applied to each source after normalization.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    pub hacker_news: f64,
+    pub github: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            hacker_news: 1.0,
+            github: 1.0,
+        }
+    }
+}
+
+/// Meta-crawler that merges the day's Hacker News and GitHub outputs into one
+/// ranked list.
+pub struct BestOfDayBuilder {
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    weights: Weights,
+}
+
+impl BestOfDayBuilder {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self {
+            publisher: Publisher::for_crawler(config, "best_of_day")?,
+            output_formats: config.output_formats_for("best_of_day"),
+            weights: Weights {
+                hacker_news: config.best_of_day_weight("hacker_news"),
+                github: config.best_of_day_weight("github"),
+            },
+        })
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let today = ctx.date;
+        let publisher = ctx.publisher(&self.publisher);
+        let storage = publisher.storage();
+        let stories: Vec<StoryData> = load_items(storage, &format!("{}/hacker-news", today))
+            .await?
+            .unwrap_or_else(|| {
+                warn!(
+                    "No structured Hacker News output for {}; enable json output for hacker_news",
+                    today
+                );
+                Vec::new()
+            });
+        let repositories: Vec<Repository> =
+            load_items(storage, &format!("{}/github-trending", today))
+                .await?
+                .unwrap_or_else(|| {
+                    warn!(
+                        "No structured GitHub output for {}; enable json output for github",
+                        today
+                    );
+                    Vec::new()
+                });
+
+        let entries = rank(&stories, &repositories, self.weights, MAX_ENTRIES);
+        if entries.is_empty() {
+            info!("Nothing to rank for best of day.");
+            return Ok(());
+        }
+
+        let stem = format!("{}/best-of-day", today);
+        let written = publisher
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_markdown(&today.to_string(), &entries),
+                &entries,
+            )
+            .await?;
+        info!(
+            "Uploaded {} best-of-day entries to {}",
+            entries.len(),
+            written.join(", ")
+        );
+        Ok(())
+    }
+}
+
+/// Scales each source so its best item scores 1.0, applies the source weight
+/// and returns the top `limit` entries across sources.
+pub fn rank(
+    stories: &[StoryData],
+    repositories: &[Repository],
+    weights: Weights,
+    limit: usize,
+) -> Vec<RankedEntry> {
+    let hn = stories.iter().map(|story| RankedEntry {
+        source: "hacker_news".to_string(),
+        title: story.title.clone(),
+        url: story
+            .url
+            .clone()
+            .unwrap_or_else(|| HN_ITEM_URL_FORMAT.replace("{id}", &story.story_id.to_string())),
+        raw_score: story.score.max(0) as f64,
+        score: 0.0,
+    });
+    let github = repositories.iter().map(|repo| RankedEntry {
+        source: "github".to_string(),
+        title: repo.name.clone(),
+        url: repo.link.clone(),
+        raw_score: repo.stars as f64,
+        score: 0.0,
+    });
+
+    let mut entries = normalize(hn.collect(), weights.hacker_news);
+    entries.extend(normalize(github.collect(), weights.github));
+    entries.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    entries.truncate(limit);
+    entries
+}
+
+fn normalize(mut entries: Vec<RankedEntry>, weight: f64) -> Vec<RankedEntry> {
+    let max = entries.iter().map(|e| e.raw_score).fold(0.0, f64::max);
+    for entry in &mut entries {
+        entry.score = if max > 0.0 {
+            entry.raw_score / max * weight
+        } else {
+            0.0
+        };
+    }
+    entries
+}
+
+fn source_label(source: &str) -> (&'static str, &'static str) {
+    match source {
+        "hacker_news" => ("Hacker News", "points"),
+        "github" => ("GitHub", "stars"),
+        _ => ("Other", "score"),
+    }
+}
+
+pub fn format_markdown(date: &str, entries: &[RankedEntry]) -> String {
+    let mut out = format!("{}\n\n", markdown::section(1, &format!("Best of {}", date)));
+    for (index, entry) in entries.iter().enumerate() {
+        let (label, unit) = source_label(&entry.source);
+        out.push_str(&format!(
+            "{}. {} — {}, {} {} (score {:.2})\n",
+            index + 1,
+            markdown::link(&entry.title, &entry.url),
+            label,
+            entry.raw_score,
+            unit,
+            entry.score
+        ));
+    }
+    out
+}
+
+#[async_trait]
+impl Crawler for BestOfDayBuilder {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Best of Day builder starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Best of Day"
+    }
+
+    fn slug(&self) -> &'static str {
+        "best_of_day"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if !config.best_of_day {
+            return Err("BEST_OF_DAY not set".to_string());
+        }
+        Ok(())
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["hacker_news", "github"]
+    }
+}
+
+common::register_crawler!(BestOfDayBuilder {
+    name: "Best of Day builder",
+    slug: "best_of_day",
+    required_env: ["BEST_OF_DAY"],
+    digest: ("best-of-day", "Best of the Day"),
+    new: BestOfDayBuilder::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{repo, story};
+
+    #[test]
+    fn ranks_sources_on_a_normalized_scale() {
+        let stories = [story(1, "Show HN", 500), story(2, "Ask HN", 100)];
+        let repos = [repo("rust-lang/rust", 2000), repo("tokio-rs/tokio", 1500)];
+        let weights = Weights {
+            hacker_news: 1.0,
+            github: 0.5,
+        };
+
+        let ranked = rank(&stories, &repos, weights, 3);
+        let titles: Vec<_> = ranked.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["Show HN", "rust-lang/rust", "tokio-rs/tokio"]);
+        assert_eq!(ranked[0].score, 1.0);
+        assert_eq!(ranked[1].score, 0.5);
+        assert_eq!(ranked[2].score, 0.375);
+        assert_eq!(ranked[0].url, "https://news.ycombinator.com/item?id=1");
+    }
+
+    #[test]
+    fn markdown_lists_entries_in_rank_order() {
+        let ranked = rank(
+            &[story(7, "Rust 2.0", 300)],
+            &[repo("a/b", 0)],
+            Weights::default(),
+            10,
+        );
+        assert_eq!(
+            format_markdown("2024-05-10", &ranked),
+            "# Best of 2024-05-10\n\n\
+             1. [Rust 2.0](https://news.ycombinator.com/item?id=7) — Hacker News, 300 points (score 1.00)\n\
+             2. [a/b](https://github.com/a/b) — GitHub, 0 stars (score 0.00)\n"
+        );
+    }
+}
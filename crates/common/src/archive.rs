@@ -0,0 +1,223 @@
+//! Append-only NDJSON archive of every item the crawlers parse, one file per
+//! month under `archive/{year}/{month}.ndjson`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use time::Date;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::supabase_client::SupabaseStorageClient;
+
+/// The common envelope every archived item is stored in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    /// Slug of the crawler that produced the item.
+    pub source: String,
+    /// Day the item was seen, `YYYY-MM-DD`.
+    pub date: String,
+    /// Identifier unique within the source for that day.
+    pub id: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub score: Option<f64>,
+    /// The item as the crawler published it.
+    pub raw: serde_json::Value,
+}
+
+/// A published item that has a place in the archive.
+pub trait Archivable {
+    /// The item's envelope, as seen on `date`.
+    fn into_item(self, date: Date) -> Item;
+}
+
+impl Item {
+    fn key(&self) -> (String, String, String) {
+        (self.source.clone(), self.date.clone(), self.id.clone())
+    }
+}
+
+/// `archive/2024/05.ndjson` for `2024-05-10`; `None` for a malformed date.
+pub fn archive_path(date: &str) -> Option<String> {
+    let mut parts = date.splitn(3, '-');
+    let (year, month) = (parts.next()?, parts.next()?);
+    let valid = year.len() == 4
+        && month.len() == 2
+        && year
+            .chars()
+            .chain(month.chars())
+            .all(|c| c.is_ascii_digit());
+    valid.then(|| format!("archive/{}/{}.ndjson", year, month))
+}
+
+/// The NDJSON lines for the `items` not already in `existing` (by source, date
+/// and id), and how many there are. Malformed existing lines are skipped.
+pub fn new_lines(existing: &str, items: &[Item]) -> (String, usize) {
+    let mut seen = HashSet::new();
+    for (number, line) in existing.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Item>(line) {
+            Ok(item) => {
+                seen.insert(item.key());
+            }
+            Err(e) => warn!("Skipping malformed archive line {}: {}", number + 1, e),
+        }
+    }
+
+    let mut out = String::new();
+    let mut added = 0;
+    for item in items {
+        if !seen.insert(item.key()) {
+            continue;
+        }
+        match serde_json::to_string(item) {
+            Ok(line) => {
+                out.push_str(&line);
+                out.push('\n');
+                added += 1;
+            }
+            Err(e) => warn!("Could not archive {}/{}: {}", item.source, item.id, e),
+        }
+    }
+    (out, added)
+}
+
+/// Serializes read-modify-write cycles on archive files within the process.
+fn append_lock() -> &'static tokio::sync::Mutex<()> {
+    static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+}
+
+/// Collects items and appends them to the monthly archive files on `flush`.
+pub struct ArchiveWriter {
+    storage: SupabaseStorageClient,
+    pending: Mutex<Vec<Item>>,
+}
+
+impl ArchiveWriter {
+    pub fn new(storage: SupabaseStorageClient) -> Self {
+        Self {
+            storage,
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// A writer when `CUTION_ARCHIVE` is enabled.
+    pub fn from_config(config: &Config) -> CrawlerResult<Option<Self>> {
+        if !config.archive {
+            return Ok(None);
+        }
+        Ok(Some(Self::new(SupabaseStorageClient::from_config(config)?)))
+    }
+
+    pub fn add<I: IntoIterator<Item = Item>>(&self, items: I) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(items);
+    }
+
+    /// Adds `items` and flushes them, logging rather than returning failures
+    /// so archiving never fails a crawl.
+    pub async fn record<I: IntoIterator<Item = Item>>(&self, items: I) {
+        self.add(items);
+        if let Err(e) = self.flush().await {
+            warn!("Failed to append to the archive: {}", e);
+        }
+    }
+
+    /// Appends the pending items to their month's file and returns how many
+    /// were new.
+    pub async fn flush(&self) -> CrawlerResult<usize> {
+        let items = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut by_path: BTreeMap<String, Vec<Item>> = BTreeMap::new();
+        for item in items {
+            match archive_path(&item.date) {
+                Some(path) => by_path.entry(path).or_default().push(item),
+                None => warn!(
+                    "Not archiving {}/{}: bad date '{}'",
+                    item.source, item.id, item.date
+                ),
+            }
+        }
+
+        let _guard = append_lock().lock().await;
+        let mut total = 0;
+        for (path, items) in by_path {
+            let mut added = 0;
+            self.storage
+                .append_file(&path, "application/x-ndjson", |existing| {
+                    let (lines, count) = new_lines(existing, &items);
+                    added = count;
+                    lines
+                })
+                .await
+                .map_err(|e| CrawlerError::StorageUpload(e.to_string()))?;
+            info!("Archived {} new item(s) to {}", added, path);
+            total += added;
+        }
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(source: &str, date: &str, id: &str) -> Item {
+        Item {
+            source: source.to_string(),
+            date: date.to_string(),
+            id: id.to_string(),
+            title: format!("{} {}", source, id),
+            url: None,
+            score: Some(1.0),
+            raw: json!({ "id": id }),
+        }
+    }
+
+    #[test]
+    fn paths_are_per_month() {
+        assert_eq!(
+            archive_path("2024-05-10").as_deref(),
+            Some("archive/2024/05.ndjson")
+        );
+        assert_eq!(archive_path("2024-5-10"), None);
+        assert_eq!(archive_path("yesterday"), None);
+    }
+
+    #[test]
+    fn new_lines_dedup_and_skip_malformed_lines() {
+        let existing = format!(
+            "{}\nnot json\n{{\"source\":\"truncated\"",
+            serde_json::to_string(&item("github", "2024-05-10", "a/b")).unwrap()
+        );
+        let items = vec![
+            item("github", "2024-05-10", "a/b"),
+            item("github", "2024-05-11", "a/b"),
+            item("hacker_news", "2024-05-10", "a/b"),
+            item("hacker_news", "2024-05-10", "a/b"),
+        ];
+        let (lines, added) = new_lines(&existing, &items);
+        assert_eq!(added, 2);
+        let parsed: Vec<Item> = lines
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                item("github", "2024-05-11", "a/b"),
+                item("hacker_news", "2024-05-10", "a/b")
+            ]
+        );
+
+        let appended = format!("{}\n{}", existing, lines);
+        assert_eq!(new_lines(&appended, &items), (String::new(), 0));
+    }
+}
@@ -1,5 +1,18 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::env;
-use anyhow::{Result, Context};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::dedup::SeenUrlsMode;
+use crate::gemini::GeminiClient;
+use crate::http::{ClientFactory, HttpTuning};
+use crate::output::{self, OutputFormat};
+use crate::summarize::Summarizer;
+use crate::usage::{self, ModelPrice};
 
 #[derive(Debug, Clone)]
 pub struct SupabaseConfig {
@@ -9,36 +22,551 @@ pub struct SupabaseConfig {
     pub bucket: String,
 }
 
+/// The period GitHub trending ranks repositories over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrendingRange {
+    #[default]
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl TrendingRange {
+    /// The `since` value GitHub expects.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrendingRange::Daily => "daily",
+            TrendingRange::Weekly => "weekly",
+            TrendingRange::Monthly => "monthly",
+        }
+    }
+}
+
+impl std::str::FromStr for TrendingRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            "monthly" => Ok(Self::Monthly),
+            other => Err(format!(
+                "unknown range '{}' (expected daily, weekly or monthly)",
+                other
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub supabase: SupabaseConfig,
     pub gemini_api_key: Option<String>,
     pub xai_api_key: Option<String>,
     pub custom_site_url: Option<String>,
+    /// Sites the custom site crawler fetches: `CUSTOM_SITE_URL` if set, then
+    /// the comma-separated `CUSTOM_SITE_URLS`, without repeats.
+    pub custom_site_urls: Vec<String>,
+    /// Upload every custom site to one `{date}/custom-site.md` instead of a
+    /// file per host (`CUSTOM_SITE_COMBINED`).
+    pub custom_site_combined: bool,
     pub languages: Vec<String>,
+    /// Whether `LANGUAGES` or `LANGUAGES_FILE` was set, even to nothing but separators.
+    pub languages_set: bool,
+    pub immutable_archive: bool,
+    /// Start CSV outputs with a UTF-8 byte order mark for spreadsheet apps (`CSV_BOM`).
+    pub csv_bom: bool,
+    pub output_formats: Vec<OutputFormat>,
+    pub crawler_output_formats: HashMap<String, Vec<OutputFormat>>,
+    /// Markdown banner/footer templates keyed by crawler slug; `""` holds the global default.
+    pub output_banners: HashMap<String, String>,
+    pub output_footers: HashMap<String, String>,
+    /// Maximum GitHub trending pages fetched at once (`GITHUB_LANGUAGE_CONCURRENCY`).
+    pub github_language_concurrency: usize,
+    /// Average requests per second to GitHub across trending and README fetches (`GITHUB_REQUESTS_PER_SEC`).
+    pub github_requests_per_sec: f64,
+    /// README excerpts fetched for the top N repos per language; 0 disables (`GITHUB_FETCH_README`).
+    pub github_fetch_readme: usize,
+    /// Drop archived repositories from GitHub trending (`GITHUB_EXCLUDE_ARCHIVED`).
+    pub github_exclude_archived: bool,
+    /// Drop forks from GitHub trending (`GITHUB_EXCLUDE_FORKS`).
+    pub github_exclude_forks: bool,
+    /// Upload `github-trending-{language}` files instead of one combined file (`GITHUB_FILE_PER_LANGUAGE`).
+    pub github_file_per_language: bool,
+    /// With per-language files, also upload the combined file (`GITHUB_COMBINED_FILE`).
+    pub github_combined_file: bool,
+    /// Repo pages checked per language to confirm archive/fork status (`GITHUB_CONFIRM_FLAGS_TOP`).
+    pub github_confirm_flags_top: usize,
+    /// Trending range fetched (`GITHUB_TRENDING_SINCE`, default daily).
+    pub github_trending_since: TrendingRange,
+    /// MCP servers with a shorter description are dropped as scraping noise
+    /// (`RANKING_MIN_DESCRIPTION_LEN`, default 1).
+    pub ranking_min_description_len: usize,
+    /// Read OpenRouter models from its JSON API instead of scraping the
+    /// rankings page (`OPENROUTER_USE_API`, default on).
+    pub openrouter_use_api: bool,
+    /// Hacker News item types kept in the digest (`HN_ITEM_TYPES`, default `story`).
+    pub hn_item_types: Vec<String>,
+    /// Annotate or drop Hacker News stories whose URL was published on an earlier day
+    /// (`HN_SEEN_URLS`: `off`, `annotate` or `drop`).
+    pub hn_seen_urls: SeenUrlsMode,
+    /// The same for the custom site crawler's page (`CUSTOM_SITE_SEEN_URLS`).
+    pub custom_site_seen_urls: SeenUrlsMode,
+    /// Days a published URL is remembered for (`SEEN_URLS_TTL_DAYS`, default 30).
+    pub seen_urls_ttl_days: u32,
+    /// Days a stored Hacker News summary is reused for; unset disables the cache
+    /// (`SUMMARY_CACHE_TTL_DAYS`).
+    pub summary_cache_ttl_days: Option<u32>,
+    /// Upload `{date}/hacker-news.partial.md` every this many stories while the
+    /// crawler runs; unset only uploads at the end (`PARTIAL_UPLOAD_EVERY`).
+    pub partial_upload_every: Option<usize>,
+    /// Delete date folders older than this many days after each run; unset keeps
+    /// everything (`CUTION_RETENTION_DAYS`).
+    pub retention_days: Option<u32>,
+    /// Only log what the retention cleanup would delete (`CUTION_RETENTION_DRY_RUN`).
+    pub retention_dry_run: bool,
+    /// USD per million input/output tokens by model, for usage reports (`LLM_PRICES`).
+    pub llm_prices: HashMap<String, ModelPrice>,
+    /// Monthly LLM spending after which LLM calls are skipped (`CUTION_LLM_BUDGET_USD`).
+    pub llm_budget_usd: Option<f64>,
+    /// Build the combined best-of-day list after the other crawlers (`BEST_OF_DAY`).
+    pub best_of_day: bool,
+    /// Per-source weights for the best-of-day ranking, keyed by crawler slug.
+    pub best_of_day_weights: HashMap<String, f64>,
+    /// Publish the lobste.rs front page to `{date}/lobsters.md` (`LOBSTERS_ENABLED`).
+    pub lobsters_enabled: bool,
+    /// Keep only Lobsters stories with one of these tags; empty keeps all (`LOBSTERS_TAGS`).
+    pub lobsters_tags: Vec<String>,
+    /// Lobsters stories below this score are dropped (`LOBSTERS_MIN_SCORE`, default 5).
+    pub lobsters_min_score: i64,
+    /// Product Hunt API token; the Product Hunt crawler runs when it is set
+    /// (`PRODUCT_HUNT_TOKEN`).
+    pub product_hunt_token: Option<String>,
+    /// Launches in `{date}/product-hunt.md` (`PRODUCT_HUNT_TOP_N`, default 10).
+    pub product_hunt_top_n: usize,
+    /// Subreddits whose top posts of the day go into `{date}/reddit.md`, without
+    /// the `r/` prefix (`REDDIT_SUBREDDITS`).
+    pub reddit_subreddits: Vec<String>,
+    /// Reddit posts below this score are dropped (`REDDIT_MIN_SCORE`, default 10).
+    pub reddit_min_score: i64,
+    /// Keep only Reddit posts whose flair contains one of these, lowercased;
+    /// empty keeps all (`REDDIT_FLAIRS`).
+    pub reddit_flairs: Vec<String>,
+    /// Summarize long Reddit self-posts with Gemini (`REDDIT_SUMMARIZE`).
+    pub reddit_summarize: bool,
+    /// Path or URL of the OPML subscription list the feeds crawler reads
+    /// (`FEEDS_OPML`).
+    pub feeds_opml: Option<String>,
+    /// Feed entries older than this are left out (`FEEDS_MAX_AGE_HOURS`, default 24).
+    pub feeds_max_age_hours: u64,
+    /// Summarize long feed entries with Gemini (`FEEDS_SUMMARIZE`).
+    pub feeds_summarize: bool,
+    /// Publish the day's top dev.to articles (`DEVTO_ENABLED`).
+    pub devto_enabled: bool,
+    /// dev.to tags fetched, one section each, lowercased; empty fetches the
+    /// overall top articles grouped by their main tag (`DEVTO_TAGS`).
+    pub devto_tags: Vec<String>,
+    /// dev.to articles with fewer reactions are dropped (`DEVTO_MIN_REACTIONS`,
+    /// default 10).
+    pub devto_min_reactions: u64,
+    /// Publish crates.io and npm download stats (`REGISTRIES_ENABLED`).
+    pub registries_enabled: bool,
+    /// npm packages whose weekly downloads are tracked, in the order given
+    /// and without repeats (`NPM_WATCHLIST`).
+    pub npm_watchlist: Vec<String>,
+    /// YouTube channel IDs whose new uploads are published, in the order
+    /// given and without repeats (`YOUTUBE_CHANNELS`).
+    pub youtube_channels: Vec<String>,
+    /// arXiv papers whose body text is published, as given (`ARXIV_IDS`).
+    pub arxiv_ids: Vec<String>,
+    /// Summarize long video descriptions with Gemini (`YOUTUBE_SUMMARIZE`).
+    pub youtube_summarize: bool,
+    /// Publish Bluesky and Mastodon posts matching `fediverse_keywords`
+    /// (`FEDIVERSE_ENABLED`).
+    pub fediverse_enabled: bool,
+    /// Search terms, one digest section each, in the order given and without
+    /// repeats (`FEDIVERSE_KEYWORDS`).
+    pub fediverse_keywords: Vec<String>,
+    /// Mastodon instance whose public tag timelines are read as well, e.g.
+    /// `https://mastodon.social` (`FEDIVERSE_MASTODON_INSTANCE`).
+    pub fediverse_mastodon_instance: Option<String>,
+    /// Posts with fewer likes are dropped (`FEDIVERSE_MIN_LIKES`, default 10).
+    pub fediverse_min_likes: u64,
+    /// Posts with fewer reposts are dropped (`FEDIVERSE_MIN_REPOSTS`, default 0).
+    pub fediverse_min_reposts: u64,
+    /// Publish the most starred Papers with Code papers (`PAPERS_WITH_CODE`).
+    pub papers_with_code: bool,
+    /// Papers in `{date}/papers-with-code.md` (`PAPERS_WITH_CODE_TOP_N`, default 20).
+    pub papers_with_code_top_n: usize,
+    /// Build `{week-start}/weekly-review.md` from the week's outputs (`WEEKLY_ROLLUP`).
+    pub weekly_rollup: bool,
+    /// Entries per section of the weekly review (`WEEKLY_ROLLUP_TOP_N`, default 5).
+    pub weekly_rollup_top_n: usize,
+    /// Combine the day's markdown outputs into `{date}/digest.md` (`DAILY_DIGEST`).
+    pub daily_digest: bool,
+    /// Open the digest with a summarized TL;DR paragraph (`DIGEST_TLDR`, implies `DAILY_DIGEST`).
+    pub digest_tldr: bool,
+    /// Upload a browsable `index.html` after each run (`GENERATE_HTML_INDEX`).
+    pub generate_html_index: bool,
+    /// Upload `{date}/README.md` indexing the day's files after each run (`DAILY_INDEX`).
+    pub daily_index: bool,
+    /// Warn when an output has been identical for this many previous days (`STALE_AFTER_DAYS`).
+    pub stale_after_days: Option<usize>,
+    /// Append every parsed item to `archive/{year}/{month}.ndjson` (`CUTION_ARCHIVE`).
+    pub archive: bool,
+    /// SQLite file holding crawler state instead of `state/*.json` blobs (`CUTION_STATE_DB`).
+    pub state_db: Option<PathBuf>,
+    /// Embed the day's archived items into the `item_embeddings` table (`CUTION_EMBEDDINGS`).
+    pub embeddings: bool,
+    /// Gemini model used for embeddings (`EMBEDDING_MODEL`).
+    pub embedding_model: String,
+    /// Length every embedding must have (`EMBEDDING_DIMENSIONS`, default 768).
+    pub embedding_dimensions: usize,
+    /// Language code outputs are translated into after each run (`CUTION_TRANSLATE_TO`).
+    pub translate_to: Option<String>,
+    /// Crawler slugs whose markdown is translated (`CUTION_TRANSLATE_SOURCES`).
+    pub translate_sources: Vec<String>,
+    /// Slack incoming webhook for notifications (`NOTIFY_SLACK_URL`).
+    pub notify_slack_url: Option<String>,
+    /// Discord webhook for notifications (`NOTIFY_DISCORD_URL`).
+    pub notify_discord_url: Option<String>,
+    /// Endpoint receiving notifications as plain JSON (`NOTIFY_WEBHOOK_URL`).
+    pub notify_webhook_url: Option<String>,
+    /// Serve GETs from saved responses in this directory instead of the network (`FIXTURE_DIR`).
+    pub fixture_dir: Option<PathBuf>,
+    /// Write uploads to this directory instead of Supabase (`LOCAL_STORAGE_DIR`, default
+    /// `{FIXTURE_DIR}/output` in fixture mode).
+    pub local_storage_dir: Option<PathBuf>,
+    /// Retries of a Supabase upload failing with a network error, 429 or 5xx
+    /// (`STORAGE_UPLOAD_RETRIES`, default 2).
+    pub storage_upload_retries: u32,
+    /// Wait before the first upload retry, doubled for each further one
+    /// (`STORAGE_UPLOAD_RETRY_BASE_MS`, default 500).
+    pub storage_upload_retry_base_delay: Duration,
+    /// Timeout for every HTTP request made through `ClientFactory` (`HTTP_TIMEOUT_SECS`).
+    pub http_timeout: Duration,
+    /// Longest one crawler may run before the manager gives up on it; `None`
+    /// waits indefinitely (`CRAWLER_TIMEOUT_SECS`, default 300, `0` disables).
+    pub crawler_timeout: Option<Duration>,
+    /// HTTP/2 and connection pool settings for `ClientFactory` clients.
+    pub http_tuning: HttpTuning,
+    /// Upload `{date}/CHECKSUMS.txt` with the SHA-256 of every file written (`CUTION_CHECKSUMS`).
+    pub checksums: bool,
+    /// Secret for the HMAC-SHA256 signature uploaded next to the checksums (`CUTION_CHECKSUMS_KEY`).
+    pub checksums_key: Option<String>,
+    /// Formats of the `{date}/errors` file listing the run's failures, markdown and/or
+    /// JSON; empty uploads none (`CUTION_ERRORS_FILE`).
+    pub errors_file: Vec<OutputFormat>,
+    /// Record every outbound request and upload the list after each run (`CUTION_AUDIT_HTTP`).
+    pub audit_http: bool,
+    /// Proxy for all crawler traffic (`HTTP_PROXY_URL`).
+    pub http_proxy: Option<String>,
+    /// Fixed user agent; unset rotates through `http::USER_AGENTS` (`HTTP_USER_AGENT`).
+    pub http_user_agent: Option<String>,
 }
 
 impl Config {
+    /// Loads `.env` (if present) and then reads the configuration from the environment.
+    pub fn load() -> Result<Self> {
+        let _ = dotenv::dotenv();
+        Self::from_env()
+    }
+
     pub fn from_env() -> Result<Self> {
-        let supabase_url = env::var("SUPABASE_URL")
-            .context("SUPABASE_URL must be set")?;
-        let supabase_key = env::var("SUPABASE_SERVICE_ROLE_KEY")
-            .context("SUPABASE_SERVICE_ROLE_KEY must be set")?;
-        let supabase_bucket = env::var("SUPABASE_BUCKET_NAME")
-            .context("SUPABASE_BUCKET_NAME must be set")?;
+        let fixture_dir = non_empty_env("FIXTURE_DIR").map(PathBuf::from);
+        let local_storage_dir = non_empty_env("LOCAL_STORAGE_DIR")
+            .map(PathBuf::from)
+            .or_else(|| fixture_dir.as_ref().map(|dir| dir.join("output")));
+        // Offline runs never reach Supabase, so its settings are optional then.
+        let supabase_var = |key: &str| match (env::var(key), &local_storage_dir) {
+            (Ok(value), _) => Ok(value),
+            (Err(_), Some(_)) => Ok("local".to_string()),
+            (Err(_), None) => Err(anyhow::anyhow!("{} must be set", key)),
+        };
+        let supabase_url = supabase_var("SUPABASE_URL")?;
+        let supabase_key = supabase_var("SUPABASE_SERVICE_ROLE_KEY")?;
+        let supabase_bucket = supabase_var("SUPABASE_BUCKET_NAME")?;
 
         let storage_url = format!("{}/storage/v1", supabase_url.trim_end_matches('/'));
 
-        let languages = env::var("LANGUAGES")
-            .ok()
-            .map(|langs_str| {
-                langs_str
-                    .split(',')
-                    .map(|s| s.trim().to_string())
+        let (languages, languages_set) = match env::var("LANGUAGES") {
+            Ok(langs_str) => (parse_languages(&langs_str), true),
+            Err(_) => match env::var("LANGUAGES_FILE") {
+                Ok(path) => {
+                    let contents = fs::read_to_string(&path)
+                        .with_context(|| format!("Failed to read LANGUAGES_FILE {}", path))?;
+                    (parse_languages_file(&contents), true)
+                }
+                Err(_) => (Vec::new(), false),
+            },
+        };
+
+        let output_formats = match env::var("OUTPUT_FORMATS") {
+            Ok(raw) => output::parse_formats(&raw)
+                .map_err(|e| anyhow::anyhow!("Invalid OUTPUT_FORMATS: {}", e))?,
+            Err(_) => vec![OutputFormat::Markdown],
+        };
+        let errors_file = match non_empty_env("CUTION_ERRORS_FILE") {
+            Some(raw) => {
+                let formats = output::parse_formats(&raw)
+                    .map_err(|e| anyhow::anyhow!("Invalid CUTION_ERRORS_FILE: {}", e))?;
+                if let Some(other) = formats
+                    .iter()
+                    .find(|f| !matches!(f, OutputFormat::Markdown | OutputFormat::Json))
+                {
+                    anyhow::bail!(
+                        "Invalid CUTION_ERRORS_FILE: '{}' is not supported (use md or json)",
+                        other.extension()
+                    );
+                }
+                formats
+            }
+            None => Vec::new(),
+        };
+        let crawler_output_formats = match env::var("CRAWLER_OUTPUT_FORMATS") {
+            Ok(raw) => output::parse_crawler_formats(&raw)
+                .map_err(|e| anyhow::anyhow!("Invalid CRAWLER_OUTPUT_FORMATS: {}", e))?,
+            Err(_) => HashMap::new(),
+        };
+
+        let github_language_concurrency = positive_env::<usize>("GITHUB_LANGUAGE_CONCURRENCY", 4)?;
+
+        let github_requests_per_sec = match env::var("GITHUB_REQUESTS_PER_SEC") {
+            Ok(raw) => raw
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| rate.is_finite() && *rate > 0.0)
+                .with_context(|| {
+                    format!(
+                        "GITHUB_REQUESTS_PER_SEC must be a positive number, got '{}'",
+                        raw
+                    )
+                })?,
+            Err(_) => 2.0,
+        };
+
+        let github_fetch_readme = non_negative_env::<usize>("GITHUB_FETCH_README", 0)?;
+
+        let github_confirm_flags_top = non_negative_env::<usize>("GITHUB_CONFIRM_FLAGS_TOP", 0)?;
+
+        let github_trending_since = match non_empty_env("GITHUB_TRENDING_SINCE") {
+            Some(raw) => raw
+                .parse::<TrendingRange>()
+                .map_err(|e| anyhow::anyhow!("Invalid GITHUB_TRENDING_SINCE: {}", e))?,
+            None => TrendingRange::Daily,
+        };
+
+        let weekly_rollup_top_n = positive_env::<usize>("WEEKLY_ROLLUP_TOP_N", 5)?;
+
+        let lobsters_tags = env::var("LOBSTERS_TAGS")
+            .map(|raw| {
+                raw.split(',')
+                    .map(|t| t.trim().to_ascii_lowercase())
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let lobsters_min_score = match env::var("LOBSTERS_MIN_SCORE") {
+            Ok(raw) => raw
+                .trim()
+                .parse::<i64>()
+                .with_context(|| format!("LOBSTERS_MIN_SCORE must be an integer, got '{}'", raw))?,
+            Err(_) => 5,
+        };
+
+        let product_hunt_top_n = positive_env::<usize>("PRODUCT_HUNT_TOP_N", 10)?;
+
+        let reddit_subreddits = env::var("REDDIT_SUBREDDITS")
+            .map(|raw| {
+                raw.split(',')
+                    .map(|s| {
+                        s.trim()
+                            .trim_start_matches("/r/")
+                            .trim_start_matches("r/")
+                            .to_string()
+                    })
                     .filter(|s| !s.is_empty())
                     .collect()
             })
             .unwrap_or_default();
+        let reddit_min_score = match env::var("REDDIT_MIN_SCORE") {
+            Ok(raw) => raw
+                .trim()
+                .parse::<i64>()
+                .with_context(|| format!("REDDIT_MIN_SCORE must be an integer, got '{}'", raw))?,
+            Err(_) => 10,
+        };
+        let reddit_flairs = env::var("REDDIT_FLAIRS")
+            .map(|raw| {
+                raw.split(',')
+                    .map(|f| f.trim().to_lowercase())
+                    .filter(|f| !f.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let feeds_max_age_hours = positive_env::<u64>("FEEDS_MAX_AGE_HOURS", 24)?;
+
+        let devto_tags = env::var("DEVTO_TAGS")
+            .map(|raw| {
+                raw.split(',')
+                    .map(|t| t.trim().trim_start_matches('#').to_ascii_lowercase())
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let devto_min_reactions = non_negative_env::<u64>("DEVTO_MIN_REACTIONS", 10)?;
+
+        let fediverse_keywords = list_env("FEDIVERSE_KEYWORDS", &[','], str::to_string);
+        let fediverse_min_likes = non_negative_env::<u64>("FEDIVERSE_MIN_LIKES", 10)?;
+        let fediverse_min_reposts = non_negative_env::<u64>("FEDIVERSE_MIN_REPOSTS", 0)?;
+
+        let custom_site_url = env::var("CUSTOM_SITE_URL").ok();
+        let custom_site_urls = parse_custom_site_urls(
+            custom_site_url.as_deref(),
+            &env::var("CUSTOM_SITE_URLS").unwrap_or_default(),
+        );
+
+        let npm_watchlist = list_env("NPM_WATCHLIST", &[','], str::to_ascii_lowercase);
+
+        let youtube_channels = list_env("YOUTUBE_CHANNELS", &[','], str::to_string);
+
+        let arxiv_ids = list_env("ARXIV_IDS", &[',', ' '], str::to_string);
+
+        let papers_with_code_top_n = positive_env::<usize>("PAPERS_WITH_CODE_TOP_N", 20)?;
+
+        let ranking_min_description_len =
+            non_negative_env::<usize>("RANKING_MIN_DESCRIPTION_LEN", 1)?;
+
+        let stale_after_days = optional_positive_env::<usize>("STALE_AFTER_DAYS")?;
+
+        let embedding_dimensions = positive_env::<usize>("EMBEDDING_DIMENSIONS", 768)?;
+
+        let translate_to = match non_empty_env("CUTION_TRANSLATE_TO") {
+            Some(lang) if lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => Some(lang),
+            Some(lang) => anyhow::bail!(
+                "CUTION_TRANSLATE_TO must be a language code such as 'ja', got '{}'",
+                lang
+            ),
+            None => None,
+        };
+        let translate_sources = match env::var("CUTION_TRANSLATE_SOURCES") {
+            Ok(raw) => raw
+                .split(',')
+                .map(|s| s.trim().to_ascii_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            Err(_) => ["github", "hacker_news", "openrouter", "mcp_rankings"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        };
+
+        let hn_item_types = match env::var("HN_ITEM_TYPES") {
+            Ok(raw) => {
+                let types: Vec<String> = raw
+                    .split(',')
+                    .map(|t| t.trim().to_ascii_lowercase())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                if types.is_empty() {
+                    anyhow::bail!("HN_ITEM_TYPES must list at least one item type");
+                }
+                types
+            }
+            Err(_) => vec!["story".to_string()],
+        };
+
+        let seen_urls_mode = |key: &str| match env::var(key) {
+            Ok(raw) => raw
+                .parse::<SeenUrlsMode>()
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", key, e)),
+            Err(_) => Ok(SeenUrlsMode::Off),
+        };
+        let hn_seen_urls = seen_urls_mode("HN_SEEN_URLS")?;
+        let custom_site_seen_urls = seen_urls_mode("CUSTOM_SITE_SEEN_URLS")?;
+
+        let seen_urls_ttl_days = positive_env::<u32>("SEEN_URLS_TTL_DAYS", 30)?;
+
+        let summary_cache_ttl_days = optional_positive_env::<u32>("SUMMARY_CACHE_TTL_DAYS")?;
+
+        let partial_upload_every = optional_positive_env::<usize>("PARTIAL_UPLOAD_EVERY")?;
+
+        let retention_days = optional_positive_env::<u32>("CUTION_RETENTION_DAYS")?;
+
+        let llm_prices = match env::var("LLM_PRICES") {
+            Ok(raw) => usage::parse_prices(&raw)
+                .map_err(|e| anyhow::anyhow!("Invalid LLM_PRICES: {}", e))?,
+            Err(_) => HashMap::new(),
+        };
+
+        let llm_budget_usd = match non_empty_env("CUTION_LLM_BUDGET_USD") {
+            Some(raw) => Some(
+                raw.trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|usd| usd.is_finite() && *usd >= 0.0)
+                    .with_context(|| {
+                        format!(
+                            "CUTION_LLM_BUDGET_USD must be a non-negative number, got '{}'",
+                            raw
+                        )
+                    })?,
+            ),
+            None => None,
+        };
+
+        let best_of_day_weights = match env::var("BEST_OF_DAY_WEIGHTS") {
+            Ok(raw) => parse_weights(&raw)
+                .map_err(|e| anyhow::anyhow!("Invalid BEST_OF_DAY_WEIGHTS: {}", e))?,
+            Err(_) => HashMap::new(),
+        };
+
+        let storage_upload_retries = non_negative_env::<u32>("STORAGE_UPLOAD_RETRIES", 2)?;
+        let storage_upload_retry_base_delay = match env::var("STORAGE_UPLOAD_RETRY_BASE_MS") {
+            Ok(raw) => raw
+                .trim()
+                .parse::<u64>()
+                .map(Duration::from_millis)
+                .with_context(|| {
+                    format!(
+                        "STORAGE_UPLOAD_RETRY_BASE_MS must be a number of milliseconds, got '{}'",
+                        raw
+                    )
+                })?,
+            Err(_) => Duration::from_millis(500),
+        };
+
+        let http_timeout = optional_positive_env::<u64>("HTTP_TIMEOUT_SECS")?
+            .map_or(crate::http::DEFAULT_TIMEOUT, Duration::from_secs);
+
+        let crawler_timeout = optional_secs_env(
+            "CRAWLER_TIMEOUT_SECS",
+            Some(crate::crawler::DEFAULT_CRAWLER_TIMEOUT),
+        )?;
+
+        let tuning_defaults = HttpTuning::default();
+        let http_tuning = HttpTuning {
+            http2_prior_knowledge: env_flag("HTTP2_PRIOR_KNOWLEDGE"),
+            pool_max_idle_per_host: match non_empty_env("HTTP_POOL_MAX_IDLE_PER_HOST") {
+                Some(raw) => raw.trim().parse::<usize>().with_context(|| {
+                    format!(
+                        "HTTP_POOL_MAX_IDLE_PER_HOST must be a number, got '{}'",
+                        raw
+                    )
+                })?,
+                None => tuning_defaults.pool_max_idle_per_host,
+            },
+            pool_idle_timeout: optional_secs_env(
+                "HTTP_POOL_IDLE_TIMEOUT_SECS",
+                tuning_defaults.pool_idle_timeout,
+            )?,
+            tcp_keepalive: optional_secs_env(
+                "HTTP_TCP_KEEPALIVE_SECS",
+                tuning_defaults.tcp_keepalive,
+            )?,
+        };
 
         Ok(Config {
             supabase: SupabaseConfig {
@@ -49,21 +577,169 @@ impl Config {
             },
             gemini_api_key: env::var("GEMINI_API_KEY").ok(),
             xai_api_key: env::var("XAI_API_KEY").ok(),
-            custom_site_url: env::var("CUSTOM_SITE_URL").ok(),
+            custom_site_url,
+            custom_site_urls,
+            custom_site_combined: env_flag("CUSTOM_SITE_COMBINED"),
             languages,
+            languages_set,
+            immutable_archive: env_flag("IMMUTABLE_ARCHIVE"),
+            csv_bom: env_flag("CSV_BOM"),
+            output_formats,
+            crawler_output_formats,
+            output_banners: env_templates("OUTPUT_BANNER"),
+            output_footers: env_templates("OUTPUT_FOOTER"),
+            github_language_concurrency,
+            github_requests_per_sec,
+            github_fetch_readme,
+            github_exclude_archived: env_flag("GITHUB_EXCLUDE_ARCHIVED"),
+            github_exclude_forks: env_flag("GITHUB_EXCLUDE_FORKS"),
+            github_file_per_language: env_flag("GITHUB_FILE_PER_LANGUAGE"),
+            github_combined_file: env_flag("GITHUB_COMBINED_FILE"),
+            github_confirm_flags_top,
+            github_trending_since,
+            ranking_min_description_len,
+            openrouter_use_api: env_flag_or("OPENROUTER_USE_API", true),
+            hn_item_types,
+            hn_seen_urls,
+            custom_site_seen_urls,
+            seen_urls_ttl_days,
+            summary_cache_ttl_days,
+            partial_upload_every,
+            retention_days,
+            retention_dry_run: env_flag("CUTION_RETENTION_DRY_RUN"),
+            llm_prices,
+            llm_budget_usd,
+            best_of_day: env_flag("BEST_OF_DAY"),
+            best_of_day_weights,
+            lobsters_enabled: env_flag("LOBSTERS_ENABLED"),
+            lobsters_tags,
+            lobsters_min_score,
+            product_hunt_token: non_empty_env("PRODUCT_HUNT_TOKEN"),
+            product_hunt_top_n,
+            reddit_subreddits,
+            reddit_min_score,
+            reddit_flairs,
+            reddit_summarize: env_flag("REDDIT_SUMMARIZE"),
+            feeds_opml: non_empty_env("FEEDS_OPML"),
+            feeds_max_age_hours,
+            feeds_summarize: env_flag("FEEDS_SUMMARIZE"),
+            devto_enabled: env_flag("DEVTO_ENABLED"),
+            devto_tags,
+            devto_min_reactions,
+            registries_enabled: env_flag("REGISTRIES_ENABLED"),
+            npm_watchlist,
+            youtube_channels,
+            arxiv_ids,
+            youtube_summarize: env_flag("YOUTUBE_SUMMARIZE"),
+            fediverse_enabled: env_flag("FEDIVERSE_ENABLED"),
+            fediverse_keywords,
+            fediverse_mastodon_instance: non_empty_env("FEDIVERSE_MASTODON_INSTANCE")
+                .map(|url| url.trim_end_matches('/').to_string()),
+            fediverse_min_likes,
+            fediverse_min_reposts,
+            papers_with_code: env_flag("PAPERS_WITH_CODE"),
+            papers_with_code_top_n,
+            weekly_rollup: env_flag("WEEKLY_ROLLUP"),
+            weekly_rollup_top_n,
+            daily_digest: env_flag("DAILY_DIGEST") || env_flag("DIGEST_TLDR"),
+            digest_tldr: env_flag("DIGEST_TLDR"),
+            generate_html_index: env_flag("GENERATE_HTML_INDEX"),
+            daily_index: env_flag("DAILY_INDEX"),
+            stale_after_days,
+            fixture_dir,
+            local_storage_dir,
+            storage_upload_retries,
+            storage_upload_retry_base_delay,
+            http_timeout,
+            crawler_timeout,
+            http_tuning,
+            checksums: env_flag("CUTION_CHECKSUMS"),
+            checksums_key: non_empty_env("CUTION_CHECKSUMS_KEY"),
+            errors_file,
+            audit_http: env_flag("CUTION_AUDIT_HTTP"),
+            archive: env_flag("CUTION_ARCHIVE"),
+            state_db: non_empty_env("CUTION_STATE_DB").map(PathBuf::from),
+            embeddings: env_flag("CUTION_EMBEDDINGS"),
+            embedding_model: non_empty_env("EMBEDDING_MODEL")
+                .unwrap_or_else(|| crate::gemini::DEFAULT_EMBEDDING_MODEL.to_string()),
+            embedding_dimensions,
+            translate_to,
+            translate_sources,
+            notify_slack_url: non_empty_env("NOTIFY_SLACK_URL"),
+            notify_discord_url: non_empty_env("NOTIFY_DISCORD_URL"),
+            notify_webhook_url: non_empty_env("NOTIFY_WEBHOOK_URL"),
+            http_proxy: non_empty_env("HTTP_PROXY_URL"),
+            http_user_agent: non_empty_env("HTTP_USER_AGENT"),
         })
     }
 
+    /// Output formats for the crawler with `slug`, falling back to `OUTPUT_FORMATS`.
+    pub fn output_formats_for(&self, slug: &str) -> Vec<OutputFormat> {
+        self.crawler_output_formats
+            .get(slug)
+            .unwrap_or(&self.output_formats)
+            .clone()
+    }
+
+    /// Formats for outputs that only exist as documents (digests, reviews):
+    /// always markdown, plus HTML when `slug`'s formats include it.
+    pub fn document_formats_for(&self, slug: &str) -> Vec<OutputFormat> {
+        let mut formats = vec![OutputFormat::Markdown];
+        if self.output_formats_for(slug).contains(&OutputFormat::Html) {
+            formats.push(OutputFormat::Html);
+        }
+        formats
+    }
+
+    /// Banner template for `slug`: `OUTPUT_BANNER_{SLUG}`, then `OUTPUT_BANNER`.
+    pub fn banner_for(&self, slug: &str) -> Option<&str> {
+        template_for(&self.output_banners, slug)
+    }
+
+    /// Footer template for `slug`: `OUTPUT_FOOTER_{SLUG}`, then `OUTPUT_FOOTER`.
+    pub fn footer_for(&self, slug: &str) -> Option<&str> {
+        template_for(&self.output_footers, slug)
+    }
+
+    /// Best-of-day weight for `slug`; sources without an explicit weight count as 1.0.
+    pub fn best_of_day_weight(&self, slug: &str) -> f64 {
+        self.best_of_day_weights.get(slug).copied().unwrap_or(1.0)
+    }
+
     pub fn require_gemini_api_key(&self) -> Result<&String> {
         self.gemini_api_key
             .as_ref()
             .context("GEMINI_API_KEY must be set")
     }
 
+    /// A Gemini-backed summarizer when `GEMINI_API_KEY` is set.
+    pub fn gemini_summarizer(&self) -> Result<Option<Arc<dyn Summarizer>>> {
+        let Some(api_key) = &self.gemini_api_key else {
+            return Ok(None);
+        };
+        let client = ClientFactory::from_config(self).build()?;
+        Ok(Some(Arc::new(GeminiClient::new(client, api_key))))
+    }
+
+    /// The Gemini summarizer for a crawler whose `*_SUMMARIZE` `flag` is set,
+    /// which then requires `GEMINI_API_KEY`; `None` when the flag is off.
+    pub fn summarizer(&self, flag: bool) -> Result<Option<Arc<dyn Summarizer>>> {
+        if !flag {
+            return Ok(None);
+        }
+        self.require_gemini_api_key()?;
+        self.gemini_summarizer()
+    }
+
     pub fn require_xai_api_key(&self) -> Result<&String> {
-        self.xai_api_key
-            .as_ref()
-            .context("XAI_API_KEY must be set")
+        self.xai_api_key.as_ref().context("XAI_API_KEY must be set")
+    }
+
+    pub fn require_custom_site_urls(&self) -> Result<&[String]> {
+        if self.custom_site_urls.is_empty() {
+            anyhow::bail!("CUSTOM_SITE_URL or CUSTOM_SITE_URLS must be set");
+        }
+        Ok(&self.custom_site_urls)
     }
 
     pub fn require_custom_site_url(&self) -> Result<&String> {
@@ -73,9 +749,300 @@ impl Config {
     }
 
     pub fn require_languages(&self) -> Result<&Vec<String>> {
-        if self.languages.is_empty() {
-            anyhow::bail!("LANGUAGES environment variable must be set and non-empty");
+        if let Some(reason) = self.missing_languages() {
+            anyhow::bail!(reason);
         }
         Ok(&self.languages)
     }
-}
\ No newline at end of file
+
+    /// Why there are no trending languages to crawl, telling an unset
+    /// `LANGUAGES` apart from one that lists nothing.
+    pub fn missing_languages(&self) -> Option<String> {
+        if !self.languages.is_empty() {
+            return None;
+        }
+        Some(if self.languages_set {
+            format!(
+                "LANGUAGES is set but lists no languages (use `{}` for trending across all languages)",
+                OVERALL_LANGUAGE
+            )
+        } else {
+            "LANGUAGES not set".to_string()
+        })
+    }
+}
+
+/// Keyword in `LANGUAGES` for GitHub's trending page across all languages,
+/// which is requested as the empty language.
+pub const OVERALL_LANGUAGE: &str = "overall";
+
+fn language_entry(entry: &str) -> String {
+    if entry.eq_ignore_ascii_case(OVERALL_LANGUAGE) {
+        String::new()
+    } else {
+        entry.to_string()
+    }
+}
+
+/// Parses a comma-separated language list, skipping empty entries.
+pub fn parse_languages(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(language_entry)
+        .collect()
+}
+
+/// `single` followed by the comma-separated `listed` URLs, blanks and
+/// repeats dropped.
+fn parse_custom_site_urls(single: Option<&str>, listed: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for url in single.into_iter().chain(listed.split(',')) {
+        let url = url.trim();
+        if !url.is_empty() && !urls.iter().any(|u| u == url) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+/// Parses a newline-separated language list, skipping blank and `#` comment lines.
+pub fn parse_languages_file(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(language_entry)
+        .collect()
+}
+
+/// Parses `slug=weight` pairs separated by commas, e.g. `hacker_news=1,github=0.5`.
+pub fn parse_weights(raw: &str) -> Result<HashMap<String, f64>> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (slug, weight) = pair
+                .split_once('=')
+                .with_context(|| format!("expected slug=weight, got '{}'", pair))?;
+            let weight: f64 = weight
+                .trim()
+                .parse()
+                .ok()
+                .filter(|w: &f64| w.is_finite() && *w >= 0.0)
+                .with_context(|| {
+                    format!("weight for '{}' must be a non-negative number", slug.trim())
+                })?;
+            Ok((slug.trim().to_string(), weight))
+        })
+        .collect()
+}
+
+fn template_for<'a>(templates: &'a HashMap<String, String>, slug: &str) -> Option<&'a str> {
+    templates
+        .get(slug)
+        .or_else(|| templates.get(""))
+        .map(String::as_str)
+}
+
+/// Collects `{prefix}` (stored under `""`) and `{prefix}_{SLUG}` (stored under the
+/// lowercased slug), ignoring empty values.
+fn env_templates(prefix: &str) -> HashMap<String, String> {
+    env::vars()
+        .filter(|(_, value)| !value.trim().is_empty())
+        .filter_map(|(key, value)| {
+            if key == prefix {
+                return Some((String::new(), value));
+            }
+            let slug = key.strip_prefix(prefix)?.strip_prefix('_')?;
+            Some((slug.to_ascii_lowercase(), value))
+        })
+        .collect()
+}
+
+fn non_empty_env(key: &str) -> Option<String> {
+    env::var(key)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Reads a positive integer env var, `default` when it is unset or empty.
+fn positive_env<T>(key: &str, default: T) -> Result<T>
+where
+    T: FromStr + PartialOrd + Default,
+{
+    Ok(optional_positive_env(key)?.unwrap_or(default))
+}
+
+/// Reads a positive integer env var, `None` when it is unset or empty.
+fn optional_positive_env<T>(key: &str) -> Result<Option<T>>
+where
+    T: FromStr + PartialOrd + Default,
+{
+    match non_empty_env(key) {
+        Some(raw) => match raw.parse::<T>() {
+            Ok(n) if n > T::default() => Ok(Some(n)),
+            _ => anyhow::bail!("{} must be a positive integer, got '{}'", key, raw),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Reads a non-negative integer env var, `default` when it is unset or empty.
+fn non_negative_env<T: FromStr>(key: &str, default: T) -> Result<T> {
+    match non_empty_env(key) {
+        Some(raw) => raw
+            .parse::<T>()
+            .map_err(|_| anyhow::anyhow!("{} must be a non-negative integer, got '{}'", key, raw)),
+        None => Ok(default),
+    }
+}
+
+/// Reads a number of seconds from an env var where `0` disables the setting,
+/// `default` when it is unset or empty.
+fn optional_secs_env(key: &str, default: Option<Duration>) -> Result<Option<Duration>> {
+    match non_empty_env(key) {
+        Some(raw) => match raw.parse::<u64>() {
+            Ok(0) => Ok(None),
+            Ok(secs) => Ok(Some(Duration::from_secs(secs))),
+            Err(_) => anyhow::bail!("{} must be a number of seconds, got '{}'", key, raw),
+        },
+        None => Ok(default),
+    }
+}
+
+/// Splits a list env var on `separators` and keeps the first occurrence of
+/// each entry after `normalize`, dropping empty ones.
+fn list_env(key: &str, separators: &[char], normalize: fn(&str) -> String) -> Vec<String> {
+    let mut list: Vec<String> = Vec::new();
+    for entry in env::var(key).unwrap_or_default().split(separators) {
+        let entry = normalize(entry.trim());
+        if !entry.is_empty() && !list.contains(&entry) {
+            list.push(entry);
+        }
+    }
+    list
+}
+
+/// Reads a boolean env var, accepting `1`, `true` and `yes` (case-insensitive).
+fn env_flag(key: &str) -> bool {
+    env_flag_or(key, false)
+}
+
+/// Like `env_flag`, but `default` when the variable is unset or empty.
+fn env_flag_or(key: &str, default: bool) -> bool {
+    non_empty_env(key)
+        .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positive_env_rejects_zero_and_garbage() {
+        let key = "CUTION_TEST_POSITIVE_ENV";
+        assert_eq!(positive_env::<u32>(key, 7).unwrap(), 7);
+        env::set_var(key, " 3 ");
+        assert_eq!(positive_env::<u32>(key, 7).unwrap(), 3);
+        for bad in ["0", "-1", "many"] {
+            env::set_var(key, bad);
+            let err = optional_positive_env::<u32>(key).unwrap_err().to_string();
+            assert!(err.contains("must be a positive integer"), "{}", err);
+        }
+        env::remove_var(key);
+        assert_eq!(optional_positive_env::<u32>(key).unwrap(), None);
+    }
+
+    #[test]
+    fn non_negative_env_accepts_zero_and_treats_empty_as_unset() {
+        let key = "CUTION_TEST_NON_NEGATIVE_ENV";
+        env::set_var(key, " ");
+        assert_eq!(non_negative_env::<u64>(key, 7).unwrap(), 7);
+        env::set_var(key, "0");
+        assert_eq!(non_negative_env::<u64>(key, 7).unwrap(), 0);
+        env::set_var(key, "-1");
+        let err = non_negative_env::<u64>(key, 7).unwrap_err().to_string();
+        assert!(err.contains("must be a non-negative integer"), "{}", err);
+        env::remove_var(key);
+    }
+
+    #[test]
+    fn optional_secs_env_treats_zero_as_disabled() {
+        let key = "CUTION_TEST_OPTIONAL_SECS_ENV";
+        let default = Some(Duration::from_secs(90));
+        assert_eq!(optional_secs_env(key, default).unwrap(), default);
+        env::set_var(key, "0");
+        assert_eq!(optional_secs_env(key, default).unwrap(), None);
+        env::set_var(key, "15");
+        assert_eq!(
+            optional_secs_env(key, default).unwrap(),
+            Some(Duration::from_secs(15))
+        );
+        env::set_var(key, "soon");
+        assert!(optional_secs_env(key, default).is_err());
+        env::remove_var(key);
+    }
+
+    #[test]
+    fn list_env_normalizes_and_drops_repeats() {
+        let key = "CUTION_TEST_LIST_ENV";
+        env::set_var(key, " React, ,react vue,Vue ");
+        assert_eq!(
+            list_env(key, &[',', ' '], str::to_ascii_lowercase),
+            ["react", "vue"]
+        );
+        env::remove_var(key);
+        assert!(list_env(key, &[','], str::to_string).is_empty());
+    }
+
+    #[test]
+    fn parse_languages_file_skips_comments_and_blanks() {
+        let contents = "# systems\nrust\n\n  go  \n#python\ntypescript\n";
+        assert_eq!(
+            parse_languages_file(contents),
+            vec!["rust", "go", "typescript"]
+        );
+        assert_eq!(parse_languages_file("rust\nOverall\n"), vec!["rust", ""]);
+    }
+
+    #[test]
+    fn custom_site_urls_keep_the_singular_variable_first() {
+        assert_eq!(
+            parse_custom_site_urls(
+                Some("https://a.example"),
+                " https://b.example,,https://a.example "
+            ),
+            vec!["https://a.example", "https://b.example"]
+        );
+        assert!(parse_custom_site_urls(None, " , ").is_empty());
+    }
+
+    #[test]
+    fn trending_ranges_parse_case_insensitively() {
+        assert_eq!(" Weekly ".parse(), Ok(TrendingRange::Weekly));
+        assert_eq!("monthly".parse(), Ok(TrendingRange::Monthly));
+        assert!("yearly".parse::<TrendingRange>().is_err());
+        assert_eq!(TrendingRange::default().as_str(), "daily");
+    }
+
+    #[test]
+    fn overall_keyword_is_the_empty_language() {
+        assert_eq!(
+            parse_languages(" rust, overall ,go"),
+            vec!["rust", "", "go"]
+        );
+        assert!(parse_languages(" , ,").is_empty());
+    }
+
+    #[test]
+    fn parse_weights_reads_pairs() {
+        let weights = parse_weights("hacker_news=1, github=0.5,").unwrap();
+        assert_eq!(weights.get("hacker_news"), Some(&1.0));
+        assert_eq!(weights.get("github"), Some(&0.5));
+        assert!(parse_weights("github").is_err());
+        assert!(parse_weights("github=-1").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Shared services handed to every crawler run, so cross-cutting concerns
+//! (the date outputs are filed under, storage, HTTP, summarizing) can be
+//! swapped in one place instead of per crawler.
+
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use time::{Date, OffsetDateTime};
+
+use crate::http::HttpClient;
+use crate::progress::{NoopProgress, ProgressEvent, ProgressSink};
+use crate::publish::Publisher;
+use crate::summarize::{Summarizer, TruncatingSummarizer};
+use crate::supabase_client::SupabaseStorageClient;
+
+/// Source of the current time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// The system clock, in UTC.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// A clock stopped at one instant, for tests and reruns of a past day.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub OffsetDateTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> OffsetDateTime {
+        self.0
+    }
+}
+
+/// One item (a story, a language, a page) a crawler gave up on while the
+/// rest of its run went on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ItemError {
+    pub item: String,
+    pub error: String,
+}
+
+/// Collects `ItemError`s; clones share the same list, so spawned tasks can
+/// report into it.
+#[derive(Debug, Clone, Default)]
+pub struct ItemErrors(Arc<Mutex<Vec<ItemError>>>);
+
+impl ItemErrors {
+    pub fn push(&self, item: impl Into<String>, error: impl ToString) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(ItemError {
+                item: item.into(),
+                error: error.to_string(),
+            });
+    }
+
+    /// The errors so far, leaving the list empty.
+    pub fn take(&self) -> Vec<ItemError> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// What `Crawler::run_with_context` receives. The HTTP client and storage
+/// are overrides: when unset, crawlers keep the ones they built from `Config`.
+#[derive(Clone)]
+pub struct CrawlerContext {
+    pub http: Option<HttpClient>,
+    pub storage: Option<SupabaseStorageClient>,
+    pub summarizer: Arc<dyn Summarizer>,
+    pub clock: Arc<dyn Clock>,
+    pub progress: Arc<dyn ProgressSink>,
+    /// The day outputs are written under, as in `{date}/hacker-news.md`.
+    pub date: Date,
+    /// Set by `with_date`; otherwise `for_run` re-reads the date from the clock.
+    pub(crate) date_pinned: bool,
+    /// Where per-item failures go; `run_one` gives each crawler its own.
+    pub item_errors: ItemErrors,
+}
+
+impl Default for CrawlerContext {
+    fn default() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+}
+
+impl CrawlerContext {
+    /// A context whose output date is `clock`'s current day.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            http: None,
+            storage: None,
+            summarizer: Arc::new(TruncatingSummarizer::default()),
+            progress: Arc::new(NoopProgress),
+            date: clock.now().date(),
+            date_pinned: false,
+            clock,
+            item_errors: ItemErrors::default(),
+        }
+    }
+
+    /// Pins the output date, e.g. to rerun a past day.
+    pub fn with_date(mut self, date: Date) -> Self {
+        self.date = date;
+        self.date_pinned = true;
+        self
+    }
+
+    /// The context for a run starting now: dated by the clock's current day
+    /// unless `with_date` pinned the date, so a long-lived manager does not
+    /// keep publishing into the day it was built.
+    pub fn for_run(&self) -> Self {
+        let mut ctx = self.clone();
+        if !self.date_pinned {
+            ctx.date = self.clock.now().date();
+        }
+        ctx
+    }
+
+    pub fn with_http(mut self, http: HttpClient) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    pub fn with_storage(mut self, storage: SupabaseStorageClient) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = summarizer;
+        self
+    }
+
+    pub fn with_progress(mut self, progress: Arc<dyn ProgressSink>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Reports that crawler `name` has finished `done` of `total` items.
+    pub fn item_processed(&self, name: &str, done: usize, total: usize) {
+        self.progress.emit(ProgressEvent::ItemProcessed {
+            name: name.to_string(),
+            done,
+            total,
+        });
+    }
+
+    /// Records that `item` failed with `error` without failing the run.
+    pub fn item_failed(&self, item: impl Into<String>, error: impl ToString) {
+        self.item_errors.push(item, error);
+    }
+
+    pub fn now(&self) -> OffsetDateTime {
+        self.clock.now()
+    }
+
+    /// The shared client if there is one, otherwise `own`.
+    pub fn http_client(&self, own: &HttpClient) -> HttpClient {
+        self.http.clone().unwrap_or_else(|| own.clone())
+    }
+
+    /// The shared storage if there is one, otherwise `own`.
+    pub fn storage_client(&self, own: &SupabaseStorageClient) -> SupabaseStorageClient {
+        self.storage.clone().unwrap_or_else(|| own.clone())
+    }
+
+    /// `own`, dated `date` and writing to the shared storage if there is one.
+    pub fn publisher(&self, own: &Publisher) -> Publisher {
+        let publisher = own.clone().with_date(self.date);
+        match &self.storage {
+            Some(storage) => publisher.with_storage(storage.clone()),
+            None => publisher,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::ClientFactory;
+    use time::macros::datetime;
+
+    #[test]
+    fn output_date_follows_the_clock_unless_overridden() {
+        let clock = Arc::new(FixedClock(datetime!(2024-05-10 23:30 UTC)));
+        let ctx = CrawlerContext::with_clock(clock);
+        assert_eq!(ctx.date.to_string(), "2024-05-10");
+        assert_eq!(ctx.now(), datetime!(2024-05-10 23:30 UTC));
+
+        let backfill = ctx.with_date(time::macros::date!(2024 - 05 - 01));
+        assert_eq!(backfill.date.to_string(), "2024-05-01");
+    }
+
+    #[test]
+    fn shared_client_replaces_the_crawlers_own() {
+        let own = ClientFactory::default()
+            .with_user_agent("own")
+            .build()
+            .unwrap();
+        let ctx = CrawlerContext::default();
+        assert_eq!(ctx.http_client(&own).user_agent(), "own");
+        let shared = ClientFactory::default()
+            .with_user_agent("shared")
+            .build()
+            .unwrap();
+        let ctx = ctx.with_http(shared);
+        assert_eq!(ctx.http_client(&own).user_agent(), "shared");
+    }
+}
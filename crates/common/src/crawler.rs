@@ -1,79 +1,743 @@
+use crate::config::Config;
+use crate::context::{CrawlerContext, ItemError, ItemErrors};
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::progress::ProgressEvent;
+use crate::usage::UsageSummary;
 use async_trait::async_trait;
-use crate::error::CrawlerResult;
+use std::time::Duration;
+use time::{Date, OffsetDateTime};
 
 #[async_trait]
 pub trait Crawler: Send + Sync {
-    async fn run(&self) -> CrawlerResult<()>;
+    /// Runs with a default context: today's date and the crawler's own clients.
+    async fn run(&self) -> CrawlerResult<()> {
+        self.run_with_context(&CrawlerContext::default()).await
+    }
+
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()>;
+
     fn name(&self) -> &'static str;
+
+    /// Short identifier used in config maps and file names.
+    fn slug(&self) -> &'static str {
+        self.name()
+    }
+
+    /// Default cron expression when the crawler is scheduled on its own.
+    fn schedule(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Slugs of crawlers whose output this one reads. The manager runs it after
+    /// them (whether they succeeded or not); unregistered slugs are ignored.
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Checks that `config` has what this crawler needs, returning a
+    /// human-readable reason when it cannot run.
+    fn precheck(_config: &Config) -> Result<(), String>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
 }
 
 #[async_trait]
 pub trait DataSource: Send + Sync {
     type Item;
-    
+
     async fn fetch_data(&self) -> CrawlerResult<Vec<Self::Item>>;
     fn format_output(&self, items: &[Self::Item]) -> String;
 }
 
+/// Result of a single crawler within a manager run.
+#[derive(Debug, Clone)]
+pub struct CrawlerOutcome {
+    pub name: String,
+    pub duration: Duration,
+    pub error: Option<String>,
+    /// Storage paths the crawler's `Publisher` wrote during the run.
+    pub uploaded: Vec<String>,
+    /// The subset of `uploaded` skipped because its content was unchanged.
+    pub unchanged: Vec<String>,
+    /// `(path, SHA-256 hex)` for each entry of `uploaded`.
+    pub checksums: Vec<(String, String)>,
+    /// Items the crawler skipped after an error, reported through
+    /// `CrawlerContext::item_failed`.
+    pub item_errors: Vec<ItemError>,
+}
+
+impl CrawlerOutcome {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Summary of a `CrawlerManager` run, one outcome per registered crawler.
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    pub outcomes: Vec<CrawlerOutcome>,
+    /// Date the run's outputs were written under; `None` for a report not
+    /// produced by a manager.
+    pub date: Option<Date>,
+    /// LLM usage recorded by the process's tracker when the run finished.
+    pub usage: UsageSummary,
+}
+
+impl RunReport {
+    /// The run's date, or today's when the report carries none.
+    pub fn date(&self) -> Date {
+        self.date
+            .unwrap_or_else(|| OffsetDateTime::now_utc().date())
+    }
+
+    pub fn success_count(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.is_success()).count()
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.outcomes.len() - self.success_count()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error_count() == 0
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &CrawlerOutcome> {
+        self.outcomes.iter().filter(|o| !o.is_success())
+    }
+
+    /// Converts a report with failures into the error `run_all` has always returned.
+    pub fn into_result(self) -> CrawlerResult<Self> {
+        if self.is_success() {
+            return Ok(self);
+        }
+        Err(crate::error::CrawlerError::Api(format!(
+            "Some crawlers failed: {} failed, {} succeeded",
+            self.error_count(),
+            self.success_count()
+        )))
+    }
+}
+
+/// How long a manager lets one crawler run unless told otherwise.
+pub const DEFAULT_CRAWLER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 pub struct CrawlerManager {
     crawlers: Vec<Box<dyn Crawler>>,
+    context: CrawlerContext,
+    /// Most crawlers running at once; `None` runs each wave all together.
+    max_concurrency: Option<usize>,
+    /// Longest a single crawler may run; `None` lets it run until it finishes.
+    crawler_timeout: Option<Duration>,
 }
 
-impl CrawlerManager {
-    pub fn new() -> Self {
+impl Default for CrawlerManager {
+    fn default() -> Self {
         Self {
             crawlers: Vec::new(),
+            context: CrawlerContext::default(),
+            max_concurrency: None,
+            crawler_timeout: Some(DEFAULT_CRAWLER_TIMEOUT),
         }
     }
+}
+
+impl CrawlerManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs every crawler with `context` instead of the default one.
+    pub fn with_context(mut self, context: CrawlerContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Runs at most `n` crawlers at a time. Crawlers waiting for a slot still
+    /// start in registration order within their wave.
+    pub fn with_max_concurrency(mut self, n: usize) -> Self {
+        self.max_concurrency = Some(n.max(1));
+        self
+    }
+
+    /// Gives up on a crawler that runs longer than `limit` (by default
+    /// `DEFAULT_CRAWLER_TIMEOUT`), cancelling it and recording it as failed.
+    /// Each crawler gets the full `limit`; zero disables the timeout.
+    pub fn with_crawler_timeout(mut self, limit: Duration) -> Self {
+        self.crawler_timeout = Some(limit).filter(|limit| !limit.is_zero());
+        self
+    }
 
     pub fn add_crawler(mut self, crawler: Box<dyn Crawler>) -> Self {
         self.crawlers.push(crawler);
         self
     }
 
+    pub fn len(&self) -> usize {
+        self.crawlers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.crawlers.is_empty()
+    }
+
     pub async fn run_all(&self) -> CrawlerResult<()> {
+        self.run_with_report().await.into_result().map(|_| ())
+    }
+
+    pub fn crawlers(&self) -> impl Iterator<Item = &dyn Crawler> {
+        self.crawlers.iter().map(|c| c.as_ref())
+    }
+
+    /// Looks up a crawler by slug or display name.
+    pub fn find(&self, name: &str) -> Option<&dyn Crawler> {
+        self.crawlers()
+            .find(|c| c.slug() == name || c.name() == name)
+    }
+
+    /// Runs every crawler and reports per-crawler outcomes instead of
+    /// collapsing them into a single error. Crawlers run concurrently, in waves
+    /// ordered by `depends_on`; one that panics is recorded as failed and the
+    /// rest carry on.
+    pub async fn run_with_report(&self) -> RunReport {
         use futures::future::join_all;
-        use tracing::{info, warn};
-
-        let futures = self.crawlers.iter().map(|crawler| {
-            let name = crawler.name();
-            async move {
-                match crawler.run().await {
-                    Ok(_) => {
-                        info!("{} completed successfully", name);
-                        Ok(())
-                    }
-                    Err(e) => {
-                        warn!("{} failed: {}", name, e);
-                        Err(e)
-                    }
-                }
+        use tokio::sync::Semaphore;
+        use tracing::info;
+
+        let context = self.context.for_run();
+        let slots = self.max_concurrency.map(Semaphore::new);
+        let mut outcomes = Vec::new();
+        for wave in self.waves() {
+            let runs = wave.into_iter().map(|crawler| async {
+                let _permit = match &slots {
+                    Some(slots) => match slots.acquire().await {
+                        Ok(permit) => Some(permit),
+                        Err(e) => {
+                            let error = CrawlerError::Config(
+                                anyhow::Error::new(e).context("crawler concurrency limit closed"),
+                            );
+                            return failed_outcome(crawler.name(), error);
+                        }
+                    },
+                    None => None,
+                };
+                run_one(crawler, &context, self.crawler_timeout).await
+            });
+            outcomes.extend(join_all(runs).await);
+        }
+        let report = RunReport {
+            outcomes,
+            date: Some(context.date),
+            usage: crate::usage::global().summary(),
+        };
+
+        info!(
+            "All crawlers finished. Successful: {}, Failed: {}",
+            report.success_count(),
+            report.error_count()
+        );
+        for (source, (tokens, cost)) in report.usage.by_source() {
+            info!("LLM usage of {}: {} token(s), ${:.4}", source, tokens, cost);
+        }
+
+        report
+    }
+
+    /// Groups crawlers so each one comes after every registered crawler it
+    /// depends on. A dependency cycle is logged and its members run together.
+    fn waves(&self) -> Vec<Vec<&dyn Crawler>> {
+        use std::collections::HashSet;
+
+        let registered: HashSet<&str> = self.crawlers().map(|c| c.slug()).collect();
+        let mut done: HashSet<&str> = HashSet::new();
+        let mut pending: Vec<&dyn Crawler> = self.crawlers().collect();
+        let mut waves = Vec::new();
+        while !pending.is_empty() {
+            let (mut ready, blocked): (Vec<_>, Vec<_>) = pending.into_iter().partition(|c| {
+                c.depends_on()
+                    .iter()
+                    .all(|dep| done.contains(dep) || !registered.contains(dep))
+            });
+            if ready.is_empty() {
+                tracing::warn!(
+                    "Dependency cycle between crawlers: {}",
+                    blocked
+                        .iter()
+                        .map(|c| c.slug())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                ready = blocked;
+                pending = Vec::new();
+            } else {
+                pending = blocked;
             }
+            done.extend(ready.iter().map(|c| c.slug()));
+            waves.push(ready);
+        }
+        waves
+    }
+
+    /// Runs a single crawler identified by slug or display name.
+    pub async fn run_named(&self, name: &str) -> CrawlerResult<RunReport> {
+        let crawler = self.find(name).ok_or_else(|| {
+            crate::error::CrawlerError::Config(anyhow::anyhow!("Unknown crawler: {}", name))
+        })?;
+        let context = self.context.for_run();
+        let outcome = run_one(crawler, &context, self.crawler_timeout).await;
+        Ok(RunReport {
+            outcomes: vec![outcome],
+            date: Some(context.date),
+            usage: crate::usage::global().summary(),
+        })
+    }
+}
+
+/// Outcome of a crawler that failed before it could start.
+fn failed_outcome(name: &str, error: CrawlerError) -> CrawlerOutcome {
+    tracing::error!("{} failed: {}", name, error);
+    CrawlerOutcome {
+        name: name.to_string(),
+        duration: Duration::ZERO,
+        error: Some(error.to_string()),
+        uploaded: vec![],
+        unchanged: vec![],
+        checksums: vec![],
+        item_errors: vec![],
+    }
+}
+
+async fn run_one(
+    crawler: &dyn Crawler,
+    ctx: &CrawlerContext,
+    timeout: Option<Duration>,
+) -> CrawlerOutcome {
+    use futures::FutureExt;
+    use tracing::{error, info, warn};
+
+    let name = crawler.name();
+    let started = std::time::Instant::now();
+    ctx.progress.emit(ProgressEvent::CrawlerStarted {
+        name: name.to_string(),
+    });
+    let ctx = &CrawlerContext {
+        item_errors: ItemErrors::default(),
+        ..ctx.clone()
+    };
+    let run = crate::usage::attribute(crawler.slug(), crawler.run_with_context(ctx));
+    // Crawlers share the manager's task, so a panic is caught here rather
+    // than unwinding through every other crawler in the wave.
+    let run = std::panic::AssertUnwindSafe(run)
+        .catch_unwind()
+        .map(|result| {
+            result.unwrap_or_else(|payload| {
+                let message = panic_message(payload.as_ref());
+                error!("{} panicked: {}", name, message);
+                Err(CrawlerError::Panicked(message))
+            })
         });
+    let run = async {
+        match timeout {
+            // Dropping the timed-out future cancels the crawler.
+            Some(limit) => tokio::time::timeout(limit, run).await.unwrap_or_else(|_| {
+                Err(CrawlerError::Timeout(format!(
+                    "{} still running after {:?}",
+                    name, limit
+                )))
+            }),
+            None => run.await,
+        }
+    };
+    let (result, uploads) = crate::publish::record_uploads(run).await;
+    let error = match result {
+        Ok(_) => {
+            info!("{} completed successfully", name);
+            None
+        }
+        Err(e) => {
+            warn!("{} failed: {}", name, e);
+            Some(e.to_string())
+        }
+    };
+    ctx.progress.emit(ProgressEvent::CrawlerFinished {
+        name: name.to_string(),
+        result: error.clone().map_or(Ok(()), Err),
+    });
+    CrawlerOutcome {
+        name: name.to_string(),
+        duration: started.elapsed(),
+        error,
+        uploaded: uploads.paths,
+        unchanged: uploads.unchanged,
+        checksums: uploads.checksums,
+        item_errors: ctx.item_errors.take(),
+    }
+}
+
+/// The message a panic was raised with, if it was given one.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "no panic message".to_string())
+}
 
-        let results = join_all(futures).await;
-        
-        let mut success_count = 0;
-        let mut error_count = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for result in results {
-            match result {
-                Ok(_) => success_count += 1,
-                Err(_) => error_count += 1,
+    struct Stub(&'static str, &'static [&'static str]);
+
+    #[async_trait]
+    impl Crawler for Stub {
+        async fn run_with_context(&self, _ctx: &CrawlerContext) -> CrawlerResult<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn depends_on(&self) -> &'static [&'static str] {
+            self.1
+        }
+    }
+
+    fn slugs(manager: &CrawlerManager) -> Vec<Vec<&str>> {
+        manager
+            .waves()
+            .iter()
+            .map(|wave| wave.iter().map(|c| c.slug()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn dependents_run_after_their_sources() {
+        let manager = CrawlerManager::new()
+            .add_crawler(Box::new(Stub(
+                "best_of_day",
+                &["hacker_news", "github", "reddit"],
+            )))
+            .add_crawler(Box::new(Stub("github", &[])))
+            .add_crawler(Box::new(Stub("hacker_news", &[])));
+        assert_eq!(
+            slugs(&manager),
+            vec![vec!["github", "hacker_news"], vec!["best_of_day"]]
+        );
+    }
+
+    #[test]
+    fn cycles_still_run_once() {
+        let manager = CrawlerManager::new()
+            .add_crawler(Box::new(Stub("a", &["b"])))
+            .add_crawler(Box::new(Stub("b", &["a"])))
+            .add_crawler(Box::new(Stub("c", &[])));
+        assert_eq!(slugs(&manager), vec![vec!["c"], vec!["a", "b"]]);
+    }
+
+    /// Fails unless it runs for the expected output date.
+    struct DateCheck(time::Date);
+
+    #[async_trait]
+    impl Crawler for DateCheck {
+        async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+            if ctx.date != self.0 {
+                return Err(crate::CrawlerError::Api(format!("ran for {}", ctx.date)));
             }
+            Ok(())
         }
 
-        info!(
-            "All crawlers finished. Successful: {}, Failed: {}",
-            success_count, error_count
+        fn name(&self) -> &'static str {
+            "date_check"
+        }
+    }
+
+    #[tokio::test]
+    async fn manager_passes_its_context_to_every_crawler() {
+        let backfill = time::macros::date!(2024 - 05 - 10);
+        let manager = CrawlerManager::new()
+            .add_crawler(Box::new(DateCheck(backfill)))
+            .with_context(CrawlerContext::default().with_date(backfill));
+        assert!(manager.run_with_report().await.is_success());
+        assert!(DateCheck(backfill).run().await.is_err(), "run() uses today");
+    }
+
+    /// A clock tests can move forward.
+    struct SteppingClock(std::sync::Mutex<time::OffsetDateTime>);
+
+    impl crate::context::Clock for SteppingClock {
+        fn now(&self) -> time::OffsetDateTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn each_run_is_dated_by_the_clock_at_its_start() {
+        let clock = std::sync::Arc::new(SteppingClock(std::sync::Mutex::new(
+            time::macros::datetime!(2024-05-10 09:00 UTC),
+        )));
+        let manager = CrawlerManager::new()
+            .add_crawler(Box::new(DateCheck(time::macros::date!(2024 - 05 - 10))))
+            .with_context(CrawlerContext::with_clock(clock.clone()));
+        assert!(manager.run_named("date_check").await.unwrap().is_success());
+
+        *clock.0.lock().unwrap() = time::macros::datetime!(2024-05-11 09:00 UTC);
+        let report = manager.run_named("date_check").await.unwrap();
+        assert_eq!(
+            report.outcomes[0].error.as_deref(),
+            Some("API error: ran for 2024-05-11")
+        );
+        assert_eq!(report.date, Some(time::macros::date!(2024 - 05 - 11)));
+    }
+
+    #[tokio::test]
+    async fn progress_events_bracket_each_run() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let ctx = CrawlerContext::default().with_progress(std::sync::Arc::new(tx));
+        let manager = CrawlerManager::new()
+            .add_crawler(Box::new(DateCheck(time::macros::date!(2000 - 01 - 01))))
+            .with_context(ctx);
+        manager.run_with_report().await;
+        drop(manager);
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0],
+            ProgressEvent::CrawlerStarted {
+                name: "date_check".to_string()
+            }
         );
+        assert!(matches!(
+            &events[1],
+            ProgressEvent::CrawlerFinished { name, result: Err(e) }
+                if name == "date_check" && e.contains("ran for")
+        ));
+    }
+
+    /// Tracks how many instances run at once and the most seen.
+    struct Counted {
+        running: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+        peak: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Crawler for Counted {
+        async fn run_with_context(&self, _ctx: &CrawlerContext) -> CrawlerResult<()> {
+            use std::sync::atomic::Ordering;
 
-        if error_count > 0 {
-            return Err(crate::error::CrawlerError::Api(
-                format!("Some crawlers failed: {} failed, {} succeeded", error_count, success_count)
-            ));
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
         }
 
-        Ok(())
+        fn name(&self) -> &'static str {
+            "counted"
+        }
+    }
+
+    async fn peak_concurrency(limit: Option<usize>) -> usize {
+        let running = std::sync::Arc::default();
+        let peak = std::sync::Arc::<std::sync::atomic::AtomicUsize>::default();
+        let mut manager = CrawlerManager::new();
+        for _ in 0..6 {
+            manager = manager.add_crawler(Box::new(Counted {
+                running: std::sync::Arc::clone(&running),
+                peak: std::sync::Arc::clone(&peak),
+            }));
+        }
+        if let Some(limit) = limit {
+            manager = manager.with_max_concurrency(limit);
+        }
+        assert!(manager.run_with_report().await.is_success());
+        peak.load(std::sync::atomic::Ordering::SeqCst)
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn max_concurrency_caps_running_crawlers() {
+        assert_eq!(peak_concurrency(Some(2)).await, 2);
+        assert_eq!(
+            peak_concurrency(Some(0)).await,
+            1,
+            "zero still makes progress"
+        );
+        assert_eq!(peak_concurrency(None).await, 6);
+    }
+
+    /// Sleeps for its duration, then succeeds.
+    struct Sleepy(&'static str, Duration);
+
+    #[async_trait]
+    impl Crawler for Sleepy {
+        async fn run_with_context(&self, _ctx: &CrawlerContext) -> CrawlerResult<()> {
+            tokio::time::sleep(self.1).await;
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn crawlers_running_past_the_timeout_fail_alone() {
+        let manager = || {
+            CrawlerManager::new()
+                .add_crawler(Box::new(Sleepy("hung", Duration::from_secs(60))))
+                .add_crawler(Box::new(Sleepy("quick", Duration::from_millis(10))))
+                .add_crawler(Box::new(Sleepy("steady", Duration::from_millis(150))))
+        };
+        let started = std::time::Instant::now();
+        let report = manager()
+            .with_max_concurrency(1)
+            .with_crawler_timeout(Duration::from_millis(200))
+            .run_with_report()
+            .await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let errors: Vec<_> = report
+            .outcomes
+            .iter()
+            .map(|o| (o.name.as_str(), o.error.as_deref()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("hung", Some("Timed out: hung still running after 200ms")),
+                ("quick", None),
+                ("steady", None),
+            ],
+            "the limit applies to each crawler, not the whole run"
+        );
+
+        let report = manager()
+            .with_crawler_timeout(Duration::ZERO)
+            .run_named("steady")
+            .await
+            .unwrap();
+        assert!(report.is_success(), "a zero timeout is off");
+    }
+
+    /// Never finishes; notes when its run is dropped.
+    struct Forever(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+    struct SetOnDrop(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+    impl Drop for SetOnDrop {
+        fn drop(&mut self) {
+            self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl Crawler for Forever {
+        async fn run_with_context(&self, _ctx: &CrawlerContext) -> CrawlerResult<()> {
+            let _cancelled = SetOnDrop(self.0.clone());
+            std::future::pending().await
+        }
+
+        fn name(&self) -> &'static str {
+            "forever"
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hung_crawlers_are_cancelled_after_the_default_timeout() {
+        let cancelled = std::sync::Arc::default();
+        let report = CrawlerManager::new()
+            .add_crawler(Box::new(Forever(std::sync::Arc::clone(&cancelled))))
+            .add_crawler(Box::new(Stub("done", &[])))
+            .run_with_report()
+            .await;
+        assert!(cancelled.load(std::sync::atomic::Ordering::SeqCst));
+        assert_eq!(report.error_count(), 1);
+        assert_eq!(
+            report.outcomes[0].error.as_deref(),
+            Some("Timed out: forever still running after 300s")
+        );
+        assert!(report.outcomes[1].is_success());
+    }
+
+    struct Panicky;
+
+    #[async_trait]
+    impl Crawler for Panicky {
+        async fn run_with_context(&self, _ctx: &CrawlerContext) -> CrawlerResult<()> {
+            tokio::task::yield_now().await;
+            panic!("selector went missing");
+        }
+
+        fn name(&self) -> &'static str {
+            "panicky"
+        }
+    }
+
+    #[tokio::test]
+    async fn a_panicking_crawler_does_not_take_down_the_others() {
+        let report = CrawlerManager::new()
+            .add_crawler(Box::new(Stub("before", &[])))
+            .add_crawler(Box::new(Panicky))
+            .add_crawler(Box::new(Sleepy("after", Duration::from_millis(10))))
+            .add_crawler(Box::new(Stub("dependent", &["panicky"])))
+            .run_with_report()
+            .await;
+        let errors: Vec<_> = report
+            .outcomes
+            .iter()
+            .map(|o| (o.name.as_str(), o.error.as_deref()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("before", None),
+                ("panicky", Some("Crawler panicked: selector went missing")),
+                ("after", None),
+                ("dependent", None),
+            ]
+        );
+    }
+
+    /// Skips one item from a spawned task and one inline.
+    struct Partial;
+
+    #[async_trait]
+    impl Crawler for Partial {
+        async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+            let errors = ctx.item_errors.clone();
+            tokio::spawn(async move { errors.push("item 1", "HTTP 503") })
+                .await
+                .unwrap();
+            ctx.item_failed("item 2", "parse error");
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            "partial"
+        }
+    }
+
+    #[tokio::test]
+    async fn item_errors_are_kept_per_crawler() {
+        let manager = CrawlerManager::new()
+            .add_crawler(Box::new(Partial))
+            .add_crawler(Box::new(Stub("clean", &[])));
+        let report = manager.run_with_report().await;
+        assert!(report.is_success(), "item errors do not fail the crawler");
+        let items: Vec<_> = report.outcomes[0]
+            .item_errors
+            .iter()
+            .map(|e| e.item.as_str())
+            .collect();
+        assert_eq!(items, ["item 1", "item 2"]);
+        assert!(report.outcomes[1].item_errors.is_empty());
+        assert!(manager.context.item_errors.take().is_empty());
+    }
+}
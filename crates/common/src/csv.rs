@@ -0,0 +1,211 @@
+//! RFC 4180 CSV for spreadsheet users. Rows are written straight to any
+//! `io::Write`, and serializable items become one row each with the same
+//! field names as their JSON output.
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Byte order mark some spreadsheet apps need to detect UTF-8.
+pub const UTF8_BOM: &str = "\u{feff}";
+
+/// Writes CSV records to `out`, one `\r\n`-terminated line each.
+pub struct CsvWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// A writer that starts the output with `UTF8_BOM`.
+    pub fn with_bom(mut out: W) -> io::Result<Self> {
+        out.write_all(UTF8_BOM.as_bytes())?;
+        Ok(Self { out })
+    }
+
+    pub fn write_record<I, S>(&mut self, fields: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for (i, field) in fields.into_iter().enumerate() {
+            if i > 0 {
+                self.out.write_all(b",")?;
+            }
+            self.out
+                .write_all(escape_field(field.as_ref()).as_bytes())?;
+        }
+        self.out.write_all(b"\r\n")
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// `field` quoted when it contains a comma, quote, line break or edge
+/// whitespace, with inner quotes doubled.
+pub fn escape_field(field: &str) -> Cow<'_, str> {
+    let needs_quotes = field.contains([',', '"', '\n', '\r'])
+        || field.starts_with(char::is_whitespace)
+        || field.ends_with(char::is_whitespace);
+    if needs_quotes {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Writes `items` as a header row plus one row per item. Columns are the
+/// items' top-level fields in serialization order (the union across items, so
+/// fields skipped when empty still get a column); nested values are written as
+/// compact JSON. Writes nothing but the optional BOM when `items` is empty;
+/// items that do not serialize to objects are an error.
+///
+/// The columns take a first pass over `items`; the second writes each row to
+/// `out` as soon as it is serialized, so only one row is held at a time.
+pub fn write_items<T: Serialize, W: Write>(out: W, items: &[T], bom: bool) -> io::Result<W> {
+    let mut columns: Vec<String> = Vec::new();
+    for item in items {
+        for (name, _) in Row::of(item)?.0 {
+            if !columns.contains(&name) {
+                columns.push(name);
+            }
+        }
+    }
+
+    let mut writer = if bom {
+        CsvWriter::with_bom(out)?
+    } else {
+        CsvWriter::new(out)
+    };
+    if items.is_empty() {
+        return Ok(writer.into_inner());
+    }
+    writer.write_record(&columns)?;
+    for item in items {
+        let row = Row::of(item)?;
+        writer.write_record(columns.iter().map(|column| {
+            row.0
+                .iter()
+                .find(|(name, _)| name == column)
+                .map(|(_, value)| cell(value))
+                .unwrap_or_default()
+        }))?;
+    }
+    Ok(writer.into_inner())
+}
+
+fn cell(value: &Value) -> Cow<'_, str> {
+    match value {
+        Value::Null => Cow::Borrowed(""),
+        Value::String(s) => Cow::Borrowed(s),
+        // serde_json prints numbers without grouping or locale separators.
+        Value::Number(n) => Cow::Owned(n.to_string()),
+        Value::Bool(b) => Cow::Borrowed(if *b { "true" } else { "false" }),
+        nested => Cow::Owned(nested.to_string()),
+    }
+}
+
+/// A JSON object's fields in document order (`serde_json::Map` sorts them).
+struct Row(Vec<(String, Value)>);
+
+impl Row {
+    fn of<T: Serialize>(item: &T) -> serde_json::Result<Self> {
+        serde_json::from_slice(&serde_json::to_vec(item)?)
+    }
+}
+
+impl<'de> Deserialize<'de> for Row {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RowVisitor;
+
+        impl<'de> Visitor<'de> for RowVisitor {
+            type Value = Row;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object per CSV row")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Row, A::Error> {
+                let mut fields = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    fields.push(entry);
+                }
+                Ok(Row(fields))
+            }
+        }
+
+        deserializer.deserialize_map(RowVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fields: &[&str]) -> String {
+        let mut writer = CsvWriter::new(Vec::new());
+        writer.write_record(fields).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn descriptions_with_commas_quotes_and_newlines_are_quoted() {
+        assert_eq!(record(&["plain", "1.5"]), "plain,1.5\r\n");
+        assert_eq!(record(&["a, b"]), "\"a, b\"\r\n");
+        assert_eq!(record(&["say \"hi\""]), "\"say \"\"hi\"\"\"\r\n");
+        assert_eq!(
+            record(&["line\nbreak", "cr\r"]),
+            "\"line\nbreak\",\"cr\r\"\r\n"
+        );
+        assert_eq!(record(&[" padded", ""]), "\" padded\",\r\n");
+        assert_eq!(record(&["日本語"]), "日本語\r\n");
+    }
+
+    #[derive(Serialize)]
+    struct Model {
+        name: String,
+        tokens: u64,
+        share: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        tags: Vec<&'static str>,
+    }
+
+    #[test]
+    fn items_become_rows_in_field_order() {
+        let items = vec![
+            Model {
+                name: "zeta".to_string(),
+                tokens: 1_234_567,
+                share: 0.25,
+                description: None,
+                tags: vec![],
+            },
+            Model {
+                name: "alpha".to_string(),
+                tokens: 42,
+                share: 1.0,
+                description: Some("Fast, \"cheap\"".to_string()),
+                tags: vec!["chat"],
+            },
+        ];
+        let csv = String::from_utf8(write_items(Vec::new(), &items, false).unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "name,tokens,share,tags,description\r\n\
+             zeta,1234567,0.25,[],\r\n\
+             alpha,42,1.0,\"[\"\"chat\"\"]\",\"Fast, \"\"cheap\"\"\"\r\n"
+        );
+
+        let bom = write_items(Vec::new(), &items[..0], true).unwrap();
+        assert_eq!(bom, UTF8_BOM.as_bytes());
+        assert!(write_items(Vec::new(), &[1, 2], false).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+//! Remembers which article URLs have already been published, so stories that
+//! resurface on later days can be marked or skipped. URLs are normalized
+//! first, and entries older than the TTL are pruned on load.
+
+use reqwest::Url;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use time::{Date, Duration, Month};
+use tracing::info;
+
+use crate::error::CrawlerResult;
+use crate::state::StateStore;
+
+const NAMESPACE: &str = "seen_urls";
+const KEY: &str = "urls";
+
+/// Query parameters that only track where a click came from.
+const NOISE_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "ref", "ref_src",
+    "source", "_hsenc", "_hsmi",
+];
+
+/// What a crawler does with stories whose URL it has published before.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SeenUrlsMode {
+    /// Publish everything without checking.
+    #[default]
+    Off,
+    /// Keep repeats but note when they were first seen.
+    Annotate,
+    /// Leave repeats out.
+    Drop,
+}
+
+impl FromStr for SeenUrlsMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "off" | "0" | "false" => Ok(Self::Off),
+            "annotate" => Ok(Self::Annotate),
+            "drop" => Ok(Self::Drop),
+            other => Err(format!(
+                "unknown mode '{}' (expected off, annotate or drop)",
+                other
+            )),
+        }
+    }
+}
+
+/// Result of `SeenUrls::check_and_mark`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeenStatus {
+    New,
+    /// Already published; the date it was first seen.
+    SeenOn(Date),
+}
+
+impl fmt::Display for SeenStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeenStatus::New => write!(f, "new"),
+            SeenStatus::SeenOn(date) => write!(f, "first seen on {}", date),
+        }
+    }
+}
+
+/// The normalized URLs seen within the TTL, with the day each was first seen.
+/// Shared by every crawler through the `seen_urls` state namespace.
+pub struct SeenUrls {
+    store: Arc<dyn StateStore>,
+    today: Date,
+    seen: BTreeMap<String, Date>,
+    marked: Map<String, Value>,
+    pruned: bool,
+    ttl: Duration,
+}
+
+impl SeenUrls {
+    /// Loads the URLs first seen less than `ttl_days` before `today`.
+    pub async fn load(
+        store: Arc<dyn StateStore>,
+        today: Date,
+        ttl_days: u32,
+    ) -> CrawlerResult<Self> {
+        let stored = store.get(NAMESPACE, KEY).await?;
+        let ttl = Duration::days(ttl_days.into());
+        let mut seen = BTreeMap::new();
+        let mut pruned = false;
+        if let Some(Value::Object(entries)) = stored {
+            for (url, date) in entries {
+                match date.as_str().and_then(parse_date) {
+                    Some(date) if today - date < ttl => {
+                        seen.insert(url, date);
+                    }
+                    _ => pruned = true,
+                }
+            }
+        }
+        Ok(Self {
+            store,
+            today,
+            seen,
+            marked: Map::new(),
+            pruned,
+            ttl,
+        })
+    }
+
+    /// Whether `url` was seen before, recording it as seen today if not.
+    pub fn check_and_mark(&mut self, url: &str) -> SeenStatus {
+        let status = self.check(url);
+        self.mark(url);
+        status
+    }
+
+    /// Whether `url` was seen before, without recording it.
+    pub fn check(&self, url: &str) -> SeenStatus {
+        match self.seen.get(&normalize_url(url)) {
+            Some(date) => SeenStatus::SeenOn(*date),
+            None => SeenStatus::New,
+        }
+    }
+
+    /// Records `url` as seen today unless it was seen earlier.
+    pub fn mark(&mut self, url: &str) {
+        let key = normalize_url(url);
+        if self.seen.contains_key(&key) {
+            return;
+        }
+        self.seen.insert(key.clone(), self.today);
+        self.marked
+            .insert(key, Value::String(self.today.to_string()));
+    }
+
+    /// Persists the URLs marked since `load`, rewriting the entry without
+    /// expired URLs when any were found.
+    pub async fn save(&self) -> CrawlerResult<()> {
+        if self.marked.is_empty() && !self.pruned {
+            return Ok(());
+        }
+        // Merge first so URLs other crawlers marked meanwhile survive the prune.
+        let merged = self
+            .store
+            .merge(NAMESPACE, KEY, Value::Object(self.marked.clone()))
+            .await?;
+        if self.pruned {
+            let Value::Object(entries) = merged else {
+                return Ok(());
+            };
+            let before = entries.len();
+            let kept: Map<String, Value> = entries
+                .into_iter()
+                .filter(|(_, date)| {
+                    date.as_str()
+                        .and_then(parse_date)
+                        .is_some_and(|date| self.today - date < self.ttl)
+                })
+                .collect();
+            info!("Pruned {} expired URL(s)", before - kept.len());
+            self.store.set(NAMESPACE, KEY, Value::Object(kept)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// `2024-05-10` as a `Date`.
+pub fn parse_date(raw: &str) -> Option<Date> {
+    let mut parts = raw.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = Month::try_from(parts.next()?.parse::<u8>().ok()?).ok()?;
+    let day = parts.next()?.parse().ok()?;
+    Date::from_calendar_date(year, month, day).ok()
+}
+
+/// The key two URLs for the same article share: host lowercased without
+/// `www.`, no scheme, default port, fragment or trailing slash, and query
+/// parameters sorted with `utm_*` and click-tracking ones removed. Strings
+/// that are not absolute URLs are only trimmed and lowercased.
+pub fn normalize_url(url: &str) -> String {
+    let trimmed = url.trim();
+    let Ok(parsed) = Url::parse(trimmed) else {
+        return trimmed.to_lowercase();
+    };
+    let Some(host) = parsed.host_str() else {
+        return trimmed.to_lowercase();
+    };
+
+    let mut key = host
+        .trim_start_matches("www.")
+        .trim_end_matches('.')
+        .to_string();
+    // `Url` already drops ports that are the scheme's default.
+    if let Some(port) = parsed.port() {
+        let default = match parsed.scheme() {
+            "http" => 80,
+            "https" => 443,
+            _ => 0,
+        };
+        if port != default {
+            key.push_str(&format!(":{}", port));
+        }
+    }
+    key.push_str(parsed.path().trim_end_matches('/'));
+
+    let mut params: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| {
+            let name = name.to_ascii_lowercase();
+            !name.starts_with("utm_") && !NOISE_PARAMS.contains(&name.as_str())
+        })
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if !params.is_empty() {
+        params.sort();
+        let query: Vec<String> = params
+            .iter()
+            .map(|(name, value)| {
+                if value.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}={}", name, value)
+                }
+            })
+            .collect();
+        key.push('?');
+        key.push_str(&query.join("&"));
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::SqliteStateStore;
+    use time::macros::date;
+
+    #[test]
+    fn normalization_ignores_presentation_noise() {
+        let same = [
+            "https://example.com/post",
+            "http://example.com/post",
+            "https://EXAMPLE.com/post/",
+            "https://www.example.com/post",
+            "https://example.com:443/post",
+            "http://example.com:80/post",
+            "https://example.com/post#comments",
+            "https://example.com/post?utm_source=hn&utm_medium=social",
+            "https://example.com/post?UTM_Campaign=x&fbclid=abc&ref=hn",
+            "  https://example.com/post  ",
+            "https://example.com./post",
+        ];
+        for url in same {
+            assert_eq!(normalize_url(url), "example.com/post", "{}", url);
+        }
+    }
+
+    #[test]
+    fn normalization_keeps_what_identifies_the_page() {
+        assert_eq!(normalize_url("https://example.com"), "example.com");
+        assert_eq!(normalize_url("https://example.com/"), "example.com");
+        assert_eq!(
+            normalize_url("https://example.com:8080/a"),
+            "example.com:8080/a"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/Post"),
+            "example.com/Post"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/watch?v=abc&t=10&utm_source=x"),
+            "example.com/watch?t=10&v=abc"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/a?b=2&a=1"),
+            normalize_url("https://example.com/a?a=1&b=2")
+        );
+        assert_eq!(
+            normalize_url("https://example.com/a?flag"),
+            "example.com/a?flag"
+        );
+        assert_ne!(
+            normalize_url("https://blog.example.com/a"),
+            normalize_url("https://example.com/a")
+        );
+        assert_eq!(normalize_url("Not A URL"), "not a url");
+        assert_eq!(
+            normalize_url("mailto:someone@example.com"),
+            "mailto:someone@example.com"
+        );
+    }
+
+    #[test]
+    fn modes_parse_from_config_values() {
+        assert_eq!("".parse(), Ok(SeenUrlsMode::Off));
+        assert_eq!(" Annotate ".parse(), Ok(SeenUrlsMode::Annotate));
+        assert_eq!("drop".parse(), Ok(SeenUrlsMode::Drop));
+        assert!("skip".parse::<SeenUrlsMode>().is_err());
+    }
+
+    /// A fresh SQLite store in the temp dir, removed on drop.
+    struct TempStore(std::path::PathBuf, Arc<dyn StateStore>);
+
+    impl TempStore {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "cution-dedup-{}-{}.db",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&path);
+            let store = Arc::new(SqliteStateStore::open(&path).unwrap());
+            Self(path, store)
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.0.display(), suffix));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn repeats_report_their_first_day_until_they_expire() {
+        let store = TempStore::new("ttl");
+        let mut day1 = SeenUrls::load(store.1.clone(), date!(2024 - 05 - 01), 7)
+            .await
+            .unwrap();
+        assert_eq!(
+            day1.check_and_mark("https://a.test/x?utm_source=hn"),
+            SeenStatus::New
+        );
+        assert_eq!(
+            day1.check_and_mark("https://a.test/x/"),
+            SeenStatus::SeenOn(date!(2024 - 05 - 01))
+        );
+        day1.save().await.unwrap();
+
+        let mut day3 = SeenUrls::load(store.1.clone(), date!(2024 - 05 - 03), 7)
+            .await
+            .unwrap();
+        assert_eq!(
+            day3.check_and_mark("http://www.a.test/x"),
+            SeenStatus::SeenOn(date!(2024 - 05 - 01))
+        );
+        assert_eq!(day3.check_and_mark("https://b.test/"), SeenStatus::New);
+        day3.save().await.unwrap();
+
+        let mut day9 = SeenUrls::load(store.1.clone(), date!(2024 - 05 - 09), 7)
+            .await
+            .unwrap();
+        assert!(day9.pruned);
+        assert_eq!(
+            day9.check_and_mark("https://b.test"),
+            SeenStatus::SeenOn(date!(2024 - 05 - 03))
+        );
+        day9.save().await.unwrap();
+        let stored = store.1.get(NAMESPACE, KEY).await.unwrap().unwrap();
+        assert_eq!(
+            stored,
+            serde_json::json!({"b.test": "2024-05-03"}),
+            "a.test/x expired"
+        );
+    }
+}
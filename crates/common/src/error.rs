@@ -4,27 +4,52 @@ use thiserror::Error;
 pub enum CrawlerError {
     #[error("HTTP request failed: {0}")]
     HttpRequest(#[from] reqwest::Error),
-    
+
     #[error("HTML parsing failed: {0}")]
     HtmlParse(String),
-    
+
     #[error("Storage upload failed: {0}")]
     StorageUpload(String),
-    
+
+    #[error("Object already exists: {0}")]
+    AlreadyExists(String),
+
     #[error("Configuration error: {0}")]
     Config(#[from] anyhow::Error),
-    
+
     #[error("Environment variable error: {0}")]
     EnvVar(String),
-    
+
     #[error("API error: {0}")]
     Api(String),
-    
+
     #[error("Parsing error: {0}")]
     Parse(String),
-    
+
+    #[error("HTTP {status} from {url}: {snippet}")]
+    HttpStatus {
+        url: String,
+        status: u16,
+        snippet: String,
+    },
+
+    #[error("Rate limited by {url}: {snippet}")]
+    RateLimited { url: String, snippet: String },
+
+    #[error("State store error: {0}")]
+    State(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Crawler panicked: {0}")]
+    Panicked(String),
+
+    #[error("LLM budget exceeded: {0}")]
+    BudgetExceeded(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
-pub type CrawlerResult<T> = Result<T, CrawlerError>;
\ No newline at end of file
+pub type CrawlerResult<T> = Result<T, CrawlerError>;
@@ -0,0 +1,258 @@
+//! RSS 2.0, RSS 1.0 (RDF) and Atom feeds parsed into one shape.
+
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::OffsetDateTime;
+
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::html::{self, TextOptions};
+use crate::xml::{self, Node};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feed {
+    pub title: Option<String>,
+    /// The site the feed belongs to.
+    pub link: Option<String>,
+    pub entries: Vec<FeedEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedEntry {
+    /// The entry's guid or id, falling back to its link or title.
+    pub id: String,
+    pub title: String,
+    pub url: Option<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub published: Option<OffsetDateTime>,
+    /// The full content, or else the description, as plain text.
+    pub content: Option<String>,
+}
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const RSS1_NS: &str = "http://purl.org/rss/1.0/";
+const CONTENT_NS: &str = "http://purl.org/rss/1.0/modules/content/";
+const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+const MEDIA_NS: &str = "http://search.yahoo.com/mrss/";
+
+/// Parses an RSS or Atom document.
+pub fn parse_feed(body: &str) -> CrawlerResult<Feed> {
+    let document = xml::parse(body)?;
+    let root = document.root_element();
+    match root.tag_name().name() {
+        "rss" => {
+            let channel = rss_child(root, "channel")
+                .ok_or_else(|| CrawlerError::Parse("RSS feed without a <channel>".to_string()))?;
+            Ok(rss_feed(Some(channel), rss_children(channel, "item")))
+        }
+        // RSS 1.0 keeps the items next to the channel.
+        "RDF" => Ok(rss_feed(
+            rss_child(root, "channel"),
+            rss_children(root, "item"),
+        )),
+        "feed" => Ok(atom_feed(root)),
+        other => Err(CrawlerError::Parse(format!(
+            "not an RSS or Atom feed (root element <{}>)",
+            other
+        ))),
+    }
+}
+
+/// RSS elements are unqualified in RSS 2.0 and in the RSS 1.0 namespace
+/// there; anything else, such as `atom:link`, is an extension.
+fn rss_children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    xml::children(node, name)
+        .filter(|child| matches!(child.tag_name().namespace(), None | Some(RSS1_NS)))
+}
+
+fn rss_child<'a, 'input>(node: Node<'a, 'input>, name: &'a str) -> Option<Node<'a, 'input>> {
+    rss_children(node, name).next()
+}
+
+fn rss_text(node: Node, name: &str) -> Option<String> {
+    rss_child(node, name)
+        .map(xml::text)
+        .filter(|text| !text.is_empty())
+}
+
+fn rss_feed<'a, 'input: 'a>(
+    channel: Option<Node<'a, 'input>>,
+    items: impl Iterator<Item = Node<'a, 'input>>,
+) -> Feed {
+    Feed {
+        title: channel.and_then(|channel| rss_text(channel, "title")),
+        link: channel.and_then(|channel| rss_text(channel, "link")),
+        entries: items
+            .map(|item| {
+                let url = rss_text(item, "link");
+                let title = rss_text(item, "title");
+                FeedEntry {
+                    id: rss_text(item, "guid")
+                        .or_else(|| item.attribute((RDF_NS, "about")).map(str::to_string))
+                        .or_else(|| url.clone())
+                        .or_else(|| title.clone())
+                        .unwrap_or_default(),
+                    title: title.unwrap_or_default(),
+                    published: rss_text(item, "pubDate")
+                        .or_else(|| xml::child_text(item, (DC_NS, "date")))
+                        .and_then(|raw| parse_date(&raw)),
+                    content: xml::child_text(item, (CONTENT_NS, "encoded"))
+                        .or_else(|| rss_text(item, "description"))
+                        .map(|raw| to_text(&raw))
+                        .filter(|text| !text.is_empty()),
+                    url,
+                }
+            })
+            .collect(),
+    }
+}
+
+fn atom_feed(feed: Node) -> Feed {
+    Feed {
+        title: xml::child_text(feed, "title"),
+        link: atom_link(feed),
+        entries: xml::children(feed, "entry")
+            .map(|entry| {
+                let url = atom_link(entry);
+                let title = xml::child_text(entry, "title");
+                FeedEntry {
+                    id: xml::child_text(entry, "id")
+                        .or_else(|| url.clone())
+                        .or_else(|| title.clone())
+                        .unwrap_or_default(),
+                    title: title.unwrap_or_default(),
+                    published: xml::child_text(entry, "published")
+                        .or_else(|| xml::child_text(entry, "updated"))
+                        .and_then(|raw| parse_date(&raw)),
+                    content: xml::child_text(entry, "content")
+                        .or_else(|| xml::child_text(entry, "summary"))
+                        // YouTube keeps the video description in Media RSS.
+                        .or_else(|| {
+                            xml::child(entry, (MEDIA_NS, "group"))
+                                .and_then(|group| xml::child_text(group, (MEDIA_NS, "description")))
+                        })
+                        .map(|raw| to_text(&raw))
+                        .filter(|text| !text.is_empty()),
+                    url,
+                }
+            })
+            .collect(),
+    }
+}
+
+/// The `alternate` link, or the first link without a `rel`.
+fn atom_link(element: Node) -> Option<String> {
+    xml::children(element, "link")
+        .find(|link| matches!(link.attribute("rel"), None | Some("alternate")))
+        .and_then(|link| link.attribute("href"))
+        .map(str::to_string)
+}
+
+fn to_text(raw: &str) -> String {
+    html::to_text(raw, &TextOptions::fragment())
+}
+
+/// RFC 2822 as RSS uses it, or RFC 3339 as Atom and Dublin Core do.
+pub fn parse_date(raw: &str) -> Option<OffsetDateTime> {
+    let raw = raw.trim();
+    OffsetDateTime::parse(raw, &Rfc2822)
+        .or_else(|_| OffsetDateTime::parse(raw, &Rfc3339))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn reads_rss_items() {
+        let feed = parse_feed(
+            r#"<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+<channel><title>Blog</title><link>https://blog.example</link>
+<item>
+  <title>Hello &amp; welcome</title>
+  <link>https://blog.example/hello</link>
+  <guid isPermaLink="false">post-1</guid>
+  <pubDate>Thu, 09 May 2024 14:00:00 GMT</pubDate>
+  <description>&lt;p&gt;Short&lt;/p&gt;</description>
+  <content:encoded><![CDATA[<p>The <em>full</em> post.</p>]]></content:encoded>
+</item>
+<item><title>Undated</title><link>https://blog.example/undated</link></item>
+</channel></rss>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Blog"));
+        assert_eq!(
+            feed.entries[0],
+            FeedEntry {
+                id: "post-1".to_string(),
+                title: "Hello & welcome".to_string(),
+                url: Some("https://blog.example/hello".to_string()),
+                published: Some(datetime!(2024-05-09 14:00 UTC)),
+                content: Some("The full post.".to_string()),
+            }
+        );
+        assert_eq!(feed.entries[1].id, "https://blog.example/undated");
+        assert_eq!(feed.entries[1].published, None);
+    }
+
+    #[test]
+    fn reads_atom_entries() {
+        let feed = parse_feed(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom"><title type="text">Notes</title>
+<link rel="self" href="https://notes.example/atom.xml"/><link href="https://notes.example/"/>
+<entry><id>tag:notes.example,2024:1</id><title>Atom entry</title>
+  <link rel="alternate" href="https://notes.example/1"/>
+  <updated>2024-05-09T08:30:00+02:00</updated>
+  <summary type="html">&lt;b&gt;Bold&lt;/b&gt; summary</summary>
+</entry></feed>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.link.as_deref(), Some("https://notes.example/"));
+        let entry = &feed.entries[0];
+        assert_eq!(entry.url.as_deref(), Some("https://notes.example/1"));
+        assert_eq!(entry.published, Some(datetime!(2024-05-09 06:30 UTC)));
+        assert_eq!(entry.content.as_deref(), Some("Bold summary"));
+        assert!(parse_feed("<html><body>nope</body></html>").is_err());
+    }
+
+    #[test]
+    fn reads_rdf_items_by_namespace() {
+        let feed = parse_feed(
+            r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+  xmlns="http://purl.org/rss/1.0/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+<channel rdf:about="https://papers.example/"><title>Papers</title></channel>
+<item rdf:about="https://papers.example/42"><title>A paper</title>
+  <link>https://papers.example/42</link><dc:date>2024-05-09T10:00:00Z</dc:date>
+</item></rdf:RDF>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Papers"));
+        assert_eq!(feed.entries[0].id, "https://papers.example/42");
+        assert_eq!(
+            feed.entries[0].published,
+            Some(datetime!(2024-05-09 10:00 UTC))
+        );
+    }
+
+    #[test]
+    fn reads_youtube_descriptions_from_media_groups() {
+        let feed = parse_feed(
+            r#"<feed xmlns:media="http://search.yahoo.com/mrss/" xmlns="http://www.w3.org/2005/Atom">
+<title>Channel</title>
+<entry><id>yt:video:abc</id><title>Talk</title>
+  <link rel="alternate" href="https://www.youtube.com/watch?v=abc"/>
+  <published>2024-05-09T17:00:00+00:00</published>
+  <media:group><media:title>Talk</media:title><media:description>Slides &amp; code below.</media:description></media:group>
+</entry></feed>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            feed.entries[0].content.as_deref(),
+            Some("Slides & code below.")
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Minimal client for Gemini's `generateContent` and `batchEmbedContents`
+//! endpoints.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::http::{parse_json, send_text, HttpClient};
+use crate::summarize::Summarizer;
+use crate::usage::{self, UsageTracker};
+
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+pub const DEFAULT_MODEL: &str = "gemini-2.0-flash";
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-004";
+
+/// Translates text into another language.
+#[async_trait]
+pub trait Translator: Send + Sync {
+    /// `language` is a code such as `ja`.
+    async fn translate(&self, text: &str, language: &str) -> CrawlerResult<String>;
+}
+
+#[derive(Debug, Clone)]
+pub struct GeminiClient {
+    client: HttpClient,
+    api_key: String,
+    model: String,
+    base_url: String,
+    usage: Arc<UsageTracker>,
+}
+
+#[derive(Deserialize)]
+struct GenerateResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    #[serde(default, rename = "usageMetadata")]
+    usage_metadata: Option<UsageMetadata>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
+}
+
+#[derive(Deserialize)]
+struct Candidate {
+    content: Option<Content>,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Deserialize)]
+struct Part {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct BatchEmbedResponse {
+    #[serde(default)]
+    embeddings: Vec<Embedding>,
+}
+
+#[derive(Deserialize)]
+struct Embedding {
+    #[serde(default)]
+    values: Vec<f32>,
+}
+
+impl GeminiClient {
+    pub fn new(client: HttpClient, api_key: &str) -> Self {
+        Self {
+            client,
+            api_key: api_key.to_string(),
+            model: DEFAULT_MODEL.to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            usage: usage::global(),
+        }
+    }
+
+    /// Reports token usage to `usage` instead of the process-wide tracker.
+    pub fn with_usage(mut self, usage: Arc<UsageTracker>) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    async fn post(&self, method: &str, body: &Value) -> CrawlerResult<(String, String)> {
+        self.usage.check_budget()?;
+        let url = format!("{}/models/{}:{}", self.base_url, self.model, method);
+        let request = self
+            .client
+            .inner()
+            .post(&url)
+            .header("x-goog-api-key", &self.api_key)
+            .header("Content-Type", "application/json")
+            .body(body.to_string());
+        let response = send_text(request, &url).await?;
+        Ok((url, response))
+    }
+
+    /// Sends `prompt` as a single user turn and returns the first candidate's text.
+    pub async fn generate(&self, prompt: &str) -> CrawlerResult<String> {
+        let body = json!({ "contents": [{ "role": "user", "parts": [{ "text": prompt }] }] });
+        let (url, response) = self.post("generateContent", &body).await?;
+        let response: GenerateResponse = parse_json(&url, &response)?;
+        let (input, output) = response
+            .usage_metadata
+            .as_ref()
+            .map_or((0, 0), |m| (m.prompt_token_count, m.candidates_token_count));
+        self.usage.record(&self.model, input, output);
+        response_text(&url, response)
+    }
+
+    /// One embedding per text, in order, from a single batch request. Use an
+    /// embedding model (see `with_model`).
+    pub async fn embed(&self, texts: &[String]) -> CrawlerResult<Vec<Vec<f32>>> {
+        let model = format!("models/{}", self.model);
+        let requests: Vec<Value> = texts
+            .iter()
+            .map(|text| json!({ "model": model, "content": { "parts": [{ "text": text }] } }))
+            .collect();
+        let (url, response) = self
+            .post("batchEmbedContents", &json!({ "requests": requests }))
+            .await?;
+        let response: BatchEmbedResponse = parse_json(&url, &response)?;
+        // The embedding endpoint reports no token counts; record the call.
+        self.usage.record(&self.model, 0, 0);
+        if response.embeddings.len() != texts.len() {
+            return Err(CrawlerError::Parse(format!(
+                "{} returned {} embedding(s) for {} text(s)",
+                url,
+                response.embeddings.len(),
+                texts.len()
+            )));
+        }
+        Ok(response.embeddings.into_iter().map(|e| e.values).collect())
+    }
+}
+
+fn response_text(url: &str, response: GenerateResponse) -> CrawlerResult<String> {
+    let text: String = response
+        .candidates
+        .into_iter()
+        .next()
+        .and_then(|candidate| candidate.content)
+        .map(|content| content.parts.into_iter().map(|part| part.text).collect())
+        .unwrap_or_default();
+    if text.trim().is_empty() {
+        return Err(CrawlerError::Parse(format!("{} returned no text", url)));
+    }
+    Ok(text)
+}
+
+#[async_trait]
+impl Translator for GeminiClient {
+    async fn translate(&self, text: &str, language: &str) -> CrawlerResult<String> {
+        let prompt = format!(
+            "Translate the following Markdown into the language with code '{}'. \
+             Keep the Markdown formatting, and copy every token of the form ⟦n⟧ \
+             exactly as it appears. Reply with the translation only.\n\n{}",
+            language, text
+        );
+        self.generate(&prompt).await
+    }
+}
+
+#[async_trait]
+impl Summarizer for GeminiClient {
+    async fn summarize(&self, title: &str, content: &str) -> CrawlerResult<String> {
+        let prompt = format!(
+            "Summarize the following post titled \"{}\" in two or three plain \
+             sentences. Reply with the summary only.\n\n{}",
+            title, content
+        );
+        self.generate(&prompt).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_first_candidate_text() {
+        let parse = |body: &str| parse_json::<GenerateResponse>("u", body).unwrap();
+        let body = r#"{"candidates":[{"content":{"parts":[{"text":"こんにちは"},{"text":"世界"}],"role":"model"}}]}"#;
+        assert_eq!(response_text("u", parse(body)).unwrap(), "こんにちは世界");
+        let blocked = r#"{"candidates":[],"promptFeedback":{"blockReason":"SAFETY"}}"#;
+        assert!(matches!(
+            response_text("u", parse(blocked)),
+            Err(CrawlerError::Parse(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn embedding_count_mismatch_is_an_error() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "embeddings": [{ "values": [0.5] }] })),
+            )
+            .mount(&server)
+            .await;
+        let client =
+            GeminiClient::new(HttpClient::new().unwrap(), "key").with_base_url(&server.uri());
+        let texts = vec!["a".to_string(), "b".to_string()];
+        assert!(matches!(
+            client.embed(&texts).await,
+            Err(CrawlerError::Parse(_))
+        ));
+    }
+}
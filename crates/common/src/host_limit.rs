@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+
+/// Requests in flight to a single host when `PER_HOST_CONCURRENCY` is unset.
+pub const DEFAULT_PER_HOST_CONCURRENCY: usize = 4;
+
+/// Caps the number of concurrent requests to each host, whichever crawler
+/// issues them. One semaphore is created lazily per host.
+#[derive(Debug)]
+pub struct HostLimiter {
+    default_limit: usize,
+    overrides: HashMap<String, usize>,
+    semaphores: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostLimiter {
+    pub fn new(default_limit: usize, overrides: HashMap<String, usize>) -> Self {
+        Self {
+            default_limit,
+            overrides,
+            semaphores: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The process-wide limiter used by `common::http`, configured from
+    /// `PER_HOST_CONCURRENCY` on first use.
+    pub fn global() -> &'static HostLimiter {
+        static GLOBAL: OnceLock<HostLimiter> = OnceLock::new();
+        GLOBAL.get_or_init(|| {
+            let (default_limit, overrides) = match env::var("PER_HOST_CONCURRENCY") {
+                Ok(raw) => parse_limits(&raw).unwrap_or_else(|e| {
+                    warn!("Ignoring invalid PER_HOST_CONCURRENCY: {}", e);
+                    (DEFAULT_PER_HOST_CONCURRENCY, HashMap::new())
+                }),
+                Err(_) => (DEFAULT_PER_HOST_CONCURRENCY, HashMap::new()),
+            };
+            HostLimiter::new(default_limit, overrides)
+        })
+    }
+
+    pub fn limit_for(&self, host: &str) -> usize {
+        self.overrides
+            .get(host)
+            .copied()
+            .unwrap_or(self.default_limit)
+    }
+
+    /// Waits for a free slot on `host`; the slot is released when the permit drops.
+    pub async fn acquire(&self, host: &str) -> OwnedSemaphorePermit {
+        let host = host.to_ascii_lowercase();
+        let semaphore = {
+            let mut semaphores = self.semaphores.lock().unwrap_or_else(|e| e.into_inner());
+            semaphores
+                .entry(host.clone())
+                .or_insert_with(|| Arc::new(Semaphore::new(self.limit_for(&host))))
+                .clone()
+        };
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("host semaphores are never closed")
+    }
+}
+
+/// Parses `PER_HOST_CONCURRENCY`: a default limit and/or `host=n` overrides,
+/// comma-separated, e.g. `4,github.com=2`.
+pub fn parse_limits(raw: &str) -> Result<(usize, HashMap<String, usize>), String> {
+    let parse_limit = |value: &str| {
+        value
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| format!("'{}' is not a positive integer", value.trim()))
+    };
+
+    let mut default_limit = DEFAULT_PER_HOST_CONCURRENCY;
+    let mut overrides = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match entry.split_once('=') {
+            Some((host, limit)) => {
+                overrides.insert(host.trim().to_ascii_lowercase(), parse_limit(limit)?);
+            }
+            None => default_limit = parse_limit(entry)?,
+        }
+    }
+    Ok((default_limit, overrides))
+}
+
+/// Host part of `url`, used as the limiter key.
+pub fn host_of(url: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(str::to_ascii_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_default_and_host_overrides() {
+        let (default_limit, overrides) = parse_limits("8, GitHub.com=2").unwrap();
+        assert_eq!(default_limit, 8);
+        assert_eq!(overrides.get("github.com"), Some(&2));
+        assert_eq!(
+            parse_limits("api.x.ai=1").unwrap().0,
+            DEFAULT_PER_HOST_CONCURRENCY
+        );
+        assert!(parse_limits("0").is_err());
+        assert!(parse_limits("github.com=many").is_err());
+    }
+
+    #[test]
+    fn host_of_extracts_lowercase_host() {
+        assert_eq!(
+            host_of("https://GitHub.com/trending/rust").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(host_of("not a url"), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limits_each_host_independently() {
+        let limiter = HostLimiter::new(1, HashMap::from([("github.com".to_string(), 2)]));
+        let wait = Duration::from_secs(1);
+
+        let _first = limiter.acquire("example.com").await;
+        assert!(tokio::time::timeout(wait, limiter.acquire("example.com"))
+            .await
+            .is_err());
+
+        let _a = limiter.acquire("github.com").await;
+        let _b = limiter.acquire("GITHUB.com").await;
+        assert!(tokio::time::timeout(wait, limiter.acquire("github.com"))
+            .await
+            .is_err());
+
+        drop(_first);
+        assert!(tokio::time::timeout(wait, limiter.acquire("example.com"))
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,386 @@
+//! Plain-text extraction from HTML pages and fragments.
+
+use scraper::{ElementRef, Html, Selector};
+
+/// How `to_text` parses its input and what it keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextOptions {
+    /// Parse a whole page and read only its `<body>`; otherwise parse a fragment.
+    pub document: bool,
+    /// Render links as `text (url)` instead of just their text.
+    pub preserve_links: bool,
+}
+
+impl TextOptions {
+    /// Options for a snippet such as an HN comment or story body.
+    pub fn fragment() -> Self {
+        Self::default()
+    }
+
+    /// Options for a complete page.
+    pub fn document() -> Self {
+        Self {
+            document: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_links(mut self) -> Self {
+        self.preserve_links = true;
+        self
+    }
+}
+
+/// Converts HTML to readable text: block elements become line or paragraph
+/// breaks, list items get markers, table cells are joined with ` | `,
+/// `<pre>` keeps its whitespace and script/style content is dropped. Entities
+/// are decoded by the parser.
+pub fn to_text(html: &str, options: &TextOptions) -> String {
+    let mut writer = Writer::new(options.preserve_links);
+    if options.document {
+        let document = Html::parse_document(html);
+        let body = Selector::parse("body")
+            .ok()
+            .and_then(|selector| document.select(&selector).next());
+        writer.children(body.unwrap_or_else(|| document.root_element()));
+    } else {
+        writer.children(Html::parse_fragment(html).root_element());
+    }
+    writer.finish()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Break {
+    None,
+    Line,
+    Paragraph,
+}
+
+struct Writer {
+    out: String,
+    pending: Break,
+    space: bool,
+    /// Written before the next text, e.g. a list marker or cell separator.
+    prefix: Option<String>,
+    lists: Vec<Option<usize>>,
+    in_pre: bool,
+    preserve_links: bool,
+}
+
+impl Writer {
+    fn new(preserve_links: bool) -> Self {
+        Self {
+            out: String::new(),
+            pending: Break::None,
+            space: false,
+            prefix: None,
+            lists: Vec::new(),
+            in_pre: false,
+            preserve_links,
+        }
+    }
+
+    fn request(&mut self, kind: Break) {
+        self.pending = self.pending.max(kind);
+        self.space = false;
+    }
+
+    /// Emits any pending break, prefix or space ahead of new text.
+    fn flush(&mut self) {
+        if !self.out.is_empty() {
+            match self.pending {
+                Break::Paragraph => self.out.push_str("\n\n"),
+                Break::Line => self.out.push('\n'),
+                Break::None if self.space && !self.out.ends_with([' ', '\n']) => self.out.push(' '),
+                Break::None => {}
+            }
+        }
+        self.pending = Break::None;
+        self.space = false;
+        if let Some(prefix) = self.prefix.take() {
+            self.out.push_str(&prefix);
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.in_pre {
+            if !text.is_empty() {
+                self.flush();
+                self.out.push_str(text);
+            }
+            return;
+        }
+        if text.starts_with(char::is_whitespace) {
+            self.space = true;
+        }
+        let mut words = text.split_whitespace().peekable();
+        if words.peek().is_none() {
+            return;
+        }
+        self.flush();
+        let mut first = true;
+        for word in words {
+            if !first {
+                self.out.push(' ');
+            }
+            self.out.push_str(word);
+            first = false;
+        }
+        if text.ends_with(char::is_whitespace) {
+            self.space = true;
+        }
+    }
+
+    fn children(&mut self, element: ElementRef) {
+        for child in element.children() {
+            if let Some(child) = ElementRef::wrap(child) {
+                self.element(child);
+            } else if let Some(text) = child.value().as_text() {
+                self.text(text);
+            }
+        }
+    }
+
+    fn element(&mut self, element: ElementRef) {
+        let name = element.value().name();
+        match name {
+            "script" | "style" | "noscript" | "template" | "head" | "title" => {}
+            "br" => self.request(Break::Line),
+            "hr" => self.request(Break::Paragraph),
+            "ul" | "ol" => {
+                self.request(if self.lists.is_empty() {
+                    Break::Paragraph
+                } else {
+                    Break::Line
+                });
+                self.lists.push((name == "ol").then_some(0));
+                self.children(element);
+                self.lists.pop();
+                self.request(if self.lists.is_empty() {
+                    Break::Paragraph
+                } else {
+                    Break::Line
+                });
+            }
+            "li" => {
+                self.request(Break::Line);
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", n)
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.prefix = Some(format!("{}{}", "  ".repeat(depth), marker));
+                self.children(element);
+                self.prefix = None;
+                self.request(Break::Line);
+            }
+            "tr" | "dt" | "dd" => {
+                self.request(Break::Line);
+                self.children(element);
+                self.request(Break::Line);
+            }
+            "td" | "th" => {
+                let first_cell = element
+                    .prev_siblings()
+                    .all(|s| ElementRef::wrap(s).is_none());
+                if !first_cell {
+                    self.space = false;
+                    self.prefix = Some(" | ".to_string());
+                }
+                self.children(element);
+            }
+            "pre" => {
+                self.request(Break::Paragraph);
+                self.in_pre = true;
+                self.children(element);
+                self.in_pre = false;
+                self.request(Break::Paragraph);
+            }
+            "a" if self.preserve_links => {
+                self.children(element);
+                if let Some(href) = element.value().attr("href") {
+                    let href = href.trim();
+                    let label: String = element.text().collect();
+                    if !href.is_empty()
+                        && !href.starts_with('#')
+                        && !href.starts_with("javascript:")
+                        && label.trim() != href
+                    {
+                        self.text(&format!(" ({})", href));
+                    }
+                }
+            }
+            _ if is_block(name) => {
+                self.request(Break::Paragraph);
+                self.children(element);
+                self.request(Break::Paragraph);
+            }
+            _ => self.children(element),
+        }
+    }
+
+    fn finish(self) -> String {
+        self.out
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_matches('\n')
+            .to_string()
+    }
+}
+
+/// Whether a scraped table row is a header: it holds `<th>` cells or sits in
+/// a `<thead>`.
+pub fn is_header_row(row: ElementRef) -> bool {
+    let in_thead = row
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|parent| parent.value().name() == "thead");
+    in_thead
+        || row
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .any(|cell| cell.value().name() == "th")
+}
+
+/// Whether a scraped name is really a column heading such as "Rank" or
+/// "Server Name".
+pub fn looks_like_header(name: &str) -> bool {
+    let name = name.trim().trim_end_matches(':').to_ascii_lowercase();
+    matches!(
+        name.as_str(),
+        "#" | "rank"
+            | "name"
+            | "server"
+            | "server name"
+            | "model"
+            | "model name"
+            | "description"
+            | "score"
+            | "stars"
+    )
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div"
+            | "section"
+            | "article"
+            | "header"
+            | "footer"
+            | "main"
+            | "nav"
+            | "aside"
+            | "blockquote"
+            | "figure"
+            | "figcaption"
+            | "table"
+            | "dl"
+            | "address"
+            | "form"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment(html: &str) -> String {
+        to_text(html, &TextOptions::fragment())
+    }
+
+    #[test]
+    fn paragraphs_and_line_breaks() {
+        assert_eq!(
+            fragment("First   para<p>Second\n  para</p><p>Third<br>line</p>"),
+            "First para\n\nSecond para\n\nThird\nline"
+        );
+        assert_eq!(fragment("<b>bold</b> and <i>italic</i>"), "bold and italic");
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(
+            fragment("Tom &amp; Jerry &lt;3 it&#x27;s &quot;fine&quot; &#8212; ok"),
+            "Tom & Jerry <3 it's \"fine\" — ok"
+        );
+    }
+
+    #[test]
+    fn drops_script_and_style() {
+        let html = "<html><head><title>T</title><style>p{}</style></head>\
+                    <body><script>alert(1)</script><p>Visible</p><noscript>nope</noscript></body></html>";
+        assert_eq!(to_text(html, &TextOptions::document()), "Visible");
+    }
+
+    #[test]
+    fn nested_lists_are_indented() {
+        let html = "<p>Steps:</p><ol><li>Install<ul><li>cargo</li><li>rustup</li></ul></li><li>Run</li></ol><p>Done</p>";
+        assert_eq!(
+            fragment(html),
+            "Steps:\n\n1. Install\n  - cargo\n  - rustup\n2. Run\n\nDone"
+        );
+    }
+
+    #[test]
+    fn tables_put_rows_on_lines() {
+        let html = "<table><tr><th>Name</th><th>Stars</th></tr>\
+                    <tr><td>ripgrep</td><td>45k</td></tr><tr><td>fd</td><td>33k</td></tr></table>";
+        assert_eq!(fragment(html), "Name | Stars\nripgrep | 45k\nfd | 33k");
+    }
+
+    #[test]
+    fn code_blocks_keep_whitespace() {
+        let html = "<p>Example:</p><pre><code>fn main() {\n    println!(\"&lt;hi&gt;\");\n}</code></pre><p>After</p>";
+        assert_eq!(
+            fragment(html),
+            "Example:\n\nfn main() {\n    println!(\"<hi>\");\n}\n\nAfter"
+        );
+    }
+
+    #[test]
+    fn links_optionally_keep_urls() {
+        let html = r##"See <a href="https://example.com/x">the docs</a>, <a href="https://a.test">https://a.test</a> or <a href="#top">top</a>."##;
+        assert_eq!(fragment(html), "See the docs, https://a.test or top.");
+        assert_eq!(
+            to_text(html, &TextOptions::fragment().with_links()),
+            "See the docs (https://example.com/x), https://a.test or top."
+        );
+    }
+
+    #[test]
+    fn document_mode_reads_body_only() {
+        let html = "<!DOCTYPE html><html><head><title>Title</title></head><body><h1>Heading</h1><div>Body text</div></body></html>";
+        assert_eq!(
+            to_text(html, &TextOptions::document()),
+            "Heading\n\nBody text"
+        );
+        assert_eq!(fragment(""), "");
+    }
+
+    #[test]
+    fn header_rows_are_recognised() {
+        let document = Html::parse_document(
+            "<table><thead><tr><td>Rank</td></tr></thead>\
+             <tbody><tr><th>Name</th></tr><tr><td>fd</td></tr></tbody></table>",
+        );
+        let rows = Selector::parse("tr").unwrap();
+        let headers: Vec<bool> = document.select(&rows).map(is_header_row).collect();
+        assert_eq!(headers, vec![true, true, false]);
+
+        assert!(looks_like_header(" Server Name: "));
+        assert!(looks_like_header("RANK"));
+        assert!(!looks_like_header("ranker"));
+    }
+}
@@ -0,0 +1,203 @@
+//! Skips uploads whose content is already in storage, using a per-day manifest
+//! of SHA-256 hashes at `{date}/.hashes.json`, so reruns on the same day do not
+//! create new object versions for identical files.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::publish::sha256_hex;
+use crate::supabase_client::SupabaseStorageClient;
+
+pub const MANIFEST_FILE: &str = ".hashes.json";
+
+/// What `upload_if_changed` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadStatus {
+    Written,
+    /// Skipped: the manifest already records this content for the path.
+    Unchanged,
+}
+
+/// File name → full hex SHA-256 of its last uploaded content.
+type Manifest = BTreeMap<String, String>;
+
+/// Uploads `content` to `path` unless the day's manifest shows the same bytes
+/// were uploaded there before, then records the new hash. Paths outside a
+/// `{YYYY-MM-DD}/` folder are always uploaded. A missing or unreadable
+/// manifest only means nothing is skipped.
+pub async fn upload_if_changed(
+    storage: &SupabaseStorageClient,
+    path: &str,
+    content: String,
+    content_type: &str,
+) -> CrawlerResult<UploadStatus> {
+    let Some((date, name)) = split_day(path) else {
+        upload(storage, path, content, content_type).await?;
+        return Ok(UploadStatus::Written);
+    };
+    let manifest_path = format!("{}/{}", date, MANIFEST_FILE);
+    let hash = sha256_hex(&content);
+    if load_manifest(storage, &manifest_path).await.get(name) == Some(&hash) {
+        info!("Skipped {} (unchanged)", path);
+        return Ok(UploadStatus::Unchanged);
+    }
+
+    upload(storage, path, content, content_type).await?;
+
+    // Crawlers publish concurrently; reread under the lock so no entry is lost.
+    let _guard = manifest_lock().lock().await;
+    let mut manifest = load_manifest(storage, &manifest_path).await;
+    manifest.insert(name.to_string(), hash);
+    let body = serde_json::to_string_pretty(&manifest).unwrap_or_default();
+    if let Err(e) = storage
+        .upload_file(&manifest_path, body, "application/json")
+        .await
+    {
+        warn!("Failed to update {}: {}", manifest_path, e);
+    }
+    Ok(UploadStatus::Written)
+}
+
+async fn upload(
+    storage: &SupabaseStorageClient,
+    path: &str,
+    content: String,
+    content_type: &str,
+) -> CrawlerResult<()> {
+    storage
+        .upload_file(path, content, content_type)
+        .await
+        .map_err(|e| CrawlerError::StorageUpload(e.to_string()))
+}
+
+fn manifest_lock() -> &'static tokio::sync::Mutex<()> {
+    static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+}
+
+async fn load_manifest(storage: &SupabaseStorageClient, path: &str) -> Manifest {
+    match storage.download_file(path).await {
+        Ok(body) => parse_manifest(path, body.as_deref()),
+        Err(e) => {
+            warn!("Failed to read {}, uploading without it: {}", path, e);
+            Manifest::new()
+        }
+    }
+}
+
+/// The manifest in `body`, keeping only string entries; anything that is not
+/// a JSON object counts as empty.
+fn parse_manifest(path: &str, body: Option<&str>) -> Manifest {
+    let Some(body) = body else {
+        return Manifest::new();
+    };
+    match serde_json::from_str::<Value>(body) {
+        Ok(Value::Object(entries)) => entries
+            .into_iter()
+            .filter_map(|(name, hash)| Some((name, hash.as_str()?.to_string())))
+            .collect(),
+        _ => {
+            warn!("Ignoring corrupt {}", path);
+            Manifest::new()
+        }
+    }
+}
+
+/// `2024-05-10/github-trending.md` → (`2024-05-10`, `github-trending.md`).
+fn split_day(path: &str) -> Option<(&str, &str)> {
+    let (date, name) = path.trim_start_matches('/').split_once('/')?;
+    let is_date = date.len() == 10
+        && date.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+    (is_date && !name.is_empty()).then_some((date, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempStorage(std::path::PathBuf);
+
+    impl TempStorage {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "cution-idempotency-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+
+        fn client(&self) -> SupabaseStorageClient {
+            SupabaseStorageClient::local(&self.0)
+        }
+    }
+
+    impl Drop for TempStorage {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn identical_content_is_skipped_and_changes_are_written() {
+        let dir = TempStorage::new("skip");
+        let storage = dir.client();
+        let path = "2024-05-10/hacker-news.md";
+
+        let first = upload_if_changed(&storage, path, "# v1".into(), "text/markdown").await;
+        assert_eq!(first.unwrap(), UploadStatus::Written);
+        let again = upload_if_changed(&storage, path, "# v1".into(), "text/markdown").await;
+        assert_eq!(again.unwrap(), UploadStatus::Unchanged);
+
+        let changed = upload_if_changed(&storage, path, "# v2".into(), "text/markdown").await;
+        assert_eq!(changed.unwrap(), UploadStatus::Written);
+        assert_eq!(
+            storage.download_file(path).await.unwrap().as_deref(),
+            Some("# v2")
+        );
+        let manifest = load_manifest(&storage, "2024-05-10/.hashes.json").await;
+        assert_eq!(manifest.get("hacker-news.md"), Some(&sha256_hex("# v2")));
+    }
+
+    #[tokio::test]
+    async fn corrupt_manifest_is_replaced() {
+        let dir = TempStorage::new("corrupt");
+        let storage = dir.client();
+        storage
+            .upload_file(
+                "2024-05-10/.hashes.json",
+                "{not json".into(),
+                "application/json",
+            )
+            .await
+            .unwrap();
+
+        let status =
+            upload_if_changed(&storage, "2024-05-10/a.md", "a".into(), "text/markdown").await;
+        assert_eq!(status.unwrap(), UploadStatus::Written);
+        let manifest = load_manifest(&storage, "2024-05-10/.hashes.json").await;
+        assert_eq!(manifest.len(), 1);
+    }
+
+    #[test]
+    fn only_dated_paths_have_a_manifest() {
+        assert_eq!(
+            split_day("2024-05-10/a/b.md"),
+            Some(("2024-05-10", "a/b.md"))
+        );
+        assert_eq!(split_day("index.html"), None);
+        assert_eq!(split_day("archive/2024/05.ndjson"), None);
+        assert_eq!(
+            parse_manifest("m", Some(r#"{"a.md": "ab", "b.md": 3}"#)),
+            Manifest::from([("a.md".to_string(), "ab".to_string())])
+        );
+        assert!(parse_manifest("m", Some("[]")).is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+//! The per-day `README.md` listing what a run published.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::crawler::RunReport;
+use crate::markdown::{self, Table};
+use crate::publish::SHORT_HASH_LEN;
+use crate::supabase_client::StorageObject;
+
+/// Name of the index file inside each `{date}/` folder.
+pub const INDEX_FILE: &str = "README.md";
+
+/// Splits `github-trending-latest.md` into (`github-trending`, `md`). Hashed
+/// archive copies return `None` in favour of the `-latest` file, as do
+/// dotfiles like the hash manifest, folders and other names without an extension.
+pub fn split_output_name(file: &str) -> Option<(&str, &str)> {
+    let (stem, ext) = file.rsplit_once('.')?;
+    if stem.is_empty() || stem.starts_with('.') {
+        return None;
+    }
+    if let Some(base) = stem.strip_suffix("-latest") {
+        return Some((base, ext));
+    }
+    if let Some((_, hash)) = stem.rsplit_once('-') {
+        if hash.len() == SHORT_HASH_LEN && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+    }
+    Some((stem, ext))
+}
+
+/// `github-trending` → `GitHub Trending`.
+pub fn display_title(stem: &str) -> String {
+    stem.split(['-', '_'])
+        .filter(|word| !word.is_empty())
+        .map(|word| match word {
+            "github" => "GitHub".to_string(),
+            "mcp" => "MCP".to_string(),
+            "openrouter" => "OpenRouter".to_string(),
+            "xai" => "xAI".to_string(),
+            _ => {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `1536` → `1.5 KB`.
+pub fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let size = bytes as f64;
+    if size < KB {
+        format!("{} B", bytes)
+    } else if size < KB * KB {
+        format!("{:.1} KB", size / KB)
+    } else {
+        format!("{:.1} MB", size / (KB * KB))
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1}s", duration.as_secs_f64())
+}
+
+/// The files under `{date}/` that the report says were uploaded, for when the
+/// folder cannot be listed. Sizes are unknown.
+pub fn objects_from_report(report: &RunReport, date: &str) -> Vec<StorageObject> {
+    let prefix = format!("{}/", date);
+    let mut names: Vec<&str> = report
+        .outcomes
+        .iter()
+        .flat_map(|outcome| &outcome.uploaded)
+        .filter_map(|path| path.strip_prefix(&prefix))
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| StorageObject {
+            name: name.to_string(),
+            size: None,
+            updated: None,
+        })
+        .collect()
+}
+
+/// Renders the day's index: a status table for the run followed by the
+/// published files grouped by source. Crawlers and files are sorted by name,
+/// so the same inputs always give the same document.
+pub fn build_index(objects: &[StorageObject], report: &RunReport) -> String {
+    let mut out = markdown::section(1, "Daily Index");
+    out.push_str("\n\n");
+
+    if !report.outcomes.is_empty() {
+        out.push_str(&markdown::section(2, "Run status"));
+        out.push_str("\n\n");
+        let mut outcomes: Vec<_> = report.outcomes.iter().collect();
+        outcomes.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut table = Table::new();
+        table
+            .add_column("Crawler")
+            .add_column("Status")
+            .add_column("Duration")
+            .add_column("Files");
+        for outcome in outcomes {
+            let status = match &outcome.error {
+                None => "ok".to_string(),
+                Some(error) => format!("failed: {}", error),
+            };
+            table.add_row([
+                outcome.name.clone(),
+                status,
+                format_duration(outcome.duration),
+                match outcome.unchanged.len() {
+                    0 => outcome.uploaded.len().to_string(),
+                    n => format!("{} ({} unchanged)", outcome.uploaded.len(), n),
+                },
+            ]);
+        }
+        out.push_str(&table.render());
+        out.push('\n');
+    }
+
+    let mut sources: BTreeMap<&str, Vec<&StorageObject>> = BTreeMap::new();
+    for object in objects {
+        if object.name == INDEX_FILE {
+            continue;
+        }
+        if let Some((stem, _)) = split_output_name(&object.name) {
+            sources.entry(stem).or_default().push(object);
+        }
+    }
+
+    out.push_str(&markdown::section(2, "Files"));
+    out.push_str("\n\n");
+    if sources.is_empty() {
+        out.push_str("No files were published.\n");
+        return out;
+    }
+    for (stem, mut files) in sources {
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        out.push_str(&markdown::section(3, &display_title(stem)));
+        out.push_str("\n\n");
+        for file in files {
+            out.push_str("- ");
+            out.push_str(&markdown::link(&file.name, &file.name));
+            if let Some(size) = file.size {
+                out.push_str(&format!(" ({})", format_size(size)));
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::CrawlerOutcome;
+
+    fn object(name: &str, size: Option<u64>) -> StorageObject {
+        StorageObject {
+            name: name.to_string(),
+            size,
+            updated: None,
+        }
+    }
+
+    fn report() -> RunReport {
+        RunReport {
+            outcomes: vec![
+                CrawlerOutcome {
+                    name: "OpenRouter".to_string(),
+                    duration: Duration::from_millis(2500),
+                    error: Some("timeout".to_string()),
+                    uploaded: vec![],
+                    unchanged: vec![],
+                    checksums: vec![],
+                    item_errors: vec![],
+                },
+                CrawlerOutcome {
+                    name: "GitHub Trending".to_string(),
+                    duration: Duration::from_millis(12_340),
+                    error: None,
+                    uploaded: vec![
+                        "2024-05-10/github-trending.md".to_string(),
+                        "2024-05-10/github-trending.json".to_string(),
+                    ],
+                    unchanged: vec!["2024-05-10/github-trending.json".to_string()],
+                    checksums: vec![],
+                    item_errors: vec![],
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn index_snapshot() {
+        let objects = vec![
+            object("hacker-news.md", Some(20_480)),
+            object("github-trending.md", Some(512)),
+            object("github-trending-0123456789ab.md", Some(512)),
+            object("README.md", Some(100)),
+            object(".hashes.json", Some(200)),
+            object("rust", None),
+            object("github-trending.json", Some(3 * 1024 * 1024)),
+        ];
+        let expected = "\
+# Daily Index
+
+## Run status
+
+| Crawler         | Status          | Duration | Files           |
+| --------------- | --------------- | -------- | --------------- |
+| GitHub Trending | ok              | 12.3s    | 2 (1 unchanged) |
+| OpenRouter      | failed: timeout | 2.5s     | 0               |
+
+## Files
+
+### GitHub Trending
+
+- [github-trending.json](github-trending.json) (3.0 MB)
+- [github-trending.md](github-trending.md) (512 B)
+
+### Hacker News
+
+- [hacker-news.md](hacker-news.md) (20.0 KB)
+";
+        assert_eq!(build_index(&objects, &report()), expected);
+
+        let mut shuffled = objects.clone();
+        shuffled.reverse();
+        assert_eq!(build_index(&shuffled, &report()), expected);
+    }
+
+    #[test]
+    fn falls_back_to_uploaded_paths() {
+        let mut report = report();
+        report.outcomes[1]
+            .uploaded
+            .push("2024-05-09/github-trending.md".to_string());
+        let objects = objects_from_report(&report, "2024-05-10");
+        assert_eq!(
+            objects,
+            vec![
+                object("github-trending.json", None),
+                object("github-trending.md", None)
+            ]
+        );
+        let index = build_index(&objects, &report);
+        assert!(index.ends_with(
+            "### GitHub Trending\n\n\
+             - [github-trending.json](github-trending.json)\n\
+             - [github-trending.md](github-trending.md)\n"
+        ));
+        assert!(build_index(&[], &RunReport::default())
+            .ends_with("## Files\n\nNo files were published.\n"));
+    }
+
+    #[test]
+    fn sizes_are_human_readable() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+}
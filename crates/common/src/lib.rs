@@ -1,9 +1,51 @@
+pub mod archive;
+pub mod audit;
 pub mod config;
+pub mod context;
 pub mod crawler;
+pub mod csv;
+pub mod dedup;
 pub mod error;
+pub mod feed;
+pub mod gemini;
+pub mod host_limit;
+pub mod html;
+pub mod http;
+pub mod idempotency;
+pub mod index;
+pub mod logging;
+pub mod markdown;
+pub mod output;
+pub mod partial;
+pub mod progress;
+pub mod publish;
+pub mod ranking;
+pub mod rate_limit;
+pub mod registry;
+pub mod relative_time;
+pub mod render;
+pub mod retry;
+pub mod state;
+pub mod summarize;
+pub mod summary_cache;
 pub mod supabase_client;
+pub mod supabase_table;
+pub mod usage;
+pub mod xml;
 
 pub use config::Config;
-pub use crawler::{Crawler, CrawlerManager, DataSource};
+pub use context::{Clock, CrawlerContext, FixedClock, ItemError, ItemErrors, SystemClock};
+pub use crawler::{Crawler, CrawlerManager, CrawlerOutcome, DataSource, RunReport};
 pub use error::{CrawlerError, CrawlerResult};
-pub use supabase_client::SupabaseStorageClient;
+pub use gemini::{GeminiClient, Translator};
+pub use http::{ClientFactory, HttpClient};
+pub use logging::init_tracing;
+pub use output::OutputFormat;
+pub use progress::{NoopProgress, ProgressEvent, ProgressSink};
+pub use publish::{PublishOptions, Publisher};
+pub use rate_limit::{RateLimitedClient, RateLimiter};
+pub use retry::{retry_async, retry_async_with_delay, RetryPolicy};
+pub use state::StateStore;
+pub use summarize::{summarize_long, Summarizable, Summarizer, TruncatingSummarizer};
+pub use supabase_client::{StorageObject, SupabaseStorageClient};
+pub use supabase_table::SupabaseTableClient;
@@ -0,0 +1,12 @@
+use anyhow::Result;
+use tracing::Level;
+use tracing_subscriber::FmtSubscriber;
+
+/// Installs the INFO-level stdout subscriber shared by every binary.
+pub fn init_tracing() -> Result<()> {
+    let subscriber = FmtSubscriber::builder()
+        .with_max_level(Level::INFO)
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+    Ok(())
+}
@@ -0,0 +1,346 @@
+//! Small helpers for building the markdown documents crawlers upload.
+
+/// Characters that end a table cell or a link's text early, and the backslash
+/// that escapes them. Other punctuation is left alone so names such as
+/// `snake_case` and `C++` read as written.
+const SPECIAL: &[char] = &['\\', '[', ']', '|'];
+
+/// Escapes `text` for use inline, in a table cell or as link text: `SPECIAL`
+/// characters are backslash-escaped and line breaks collapse to a single space
+/// so the text stays on one line.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    if let Some(first) = lines.next() {
+        push_escaped(&mut out, first);
+    }
+    for line in lines {
+        out.push(' ');
+        push_escaped(&mut out, line);
+    }
+    out
+}
+
+fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if SPECIAL.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// A heading of `level` (clamped to 1..=6), without a trailing newline.
+pub fn section(level: usize, title: &str) -> String {
+    format!("{} {}", "#".repeat(level.clamp(1, 6)), escape(title))
+}
+
+/// A level-1 `title` over a level-2 section per `(heading, entries)`, each
+/// section's entries joined by `separator`.
+pub fn sectioned<H: AsRef<str>>(
+    title: &str,
+    sections: impl IntoIterator<Item = (H, Vec<String>)>,
+    separator: &str,
+) -> String {
+    let mut markdown = section(1, title);
+    for (heading, entries) in sections {
+        markdown.push_str("\n\n");
+        markdown.push_str(&section(2, heading.as_ref()));
+        if !entries.is_empty() {
+            markdown.push_str("\n\n");
+            markdown.push_str(&entries.join(separator));
+        }
+    }
+    markdown
+}
+
+/// `entries` one after another, separated by `---` rules.
+pub fn rule_separated(entries: impl IntoIterator<Item = String>) -> String {
+    entries.into_iter().collect::<Vec<_>>().join("\n\n---\n\n")
+}
+
+/// An inline link. The text is escaped; spaces and parentheses in the URL are
+/// percent-encoded so they cannot end the link early.
+pub fn link(text: &str, url: &str) -> String {
+    let mut target = String::with_capacity(url.len());
+    for c in url.trim().chars() {
+        match c {
+            ' ' => target.push_str("%20"),
+            '(' => target.push_str("%28"),
+            ')' => target.push_str("%29"),
+            '<' => target.push_str("%3C"),
+            '>' => target.push_str("%3E"),
+            _ => target.push(c),
+        }
+    }
+    format!("[{}]({})", escape(text), target)
+}
+
+/// The first `max_chars` characters of `text`, cut back to a word boundary and
+/// ended with `…` when anything was dropped.
+pub fn excerpt(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    format!("{}…", cut.trim_end())
+}
+
+/// A GitHub-flavoured table with escaped cells, padded so columns line up.
+/// Rows are cut or filled with empty cells to match `headers`.
+pub fn table<S: AsRef<str>>(headers: &[&str], rows: &[Vec<S>]) -> String {
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().map(|cell| escape(cell.as_ref())).collect())
+        .collect();
+    layout(headers, &rows)
+}
+
+/// Lays out already escaped `rows` under `headers`.
+fn layout(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let header_cells: Vec<String> = headers.iter().map(|h| escape(h)).collect();
+    let body: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            (0..headers.len())
+                .map(|i| row.get(i).cloned().unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            body.iter()
+                .map(|row| row[i].chars().count())
+                .chain([header_cells[i].chars().count(), 3])
+                .max()
+                .unwrap_or(3)
+        })
+        .collect();
+
+    let mut out = String::new();
+    push_row(&mut out, &header_cells, &widths);
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    push_row(&mut out, &rule, &widths);
+    for row in &body {
+        push_row(&mut out, row, &widths);
+    }
+    out
+}
+
+/// Builds a table column by column and row by row; see `table` for the output.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_column(&mut self, name: impl Into<String>) -> &mut Self {
+        self.columns.push(name.into());
+        self
+    }
+
+    /// Appends a row. Missing cells render empty and extra cells are dropped.
+    pub fn add_row<I>(&mut self, values: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: ToString,
+    {
+        self.rows.push(
+            values
+                .into_iter()
+                .map(|value| escape(&value.to_string()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Appends a row of cells that are already markdown, such as `link`s.
+    /// They are not escaped, so plain text in them should go through `escape`.
+    pub fn add_markdown_row<I>(&mut self, cells: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn render(&self) -> String {
+        let headers: Vec<&str> = self.columns.iter().map(String::as_str).collect();
+        layout(&headers, &self.rows)
+    }
+}
+
+fn push_row(out: &mut String, cells: &[String], widths: &[usize]) {
+    out.push('|');
+    for (cell, width) in cells.iter().zip(widths) {
+        let padding = width.saturating_sub(cell.chars().count());
+        out.push(' ');
+        out.push_str(cell);
+        out.push_str(&" ".repeat(padding));
+        out.push_str(" |");
+    }
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rule_separated_puts_rules_between_entries() {
+        assert_eq!(
+            rule_separated(["a".to_string(), "b".to_string()]),
+            "a\n\n---\n\nb"
+        );
+        assert_eq!(rule_separated(Vec::new()), "");
+    }
+
+    #[test]
+    fn excerpt_cuts_at_a_word_boundary() {
+        assert_eq!(excerpt("short text", 20), "short text");
+        assert_eq!(excerpt("one two three four", 10), "one two…");
+    }
+
+    #[test]
+    fn escape_leaves_plain_text_alone() {
+        assert_eq!(
+            escape("rust-lang/rust 1.80: faster builds!"),
+            "rust-lang/rust 1.80: faster builds!"
+        );
+        assert_eq!(escape(""), "");
+    }
+
+    #[test]
+    fn escape_handles_pipes_and_brackets_only() {
+        assert_eq!(escape("a | b"), "a \\| b");
+        assert_eq!(escape("[1] see [x](y)"), "\\[1\\] see \\[x\\](y)");
+        assert_eq!(escape("C:\\path"), "C:\\\\path");
+        assert_eq!(
+            escape("snake_case *C++* `code` <T>"),
+            "snake_case *C++* `code` <T>"
+        );
+    }
+
+    #[test]
+    fn escape_collapses_newlines() {
+        assert_eq!(
+            escape("first line\r\n\n  second line\n"),
+            "first line second line"
+        );
+    }
+
+    #[test]
+    fn section_clamps_level_and_escapes_title() {
+        assert_eq!(section(1, "MCP Server Rankings"), "# MCP Server Rankings");
+        assert_eq!(section(0, "Top"), "# Top");
+        assert_eq!(section(9, "Deep"), "###### Deep");
+        assert_eq!(section(2, "a|b\nc"), "## a\\|b c");
+    }
+
+    #[test]
+    fn sectioned_joins_entries_under_each_heading() {
+        let sections = [
+            ("r/rust", vec!["one".to_string(), "two".to_string()]),
+            ("r/empty", Vec::new()),
+        ];
+        assert_eq!(
+            sectioned("Digest", sections, "\n\n---\n\n"),
+            "# Digest\n\n## r/rust\n\none\n\n---\n\ntwo\n\n## r/empty"
+        );
+    }
+
+    #[test]
+    fn link_escapes_brackets_in_text_and_encodes_url() {
+        assert_eq!(
+            link("View Link", "https://example.com/a"),
+            "[View Link](https://example.com/a)"
+        );
+        assert_eq!(
+            link("[RFC] the ]] case", "https://example.com/a b"),
+            "[\\[RFC\\] the \\]\\] case](https://example.com/a%20b)"
+        );
+        assert_eq!(
+            link(
+                "wiki",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+            ),
+            "[wiki](https://en.wikipedia.org/wiki/Rust_%28programming_language%29)"
+        );
+    }
+
+    #[test]
+    fn table_pads_columns() {
+        let rows = vec![vec!["1", "openai/gpt-4o"], vec!["10", "x"]];
+        assert_eq!(
+            table(&["Rank", "Model"], &rows),
+            "| Rank | Model         |\n\
+             | ---- | ------------- |\n\
+             | 1    | openai/gpt-4o |\n\
+             | 10   | x             |\n"
+        );
+    }
+
+    #[test]
+    fn table_escapes_cells_and_normalizes_row_length() {
+        let rows = vec![
+            vec![
+                "a|b".to_string(),
+                "line\nbreak".to_string(),
+                "extra".to_string(),
+            ],
+            vec!["only".to_string()],
+        ];
+        assert_eq!(
+            table(&["A", "B"], &rows),
+            "| A    | B          |\n\
+             | ---- | ---------- |\n\
+             | a\\|b | line break |\n\
+             | only |            |\n"
+        );
+    }
+
+    #[test]
+    fn table_builder_matches_table() {
+        let mut builder = Table::new();
+        builder.add_column("Rank").add_column("Name | alias");
+        builder.add_row([1.to_string(), "a|b".to_string()]);
+        builder.add_row(vec!["2"]);
+        assert_eq!(builder.len(), 2);
+        assert_eq!(
+            builder.render(),
+            table(&["Rank", "Name | alias"], &[vec!["1", "a|b"], vec!["2"]])
+        );
+        assert!(builder.render().starts_with("| Rank | Name \\| alias |\n"));
+    }
+
+    #[test]
+    fn markdown_rows_keep_their_links() {
+        let mut builder = Table::new();
+        builder.add_column("Name").add_column("Note");
+        builder.add_markdown_row([link("a|b", "https://x.test/a"), escape("c|d")]);
+        assert!(builder
+            .render()
+            .contains("| [a\\|b](https://x.test/a) | c\\|d |"));
+    }
+
+    #[test]
+    fn table_without_rows_keeps_header() {
+        assert_eq!(table::<&str>(&["Name"], &[]), "| Name |\n| ---- |\n");
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::info;
+
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::publish::suffixed_path;
+use crate::supabase_client::SupabaseStorageClient;
+
+/// File formats a crawler can publish its results in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    Markdown,
+    Json,
+    /// One compact JSON object per line.
+    Ndjson,
+    /// The markdown rendered as a standalone page.
+    Html,
+    /// One row per item, columns named like the JSON fields.
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Markdown => "md",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Html => "html",
+            OutputFormat::Csv => "csv",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Markdown => "text/markdown",
+            OutputFormat::Json => "application/json",
+            OutputFormat::Ndjson => "application/x-ndjson",
+            OutputFormat::Html => "text/html; charset=utf-8",
+            OutputFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            "html" | "htm" => Ok(OutputFormat::Html),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!(
+                "unknown output format '{}' (expected markdown, json, ndjson, html or csv)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Markdown => write!(f, "markdown"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Ndjson => write!(f, "ndjson"),
+            OutputFormat::Html => write!(f, "html"),
+            OutputFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+/// Serializes `items` as newline-delimited JSON, one compact object per line.
+pub fn to_ndjson<T: Serialize>(items: &[T]) -> serde_json::Result<String> {
+    let mut out = String::new();
+    for item in items {
+        out.push_str(&serde_json::to_string(item)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Parses a published JSON array or NDJSON body back into items.
+pub fn parse_items<T: DeserializeOwned>(
+    path: &str,
+    body: &str,
+    format: OutputFormat,
+) -> CrawlerResult<Vec<T>> {
+    match format {
+        OutputFormat::Ndjson => body
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| crate::http::parse_json(path, line))
+            .collect(),
+        _ => crate::http::parse_json(path, body),
+    }
+}
+
+/// Loads `{stem}.json` (or its `-latest` copy in archive mode), falling back
+/// to NDJSON. `None` means nothing structured was published under `stem`.
+pub async fn load_items<T: DeserializeOwned>(
+    storage: &SupabaseStorageClient,
+    stem: &str,
+) -> CrawlerResult<Option<Vec<T>>> {
+    for format in [OutputFormat::Json, OutputFormat::Ndjson] {
+        let path = format!("{}.{}", stem, format.extension());
+        for candidate in [suffixed_path(&path, "latest"), path] {
+            let body = storage
+                .download_file(&candidate)
+                .await
+                .map_err(|e| CrawlerError::Api(e.to_string()))?;
+            if let Some(body) = body {
+                info!("Reading {}", candidate);
+                return parse_items(&candidate, &body, format).map(Some);
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Loads `{stem}.md`, preferring its `-latest` copy in archive mode. `None`
+/// means no markdown was published under `stem`.
+pub async fn load_markdown(
+    storage: &SupabaseStorageClient,
+    stem: &str,
+) -> CrawlerResult<Option<String>> {
+    let path = format!("{}.{}", stem, OutputFormat::Markdown.extension());
+    for candidate in [suffixed_path(&path, "latest"), path] {
+        let body = storage
+            .download_file(&candidate)
+            .await
+            .map_err(|e| CrawlerError::Api(e.to_string()))?;
+        if body.is_some() {
+            info!("Reading {}", candidate);
+            return Ok(body);
+        }
+    }
+    Ok(None)
+}
+
+/// Parses a comma-separated format list such as `markdown,json`, dropping duplicates.
+pub fn parse_formats(raw: &str) -> Result<Vec<OutputFormat>, String> {
+    let mut formats = Vec::new();
+    for part in raw.split(',').filter(|p| !p.trim().is_empty()) {
+        let format = part.parse::<OutputFormat>()?;
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    if formats.is_empty() {
+        return Err("at least one output format is required".to_string());
+    }
+    Ok(formats)
+}
+
+/// Parses per-crawler overrides like `openrouter=json;hacker_news=markdown,json`.
+pub fn parse_crawler_formats(raw: &str) -> Result<HashMap<String, Vec<OutputFormat>>, String> {
+    let mut overrides = HashMap::new();
+    for entry in raw.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+        let (crawler, formats) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected 'crawler=formats', got '{}'", entry))?;
+        overrides.insert(crawler.trim().to_string(), parse_formats(formats)?);
+    }
+    Ok(overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_formats_accepts_aliases_and_dedups() {
+        assert_eq!(
+            parse_formats("md, JSON,markdown").unwrap(),
+            vec![OutputFormat::Markdown, OutputFormat::Json]
+        );
+        assert!(parse_formats("xml").is_err());
+        assert!(parse_formats(" , ").is_err());
+    }
+
+    #[test]
+    fn ndjson_writes_one_compact_object_per_line() {
+        #[derive(Serialize)]
+        struct Story {
+            title: &'static str,
+            summary: Option<&'static str>,
+        }
+        let stories = vec![
+            Story {
+                title: "Rust 2.0",
+                summary: Some("line one\nline two"),
+            },
+            Story {
+                title: "Ask HN",
+                summary: None,
+            },
+        ];
+        let ndjson = to_ndjson(&stories).unwrap();
+        let lines: Vec<&str> = ndjson.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"title":"Rust 2.0","summary":"line one\nline two"}"#,
+                r#"{"title":"Ask HN","summary":null}"#,
+            ]
+        );
+        assert!(ndjson.ends_with('\n'));
+        assert_eq!("jsonl".parse::<OutputFormat>(), Ok(OutputFormat::Ndjson));
+    }
+
+    #[test]
+    fn parse_crawler_formats_reads_overrides() {
+        let overrides =
+            parse_crawler_formats("openrouter=json; hacker_news=markdown,json").unwrap();
+        assert_eq!(overrides["openrouter"], vec![OutputFormat::Json]);
+        assert_eq!(
+            overrides["hacker_news"],
+            vec![OutputFormat::Markdown, OutputFormat::Json]
+        );
+        assert!(parse_crawler_formats("openrouter").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Uploads of a crawler's output while it is still running, so a crash late
+//! in a long run leaves what was done so far in `{stem}.partial.md` instead
+//! of nothing.
+
+use tracing::{info, warn};
+
+use crate::supabase_client::SupabaseStorageClient;
+
+/// Line a crawler puts at the top of its output when it found an earlier
+/// run's partial.
+pub const RECOVERED_NOTE: &str =
+    "> *Recovered partial from earlier run: a previous run today did not finish.*";
+
+/// `2024-05-10/hacker-news.partial.md` for the stem `2024-05-10/hacker-news`.
+pub fn partial_path(stem: &str) -> String {
+    format!("{}.partial.md", stem)
+}
+
+/// Keeps `{stem}.partial.md` current as items complete, uploading at most once
+/// every `every` items. Dropping it before `promote` uploads anything still
+/// pending (on a spawned task, so only while the runtime is alive).
+pub struct PartialUploader {
+    storage: SupabaseStorageClient,
+    path: String,
+    every: usize,
+    /// Items completed since the last upload.
+    pending: usize,
+    latest: String,
+    /// Whether storage may hold the partial, from this run or an earlier one.
+    exists: bool,
+    promoted: bool,
+}
+
+impl PartialUploader {
+    pub fn new(storage: SupabaseStorageClient, stem: &str, every: usize) -> Self {
+        Self {
+            storage,
+            path: partial_path(stem),
+            every: every.max(1),
+            pending: 0,
+            latest: String::new(),
+            exists: false,
+            promoted: false,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The partial an earlier run left behind, if any.
+    pub async fn recover(&mut self) -> Option<String> {
+        match self.storage.download_file(&self.path).await {
+            Ok(Some(partial)) => {
+                self.exists = true;
+                warn!(
+                    "Found {} from an earlier run that did not finish",
+                    self.path
+                );
+                Some(partial)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Could not check for {}: {}", self.path, e);
+                None
+            }
+        }
+    }
+
+    /// Records one more completed item and `markdown`, the output so far.
+    /// Upload failures are logged: the partial is a fallback, not an output.
+    pub async fn item_done(&mut self, markdown: String) {
+        self.latest = markdown;
+        self.pending += 1;
+        if self.pending >= self.every {
+            self.flush().await;
+        }
+    }
+
+    async fn flush(&mut self) {
+        match upload(&self.storage, &self.path, self.latest.clone()).await {
+            Ok(()) => {
+                self.pending = 0;
+                self.exists = true;
+            }
+            Err(e) => warn!("Failed to upload {}: {}", self.path, e),
+        }
+    }
+
+    /// Deletes the partial once the final output has been published under its
+    /// real name.
+    pub async fn promote(mut self) {
+        self.promoted = true;
+        if !self.exists {
+            return;
+        }
+        match self.storage.delete_files(&[self.path.clone()]).await {
+            Ok(()) => info!("Replaced {} with the final output", self.path),
+            Err(e) => warn!("Failed to delete {}: {}", self.path, e),
+        }
+    }
+}
+
+async fn upload(
+    storage: &SupabaseStorageClient,
+    path: &str,
+    markdown: String,
+) -> anyhow::Result<()> {
+    storage.upload_file(path, markdown, "text/markdown").await
+}
+
+impl Drop for PartialUploader {
+    fn drop(&mut self) {
+        if self.promoted || self.pending == 0 {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let storage = self.storage.clone();
+        let path = std::mem::take(&mut self.path);
+        let markdown = std::mem::take(&mut self.latest);
+        runtime.spawn(async move {
+            if let Err(e) = upload(&storage, &path, markdown).await {
+                warn!("Failed to upload {}: {}", path, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn local(name: &str) -> (SupabaseStorageClient, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("cution-partial-{}-{}", name, std::process::id()));
+        (SupabaseStorageClient::local(&dir), dir)
+    }
+
+    async fn read(storage: &SupabaseStorageClient, path: &str) -> Option<String> {
+        storage.download_file(path).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn uploads_every_n_items() {
+        let (storage, dir) = local("throttle");
+        let mut partial = PartialUploader::new(storage.clone(), "2024-05-10/hn", 3);
+        assert_eq!(partial.path(), "2024-05-10/hn.partial.md");
+        let mut seen = Vec::new();
+        for n in 1..=7 {
+            partial.item_done(n.to_string()).await;
+            seen.push(read(&storage, partial.path()).await);
+        }
+        let expected: Vec<Option<String>> = [
+            None,
+            None,
+            Some("3"),
+            Some("3"),
+            Some("3"),
+            Some("6"),
+            Some("6"),
+        ]
+        .into_iter()
+        .map(|s| s.map(str::to_string))
+        .collect();
+        assert_eq!(seen, expected);
+        partial.promote().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn promote_deletes_the_partial() {
+        let (storage, dir) = local("promote");
+        let mut partial = PartialUploader::new(storage.clone(), "2024-05-10/hn", 1);
+        partial.item_done("story".to_string()).await;
+        assert_eq!(partial.recover().await.as_deref(), Some("story"));
+        partial.promote().await;
+        assert_eq!(read(&storage, "2024-05-10/hn.partial.md").await, None);
+        assert_eq!(
+            PartialUploader::new(storage, "2024-05-10/hn", 1)
+                .recover()
+                .await,
+            None
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn drop_uploads_pending_items_for_the_next_run() {
+        let (storage, dir) = local("drop");
+        {
+            let mut partial = PartialUploader::new(storage.clone(), "2024-05-10/hn", 10);
+            partial.item_done("one".to_string()).await;
+            partial.item_done("one\ntwo".to_string()).await;
+            // The run fails here, before `promote`.
+        }
+        let mut recovered = None;
+        for _ in 0..100 {
+            recovered = PartialUploader::new(storage.clone(), "2024-05-10/hn", 10)
+                .recover()
+                .await;
+            if recovered.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(recovered.as_deref(), Some("one\ntwo"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
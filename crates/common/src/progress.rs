@@ -0,0 +1,52 @@
+//! Progress events for embedders (CLI progress bars, TUIs) that would rather
+//! not parse logs. Sinks are shared between concurrently running crawlers.
+
+use tokio::sync::mpsc::UnboundedSender;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    CrawlerStarted {
+        name: String,
+    },
+    /// `done` of `total` units of a crawler's work (stories, languages, ...).
+    ItemProcessed {
+        name: String,
+        done: usize,
+        total: usize,
+    },
+    /// `result` carries the error message when the crawler failed.
+    CrawlerFinished {
+        name: String,
+        result: Result<(), String>,
+    },
+}
+
+/// Receives `ProgressEvent`s. Implemented for closures and for unbounded
+/// channel senders; `emit` must not block.
+pub trait ProgressSink: Send + Sync {
+    fn emit(&self, event: ProgressEvent);
+}
+
+/// Discards every event; the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopProgress;
+
+impl ProgressSink for NoopProgress {
+    fn emit(&self, _event: ProgressEvent) {}
+}
+
+impl<F> ProgressSink for F
+where
+    F: Fn(ProgressEvent) + Send + Sync,
+{
+    fn emit(&self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+impl ProgressSink for UnboundedSender<ProgressEvent> {
+    fn emit(&self, event: ProgressEvent) {
+        // A dropped receiver just means nobody is watching any more.
+        let _ = self.send(event);
+    }
+}
@@ -0,0 +1,368 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::future::Future;
+use time::{Date, OffsetDateTime};
+use tracing::info;
+
+use crate::config::Config;
+use crate::csv;
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::idempotency::{self, UploadStatus};
+use crate::index;
+use crate::output::{self, OutputFormat};
+use crate::render;
+use crate::supabase_client::SupabaseStorageClient;
+
+/// Length of the hex hash prefix appended to archived filenames.
+pub(crate) const SHORT_HASH_LEN: usize = 12;
+
+tokio::task_local! {
+    static UPLOADED: RefCell<Uploads>;
+}
+
+/// Paths published during a `record_uploads` scope.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Uploads {
+    /// Every path published, whether or not it was rewritten.
+    pub paths: Vec<String>,
+    /// The subset skipped because storage already had identical content.
+    pub unchanged: Vec<String>,
+    /// `(path, full SHA-256 hex)` of every path's content.
+    pub checksums: Vec<(String, String)>,
+}
+
+/// Runs `future` and returns the paths every `Publisher` wrote while it ran on
+/// this task (uploads from spawned tasks are not seen).
+pub async fn record_uploads<F: Future>(future: F) -> (F::Output, Uploads) {
+    UPLOADED
+        .scope(RefCell::new(Uploads::default()), async {
+            let output = future.await;
+            (output, UPLOADED.with(|uploads| uploads.take()))
+        })
+        .await
+}
+
+fn note_upload(path: &str, status: UploadStatus, sha256: &str) {
+    let _ = UPLOADED.try_with(|uploads| {
+        let mut uploads = uploads.borrow_mut();
+        uploads.paths.push(path.to_string());
+        uploads
+            .checksums
+            .push((path.to_string(), sha256.to_string()));
+        if status == UploadStatus::Unchanged {
+            uploads.unchanged.push(path.to_string());
+        }
+    });
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PublishOptions {
+    /// Append a content hash to filenames and keep a `-latest` copy instead of overwriting.
+    pub immutable_archive: bool,
+    /// Crawler slug substituted for `{crawler}` in the banner and footer.
+    pub crawler: String,
+    /// Markdown prepended to every document; supports `{crawler}` and `{date}`.
+    pub banner: Option<String>,
+    /// Markdown appended to every document; supports `{crawler}` and `{date}`.
+    pub footer: Option<String>,
+    /// Start CSV files with a UTF-8 byte order mark.
+    pub csv_bom: bool,
+}
+
+impl PublishOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            immutable_archive: config.immutable_archive,
+            ..Self::default()
+        }
+    }
+
+    /// Options for `slug`, including its banner and footer templates.
+    pub fn for_crawler(config: &Config, slug: &str) -> Self {
+        Self {
+            immutable_archive: config.immutable_archive,
+            crawler: slug.to_string(),
+            banner: config.banner_for(slug).map(str::to_string),
+            footer: config.footer_for(slug).map(str::to_string),
+            csv_bom: config.csv_bom,
+        }
+    }
+
+    /// Wraps `markdown` in the configured banner and footer.
+    pub fn decorate_markdown(&self, markdown: String, date: Date) -> String {
+        if self.banner.is_none() && self.footer.is_none() {
+            return markdown;
+        }
+        let mut out = String::new();
+        if let Some(banner) = &self.banner {
+            out.push_str(&render_template(banner, &self.crawler, date));
+            out.push_str("\n\n");
+        }
+        out.push_str(&markdown);
+        if let Some(footer) = &self.footer {
+            if !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push('\n');
+            out.push_str(&render_template(footer, &self.crawler, date));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Fills `{crawler}` and `{date}` (YYYY-MM-DD) in a banner or footer template.
+pub fn render_template(template: &str, crawler: &str, date: Date) -> String {
+    template
+        .replace("{crawler}", crawler)
+        .replace("{date}", &date.to_string())
+}
+
+/// Uploads crawler output to storage, applying the configured naming scheme.
+#[derive(Clone)]
+pub struct Publisher {
+    storage: SupabaseStorageClient,
+    options: PublishOptions,
+    /// Fills `{date}` in banners and footers; today when unset.
+    date: Option<Date>,
+}
+
+impl Publisher {
+    pub fn new(storage: SupabaseStorageClient, options: PublishOptions) -> Self {
+        Self {
+            storage,
+            options,
+            date: None,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self::new(
+            SupabaseStorageClient::from_config(config)?,
+            PublishOptions::from_config(config),
+        ))
+    }
+
+    /// Publisher for one crawler, applying its banner and footer to markdown output.
+    pub fn for_crawler(config: &Config, slug: &str) -> CrawlerResult<Self> {
+        Ok(Self::new(
+            SupabaseStorageClient::from_config(config)?,
+            PublishOptions::for_crawler(config, slug),
+        ))
+    }
+
+    pub fn storage(&self) -> &SupabaseStorageClient {
+        &self.storage
+    }
+
+    /// The same publisher writing to `storage` instead.
+    pub fn with_storage(mut self, storage: SupabaseStorageClient) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// The same publisher dating banners and footers `date`.
+    pub fn with_date(mut self, date: Date) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    /// Uploads `content` to `path` and returns the path that was written.
+    ///
+    /// In immutable-archive mode the content lands at `{stem}-{hash}.{ext}` and a
+    /// copy is written to `{stem}-latest.{ext}`. The pointer is per file rather
+    /// than one `latest.md`, since several outputs share each day's folder.
+    pub async fn publish(
+        &self,
+        path: &str,
+        content: String,
+        content_type: &str,
+    ) -> CrawlerResult<String> {
+        let sha256 = sha256_hex(&content);
+        if !self.options.immutable_archive {
+            let status = self.upload(path, content, content_type).await?;
+            note_upload(path, status, &sha256);
+            return Ok(path.to_string());
+        }
+
+        let hash = sha256[..SHORT_HASH_LEN].to_string();
+        let archived_path = suffixed_path(path, &hash);
+        let latest_path = suffixed_path(path, "latest");
+
+        // Identical content hashes to the same name, so an existing archive is already correct.
+        let archived_status = match self
+            .storage
+            .upload_new_only(&archived_path, content.clone(), content_type)
+            .await
+        {
+            Ok(()) => UploadStatus::Written,
+            Err(CrawlerError::AlreadyExists(_)) => UploadStatus::Unchanged,
+            Err(e) => return Err(e),
+        };
+        let status = self.upload(&latest_path, content, content_type).await?;
+        note_upload(&archived_path, archived_status, &sha256);
+        note_upload(&latest_path, status, &sha256);
+        info!(
+            "Archived {} as {} (latest: {})",
+            path, archived_path, latest_path
+        );
+        Ok(archived_path)
+    }
+
+    /// Publishes `{stem}.{ext}` for each requested format: the markdown is only
+    /// rendered when markdown or HTML is requested (HTML is the same document as a
+    /// page), and JSON/NDJSON/CSV are `items` serialized with serde.
+    pub async fn publish_outputs<T, F>(
+        &self,
+        stem: &str,
+        formats: &[OutputFormat],
+        render_markdown: F,
+        items: &[T],
+    ) -> CrawlerResult<Vec<String>>
+    where
+        T: Serialize,
+        F: FnOnce() -> String,
+    {
+        let mut render_markdown = Some(render_markdown);
+        let mut markdown: Option<String> = None;
+        let mut document = || {
+            markdown
+                .get_or_insert_with(|| {
+                    let render = render_markdown.take().expect("markdown is rendered once");
+                    let date = self
+                        .date
+                        .unwrap_or_else(|| OffsetDateTime::now_utc().date());
+                    self.options.decorate_markdown(render(), date)
+                })
+                .clone()
+        };
+        let mut written = Vec::new();
+        for (i, format) in formats.iter().enumerate() {
+            if formats[..i].contains(format) {
+                continue;
+            }
+            let content = match format {
+                OutputFormat::Markdown => document(),
+                OutputFormat::Html => {
+                    let name = stem.rsplit('/').next().unwrap_or(stem);
+                    render::markdown_to_html(&document(), &index::display_title(name))
+                }
+                OutputFormat::Json => serde_json::to_string_pretty(items)
+                    .map_err(|e| CrawlerError::Parse(format!("Failed to serialize JSON: {}", e)))?,
+                OutputFormat::Ndjson => output::to_ndjson(items).map_err(|e| {
+                    CrawlerError::Parse(format!("Failed to serialize NDJSON: {}", e))
+                })?,
+                OutputFormat::Csv => csv::write_items(Vec::new(), items, self.options.csv_bom)
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                    .map_err(|e| CrawlerError::Parse(format!("Failed to serialize CSV: {}", e)))?,
+            };
+            let path = format!("{}.{}", stem, format.extension());
+            written.push(self.publish(&path, content, format.content_type()).await?);
+        }
+        Ok(written)
+    }
+
+    async fn upload(
+        &self,
+        path: &str,
+        content: String,
+        content_type: &str,
+    ) -> CrawlerResult<UploadStatus> {
+        idempotency::upload_if_changed(&self.storage, path, content, content_type).await
+    }
+}
+
+/// Short hex SHA-256 prefix of `content`.
+pub fn content_hash(content: &str) -> String {
+    sha256_hex(content)[..SHORT_HASH_LEN].to_string()
+}
+
+/// Full hex SHA-256 of `content`.
+pub fn sha256_hex(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+/// Inserts `-{suffix}` between the file stem and its extension.
+pub fn suffixed_path(path: &str, suffix: &str) -> String {
+    let file_start = path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match path[file_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let dot = file_start + dot;
+            format!("{}-{}{}", &path[..dot], suffix, &path[dot..])
+        }
+        _ => format!("{}-{}", path, suffix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_is_short_and_stable() {
+        let hash = content_hash("hello");
+        assert_eq!(hash.len(), SHORT_HASH_LEN);
+        assert_eq!(hash, content_hash("hello"));
+        assert_ne!(hash, content_hash("hello!"));
+    }
+
+    #[test]
+    fn banner_and_footer_wrap_markdown() {
+        let options = PublishOptions {
+            crawler: "github".to_string(),
+            banner: Some("> Generated automatically by {crawler} — do not edit".to_string()),
+            footer: Some("_Source: {crawler}, {date}_".to_string()),
+            ..PublishOptions::default()
+        };
+        let date = Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        assert_eq!(
+            options.decorate_markdown("# Trending\n".to_string(), date),
+            "> Generated automatically by github — do not edit\n\n# Trending\n\n_Source: github, 2024-05-10_\n"
+        );
+        assert_eq!(
+            PublishOptions::default().decorate_markdown("# Trending".to_string(), date),
+            "# Trending"
+        );
+    }
+
+    #[test]
+    fn suffixed_path_inserts_before_extension() {
+        assert_eq!(
+            suffixed_path("2024-01-01/github-trending.md", "abc123"),
+            "2024-01-01/github-trending-abc123.md"
+        );
+        assert_eq!(suffixed_path("a.b/notes", "latest"), "a.b/notes-latest");
+        assert_eq!(suffixed_path(".hidden", "x"), ".hidden-x");
+    }
+
+    #[tokio::test]
+    async fn existing_archive_is_recorded_as_unchanged() {
+        let dir = std::env::temp_dir().join(format!("cution-archive-{}", std::process::id()));
+        let publisher = Publisher::new(
+            SupabaseStorageClient::local(&dir),
+            PublishOptions {
+                immutable_archive: true,
+                ..PublishOptions::default()
+            },
+        );
+        let publish =
+            || publisher.publish("2024-05-10/notes.md", "hello".to_string(), "text/markdown");
+
+        let (archived, first) = record_uploads(publish()).await;
+        let archived = archived.unwrap();
+        assert_eq!(
+            archived,
+            format!("2024-05-10/notes-{}.md", content_hash("hello"))
+        );
+        assert_eq!(
+            first.paths,
+            vec![archived.clone(), "2024-05-10/notes-latest.md".to_string()]
+        );
+        assert!(first.unchanged.is_empty());
+
+        let (_, second) = record_uploads(publish()).await;
+        assert!(second.unchanged.contains(&archived), "{:?}", second);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+//! Scoring and ranking shared by the sources: wrap items in `Scored`, build
+//! scores from weighted terms, normalize a batch and keep the top N.
+
+use serde::{Deserialize, Serialize};
+
+/// An item with its score and the reasons that produced it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scored<T> {
+    pub item: T,
+    pub score: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+/// `value`, or 0 when it is NaN or infinite, so one bad input cannot poison
+/// a sort or a batch's maximum.
+fn finite(value: f64) -> f64 {
+    if value.is_finite() {
+        value
+    } else {
+        0.0
+    }
+}
+
+impl<T> Scored<T> {
+    pub fn new(item: T, score: f64) -> Self {
+        Self {
+            item,
+            score: finite(score),
+            reasons: Vec::new(),
+        }
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reasons.push(reason.into());
+        self
+    }
+
+    /// Adds `weight * value` to the score, noting `reason`.
+    pub fn add(mut self, weight: f64, value: f64, reason: impl Into<String>) -> Self {
+        self.score += finite(weight * value);
+        self.with_reason(reason)
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Scored<U> {
+        Scored {
+            item: f(self.item),
+            score: self.score,
+            reasons: self.reasons,
+        }
+    }
+}
+
+/// Sum of `weight * value` over `terms`, skipping non-finite products.
+pub fn weighted_sum(terms: impl IntoIterator<Item = (f64, f64)>) -> f64 {
+    terms
+        .into_iter()
+        .map(|(weight, value)| finite(weight * value))
+        .sum()
+}
+
+/// Scores each item with `score`.
+pub fn score_by<T>(
+    items: impl IntoIterator<Item = T>,
+    score: impl Fn(&T) -> f64,
+) -> Vec<Scored<T>> {
+    items
+        .into_iter()
+        .map(|item| {
+            let value = score(&item);
+            Scored::new(item, value)
+        })
+        .collect()
+}
+
+/// Scales the batch so its best score is 1.0. Negative scores become 0, and
+/// a batch with no positive score is all zeros.
+pub fn normalize<T>(items: &mut [Scored<T>]) {
+    let max = items.iter().map(|s| s.score).fold(0.0, f64::max);
+    for scored in items {
+        scored.score = if max > 0.0 {
+            (scored.score / max).max(0.0)
+        } else {
+            0.0
+        };
+    }
+}
+
+/// The items scoring at least `min`.
+pub fn at_least<T>(items: Vec<Scored<T>>, min: f64) -> Vec<Scored<T>> {
+    items.into_iter().filter(|s| s.score >= min).collect()
+}
+
+/// The `n` best items, highest score first; ties keep their input order.
+pub fn top_n<T>(mut items: Vec<Scored<T>>, n: usize) -> Vec<Scored<T>> {
+    items.sort_by(|a, b| b.score.total_cmp(&a.score));
+    items.truncate(n);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random scores, including negatives and NaN.
+    fn batches() -> Vec<Vec<f64>> {
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        (0..200)
+            .map(|_| {
+                let len = (next() % 12) as usize;
+                (0..len)
+                    .map(|_| match next() % 10 {
+                        0 => f64::NAN,
+                        1 => -((next() % 100) as f64),
+                        2 => 5.0,
+                        _ => (next() % 1000) as f64 / 10.0,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn normalized_scores_stay_in_range() {
+        for batch in batches() {
+            let mut scored = score_by(batch.iter().copied().enumerate(), |(_, v)| *v);
+            normalize(&mut scored);
+            assert!(
+                scored.iter().all(|s| (0.0..=1.0).contains(&s.score)),
+                "{:?}",
+                batch
+            );
+            if batch.iter().any(|v| *v > 0.0) {
+                assert!(scored.iter().any(|s| s.score == 1.0), "{:?}", batch);
+            }
+        }
+    }
+
+    #[test]
+    fn normalize_edge_cases() {
+        let normalized = |values: &[f64]| {
+            let mut scored = score_by(values.iter().copied(), |v| *v);
+            normalize(&mut scored);
+            scored.into_iter().map(|s| s.score).collect::<Vec<_>>()
+        };
+        assert_eq!(normalized(&[]), Vec::<f64>::new());
+        assert_eq!(normalized(&[42.0]), [1.0]);
+        assert_eq!(normalized(&[3.0, 3.0, 3.0]), [1.0, 1.0, 1.0]);
+        assert_eq!(normalized(&[0.0, 0.0]), [0.0, 0.0]);
+        assert_eq!(normalized(&[f64::NAN, 4.0, f64::INFINITY]), [0.0, 1.0, 0.0]);
+        assert_eq!(normalized(&[-2.0, 2.0]), [0.0, 1.0]);
+    }
+
+    #[test]
+    fn top_n_is_stable_and_ordered() {
+        for batch in batches() {
+            let ranked = top_n(score_by(batch.iter().copied().enumerate(), |(_, v)| *v), 5);
+            assert!(ranked.len() <= 5);
+            for pair in ranked.windows(2) {
+                assert!(pair[0].score >= pair[1].score);
+                if pair[0].score == pair[1].score {
+                    assert!(pair[0].item.0 < pair[1].item.0, "ties keep input order");
+                }
+            }
+        }
+        let ranked = top_n(
+            score_by(["a", "b", "c", "d"], |s| (*s != "a") as u8 as f64),
+            2,
+        );
+        assert_eq!(
+            ranked.iter().map(|s| s.item).collect::<Vec<_>>(),
+            ["b", "c"]
+        );
+    }
+
+    #[test]
+    fn weighted_terms_and_reasons() {
+        assert_eq!(weighted_sum([(2.0, 3.0), (0.5, 4.0), (1.0, f64::NAN)]), 8.0);
+        let scored = Scored::new("paper", 1.0)
+            .add(2.0, 1.0, "mentions 'rust'")
+            .add(1.0, f64::INFINITY, "broken term");
+        assert_eq!(scored.score, 3.0);
+        assert_eq!(scored.reasons, ["mentions 'rust'", "broken term"]);
+        assert_eq!(
+            at_least(vec![scored.clone(), Scored::new("x", 2.9)], 3.0).len(),
+            1
+        );
+
+        let json = serde_json::to_value(scored.map(str::len)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "item": 5, "score": 3.0, "reasons": ["mentions 'rust'", "broken term"] })
+        );
+        let plain: Scored<u8> = serde_json::from_str(r#"{"item":1,"score":0.5}"#).unwrap();
+        assert!(plain.reasons.is_empty());
+    }
+}
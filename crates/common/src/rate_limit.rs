@@ -0,0 +1,155 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::error::CrawlerResult;
+use crate::http::HttpClient;
+
+/// Token bucket shared by clones: `rate` tokens per second refill a bucket of
+/// at most `burst` tokens, and each `acquire` takes one.
+///
+/// Waiters queue on a FIFO mutex and sleep until their token is due, so callers
+/// are served in arrival order without polling.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    rate: f64,
+    burst: f64,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `rate` acquisitions per second on average and up to
+    /// `burst` at once. Panics unless `rate` is positive and `burst` at least 1.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        assert!(rate > 0.0 && rate.is_finite(), "rate must be positive");
+        assert!(burst >= 1, "burst must be at least 1");
+        Self {
+            inner: Arc::new(Inner {
+                rate,
+                burst: burst as f64,
+                state: Mutex::new(Bucket {
+                    tokens: burst as f64,
+                    refilled_at: Instant::now(),
+                }),
+            }),
+        }
+    }
+
+    /// One acquisition every `interval`, without bursting.
+    pub fn every(interval: Duration) -> Self {
+        Self::new(1.0 / interval.as_secs_f64(), 1)
+    }
+
+    /// Waits until a token is available and takes it.
+    pub async fn acquire(&self) {
+        let mut bucket = self.inner.state.lock().await;
+        self.refill(&mut bucket);
+        if bucket.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / self.inner.rate);
+            tokio::time::sleep(wait).await;
+            self.refill(&mut bucket);
+        }
+        bucket.tokens = (bucket.tokens - 1.0).max(0.0);
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.inner.rate).min(self.inner.burst);
+        bucket.refilled_at = now;
+    }
+}
+
+/// An `HttpClient` whose requests each wait for a `RateLimiter` token.
+#[derive(Debug, Clone)]
+pub struct RateLimitedClient {
+    client: HttpClient,
+    limiter: RateLimiter,
+}
+
+impl RateLimitedClient {
+    pub fn new(client: HttpClient, limiter: RateLimiter) -> Self {
+        Self { client, limiter }
+    }
+
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    /// Waits for a token, then GETs `url`; see `HttpClient::get_text`.
+    pub async fn get_text(&self, url: &str) -> CrawlerResult<String> {
+        self.limiter.acquire().await;
+        self.client.get_text(url).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_then_steady_rate() {
+        let limiter = RateLimiter::new(1.0, 2);
+        let start = Instant::now();
+        let mut elapsed = Vec::new();
+        for _ in 0..5 {
+            limiter.acquire().await;
+            elapsed.push(start.elapsed().as_millis());
+        }
+        assert_eq!(elapsed, vec![0, 0, 1000, 2000, 3000]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_time_refills_up_to_burst() {
+        let limiter = RateLimiter::new(2.0, 3);
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        let start = Instant::now();
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+        // Three tokens were saved up; the fourth waits half a second.
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_waiters_are_served_in_order() {
+        let limiter = RateLimiter::every(Duration::from_secs(1));
+        let start = Instant::now();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut tasks = Vec::new();
+        for id in 0..4 {
+            let limiter = limiter.clone();
+            let tx = tx.clone();
+            tasks.push(tokio::spawn(async move {
+                limiter.acquire().await;
+                tx.send((id, start.elapsed().as_secs())).unwrap();
+            }));
+            // Let each task reach the queue before spawning the next.
+            tokio::task::yield_now().await;
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        drop(tx);
+        let mut order = Vec::new();
+        while let Some(entry) = rx.recv().await {
+            order.push(entry);
+        }
+        assert_eq!(order, vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+    }
+}
@@ -0,0 +1,98 @@
+//! Descriptors that source crates export with `register_crawler!`, so the
+//! orchestrator can list, gate and build every source from one table.
+
+use crate::config::Config;
+use crate::crawler::Crawler;
+use crate::error::CrawlerResult;
+
+/// The registered crawler boxed, or `None` when it is not configured.
+pub type BuildResult = CrawlerResult<Option<Box<dyn Crawler>>>;
+
+/// What the orchestrator needs to know about a source crate's crawler.
+#[derive(Clone, Copy)]
+pub struct CrawlerDescriptor {
+    /// Label used in log lines, e.g. "Hacker News crawler".
+    pub name: &'static str,
+    pub slug: &'static str,
+    /// Env variables the crawler is gated on, any one of which enables it;
+    /// documentation for `precheck`.
+    pub required_env: &'static [&'static str],
+    /// The crawler type's `Crawler::precheck`.
+    pub precheck: fn(&Config) -> Result<(), String>,
+    /// Builds the crawler, or `None` when `precheck` fails.
+    pub build: fn(&Config) -> BuildResult,
+    /// The section the crawler's markdown gets in the daily digest, if any.
+    pub digest: Option<DigestEntry>,
+}
+
+/// A daily digest section: the markdown published as `{date}/{output}.md`
+/// under the heading `title`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigestEntry {
+    pub output: &'static str,
+    pub title: &'static str,
+}
+
+impl std::fmt::Debug for CrawlerDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrawlerDescriptor")
+            .field("name", &self.name)
+            .field("slug", &self.slug)
+            .field("required_env", &self.required_env)
+            .field("digest", &self.digest)
+            .finish()
+    }
+}
+
+/// `new(config)` boxed, when `C::precheck` passes. Used by `register_crawler!`.
+pub fn build_checked<C, F>(config: &Config, new: F) -> BuildResult
+where
+    C: Crawler + 'static,
+    F: FnOnce(&Config) -> CrawlerResult<C>,
+{
+    if C::precheck(config).is_err() {
+        return Ok(None);
+    }
+    Ok(Some(Box::new(new(config)?)))
+}
+
+/// Declares `pub const DESCRIPTOR: CrawlerDescriptor` for a source crate's
+/// crawler type:
+///
+/// ```ignore
+/// common::register_crawler!(HackerNewsCrawler {
+///     name: "Hacker News crawler",
+///     slug: "hacker_news",
+///     required_env: ["GEMINI_API_KEY"],
+///     digest: ("hacker-news", "Hacker News"),
+///     new: HackerNewsCrawler::new,
+/// });
+/// ```
+///
+/// `digest` is optional; it names the markdown output and heading of the
+/// crawler's section in the daily digest.
+#[macro_export]
+macro_rules! register_crawler {
+    ($crawler:ty {
+        name: $name:expr,
+        slug: $slug:expr,
+        required_env: [$($env:expr),* $(,)?],
+        $(digest: ($output:expr, $title:expr),)?
+        new: $new:expr $(,)?
+    }) => {
+        pub const DESCRIPTOR: $crate::registry::CrawlerDescriptor =
+            $crate::registry::CrawlerDescriptor {
+                name: $name,
+                slug: $slug,
+                required_env: &[$($env),*],
+                precheck: <$crawler as $crate::Crawler>::precheck,
+                build: |config| $crate::registry::build_checked::<$crawler, _>(config, $new),
+                digest: {
+                    #[allow(unused_mut)]
+                    let mut digest = None;
+                    $(digest = Some($crate::registry::DigestEntry { output: $output, title: $title });)?
+                    digest
+                },
+            };
+    };
+}
@@ -0,0 +1,117 @@
+//! Parsing of relative timestamps such as "updated 2 hours ago".
+
+use time::{Duration, OffsetDateTime};
+
+/// Length of one unit, matched on singular, plural and abbreviated names.
+/// Months and years are approximated as 30 and 365 days.
+fn unit_duration(unit: &str) -> Option<Duration> {
+    let duration = match unit.trim_end_matches('.') {
+        "s" | "sec" | "secs" | "second" | "seconds" => Duration::SECOND,
+        "m" | "min" | "mins" | "minute" | "minutes" => Duration::MINUTE,
+        "h" | "hr" | "hrs" | "hour" | "hours" => Duration::HOUR,
+        "d" | "day" | "days" => Duration::DAY,
+        "w" | "wk" | "wks" | "week" | "weeks" => Duration::WEEK,
+        "mo" | "mos" | "month" | "months" => Duration::days(30),
+        "y" | "yr" | "yrs" | "year" | "years" => Duration::days(365),
+        _ => return None,
+    };
+    Some(duration)
+}
+
+/// The moment `s` refers to, relative to `now`: "5 minutes ago",
+/// "updated an hour ago", "3d ago", "yesterday" or "just now". Anything else,
+/// including future times, is `None`.
+pub fn parse_relative_time(s: &str, now: OffsetDateTime) -> Option<OffsetDateTime> {
+    let lower = s.trim().to_lowercase();
+    let text = lower
+        .strip_prefix("updated")
+        .or_else(|| lower.strip_prefix("posted"))
+        .unwrap_or(&lower)
+        .trim()
+        .trim_end_matches('.');
+
+    match text {
+        "just now" | "now" | "moments ago" | "a moment ago" => return Some(now),
+        "yesterday" => return Some(now - Duration::DAY),
+        _ => {}
+    }
+
+    let text = text.strip_suffix("ago")?.trim();
+    let (count, unit) = match text.split_once(char::is_whitespace) {
+        Some((count, unit)) => (count, unit.trim()),
+        // Compact forms such as "5m" or "2hrs".
+        None => text.split_at(text.find(|c: char| !c.is_ascii_digit())?),
+    };
+    let count: u32 = match count {
+        "a" | "an" | "one" => 1,
+        digits => digits.parse().ok()?,
+    };
+    let offset = unit_duration(unit)?.checked_mul(i32::try_from(count).ok()?)?;
+    now.checked_sub(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    const NOW: OffsetDateTime = datetime!(2024-05-10 12:00 UTC);
+
+    fn parse(s: &str) -> Option<OffsetDateTime> {
+        parse_relative_time(s, NOW)
+    }
+
+    #[test]
+    fn parses_counts_and_units() {
+        assert_eq!(
+            parse("5 minutes ago"),
+            Some(datetime!(2024-05-10 11:55 UTC))
+        );
+        assert_eq!(parse("1 minute ago"), Some(datetime!(2024-05-10 11:59 UTC)));
+        assert_eq!(parse("2 hours ago"), Some(datetime!(2024-05-10 10:00 UTC)));
+        assert_eq!(parse("3 days ago"), Some(datetime!(2024-05-07 12:00 UTC)));
+        assert_eq!(
+            parse("30 seconds ago"),
+            Some(datetime!(2024-05-10 11:59:30 UTC))
+        );
+        assert_eq!(parse("2 weeks ago"), Some(datetime!(2024-04-26 12:00 UTC)));
+        assert_eq!(parse("1 month ago"), Some(datetime!(2024-04-10 12:00 UTC)));
+        assert_eq!(parse("0 minutes ago"), Some(NOW));
+    }
+
+    #[test]
+    fn accepts_prefixes_articles_and_abbreviations() {
+        assert_eq!(
+            parse("Updated 2 hours ago"),
+            Some(datetime!(2024-05-10 10:00 UTC))
+        );
+        assert_eq!(
+            parse("  posted an hour ago. "),
+            Some(datetime!(2024-05-10 11:00 UTC))
+        );
+        assert_eq!(parse("a day ago"), Some(datetime!(2024-05-09 12:00 UTC)));
+        assert_eq!(parse("5m ago"), Some(datetime!(2024-05-10 11:55 UTC)));
+        assert_eq!(parse("2hrs ago"), Some(datetime!(2024-05-10 10:00 UTC)));
+        assert_eq!(parse("3 hr. ago"), Some(datetime!(2024-05-10 09:00 UTC)));
+        assert_eq!(parse("Yesterday"), Some(datetime!(2024-05-09 12:00 UTC)));
+        assert_eq!(parse("just now"), Some(NOW));
+    }
+
+    #[test]
+    fn rejects_everything_else() {
+        for input in [
+            "",
+            "ago",
+            "2 hours",
+            "in 2 hours",
+            "-2 hours ago",
+            "2.5 hours ago",
+            "several hours ago",
+            "2 fortnights ago",
+            "2024-05-10",
+            "99999999999 days ago",
+        ] {
+            assert_eq!(parse(input), None, "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Markdown to standalone HTML, for outputs read in a browser through a
+//! public bucket URL.
+
+use pulldown_cmark::{html, CowStr, Event, Options, Parser};
+
+const STYLESHEET: &str = "\
+body{max-width:48rem;margin:2rem auto;padding:0 1rem;font:16px/1.6 system-ui,sans-serif;color:#1f2328}
+a{color:#0969da}
+pre{background:#f6f8fa;padding:.75rem;overflow-x:auto;border-radius:6px}
+code{font-family:ui-monospace,monospace;font-size:.9em}
+table{border-collapse:collapse}
+th,td{border:1px solid #d0d7de;padding:.3rem .6rem}
+blockquote{margin:0;padding-left:1rem;border-left:.25rem solid #d0d7de;color:#59636e}
+hr{border:0;border-top:1px solid #d0d7de}";
+
+/// Renders `md` (CommonMark plus tables and strikethrough) into a complete
+/// HTML page titled `title`. Raw HTML in the markdown is shown as text rather
+/// than passed through, since crawled content ends up in it.
+pub fn markdown_to_html(md: &str, title: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+    let events = Parser::new_ext(md, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(pulldown_cmark::Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(pulldown_cmark::Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        other => other,
+    });
+    let mut body = String::with_capacity(md.len() * 3 / 2);
+    html::push_html(&mut body, events);
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        STYLESHEET,
+        body
+    )
+}
+
+/// Drops `javascript:` and similar link targets.
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    let scheme = url
+        .split_once(':')
+        .map(|(scheme, _)| scheme.trim().to_ascii_lowercase());
+    match scheme.as_deref() {
+        Some("javascript" | "vbscript" | "data") => CowStr::Borrowed("#"),
+        _ => url,
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `<body>` contents of the rendered page.
+    fn body(md: &str) -> String {
+        let page = markdown_to_html(md, "t");
+        let start = page.find("<body>\n").unwrap() + "<body>\n".len();
+        let end = page.rfind("</body>").unwrap();
+        page[start..end].to_string()
+    }
+
+    #[test]
+    fn digest_snapshot() {
+        let md = "\
+# Daily Digest
+
+## GitHub Trending
+
+| Repo | Stars |
+| ---- | ----- |
+| [rust-lang/rust](https://github.com/rust-lang/rust) | 100,000 |
+
+```rust
+fn main() { println!(\"<hi>\"); }
+```
+
+---
+";
+        let expected = "\
+<h1>Daily Digest</h1>
+<h2>GitHub Trending</h2>
+<table><thead><tr><th>Repo</th><th>Stars</th></tr></thead><tbody>
+<tr><td><a href=\"https://github.com/rust-lang/rust\">rust-lang/rust</a></td><td>100,000</td></tr>
+</tbody></table>
+<pre><code class=\"language-rust\">fn main() { println!(\"&lt;hi&gt;\"); }
+</code></pre>
+<hr />
+";
+        assert_eq!(body(md), expected);
+    }
+
+    #[test]
+    fn raw_html_and_script_links_are_neutralised() {
+        assert_eq!(
+            body("Hello <script>alert(1)</script> & [x](javascript:alert(1))"),
+            "<p>Hello &lt;script&gt;alert(1)&lt;/script&gt; &amp; <a href=\"#\">x</a></p>\n"
+        );
+        assert_eq!(body("<div>block</div>\n"), "&lt;div&gt;block&lt;/div&gt;\n");
+
+        let page = markdown_to_html("", "Tips & <Tricks>");
+        assert!(page.contains("<title>Tips &amp; &lt;Tricks&gt;</title>"));
+        assert!(page.starts_with("<!DOCTYPE html>"));
+    }
+}
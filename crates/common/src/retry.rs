@@ -0,0 +1,223 @@
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::error::CrawlerError;
+
+/// How often and how patiently an operation is retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every further retry.
+    pub base_delay: Duration,
+    /// Upper bound for a single delay.
+    pub max_delay: Duration,
+    /// Fraction (0.0–1.0) of each delay that is randomly shaved off.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `retry` (1-based), before jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    pub(crate) fn jittered(&self, delay: Duration) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return delay;
+        }
+        delay.mul_f64(1.0 - jitter * pseudo_random())
+    }
+}
+
+/// Cheap uniform-ish value in `[0, 1)`; good enough to spread retries apart.
+fn pseudo_random() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    f64::from(nanos % 1_000_000) / 1_000_000.0
+}
+
+/// Runs `operation` until it succeeds, the error is not retryable, or the
+/// policy's attempts are exhausted. The operation receives the 1-based attempt number.
+pub async fn retry_async<T, E, F, Fut, P>(
+    policy: &RetryPolicy,
+    is_retryable: P,
+    operation: F,
+) -> Result<T, E>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    P: Fn(&E) -> bool,
+    E: std::fmt::Display,
+{
+    retry_async_with_delay(policy, is_retryable, |_| None, operation).await
+}
+
+/// Like `retry_async`, but `requested_delay` may take the wait from the error
+/// itself (e.g. a `Retry-After` header) instead of the policy's backoff.
+pub async fn retry_async_with_delay<T, E, F, Fut, P, D>(
+    policy: &RetryPolicy,
+    is_retryable: P,
+    requested_delay: D,
+    mut operation: F,
+) -> Result<T, E>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    P: Fn(&E) -> bool,
+    D: Fn(&E) -> Option<Duration>,
+    E: std::fmt::Display,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match operation(attempt).await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_attempts && is_retryable(&e) => {
+                let delay =
+                    requested_delay(&e).unwrap_or_else(|| policy.jittered(policy.backoff(attempt)));
+                warn!(
+                    "Attempt {}/{} failed: {}; retrying in {:?}",
+                    attempt, max_attempts, e, delay
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+impl CrawlerError {
+    /// Whether the error is likely to go away on retry (network hiccups,
+    /// timeouts, rate limits and server errors).
+    pub fn is_transient(&self) -> bool {
+        match self {
+            CrawlerError::HttpRequest(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            CrawlerError::RateLimited { .. } | CrawlerError::Timeout(_) => true,
+            CrawlerError::HttpStatus { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(250),
+            jitter: 0.0,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let policy = policy();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(250));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn transient_failures_then_success() {
+        let calls = AtomicU32::new(0);
+        let result: Result<&str, CrawlerError> =
+            retry_async(&policy(), CrawlerError::is_transient, |attempt| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if attempt < 3 {
+                        Err(CrawlerError::Timeout("slow".to_string()))
+                    } else {
+                        Ok("done")
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn non_retryable_error_short_circuits() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), CrawlerError> =
+            retry_async(&policy(), CrawlerError::is_transient, |_| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async {
+                    Err(CrawlerError::HttpStatus {
+                        url: "https://x.test".to_string(),
+                        status: 401,
+                        snippet: String::new(),
+                    })
+                }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requested_delay_replaces_backoff() {
+        let started = tokio::time::Instant::now();
+        let result: Result<(), CrawlerError> = retry_async_with_delay(
+            &policy(),
+            |_| true,
+            |_| Some(Duration::from_secs(7)),
+            |attempt| async move {
+                if attempt < 2 {
+                    Err(CrawlerError::Api("busy".to_string()))
+                } else {
+                    Ok(())
+                }
+            },
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(started.elapsed(), Duration::from_secs(7));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), CrawlerError> = retry_async(
+            &policy(),
+            |_| true,
+            |_| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err(CrawlerError::Api("down".to_string())) }
+            },
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
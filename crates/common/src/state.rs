@@ -0,0 +1,529 @@
+//! Namespaced key/value state kept between runs, such as servers already seen.
+//!
+//! By default each namespace is a JSON blob at `state/{namespace}.json` in the
+//! bucket. With `CUTION_STATE_DB` set it is a table in a local SQLite file
+//! instead, seeded from the blob the first time the namespace is used.
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tracing::info;
+
+use crate::config::Config;
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::supabase_client::SupabaseStorageClient;
+
+/// How long a SQLite writer waits for another connection's transaction.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[async_trait]
+pub trait StateStore: Send + Sync {
+    async fn get(&self, namespace: &str, key: &str) -> CrawlerResult<Option<Value>>;
+
+    async fn set(&self, namespace: &str, key: &str, value: Value) -> CrawlerResult<()>;
+
+    /// Atomically combines `value` with the key's current value (see
+    /// `merge_values`) and returns the result.
+    async fn merge(&self, namespace: &str, key: &str, value: Value) -> CrawlerResult<Value>;
+
+    /// Every key in `namespace`.
+    async fn entries(&self, namespace: &str) -> CrawlerResult<BTreeMap<String, Value>>;
+}
+
+/// The store selected by `CUTION_STATE_DB`: SQLite when set, migrating from
+/// the bucket blobs, otherwise the bucket blobs themselves.
+pub fn from_config(config: &Config) -> CrawlerResult<Arc<dyn StateStore>> {
+    let blobs = BlobStateStore::new(SupabaseStorageClient::from_config(config)?);
+    Ok(match &config.state_db {
+        Some(path) => Arc::new(SqliteStateStore::open(path)?.with_legacy(Arc::new(blobs))),
+        None => Arc::new(blobs),
+    })
+}
+
+/// Objects are merged key by key, arrays gain the elements they lack, and
+/// anything else is replaced by `incoming`.
+pub fn merge_values(current: Option<Value>, incoming: Value) -> Value {
+    match (current, incoming) {
+        (Some(Value::Object(mut current)), Value::Object(incoming)) => {
+            for (key, value) in incoming {
+                let merged = merge_values(current.remove(&key), value);
+                current.insert(key, merged);
+            }
+            Value::Object(current)
+        }
+        (Some(Value::Array(mut current)), Value::Array(incoming)) => {
+            for value in incoming {
+                if !current.contains(&value) {
+                    current.push(value);
+                }
+            }
+            Value::Array(current)
+        }
+        (_, incoming) => incoming,
+    }
+}
+
+/// Namespaces become file and table names, so only `[a-z0-9_]` is allowed.
+fn check_namespace(namespace: &str) -> CrawlerResult<()> {
+    let valid = !namespace.is_empty()
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(CrawlerError::State(format!(
+            "invalid namespace '{}'",
+            namespace
+        )))
+    }
+}
+
+fn state_error(e: impl std::fmt::Display) -> CrawlerError {
+    CrawlerError::State(e.to_string())
+}
+
+/// Serializes read-modify-write cycles on state blobs within the process.
+fn blob_lock() -> &'static tokio::sync::Mutex<()> {
+    static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+}
+
+/// One JSON object per namespace at `state/{namespace}.json`.
+pub struct BlobStateStore {
+    storage: SupabaseStorageClient,
+}
+
+impl BlobStateStore {
+    pub fn new(storage: SupabaseStorageClient) -> Self {
+        Self { storage }
+    }
+
+    fn path(namespace: &str) -> String {
+        format!("state/{}.json", namespace)
+    }
+
+    async fn load(&self, namespace: &str) -> CrawlerResult<Map<String, Value>> {
+        check_namespace(namespace)?;
+        let path = Self::path(namespace);
+        let Some(body) = self
+            .storage
+            .download_file(&path)
+            .await
+            .map_err(state_error)?
+        else {
+            return Ok(Map::new());
+        };
+        match serde_json::from_str(&body).map_err(state_error)? {
+            Value::Object(map) => Ok(map),
+            _ => Err(CrawlerError::State(format!(
+                "{} is not a JSON object",
+                path
+            ))),
+        }
+    }
+
+    async fn save(&self, namespace: &str, map: Map<String, Value>) -> CrawlerResult<()> {
+        let body = serde_json::to_string_pretty(&Value::Object(map)).map_err(state_error)?;
+        self.storage
+            .upload_file(&Self::path(namespace), body, "application/json")
+            .await
+            .map_err(|e| CrawlerError::StorageUpload(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl StateStore for BlobStateStore {
+    async fn get(&self, namespace: &str, key: &str) -> CrawlerResult<Option<Value>> {
+        Ok(self.load(namespace).await?.remove(key))
+    }
+
+    async fn set(&self, namespace: &str, key: &str, value: Value) -> CrawlerResult<()> {
+        let _guard = blob_lock().lock().await;
+        let mut map = self.load(namespace).await?;
+        map.insert(key.to_string(), value);
+        self.save(namespace, map).await
+    }
+
+    async fn merge(&self, namespace: &str, key: &str, value: Value) -> CrawlerResult<Value> {
+        let _guard = blob_lock().lock().await;
+        let mut map = self.load(namespace).await?;
+        let merged = merge_values(map.remove(key), value);
+        map.insert(key.to_string(), merged.clone());
+        self.save(namespace, map).await?;
+        Ok(merged)
+    }
+
+    async fn entries(&self, namespace: &str) -> CrawlerResult<BTreeMap<String, Value>> {
+        Ok(self.load(namespace).await?.into_iter().collect())
+    }
+}
+
+/// A table `state_{namespace}` per namespace in a local SQLite file.
+pub struct SqliteStateStore {
+    conn: Arc<Mutex<Connection>>,
+    legacy: Option<Arc<dyn StateStore>>,
+    /// Namespaces whose table is known to exist.
+    ready: tokio::sync::Mutex<HashSet<String>>,
+}
+
+impl SqliteStateStore {
+    pub fn open(path: impl AsRef<Path>) -> CrawlerResult<Self> {
+        let conn = Connection::open(path).map_err(state_error)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(state_error)?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(state_error)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            legacy: None,
+            ready: tokio::sync::Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Seeds each namespace from `legacy` the first time its table is created.
+    pub fn with_legacy(mut self, legacy: Arc<dyn StateStore>) -> Self {
+        self.legacy = Some(legacy);
+        self
+    }
+
+    async fn blocking<T, F>(&self, f: F) -> CrawlerResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut conn)
+        })
+        .await
+        .map_err(state_error)?
+        .map_err(state_error)
+    }
+
+    /// Creates the namespace's table on first use, copying in the legacy
+    /// entries, and returns the table name.
+    async fn table(&self, namespace: &str) -> CrawlerResult<String> {
+        check_namespace(namespace)?;
+        let table = format!("state_{}", namespace);
+        let mut ready = self.ready.lock().await;
+        if ready.contains(namespace) {
+            return Ok(table);
+        }
+
+        let name = table.clone();
+        let exists = self
+            .blocking(move |conn| {
+                conn.query_row(
+                    "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                    params![name],
+                    |_| Ok(()),
+                )
+                .optional()
+                .map(|row| row.is_some())
+            })
+            .await?;
+        if !exists {
+            let legacy = match &self.legacy {
+                Some(legacy) => legacy.entries(namespace).await?,
+                None => BTreeMap::new(),
+            };
+            let count = legacy.len();
+            let name = table.clone();
+            self.blocking(move |conn| {
+                let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+                tx.execute_batch(&format!(
+                    "CREATE TABLE IF NOT EXISTS {} (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+                    name
+                ))?;
+                for (key, value) in legacy {
+                    tx.execute(
+                        &format!(
+                            "INSERT OR IGNORE INTO {} (key, value) VALUES (?1, ?2)",
+                            name
+                        ),
+                        params![key, value.to_string()],
+                    )?;
+                }
+                tx.commit()
+            })
+            .await?;
+            if count > 0 {
+                info!(
+                    "Migrated {} state key(s) for '{}' into SQLite",
+                    count, namespace
+                );
+            }
+        }
+        ready.insert(namespace.to_string());
+        Ok(table)
+    }
+}
+
+fn parse_value(raw: String) -> rusqlite::Result<Value> {
+    serde_json::from_str(&raw).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+#[async_trait]
+impl StateStore for SqliteStateStore {
+    async fn get(&self, namespace: &str, key: &str) -> CrawlerResult<Option<Value>> {
+        let table = self.table(namespace).await?;
+        let key = key.to_string();
+        self.blocking(move |conn| {
+            conn.query_row(
+                &format!("SELECT value FROM {} WHERE key = ?1", table),
+                params![key],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .map(parse_value)
+            .transpose()
+        })
+        .await
+    }
+
+    async fn set(&self, namespace: &str, key: &str, value: Value) -> CrawlerResult<()> {
+        let table = self.table(namespace).await?;
+        let key = key.to_string();
+        self.blocking(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT INTO {} (key, value) VALUES (?1, ?2) \
+                     ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                    table
+                ),
+                params![key, value.to_string()],
+            )
+            .map(|_| ())
+        })
+        .await
+    }
+
+    async fn merge(&self, namespace: &str, key: &str, value: Value) -> CrawlerResult<Value> {
+        let table = self.table(namespace).await?;
+        let key = key.to_string();
+        self.blocking(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let current = tx
+                .query_row(
+                    &format!("SELECT value FROM {} WHERE key = ?1", table),
+                    params![key],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?
+                .map(parse_value)
+                .transpose()?;
+            let merged = merge_values(current, value);
+            tx.execute(
+                &format!(
+                    "INSERT INTO {} (key, value) VALUES (?1, ?2) \
+                     ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                    table
+                ),
+                params![key, merged.to_string()],
+            )?;
+            tx.commit()?;
+            Ok(merged)
+        })
+        .await
+    }
+
+    async fn entries(&self, namespace: &str) -> CrawlerResult<BTreeMap<String, Value>> {
+        let table = self.table(namespace).await?;
+        self.blocking(move |conn| {
+            let mut statement = conn.prepare(&format!("SELECT key, value FROM {}", table))?;
+            let rows = statement.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            rows.map(|row| {
+                let (key, raw) = row?;
+                Ok((key, parse_value(raw)?))
+            })
+            .collect()
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    /// Stands in for the bucket blobs.
+    #[derive(Default)]
+    struct MemoryStore(Mutex<BTreeMap<(String, String), Value>>);
+
+    #[async_trait]
+    impl StateStore for MemoryStore {
+        async fn get(&self, namespace: &str, key: &str) -> CrawlerResult<Option<Value>> {
+            let map = self.0.lock().unwrap();
+            Ok(map.get(&(namespace.to_string(), key.to_string())).cloned())
+        }
+
+        async fn set(&self, namespace: &str, key: &str, value: Value) -> CrawlerResult<()> {
+            let mut map = self.0.lock().unwrap();
+            map.insert((namespace.to_string(), key.to_string()), value);
+            Ok(())
+        }
+
+        async fn merge(&self, namespace: &str, key: &str, value: Value) -> CrawlerResult<Value> {
+            let mut map = self.0.lock().unwrap();
+            let id = (namespace.to_string(), key.to_string());
+            let merged = merge_values(map.remove(&id), value);
+            map.insert(id, merged.clone());
+            Ok(merged)
+        }
+
+        async fn entries(&self, namespace: &str) -> CrawlerResult<BTreeMap<String, Value>> {
+            let map = self.0.lock().unwrap();
+            Ok(map
+                .iter()
+                .filter(|((ns, _), _)| ns == namespace)
+                .map(|((_, key), value)| (key.clone(), value.clone()))
+                .collect())
+        }
+    }
+
+    /// A fresh database path, removed with its WAL files on drop.
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "cution-state-{}-{}.db",
+                name,
+                std::process::id()
+            ));
+            let db = Self(path);
+            db.remove();
+            db
+        }
+
+        fn remove(&self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.0.display(), suffix));
+            }
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            self.remove();
+        }
+    }
+
+    #[test]
+    fn merges_objects_and_arrays() {
+        let current = json!({ "ids": [1, 2], "meta": { "a": 1, "b": 1 }, "n": 1 });
+        let incoming = json!({ "ids": [2, 3], "meta": { "b": 2 }, "n": 5, "new": true });
+        assert_eq!(
+            merge_values(Some(current), incoming),
+            json!({ "ids": [1, 2, 3], "meta": { "a": 1, "b": 2 }, "n": 5, "new": true })
+        );
+        assert_eq!(merge_values(None, json!([1])), json!([1]));
+        assert_eq!(merge_values(Some(json!([1])), json!("x")), json!("x"));
+    }
+
+    #[tokio::test]
+    async fn sqlite_round_trips_and_rejects_bad_namespaces() {
+        let db = TempDb::new("round-trip");
+        let store = SqliteStateStore::open(&db.0).unwrap();
+        assert_eq!(store.get("hacker_news", "seen").await.unwrap(), None);
+        store
+            .set("hacker_news", "seen", json!([1, 2]))
+            .await
+            .unwrap();
+        store
+            .set("hacker_news", "cursor", json!("abc"))
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .merge("hacker_news", "seen", json!([2, 3]))
+                .await
+                .unwrap(),
+            json!([1, 2, 3])
+        );
+        assert_eq!(
+            store.entries("hacker_news").await.unwrap(),
+            BTreeMap::from([
+                ("cursor".to_string(), json!("abc")),
+                ("seen".to_string(), json!([1, 2, 3])),
+            ])
+        );
+        assert!(store.entries("github").await.unwrap().is_empty());
+        assert!(matches!(
+            store.get("x; DROP TABLE y", "k").await,
+            Err(CrawlerError::State(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn migrates_legacy_blob_once() {
+        let db = TempDb::new("migrate");
+        let legacy = Arc::new(MemoryStore::default());
+        legacy
+            .set("mcp_rankings", "known", json!(["a"]))
+            .await
+            .unwrap();
+        legacy.set("other", "k", json!(1)).await.unwrap();
+
+        let store = SqliteStateStore::open(&db.0)
+            .unwrap()
+            .with_legacy(legacy.clone());
+        assert_eq!(
+            store.get("mcp_rankings", "known").await.unwrap(),
+            Some(json!(["a"]))
+        );
+        store
+            .set("mcp_rankings", "known", json!(["b"]))
+            .await
+            .unwrap();
+
+        // Later changes to the blob are not imported again.
+        legacy
+            .set("mcp_rankings", "known", json!(["c"]))
+            .await
+            .unwrap();
+        let reopened = SqliteStateStore::open(&db.0).unwrap().with_legacy(legacy);
+        assert_eq!(
+            reopened.entries("mcp_rankings").await.unwrap(),
+            BTreeMap::from([("known".to_string(), json!(["b"]))])
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_merges_lose_nothing() {
+        let db = TempDb::new("concurrent");
+        // Two connections to the same file, as two processes would have.
+        let stores = [
+            Arc::new(SqliteStateStore::open(&db.0).unwrap()),
+            Arc::new(SqliteStateStore::open(&db.0).unwrap()),
+        ];
+        let tasks: Vec<_> = (0..40)
+            .map(|i| {
+                let store = stores[i % 2].clone();
+                tokio::spawn(async move { store.merge("github", "seen", json!([i])).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let seen = stores[0].get("github", "seen").await.unwrap().unwrap();
+        let mut ids: Vec<u64> = seen
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (0..40).collect::<Vec<u64>>());
+    }
+}
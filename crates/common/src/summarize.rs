@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::context::CrawlerContext;
+use crate::error::CrawlerResult;
+
+/// Text at least this long is summarized when a crawler's summarization is
+/// on; shorter text is shown as it is.
+pub const SUMMARIZE_MIN_CHARS: usize = 600;
+
+/// Turns a piece of text into a short summary.
+#[async_trait]
+pub trait Summarizer: Send + Sync {
+    async fn summarize(&self, title: &str, content: &str) -> CrawlerResult<String>;
+}
+
+/// Stand-in until an LLM backend is wired up: keeps the first `max_chars`
+/// characters of the content.
+#[derive(Debug, Clone, Copy)]
+pub struct TruncatingSummarizer {
+    pub max_chars: usize,
+}
+
+impl Default for TruncatingSummarizer {
+    fn default() -> Self {
+        Self { max_chars: 200 }
+    }
+}
+
+#[async_trait]
+impl Summarizer for TruncatingSummarizer {
+    async fn summarize(&self, _title: &str, content: &str) -> CrawlerResult<String> {
+        Ok(content.chars().take(self.max_chars).collect())
+    }
+}
+
+/// An item whose text can be replaced by a summary in the output.
+pub trait Summarizable {
+    fn title(&self) -> &str;
+    /// The text a summary would stand in for.
+    fn text(&self) -> Option<&str>;
+    fn set_summary(&mut self, summary: String);
+}
+
+/// Summarizes each item whose text is at least `SUMMARIZE_MIN_CHARS` long.
+/// A failed summary is reported on `ctx` and leaves the item without one.
+pub async fn summarize_long<T: Summarizable>(
+    ctx: &CrawlerContext,
+    summarizer: &dyn Summarizer,
+    items: &mut [T],
+) {
+    for item in items {
+        let Some(text) = item
+            .text()
+            .filter(|t| t.chars().count() >= SUMMARIZE_MIN_CHARS)
+        else {
+            continue;
+        };
+        match summarizer.summarize(item.title(), text).await {
+            Ok(summary) => item.set_summary(summary.trim().to_string()),
+            Err(e) => {
+                warn!("Error summarizing {}: {}", item.title(), e);
+                ctx.item_failed(format!("summary of \"{}\"", item.title()), &e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CrawlerError;
+
+    struct Note {
+        title: &'static str,
+        text: Option<String>,
+        summary: Option<String>,
+    }
+
+    impl Summarizable for Note {
+        fn title(&self) -> &str {
+            self.title
+        }
+
+        fn text(&self) -> Option<&str> {
+            self.text.as_deref()
+        }
+
+        fn set_summary(&mut self, summary: String) {
+            self.summary = Some(summary);
+        }
+    }
+
+    struct FailingOn(&'static str);
+
+    #[async_trait]
+    impl Summarizer for FailingOn {
+        async fn summarize(&self, title: &str, content: &str) -> CrawlerResult<String> {
+            if title == self.0 {
+                return Err(CrawlerError::Parse("no summary".to_string()));
+            }
+            Ok(format!(" {} chars ", content.chars().count()))
+        }
+    }
+
+    #[tokio::test]
+    async fn summarizes_only_long_text_and_reports_failures() {
+        let long = "x".repeat(SUMMARIZE_MIN_CHARS);
+        let mut notes = [
+            Note {
+                title: "long",
+                text: Some(long.clone()),
+                summary: None,
+            },
+            Note {
+                title: "short",
+                text: Some("x".repeat(SUMMARIZE_MIN_CHARS - 1)),
+                summary: None,
+            },
+            Note {
+                title: "empty",
+                text: None,
+                summary: None,
+            },
+            Note {
+                title: "broken",
+                text: Some(long),
+                summary: None,
+            },
+        ];
+        let ctx = CrawlerContext::default();
+        summarize_long(&ctx, &FailingOn("broken"), &mut notes).await;
+
+        let summaries: Vec<_> = notes.iter().map(|n| n.summary.as_deref()).collect();
+        assert_eq!(summaries, [Some("600 chars"), None, None, None]);
+        let failed: Vec<String> = ctx.item_errors.take().into_iter().map(|e| e.item).collect();
+        assert_eq!(failed, ["summary of \"broken\""]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Summaries keyed by a hash of the text they summarize and kept in storage
+//! under `summaries/{hash}.txt`, so an article that turns up again on a later
+//! day or after a redeploy reuses its summary instead of another LLM call.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use time::{Date, Duration};
+use tracing::{info, warn};
+
+use crate::dedup::parse_date;
+use crate::error::CrawlerResult;
+use crate::publish::sha256_hex;
+use crate::summarize::Summarizer;
+use crate::supabase_client::SupabaseStorageClient;
+
+/// Storage folder holding the cached summaries.
+pub const CACHE_PREFIX: &str = "summaries";
+/// Summaries longer than this are not worth caching; they are likely a
+/// summarizer echoing its input.
+pub const MAX_SUMMARY_BYTES: usize = 16 * 1024;
+/// How long feed and YouTube summaries are reused when
+/// `SUMMARY_CACHE_TTL_DAYS` is unset; cleanup prunes entries older than this.
+pub const DEFAULT_TTL_DAYS: u32 = 7;
+/// Most summaries remembered in memory during one run.
+pub const MAX_MEMO_ENTRIES: usize = 1024;
+
+/// Reads and writes `summaries/{hash}.txt`. Each file holds the day it was
+/// written on its first line and the summary after it; entries older than the
+/// TTL count as misses and are overwritten with a fresh summary.
+#[derive(Clone)]
+pub struct SummaryCache {
+    storage: SupabaseStorageClient,
+    today: Date,
+    ttl: Duration,
+}
+
+impl SummaryCache {
+    pub fn new(storage: SupabaseStorageClient, today: Date, ttl_days: u32) -> Self {
+        Self {
+            storage,
+            today,
+            ttl: Duration::days(ttl_days.into()),
+        }
+    }
+
+    /// Storage path of the summary for `content`.
+    pub fn path(content: &str) -> String {
+        format!("{}/{}.txt", CACHE_PREFIX, sha256_hex(content))
+    }
+
+    /// The cached summary of `content`, if there is a fresh one.
+    pub async fn get(&self, content: &str) -> CrawlerResult<Option<String>> {
+        let Some(entry) = self.storage.download_file(&Self::path(content)).await? else {
+            return Ok(None);
+        };
+        let Some((written, summary)) = entry.split_once('\n') else {
+            return Ok(None);
+        };
+        let fresh = parse_date(written).is_some_and(|date| self.today - date < self.ttl);
+        Ok(fresh.then(|| summary.to_string()))
+    }
+
+    pub async fn put(&self, content: &str, summary: &str) -> CrawlerResult<()> {
+        if summary.len() > MAX_SUMMARY_BYTES {
+            return Ok(());
+        }
+        let entry = format!("{}\n{}", self.today, summary);
+        self.storage
+            .upload_file(&Self::path(content), entry, "text/plain; charset=utf-8")
+            .await?;
+        Ok(())
+    }
+}
+
+/// A `Summarizer` that checks `SummaryCache` (and an in-run memo in front of
+/// it) before calling `inner`, and caches what `inner` returns. Cache errors
+/// are logged and fall through to `inner`.
+pub struct CachedSummarizer {
+    inner: Arc<dyn Summarizer>,
+    cache: SummaryCache,
+    memo: Mutex<HashMap<String, String>>,
+}
+
+impl CachedSummarizer {
+    pub fn new(inner: Arc<dyn Summarizer>, cache: SummaryCache) -> Self {
+        Self {
+            inner,
+            cache,
+            memo: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `inner` behind the cache in `storage` when `ttl_days` is set, for a run
+    /// on `today`; otherwise `inner` as it is.
+    pub fn wrap(
+        inner: Arc<dyn Summarizer>,
+        storage: SupabaseStorageClient,
+        today: Date,
+        ttl_days: Option<u32>,
+    ) -> Arc<dyn Summarizer> {
+        match ttl_days {
+            Some(ttl_days) => Arc::new(Self::new(
+                inner,
+                SummaryCache::new(storage, today, ttl_days),
+            )),
+            None => inner,
+        }
+    }
+
+    fn remember(&self, hash: String, summary: &str) {
+        let mut memo = self.memo.lock().unwrap_or_else(|e| e.into_inner());
+        if memo.len() >= MAX_MEMO_ENTRIES {
+            memo.clear();
+        }
+        memo.insert(hash, summary.to_string());
+    }
+}
+
+#[async_trait]
+impl Summarizer for CachedSummarizer {
+    async fn summarize(&self, title: &str, content: &str) -> CrawlerResult<String> {
+        let hash = sha256_hex(content);
+        if let Some(summary) = self
+            .memo
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&hash)
+        {
+            return Ok(summary.clone());
+        }
+        match self.cache.get(content).await {
+            Ok(Some(summary)) => {
+                info!("Reusing cached summary for {}", title);
+                self.remember(hash, &summary);
+                return Ok(summary);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Summary cache lookup failed for {}: {}", title, e),
+        }
+
+        let summary = self.inner.summarize(title, content).await?;
+        if let Err(e) = self.cache.put(content, &summary).await {
+            warn!("Failed to cache the summary of {}: {}", title, e);
+        }
+        self.remember(hash, &summary);
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use time::macros::date;
+
+    /// Counts calls and summarizes as the first word.
+    #[derive(Default)]
+    struct Counting(AtomicUsize);
+
+    #[async_trait]
+    impl Summarizer for Counting {
+        async fn summarize(&self, _title: &str, content: &str) -> CrawlerResult<String> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(content
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string())
+        }
+    }
+
+    fn temp_storage(name: &str) -> (std::path::PathBuf, SupabaseStorageClient) {
+        let dir = std::env::temp_dir().join(format!(
+            "cution-summary-cache-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        (dir.clone(), SupabaseStorageClient::local(dir))
+    }
+
+    #[tokio::test]
+    async fn identical_text_is_summarized_once_across_runs() {
+        let (dir, storage) = temp_storage("runs");
+        let llm = Arc::new(Counting::default());
+
+        let day1 = CachedSummarizer::new(
+            llm.clone(),
+            SummaryCache::new(storage.clone(), date!(2024 - 05 - 01), 30),
+        );
+        assert_eq!(
+            day1.summarize("a", "Rust 2.0 is out").await.unwrap(),
+            "Rust"
+        );
+        assert_eq!(
+            day1.summarize("a again", "Rust 2.0 is out").await.unwrap(),
+            "Rust"
+        );
+        assert_eq!(day1.summarize("b", "Zig 1.0 is out").await.unwrap(), "Zig");
+        assert_eq!(llm.0.load(Ordering::SeqCst), 2);
+
+        // A new process on a later day still finds the stored summary.
+        let day5 = CachedSummarizer::new(
+            llm.clone(),
+            SummaryCache::new(storage.clone(), date!(2024 - 05 - 05), 30),
+        );
+        assert_eq!(
+            day5.summarize("a", "Rust 2.0 is out").await.unwrap(),
+            "Rust"
+        );
+        assert_eq!(llm.0.load(Ordering::SeqCst), 2);
+
+        let stored = storage
+            .download_file(&SummaryCache::path("Rust 2.0 is out"))
+            .await
+            .unwrap();
+        assert_eq!(stored.as_deref(), Some("2024-05-01\nRust"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn expired_and_malformed_entries_are_misses() {
+        let (dir, storage) = temp_storage("ttl");
+        let path = SummaryCache::path("text");
+        storage
+            .upload_file(&path, "2024-05-01\nold".to_string(), "text/plain")
+            .await
+            .unwrap();
+
+        let cache = SummaryCache::new(storage.clone(), date!(2024 - 05 - 07), 7);
+        assert_eq!(cache.get("text").await.unwrap().as_deref(), Some("old"));
+        let cache = SummaryCache::new(storage.clone(), date!(2024 - 05 - 08), 7);
+        assert_eq!(cache.get("text").await.unwrap(), None);
+
+        storage
+            .upload_file(&path, "no date line".to_string(), "text/plain")
+            .await
+            .unwrap();
+        assert_eq!(cache.get("text").await.unwrap(), None);
+
+        cache
+            .put("text", &"x".repeat(MAX_SUMMARY_BYTES + 1))
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.download_file(&path).await.unwrap().as_deref(),
+            Some("no date line"),
+            "oversized summaries are not cached"
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,13 +1,61 @@
 use anyhow::Result;
-use reqwest::Client;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use time::{Date, OffsetDateTime};
 use tracing::info;
 
+use crate::audit;
+use crate::config::Config;
+use crate::dedup::parse_date;
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::http::{ClientFactory, HttpClient};
+use crate::retry::{retry_async_with_delay, RetryPolicy};
+
+/// Objects requested per `list_files` page.
+const LIST_PAGE_SIZE: usize = 1000;
+/// Paths sent per `delete_files` request.
+const DELETE_BATCH_SIZE: usize = 1000;
+/// Longest wait honoured from a rate-limit header.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct ListedObject {
+    name: String,
+    #[serde(default)]
+    updated_at: Option<String>,
+    #[serde(default)]
+    metadata: Option<ObjectMetadata>,
+}
+
+#[derive(Deserialize)]
+struct ObjectMetadata {
+    #[serde(default)]
+    size: Option<u64>,
+}
+
+/// An entry returned by `list_objects`; folders have no size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageObject {
+    /// Name relative to the listed prefix.
+    pub name: String,
+    pub size: Option<u64>,
+    /// UTC day the object was last written, when storage reports it.
+    pub updated: Option<Date>,
+}
+
 #[derive(Clone)]
 pub struct SupabaseStorageClient {
     client: Client,
     base_url: String,
     api_key: String,
     bucket_name: String,
+    /// Set for a client that reads and writes files under a local directory.
+    local_dir: Option<PathBuf>,
+    /// Applied to uploads that fail with a network error, 429 or 5xx.
+    upload_retry: RetryPolicy,
 }
 
 impl SupabaseStorageClient {
@@ -17,36 +65,592 @@ impl SupabaseStorageClient {
             base_url: base_url.trim_end_matches('/').to_string(),
             api_key: api_key.to_string(),
             bucket_name: bucket_name.to_string(),
+            local_dir: None,
+            upload_retry: RetryPolicy::default(),
         }
     }
 
-    pub async fn upload_file(&self, path: &str, content: String, content_type: &str) -> Result<()> {
-        let url = format!(
+    /// Retries failed uploads up to `max_retries` times, waiting `base_delay`
+    /// before the first retry and doubling it for each further one.
+    pub fn with_retry(mut self, max_retries: u32, base_delay: Duration) -> Self {
+        self.upload_retry = RetryPolicy {
+            max_attempts: max_retries.saturating_add(1),
+            base_delay,
+            ..RetryPolicy::default()
+        };
+        self
+    }
+
+    /// A client that keeps objects as files under `dir`, for offline runs.
+    pub fn local(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            local_dir: Some(dir.into()),
+            ..Self::new("", "", "")
+        }
+    }
+
+    /// The configured bucket, or the `LOCAL_STORAGE_DIR` directory when set.
+    /// Sends requests through `client`'s connection pool, timeout and proxy.
+    pub fn with_client(mut self, client: &HttpClient) -> Self {
+        self.client = client.inner().clone();
+        self
+    }
+
+    /// A client for the configured bucket, with the shared HTTP settings, or
+    /// for `LOCAL_STORAGE_DIR` when that is set.
+    pub fn from_config(config: &Config) -> CrawlerResult<Self> {
+        Ok(match &config.local_storage_dir {
+            Some(dir) => Self::local(dir),
+            None => Self::new(
+                &config.supabase.storage_url,
+                &config.supabase.key,
+                &config.supabase.bucket,
+            )
+            .with_client(&ClientFactory::from_config(config).build()?)
+            .with_retry(
+                config.storage_upload_retries,
+                config.storage_upload_retry_base_delay,
+            ),
+        })
+    }
+
+    fn local_path(&self, path: &str) -> Option<PathBuf> {
+        self.local_dir
+            .as_ref()
+            .map(|dir| dir.join(path.trim_start_matches('/')))
+    }
+
+    fn object_url(&self, path: &str) -> String {
+        format!(
             "{}/object/{}/{}",
             self.base_url,
             self.bucket_name,
             path.trim_start_matches('/')
+        )
+    }
+
+    /// Downloads the object at `path`, returning `None` when it does not exist.
+    pub async fn download_file(&self, path: &str) -> Result<Option<String>> {
+        if let Some(file) = self.local_path(path) {
+            return match tokio::fs::read_to_string(&file).await {
+                Ok(body) => Ok(Some(body)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            };
+        }
+        let url = self.object_url(path);
+        let response = audit::send(
+            self.client
+                .get(&url)
+                .header("apikey", &self.api_key)
+                .header("Authorization", format!("Bearer {}", self.api_key)),
+        )
+        .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        if status.is_success() {
+            return Ok(Some(body));
+        }
+        if is_not_found_response(status, &body) {
+            return Ok(None);
+        }
+        anyhow::bail!(
+            "Failed to download from Supabase Storage ({}): {} {}",
+            url,
+            status,
+            body
         );
+    }
 
-        info!("Uploading to Supabase Storage: {} ({} bytes)", url, content.len());
+    /// Lists the entries directly under `prefix` (files and folders), relative to
+    /// the prefix, following `offset` pagination until a short page comes back.
+    pub async fn list_files(&self, prefix: &str) -> Result<Vec<String>> {
+        let objects = self.list_objects(prefix).await?;
+        Ok(objects.into_iter().map(|object| object.name).collect())
+    }
 
-        let response = self
-            .client
-            .post(&url)
-            .header("apikey", &self.api_key)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", content_type)
-            .header("x-upsert", "true")
-            .body(content)
-            .send()
+    /// Like `list_files`, with the size of each object.
+    pub async fn list_objects(&self, prefix: &str) -> Result<Vec<StorageObject>> {
+        if let Some(dir) = self.local_path(prefix.trim_matches('/')) {
+            return list_local(dir).await;
+        }
+        let url = format!("{}/object/list/{}", self.base_url, self.bucket_name);
+        let prefix = prefix.trim_matches('/');
+        let mut objects = Vec::new();
+        loop {
+            let response = audit::send(
+                self.client
+                    .post(&url)
+                    .header("apikey", &self.api_key)
+                    .header("Authorization", format!("Bearer {}", self.api_key))
+                    .json(&serde_json::json!({
+                        "prefix": prefix,
+                        "limit": LIST_PAGE_SIZE,
+                        "offset": objects.len(),
+                        "sortBy": { "column": "name", "order": "asc" },
+                    })),
+            )
             .await?;
 
-        if response.status().is_success() {
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                anyhow::bail!(
+                    "Failed to list Supabase Storage ({}, prefix '{}'): {} {}",
+                    url,
+                    prefix,
+                    status,
+                    body
+                );
+            }
+            let page: Vec<ListedObject> = response.json().await?;
+            let page_len = page.len();
+            objects.extend(page.into_iter().map(|object| {
+                StorageObject {
+                    name: object.name,
+                    size: object.metadata.and_then(|m| m.size),
+                    updated: object
+                        .updated_at
+                        .as_deref()
+                        .and_then(|at| parse_date(at.get(..10)?)),
+                }
+            }));
+            if page_len < LIST_PAGE_SIZE {
+                return Ok(objects);
+            }
+        }
+    }
+
+    /// Deletes the objects at `paths` with the batch-delete API, in batches of
+    /// `DELETE_BATCH_SIZE`. Paths that do not exist are ignored.
+    pub async fn delete_files(&self, paths: &[String]) -> Result<()> {
+        if let Some(dir) = &self.local_dir {
+            for path in paths {
+                let file = dir.join(path.trim_start_matches('/'));
+                match tokio::fs::remove_file(&file).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+            return Ok(());
+        }
+        let url = format!("{}/object/{}", self.base_url, self.bucket_name);
+        for batch in paths.chunks(DELETE_BATCH_SIZE) {
+            let response = audit::send(
+                self.client
+                    .delete(&url)
+                    .header("apikey", &self.api_key)
+                    .header("Authorization", format!("Bearer {}", self.api_key))
+                    .json(&serde_json::json!({ "prefixes": batch })),
+            )
+            .await?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                anyhow::bail!(
+                    "Failed to delete from Supabase Storage ({}): {} {}",
+                    url,
+                    status,
+                    body
+                );
+            }
+            info!("Deleted {} object(s) from Supabase Storage", batch.len());
+        }
+        Ok(())
+    }
+
+    /// Appends the text `append` returns for the current content (empty when
+    /// the object does not exist) to `path`. Storage has no native append, so
+    /// this is a download followed by an upload; callers serialize concurrent
+    /// appends to the same path themselves. Nothing is written when `append`
+    /// returns an empty string.
+    pub async fn append_file<F>(&self, path: &str, content_type: &str, append: F) -> Result<()>
+    where
+        F: FnOnce(&str) -> String,
+    {
+        let mut content = self.download_file(path).await?.unwrap_or_default();
+        let addition = append(&content);
+        if addition.is_empty() {
+            return Ok(());
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&addition);
+        self.upload_file(path, content, content_type).await
+    }
+
+    /// Uploads `content` to `path`, overwriting any existing object.
+    pub async fn upload_file(&self, path: &str, content: String, content_type: &str) -> Result<()> {
+        self.upload(path, content, content_type, true).await?;
+        Ok(())
+    }
+
+    /// Uploads `content` to `path`, failing with `CrawlerError::AlreadyExists`
+    /// instead of overwriting an existing object.
+    pub async fn upload_new_only(
+        &self,
+        path: &str,
+        content: String,
+        content_type: &str,
+    ) -> CrawlerResult<()> {
+        self.upload(path, content, content_type, false).await
+    }
+
+    async fn upload(
+        &self,
+        path: &str,
+        content: String,
+        content_type: &str,
+        overwrite: bool,
+    ) -> CrawlerResult<()> {
+        if let Some(file) = self.local_path(path) {
+            return write_local(file, content, overwrite).await;
+        }
+        let url = self.object_url(path);
+
+        info!(
+            "Uploading to Supabase Storage: {} ({} bytes)",
+            url,
+            content.len()
+        );
+
+        // Network errors, 429s and 5xx are retried; other statuses (auth,
+        // validation, duplicates) fail right away.
+        let response = retry_async_with_delay(
+            &self.upload_retry,
+            |failure: &UploadFailure| failure.retryable,
+            |failure| failure.wait,
+            |_| async {
+                let response = audit::send(
+                    self.client
+                        .post(&url)
+                        .header("apikey", &self.api_key)
+                        .header("Authorization", format!("Bearer {}", self.api_key))
+                        .header("Content-Type", content_type)
+                        .header("x-upsert", if overwrite { "true" } else { "false" })
+                        .body(content.clone()),
+                )
+                .await
+                .map_err(|e| UploadFailure {
+                    retryable: e.is_connect() || e.is_timeout() || e.is_request(),
+                    wait: None,
+                    error: e.into(),
+                })?;
+                let status = response.status();
+                if status != StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+                    return Ok(response);
+                }
+                // Supabase may tell us how long to back off.
+                let wait = rate_limit_wait(response.headers(), unix_now())
+                    .map(|wait| wait.min(MAX_RATE_LIMIT_WAIT));
+                let error_text = response.text().await.unwrap_or_default();
+                let error = if status == StatusCode::TOO_MANY_REQUESTS {
+                    crate::http::status_error(&url, status, &error_text)
+                } else {
+                    CrawlerError::StorageUpload(format!(
+                        "Failed to upload to Supabase Storage ({}): {}",
+                        url, error_text
+                    ))
+                };
+                Err(UploadFailure {
+                    error,
+                    retryable: true,
+                    wait,
+                })
+            },
+        )
+        .await
+        .map_err(|failure| failure.error)?;
+
+        let status = response.status();
+        if status.is_success() {
             info!("Successfully uploaded {} to Supabase Storage.", path);
-            Ok(())
-        } else {
-            let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Failed to upload to Supabase Storage ({}): {}", url, error_text);
+            return Ok(());
+        }
+
+        let error_text = response.text().await.unwrap_or_default();
+        if is_duplicate_response(status, &error_text) {
+            return Err(CrawlerError::AlreadyExists(path.to_string()));
+        }
+        Err(CrawlerError::StorageUpload(format!(
+            "Failed to upload to Supabase Storage ({}): {}",
+            url, error_text
+        )))
+    }
+}
+
+/// A failed upload attempt and whether, and after how long, to try again.
+struct UploadFailure {
+    error: CrawlerError,
+    retryable: bool,
+    /// Wait requested by the rate-limit headers, if any.
+    wait: Option<Duration>,
+}
+
+impl std::fmt::Display for UploadFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+async fn list_local(dir: PathBuf) -> Result<Vec<StorageObject>> {
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut objects = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        objects.push(StorageObject {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            size: metadata.is_file().then_some(metadata.len()),
+            updated: metadata
+                .modified()
+                .ok()
+                .map(|at| OffsetDateTime::from(at).date()),
+        });
+    }
+    objects.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(objects)
+}
+
+async fn write_local(file: PathBuf, content: String, overwrite: bool) -> CrawlerResult<()> {
+    if !overwrite && tokio::fs::try_exists(&file).await? {
+        return Err(CrawlerError::AlreadyExists(file.display().to_string()));
+    }
+    if let Some(parent) = file.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&file, content).await?;
+    info!("Wrote {}", file.display());
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// How long a 429 asks us to wait: `Retry-After` in seconds, else
+/// `x-ratelimit-reset` as seconds to wait or, when it looks like a Unix
+/// timestamp, the time left until then. HTTP-date values are not understood.
+fn rate_limit_wait(headers: &HeaderMap, now: u64) -> Option<Duration> {
+    let number = |name: &str| -> Option<f64> {
+        let value = headers
+            .get(name)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<f64>()
+            .ok()?;
+        (value.is_finite() && value >= 0.0).then_some(value)
+    };
+    if let Some(seconds) = number("retry-after") {
+        return Some(Duration::from_secs_f64(seconds));
+    }
+    let reset = number("x-ratelimit-reset")?;
+    // Anything past 2001 is an epoch timestamp rather than a delay.
+    if reset > 1e9 {
+        return Some(Duration::from_secs((reset as u64).saturating_sub(now)));
+    }
+    Some(Duration::from_secs_f64(reset))
+}
+
+/// Supabase reports an existing object either as HTTP 409 or as a 400 whose
+/// JSON body carries `"statusCode": "409"` / `"error": "Duplicate"`.
+fn is_duplicate_response(status: StatusCode, body: &str) -> bool {
+    match status {
+        StatusCode::CONFLICT => true,
+        StatusCode::BAD_REQUEST => {
+            body.contains("\"409\"")
+                || body.contains("Duplicate")
+                || body.contains("already exists")
         }
+        _ => false,
+    }
+}
+
+/// Missing objects come back as 404, or as a 400 with a `not_found` error body.
+fn is_not_found_response(status: StatusCode, body: &str) -> bool {
+    match status {
+        StatusCode::NOT_FOUND => true,
+        StatusCode::BAD_REQUEST => body.contains("not_found") || body.contains("Object not found"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_duplicate_responses() {
+        assert!(is_duplicate_response(StatusCode::CONFLICT, ""));
+        assert!(is_duplicate_response(
+            StatusCode::BAD_REQUEST,
+            r#"{"statusCode":"409","error":"Duplicate","message":"The resource already exists"}"#
+        ));
+        assert!(!is_duplicate_response(
+            StatusCode::BAD_REQUEST,
+            r#"{"statusCode":"400","error":"InvalidKey"}"#
+        ));
+        assert!(!is_duplicate_response(StatusCode::OK, "Duplicate"));
+        assert!(!is_duplicate_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "the resource already exists"
+        ));
+    }
+
+    #[test]
+    fn reads_rate_limit_headers() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in pairs {
+                map.insert(*name, value.parse().unwrap());
+            }
+            map
+        };
+        let now = 1_700_000_000;
+        assert_eq!(
+            rate_limit_wait(&headers(&[("retry-after", "7")]), now),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            rate_limit_wait(
+                &headers(&[("retry-after", "2"), ("x-ratelimit-reset", "30")]),
+                now
+            ),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            rate_limit_wait(&headers(&[("x-ratelimit-reset", "1.5")]), now),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            rate_limit_wait(&headers(&[("x-ratelimit-reset", "1700000012")]), now),
+            Some(Duration::from_secs(12))
+        );
+        assert_eq!(
+            rate_limit_wait(
+                &headers(&[("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT")]),
+                now
+            ),
+            None
+        );
+        assert_eq!(rate_limit_wait(&HeaderMap::new(), now), None);
+    }
+
+    #[tokio::test]
+    async fn upload_waits_out_a_rate_limit() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let responses = [
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 4\r\nConnection: close\r\n\r\nslow",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+            ];
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                // Read the head and the 5-byte body.
+                while !request.ends_with(b"hello") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let client = SupabaseStorageClient::new(&base, "key", "bucket");
+        client
+            .upload_file("a.md", "hello".to_string(), "text/markdown")
+            .await
+            .unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn local_client_keeps_objects_as_files() {
+        let dir = std::env::temp_dir().join(format!("cution-local-{}", std::process::id()));
+        let client = SupabaseStorageClient::local(&dir);
+        assert_eq!(client.download_file("2024-05-10/a.md").await.unwrap(), None);
+        assert!(client.list_objects("2024-05-10").await.unwrap().is_empty());
+
+        client
+            .upload_file("2024-05-10/a.md", "hello".to_string(), "text/markdown")
+            .await
+            .unwrap();
+        client
+            .upload_file("2024-05-10/sub/b.md", "x".to_string(), "text/markdown")
+            .await
+            .unwrap();
+        assert_eq!(
+            client
+                .download_file("/2024-05-10/a.md")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("hello")
+        );
+        let today = OffsetDateTime::now_utc().date();
+        assert_eq!(
+            client.list_objects("2024-05-10/").await.unwrap(),
+            vec![
+                StorageObject {
+                    name: "a.md".to_string(),
+                    size: Some(5),
+                    updated: Some(today)
+                },
+                StorageObject {
+                    name: "sub".to_string(),
+                    size: None,
+                    updated: Some(today)
+                },
+            ]
+        );
+        assert!(matches!(
+            client
+                .upload_new_only("2024-05-10/a.md", "again".to_string(), "text/markdown")
+                .await,
+            Err(CrawlerError::AlreadyExists(_))
+        ));
+        client
+            .delete_files(&[
+                "2024-05-10/a.md".to_string(),
+                "2024-05-10/gone.md".to_string(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(client.download_file("2024-05-10/a.md").await.unwrap(), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn detects_missing_objects() {
+        assert!(is_not_found_response(StatusCode::NOT_FOUND, ""));
+        assert!(is_not_found_response(
+            StatusCode::BAD_REQUEST,
+            r#"{"statusCode":"404","error":"not_found","message":"Object not found"}"#
+        ));
+        assert!(!is_not_found_response(
+            StatusCode::UNAUTHORIZED,
+            "invalid jwt"
+        ));
+        assert!(!is_not_found_response(StatusCode::OK, "not_found"));
+        assert!(!is_not_found_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Object not found"
+        ));
     }
 }
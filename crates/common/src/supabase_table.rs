@@ -0,0 +1,62 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::audit;
+use crate::config::Config;
+use crate::error::CrawlerResult;
+use crate::http::{ClientFactory, HttpClient};
+
+/// Writes rows to Supabase tables through the PostgREST API.
+#[derive(Clone)]
+pub struct SupabaseTableClient {
+    client: HttpClient,
+    rest_url: String,
+    api_key: String,
+}
+
+impl SupabaseTableClient {
+    /// `base_url` is the project URL (`SUPABASE_URL`), not the storage URL.
+    pub fn new(client: HttpClient, base_url: &str, api_key: &str) -> Self {
+        Self {
+            client,
+            rest_url: format!("{}/rest/v1", base_url.trim_end_matches('/')),
+            api_key: api_key.to_string(),
+        }
+    }
+
+    /// A client for the configured project, with the shared HTTP settings.
+    pub fn from_config(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self::new(
+            ClientFactory::from_config(config).build()?,
+            &config.supabase.url,
+            &config.supabase.key,
+        ))
+    }
+
+    /// Inserts `rows` into `table`, updating those whose `on_conflict`
+    /// column already exists.
+    pub async fn upsert(&self, table: &str, on_conflict: &str, rows: &[Value]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let url = format!("{}/{}?on_conflict={}", self.rest_url, table, on_conflict);
+        let response = audit::send(
+            self.client
+                .inner()
+                .post(&url)
+                .header("apikey", &self.api_key)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Content-Type", "application/json")
+                .header("Prefer", "resolution=merge-duplicates,return=minimal")
+                .body(Value::from(rows.to_vec()).to_string()),
+        )
+        .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Upsert into {} failed with {}: {}", table, status, body);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+//! Token and cost accounting for LLM calls. Clients report each response's
+//! token counts to a shared `UsageTracker`, which attributes them to the
+//! crawler (or post-run step) running at the time, prices them from
+//! `LLM_PRICES` and enforces the monthly `CUTION_LLM_BUDGET_USD`.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::{info, warn};
+
+use crate::error::{CrawlerError, CrawlerResult};
+use crate::state::StateStore;
+
+/// State namespace holding the running cost of each month, keyed `YYYY-MM`.
+pub const NAMESPACE: &str = "llm_usage";
+/// Source recorded for calls made outside any `attribute` scope.
+pub const UNATTRIBUTED: &str = "other";
+
+tokio::task_local! {
+    static SOURCE: String;
+}
+
+/// Runs `future` with the LLM calls it makes attributed to `source`.
+pub async fn attribute<F: Future>(source: &str, future: F) -> F::Output {
+    SOURCE.scope(source.to_string(), future).await
+}
+
+fn current_source() -> String {
+    SOURCE
+        .try_with(|source| source.clone())
+        .unwrap_or_else(|_| UNATTRIBUTED.to_string())
+}
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// Parses `LLM_PRICES`: comma-separated `model=input/output` entries, in USD
+/// per million tokens, e.g. `gemini-2.0-flash=0.10/0.40`.
+pub fn parse_prices(raw: &str) -> Result<HashMap<String, ModelPrice>, String> {
+    let mut prices = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (model, price) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected model=input/output, got '{}'", entry))?;
+        let (input, output) = price
+            .split_once('/')
+            .ok_or_else(|| format!("expected input/output prices for '{}'", model.trim()))?;
+        let parse = |raw: &str| {
+            raw.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|p| p.is_finite() && *p >= 0.0)
+                .ok_or_else(|| format!("invalid price '{}' for '{}'", raw.trim(), model.trim()))
+        };
+        prices.insert(
+            model.trim().to_string(),
+            ModelPrice {
+                input: parse(input)?,
+                output: parse(output)?,
+            },
+        );
+    }
+    Ok(prices)
+}
+
+/// Tokens one source spent on one model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageRow {
+    pub source: String,
+    pub model: String,
+    pub calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// `None` when `LLM_PRICES` has no price for the model.
+    pub cost_usd: Option<f64>,
+}
+
+/// Usage recorded since the tracker was last drained.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageSummary {
+    pub rows: Vec<UsageRow>,
+    /// Cost of the priced rows.
+    pub total_cost_usd: f64,
+    /// Calls refused because the monthly budget was spent.
+    pub skipped_calls: u64,
+}
+
+impl UsageSummary {
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty() && self.skipped_calls == 0
+    }
+
+    /// Total tokens per source, for log lines.
+    pub fn by_source(&self) -> BTreeMap<&str, (u64, f64)> {
+        let mut totals: BTreeMap<&str, (u64, f64)> = BTreeMap::new();
+        for row in &self.rows {
+            let total = totals.entry(&row.source).or_default();
+            total.0 += row.input_tokens + row.output_tokens;
+            total.1 += row.cost_usd.unwrap_or(0.0);
+        }
+        totals
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Counts {
+    calls: u64,
+    input: u64,
+    output: u64,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    prices: HashMap<String, ModelPrice>,
+    counts: BTreeMap<(String, String), Counts>,
+    skipped: u64,
+    /// `(limit, spent this month before the current counts)`.
+    budget: Option<(f64, f64)>,
+}
+
+impl Inner {
+    fn cost(&self) -> f64 {
+        self.counts
+            .iter()
+            .filter_map(|((_, model), counts)| cost(self.prices.get(model)?, counts))
+            .sum()
+    }
+}
+
+fn cost(price: &ModelPrice, counts: &Counts) -> Option<f64> {
+    Some((counts.input as f64 * price.input + counts.output as f64 * price.output) / 1_000_000.0)
+}
+
+/// Shared by every LLM client in the process; see `global`.
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    inner: Mutex<Inner>,
+}
+
+impl UsageTracker {
+    pub fn new(prices: HashMap<String, ModelPrice>) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                prices,
+                ..Inner::default()
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_prices(&self, prices: HashMap<String, ModelPrice>) {
+        self.lock().prices = prices;
+    }
+
+    /// Caps spending at `limit_usd` for the month, of which `spent_usd` is
+    /// already gone.
+    pub fn set_budget(&self, limit_usd: f64, spent_usd: f64) {
+        self.lock().budget = Some((limit_usd, spent_usd));
+    }
+
+    /// Records a call to `model` for the current source.
+    pub fn record(&self, model: &str, input_tokens: u64, output_tokens: u64) {
+        let source = current_source();
+        let mut inner = self.lock();
+        let counts = inner.counts.entry((source, model.to_string())).or_default();
+        counts.calls += 1;
+        counts.input += input_tokens;
+        counts.output += output_tokens;
+    }
+
+    /// Fails with `CrawlerError::BudgetExceeded` once the month's spending has
+    /// reached the budget. Clients call this before each request; callers
+    /// treat the error like any other failed enrichment and skip it.
+    pub fn check_budget(&self) -> CrawlerResult<()> {
+        let mut inner = self.lock();
+        let Some((limit, spent)) = inner.budget else {
+            return Ok(());
+        };
+        let total = spent + inner.cost();
+        if total < limit {
+            return Ok(());
+        }
+        inner.skipped += 1;
+        if inner.skipped == 1 {
+            warn!(
+                "LLM budget of ${:.2} spent (${:.2} this month); skipping LLM calls",
+                limit, total
+            );
+        }
+        Err(CrawlerError::BudgetExceeded(format!(
+            "${:.2} of ${:.2} spent this month",
+            total, limit
+        )))
+    }
+
+    pub fn summary(&self) -> UsageSummary {
+        let inner = self.lock();
+        let rows = inner
+            .counts
+            .iter()
+            .map(|((source, model), counts)| UsageRow {
+                source: source.clone(),
+                model: model.clone(),
+                calls: counts.calls,
+                input_tokens: counts.input,
+                output_tokens: counts.output,
+                cost_usd: inner.prices.get(model).and_then(|p| cost(p, counts)),
+            })
+            .collect();
+        UsageSummary {
+            rows,
+            total_cost_usd: inner.cost(),
+            skipped_calls: inner.skipped,
+        }
+    }
+
+    /// The summary so far, resetting the counts for the next run. The cost
+    /// moves into the budget's spent amount.
+    pub fn take(&self) -> UsageSummary {
+        let summary = self.summary();
+        let mut inner = self.lock();
+        if let Some((_, spent)) = &mut inner.budget {
+            *spent += summary.total_cost_usd;
+        }
+        inner.counts.clear();
+        inner.skipped = 0;
+        summary
+    }
+}
+
+/// The process-wide tracker the Gemini and xAI clients report to.
+pub fn global() -> Arc<UsageTracker> {
+    static GLOBAL: OnceLock<Arc<UsageTracker>> = OnceLock::new();
+    GLOBAL.get_or_init(Arc::default).clone()
+}
+
+/// `YYYY-MM` key of `date`'s month.
+pub fn month_key(date: time::Date) -> String {
+    format!("{}-{:02}", date.year(), u8::from(date.month()))
+}
+
+/// The cost recorded for `month` so far.
+pub async fn month_spent(store: &dyn StateStore, month: &str) -> CrawlerResult<f64> {
+    Ok(store
+        .get(NAMESPACE, month)
+        .await?
+        .and_then(|value| value.get("cost_usd").and_then(Value::as_f64))
+        .unwrap_or(0.0))
+}
+
+/// Adds `cost_usd` to `month`'s running total and returns the new total.
+pub async fn add_month_spent(
+    store: &dyn StateStore,
+    month: &str,
+    cost_usd: f64,
+) -> CrawlerResult<f64> {
+    let total = month_spent(store, month).await? + cost_usd;
+    store
+        .set(NAMESPACE, month, json!({ "cost_usd": total }))
+        .await?;
+    info!("LLM spending for {}: ${:.4}", month, total);
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> UsageTracker {
+        UsageTracker::new(parse_prices("gemini-2.0-flash=0.10/0.40, grok-3=3/15").unwrap())
+    }
+
+    #[test]
+    fn prices_parse_per_million_tokens() {
+        let prices = parse_prices("a=1/2,,b = 0.5 / 0").unwrap();
+        assert_eq!(
+            prices["a"],
+            ModelPrice {
+                input: 1.0,
+                output: 2.0
+            }
+        );
+        assert_eq!(
+            prices["b"],
+            ModelPrice {
+                input: 0.5,
+                output: 0.0
+            }
+        );
+        assert!(parse_prices("a=1").is_err());
+        assert!(parse_prices("a=-1/2").is_err());
+        assert!(parse_prices("a").is_err());
+    }
+
+    #[tokio::test]
+    async fn calls_accumulate_per_source_and_model() {
+        let tracker = tracker();
+        attribute("hacker_news", async {
+            tracker.record("gemini-2.0-flash", 1_000_000, 500_000);
+            tracker.record("gemini-2.0-flash", 1_000_000, 0);
+        })
+        .await;
+        attribute("xai_search", async {
+            tracker.record("grok-3", 1_000, 1_000)
+        })
+        .await;
+        tracker.record("mystery-model", 10, 10);
+
+        let summary = tracker.summary();
+        let row = |source: &str| summary.rows.iter().find(|r| r.source == source).unwrap();
+        assert_eq!(row("hacker_news").calls, 2);
+        assert_eq!(row("hacker_news").input_tokens, 2_000_000);
+        assert_eq!(row("hacker_news").cost_usd, Some(0.2 + 0.2));
+        assert_eq!(row("xai_search").cost_usd, Some(0.018));
+        assert_eq!(
+            row(UNATTRIBUTED).cost_usd,
+            None,
+            "unpriced models cost nothing"
+        );
+        assert!((summary.total_cost_usd - 0.418).abs() < 1e-9);
+        assert_eq!(summary.by_source()["hacker_news"], (2_500_000, 0.4));
+
+        assert_eq!(tracker.take(), summary);
+        assert!(tracker.summary().is_empty());
+    }
+
+    #[test]
+    fn calls_stop_once_the_budget_is_spent() {
+        let tracker = tracker();
+        assert!(tracker.check_budget().is_ok(), "no budget, no limit");
+
+        tracker.set_budget(1.0, 0.70);
+        assert!(tracker.check_budget().is_ok());
+        tracker.record("grok-3", 100_000, 0); // $0.30
+        assert!(matches!(
+            tracker.check_budget(),
+            Err(CrawlerError::BudgetExceeded(_))
+        ));
+        assert!(tracker.check_budget().is_err());
+        assert_eq!(tracker.summary().skipped_calls, 2);
+
+        // A later run starts from the carried-over spending.
+        tracker.take();
+        assert!(tracker.check_budget().is_err());
+        tracker.set_budget(2.0, 1.0);
+        assert!(tracker.check_budget().is_ok());
+    }
+}
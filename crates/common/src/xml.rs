@@ -0,0 +1,101 @@
+//! XML for feeds and OPML files, parsed with `roxmltree`. Names given as a
+//! bare `&str` match the local name in any namespace; a `(namespace, name)`
+//! pair matches only that namespace.
+
+pub use roxmltree::{Document, Node};
+use roxmltree::{ExpandedName, ParsingOptions};
+
+use crate::error::{CrawlerError, CrawlerResult};
+
+/// Parses `xml`, allowing the internal DTD some feeds declare entities in.
+pub fn parse(xml: &str) -> CrawlerResult<Document<'_>> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    Document::parse_with_options(xml, options)
+        .map_err(|e| CrawlerError::Parse(format!("invalid XML: {}", e)))
+}
+
+/// Child elements called `name`, in document order.
+pub fn children<'a, 'input, 'n, 'm, N>(
+    node: Node<'a, 'input>,
+    name: N,
+) -> impl Iterator<Item = Node<'a, 'input>>
+where
+    N: Into<ExpandedName<'n, 'm>> + Copy,
+{
+    node.children()
+        .filter(move |child| child.is_element() && child.has_tag_name(name))
+}
+
+/// The first child element called `name`.
+pub fn child<'a, 'input, 'n, 'm, N>(node: Node<'a, 'input>, name: N) -> Option<Node<'a, 'input>>
+where
+    N: Into<ExpandedName<'n, 'm>> + Copy,
+{
+    children(node, name).next()
+}
+
+/// The text of the first child called `name`, if it has any.
+pub fn child_text<'n, 'm, N>(node: Node, name: N) -> Option<String>
+where
+    N: Into<ExpandedName<'n, 'm>> + Copy,
+{
+    child(node, name).map(text).filter(|text| !text.is_empty())
+}
+
+/// All text inside the element, CDATA included, trimmed.
+pub fn text(node: Node) -> String {
+    let text: String = node
+        .descendants()
+        .filter(Node::is_text)
+        .filter_map(|node| node.text())
+        .collect();
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_elements_attributes_and_text() {
+        let xml = r#"<?xml version="1.0"?>
+<!DOCTYPE rss [ <!ENTITY nbsp "&#160;"> ]>
+<!-- a comment -->
+<rss version='2.0' xmlns:atom="http://www.w3.org/2005/Atom"><channel>
+  <title>Tom &amp; Jerry&#39;s&nbsp;&#x263A;</title>
+  <description><![CDATA[<p>Raw & <b>bold</b></p>]]></description>
+  <atom:link href="https://example.com/feed?a=1&amp;b=2" rel="self"/>
+  <link>https://example.com/</link>
+</channel></rss>"#;
+        let document = parse(xml).unwrap();
+        let root = document.root_element();
+        assert!(root.has_tag_name("rss"));
+        assert_eq!(root.attribute("version"), Some("2.0"));
+        let channel = child(root, "channel").unwrap();
+        assert_eq!(
+            child_text(channel, "title").unwrap(),
+            "Tom & Jerry's\u{a0}☺"
+        );
+        assert_eq!(
+            child_text(channel, "description").unwrap(),
+            "<p>Raw & <b>bold</b></p>"
+        );
+        let atom_link = child(channel, ("http://www.w3.org/2005/Atom", "link")).unwrap();
+        assert_eq!(
+            atom_link.attribute("href"),
+            Some("https://example.com/feed?a=1&b=2")
+        );
+        assert_eq!(children(channel, "link").count(), 2, "any namespace");
+        assert_eq!(child_text(channel, "missing"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        assert!(parse("<rss><channel><title>cut").is_err());
+        assert!(parse("just text").is_err());
+        assert!(parse("<a><b>x</a>").is_err());
+    }
+}
@@ -1,106 +1,470 @@
 use anyhow::Result;
-use reqwest::Client;
-use scraper::Html;
-use std::env;
-use time::OffsetDateTime;
+use async_trait::async_trait;
+use common::dedup::{SeenStatus, SeenUrls, SeenUrlsMode};
+use common::host_limit::host_of;
+use common::html::{self, TextOptions};
+use common::state::{self, StateStore};
+use common::{
+    ClientFactory, Config, Crawler, CrawlerContext, CrawlerError, CrawlerResult, HttpClient,
+    SupabaseStorageClient,
+};
+use futures::future::join_all;
+use std::sync::Arc;
+use time::Date;
+use tokio::sync::Semaphore;
 use tracing::{info, warn};
 
-#[derive(Clone)]
-struct SiteFetcher {
-    client: Client,
+/// Sites fetched at once.
+const SITE_CONCURRENCY: usize = 4;
+
+/// A fetched site, ready to upload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Page {
+    url: String,
+    summary: String,
+    /// The earlier day the page went out, for a repeat.
+    first_seen: Option<Date>,
+}
+
+/// Fetches each custom site and uploads its text to
+/// `{date}/custom-site-{hostname}.md`, or all of them to `{date}/custom-site.md`.
+/// A lone `CUSTOM_SITE_URL` keeps going to `{date}/custom-site.md`.
+pub struct CustomSiteCrawler {
+    client: HttpClient,
+    storage: SupabaseStorageClient,
+    urls: Vec<String>,
+    combined: bool,
+    /// Only `CUSTOM_SITE_URL` is set, as before `CUSTOM_SITE_URLS` existed.
+    single_legacy_url: bool,
+    seen_urls: SeenUrlsMode,
+    seen_urls_ttl_days: u32,
+    /// Only opened when `seen_urls` is on.
+    state: Option<Arc<dyn StateStore>>,
 }
 
-impl SiteFetcher {
-    fn new() -> Self {
-        Self { client: Client::new() }
+impl CustomSiteCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        let urls = config.require_custom_site_urls()?.to_vec();
+        Ok(Self {
+            client: ClientFactory::from_config(config).build()?,
+            storage: SupabaseStorageClient::from_config(config)?,
+            combined: config.custom_site_combined,
+            single_legacy_url: config.custom_site_url.is_some() && urls.len() == 1,
+            urls,
+            seen_urls: config.custom_site_seen_urls,
+            seen_urls_ttl_days: config.seen_urls_ttl_days,
+            state: match config.custom_site_seen_urls {
+                SeenUrlsMode::Off => None,
+                _ => Some(state::from_config(config)?),
+            },
+        })
     }
 
-    async fn fetch(&self, url: &str) -> Result<String> {
-        let resp = self.client.get(url).send().await?;
-        Ok(resp.text().await?)
+    /// The earlier day each URL was first published on, and the loaded set
+    /// to record the published ones in. Failures only cost the check.
+    async fn first_seen(&self, today: Date) -> (Vec<Option<Date>>, Option<SeenUrls>) {
+        let unseen = vec![None; self.urls.len()];
+        let Some(store) = self.state.clone() else {
+            return (unseen, None);
+        };
+        let seen = match SeenUrls::load(store, today, self.seen_urls_ttl_days).await {
+            Ok(seen) => seen,
+            Err(e) => {
+                warn!("Could not load seen URLs: {}", e);
+                return (unseen, None);
+            }
+        };
+        let dates = self
+            .urls
+            .iter()
+            .map(|url| match seen.check(url) {
+                SeenStatus::SeenOn(date) if date < today => Some(date),
+                _ => None,
+            })
+            .collect();
+        (dates, Some(seen))
     }
 
-    fn clean_html(&self, html: &str) -> String {
-        Html::parse_document(html)
-            .root_element()
-            .text()
-            .collect::<Vec<_>>()
-            .join("")
+    async fn fetch(&self, ctx: &CrawlerContext, url: &str) -> CrawlerResult<String> {
+        let html = ctx.http_client(&self.client).get_text(url).await?;
+        let clean_text = html::to_text(&html, &TextOptions::document());
+        Ok(summarize(&clean_text))
+    }
+
+    /// Fetches every site not dropped as a repeat, `SITE_CONCURRENCY` at a
+    /// time, in configuration order. Failed sites are reported and left out.
+    async fn fetch_pages(
+        &self,
+        ctx: &CrawlerContext,
+        first_seen: Vec<Option<Date>>,
+    ) -> (Vec<Page>, Vec<(String, CrawlerError)>) {
+        let slots = Semaphore::new(SITE_CONCURRENCY);
+        let fetches = self
+            .urls
+            .iter()
+            .zip(first_seen)
+            .filter(|(url, first_seen)| match (first_seen, self.seen_urls) {
+                (Some(date), SeenUrlsMode::Drop) => {
+                    info!("{} was already published on {}; skipping", url, date);
+                    false
+                }
+                _ => true,
+            })
+            .map(|(url, first_seen)| {
+                let slots = &slots;
+                async move {
+                    let _permit = slots.acquire().await.expect("slots are never closed");
+                    let result = self.fetch(ctx, url).await.map(|summary| Page {
+                        url: url.clone(),
+                        summary,
+                        first_seen,
+                    });
+                    (url, result)
+                }
+            });
+
+        let mut pages = Vec::new();
+        let mut failures = Vec::new();
+        for (url, result) in join_all(fetches).await {
+            match result {
+                Ok(page) => pages.push(page),
+                Err(e) => {
+                    ctx.item_failed(url.as_str(), &e);
+                    failures.push((url.clone(), e));
+                }
+            }
+        }
+        (pages, failures)
     }
 
-    async fn summarize(&self, content: &str) -> Result<String> {
-        // Placeholder summary logic
-        Ok(content.chars().take(200).collect())
+    /// `(path, markdown)` for each file to upload.
+    fn files(&self, date: Date, pages: &[Page]) -> Vec<(String, String)> {
+        if self.combined {
+            return vec![(format!("{}/custom-site.md", date), combined_markdown(pages))];
+        }
+        if self.single_legacy_url {
+            return pages
+                .iter()
+                .map(|page| {
+                    let markdown = site_markdown(&page.url, &page.summary, page.first_seen);
+                    (format!("{}/custom-site.md", date), markdown)
+                })
+                .collect();
+        }
+        let mut files: Vec<(String, String)> = Vec::new();
+        for page in pages {
+            let stem = format!("{}/custom-site-{}", date, file_host(&page.url));
+            // Two pages on one host get numbered files.
+            let mut path = format!("{}.md", stem);
+            let mut n = 1;
+            while files.iter().any(|(taken, _)| *taken == path) {
+                n += 1;
+                path = format!("{}-{}.md", stem, n);
+            }
+            let markdown = site_markdown(&page.url, &page.summary, page.first_seen);
+            files.push((path, markdown));
+        }
+        files
     }
 }
 
-struct SupabaseStorageClient {
-    client: Client,
-    base_url: String,
-    api_key: String,
-    bucket_name: String,
+common::register_crawler!(CustomSiteCrawler {
+    name: "Custom Site crawler",
+    slug: "custom_site",
+    required_env: ["CUSTOM_SITE_URL", "CUSTOM_SITE_URLS"],
+    new: CustomSiteCrawler::new,
+});
+
+/// Placeholder summary: the start of the page's text.
+fn summarize(content: &str) -> String {
+    content.chars().take(200).collect()
 }
 
-impl SupabaseStorageClient {
-    fn new(base_url: &str, api_key: &str, bucket_name: &str) -> Self {
-        Self {
-            client: Client::new(),
-            base_url: base_url.trim_end_matches('/').to_string(),
-            api_key: api_key.to_string(),
-            bucket_name: bucket_name.to_string(),
+/// The URL's host as a file name part, e.g. `blog.example.com`.
+fn file_host(url: &str) -> String {
+    host_of(url)
+        .unwrap_or_else(|| "site".to_string())
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Crawler for CustomSiteCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Custom site crawler starting up");
+
+        let (first_seen, seen) = self.first_seen(ctx.date).await;
+        let (pages, failures) = self.fetch_pages(ctx, first_seen).await;
+        if !failures.is_empty() {
+            warn!(
+                "{} of {} custom sites failed:",
+                failures.len(),
+                self.urls.len()
+            );
+            for (url, e) in &failures {
+                warn!("  {}: {}", url, e);
+            }
         }
+        if pages.is_empty() {
+            if let Some((_, e)) = failures.into_iter().next() {
+                return Err(e);
+            }
+            return Ok(());
+        }
+
+        let storage = ctx.storage_client(&self.storage);
+        for (path, markdown) in self.files(ctx.date, &pages) {
+            storage
+                .upload_file(&path, markdown, "text/markdown")
+                .await?;
+            info!("Uploaded {}", path);
+        }
+        // Only once uploaded, so a failed run leaves the pages new for the rerun.
+        if let Some(mut seen) = seen {
+            for page in &pages {
+                seen.mark(&page.url);
+            }
+            if let Err(e) = seen.save().await {
+                warn!("Failed to save seen URLs: {}", e);
+            }
+        }
+        info!(
+            "Custom site crawler finished: {} of {} sites",
+            pages.len(),
+            self.urls.len()
+        );
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Custom Site"
     }
 
-    async fn upload_file(&self, path: &str, content: String, content_type: &str) -> Result<()> {
-        let url = format!("{}/object/{}/{}", self.base_url, self.bucket_name, path.trim_start_matches('/'));
-        let resp = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", content_type)
-            .header("x-upsert", "true")
-            .body(content)
-            .send()
-            .await?;
-
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            let err = resp.text().await?;
-            anyhow::bail!("Failed to upload: {}", err)
+    fn slug(&self) -> &'static str {
+        "custom_site"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if config.custom_site_urls.is_empty() {
+            return Err("neither CUSTOM_SITE_URL nor CUSTOM_SITE_URLS set".to_string());
         }
+        Ok(())
+    }
+}
+
+/// The uploaded page: its URL, summary and, for a repeat, when it was first seen.
+fn site_markdown(url: &str, summary: &str, first_seen: Option<Date>) -> String {
+    let mut markdown = format!("# Fetched Content\n\nURL: {}\n\n{}", url, summary);
+    if let Some(date) = first_seen {
+        markdown.push_str(&format!("\n\n*First seen on {}*", date));
     }
+    markdown
 }
 
+/// Every page in one document, a section per site.
+fn combined_markdown(pages: &[Page]) -> String {
+    let mut markdown = "# Fetched Content".to_string();
+    for page in pages {
+        let host = host_of(&page.url).unwrap_or_else(|| page.url.clone());
+        markdown.push_str(&format!(
+            "\n\n## {}\n\nURL: {}\n\n{}",
+            host, page.url, page.summary
+        ));
+        if let Some(date) = page.first_seen {
+            markdown.push_str(&format!("\n\n*First seen on {}*", date));
+        }
+    }
+    markdown
+}
+
+/// Runs the crawler on its own, outside the orchestrator.
 pub async fn run_custom_site_crawler() -> Result<()> {
     let _ = dotenv::dotenv();
+    let config = Config::from_env()?;
+    if let Err(reason) = CustomSiteCrawler::precheck(&config) {
+        warn!("{}; skipping custom site crawler", reason);
+        return Ok(());
+    }
+    CustomSiteCrawler::new(&config)?.run().await?;
+    Ok(())
+}
 
-    info!("Custom site crawler starting up");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{assert_snapshot, FixtureDir, MockSupabase, TestConfig};
 
-    let url = match env::var("CUSTOM_SITE_URL") {
-        Ok(v) => v,
-        Err(_) => {
-            warn!("CUSTOM_SITE_URL not set; skipping custom site crawler");
-            return Ok(());
-        }
-    };
-    let supabase_url = env::var("SUPABASE_URL").expect("SUPABASE_URL must be set");
-    let supabase_key = env::var("SUPABASE_SERVICE_ROLE_KEY").expect("SUPABASE_SERVICE_ROLE_KEY must be set");
-    let supabase_bucket = env::var("SUPABASE_BUCKET_NAME").expect("SUPABASE_BUCKET_NAME must be set");
+    #[test]
+    fn page_markdown_matches_snapshot() {
+        let summary = "Release notes\n\nVersion 2 adds *streaming* & fixes <bugs>.";
+        let first_seen = Date::from_calendar_date(2024, time::Month::May, 8).ok();
+        assert_snapshot!(
+            "custom-site.md",
+            site_markdown("https://example.com/news?id=1", summary, first_seen)
+        );
+        assert_eq!(
+            site_markdown("https://example.com", "Hi", None),
+            "# Fetched Content\n\nURL: https://example.com\n\nHi"
+        );
+    }
 
-    let fetcher = SiteFetcher::new();
-    let storage = SupabaseStorageClient::new(&format!("{}/storage/v1", supabase_url.trim_end_matches('/')), &supabase_key, &supabase_bucket);
+    #[tokio::test]
+    async fn missing_url_is_a_config_error() {
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase).build();
+        assert!(CustomSiteCrawler::precheck(&config).is_err());
+        assert!(matches!(
+            CustomSiteCrawler::new(&config),
+            Err(CrawlerError::Config(e)) if e.to_string().contains("CUSTOM_SITE_URL")
+        ));
+    }
 
-    let html = fetcher.fetch(&url).await?;
-    let clean_text = fetcher.clean_html(&html);
-    let summary = fetcher.summarize(&clean_text).await?;
+    fn site_fixtures() -> FixtureDir {
+        let fixtures = FixtureDir::new();
+        fixtures.add(
+            "https://blog.example.com/post",
+            "<html><body><p>Blog post</p></body></html>",
+        );
+        fixtures.add(
+            "https://news.example.org/",
+            "<html><body><p>Headlines</p></body></html>",
+        );
+        fixtures
+    }
 
-    let markdown = format!("# Fetched Content\n\nURL: {}\n\n{}", url, summary);
-    let today_str = OffsetDateTime::now_utc().date().to_string();
-    let file_path = format!("{}/custom-site.md", today_str);
-    storage.upload_file(&file_path, markdown, "text/markdown").await?;
+    async fn run(combined: bool) -> (MockSupabase, Vec<String>) {
+        let fixtures = site_fixtures();
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .with(|c| {
+                c.custom_site_urls = vec![
+                    "https://blog.example.com/post".into(),
+                    "https://missing.example.net/".into(),
+                    "https://news.example.org/".into(),
+                ];
+                c.custom_site_combined = combined;
+            })
+            .build();
+        let date = Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        let ctx = CrawlerContext::default().with_date(date);
+        let crawler = CustomSiteCrawler::new(&config).unwrap();
+        crawler.run_with_context(&ctx).await.unwrap();
+        let failed = ctx.item_errors.take().into_iter().map(|e| e.item).collect();
+        (supabase, failed)
+    }
 
-    info!("Custom site crawler finished: {}", file_path);
-    Ok(())
-}
+    #[tokio::test]
+    async fn uploads_a_file_per_site_and_skips_failures() {
+        let (supabase, failed) = run(false).await;
+        assert_eq!(failed, ["https://missing.example.net/"]);
+        let mut paths = supabase.uploaded_paths().await;
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                "2024-05-10/custom-site-blog.example.com.md",
+                "2024-05-10/custom-site-news.example.org.md",
+            ]
+        );
+        let blog = supabase
+            .uploaded("2024-05-10/custom-site-blog.example.com.md")
+            .await;
+        assert!(blog.contains("URL: https://blog.example.com/post\n\nBlog post"));
+    }
+
+    #[tokio::test]
+    async fn combined_mode_uploads_one_file_with_a_section_per_site() {
+        let (supabase, failed) = run(true).await;
+        assert_eq!(failed, ["https://missing.example.net/"]);
+        assert_eq!(
+            supabase.uploaded_paths().await,
+            ["2024-05-10/custom-site.md"]
+        );
+        let markdown = supabase.uploaded("2024-05-10/custom-site.md").await;
+        let blog = markdown.find("## blog.example.com").unwrap();
+        let news = markdown.find("## news.example.org").unwrap();
+        assert!(blog < news, "sections keep configuration order");
+        assert!(!markdown.contains("missing.example.net"));
+    }
+
+    #[tokio::test]
+    async fn a_lone_custom_site_url_keeps_its_file_name() {
+        let fixtures = site_fixtures();
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .with(|c| {
+                c.custom_site_url = Some("https://blog.example.com/post".into());
+                c.custom_site_urls = vec!["https://blog.example.com/post".into()];
+            })
+            .build();
+        let date = Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        CustomSiteCrawler::new(&config)
+            .unwrap()
+            .run_with_context(&CrawlerContext::default().with_date(date))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            supabase.uploaded_paths().await,
+            ["2024-05-10/custom-site.md"]
+        );
+        assert_eq!(
+            supabase.uploaded("2024-05-10/custom-site.md").await,
+            "# Fetched Content\n\nURL: https://blog.example.com/post\n\nBlog post"
+        );
+    }
 
+    #[tokio::test]
+    async fn pages_are_only_marked_seen_once_uploaded() {
+        let fixtures = site_fixtures();
+        let state_db =
+            std::env::temp_dir().join(format!("cution-custom-site-seen-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&state_db);
+        let supabase = MockSupabase::start().await;
+        supabase.fail_uploads(401, 1).await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .with(|c| {
+                c.custom_site_urls = vec!["https://blog.example.com/post".into()];
+                c.custom_site_seen_urls = SeenUrlsMode::Drop;
+                c.state_db = Some(state_db.clone());
+            })
+            .build();
+        let crawler = CustomSiteCrawler::new(&config).unwrap();
+        for day in [9, 10, 11] {
+            let date = Date::from_calendar_date(2024, time::Month::May, day).unwrap();
+            let result = crawler
+                .run_with_context(&CrawlerContext::default().with_date(date))
+                .await;
+            assert_eq!(result.is_ok(), day != 9, "day {}", day);
+        }
+        let _ = std::fs::remove_file(&state_db);
+
+        // The failed upload on the 9th left the page new for the 10th.
+        assert_eq!(
+            supabase.uploaded_paths().await,
+            [
+                "2024-05-09/custom-site-blog.example.com.md",
+                "2024-05-10/custom-site-blog.example.com.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn file_hosts_are_safe_file_names() {
+        assert_eq!(
+            file_host("https://Blog.Example.com:8080/a"),
+            "blog.example.com"
+        );
+        assert_eq!(file_host("not a url"), "site");
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use common::{Config, Crawler, CrawlerManager};
+use tracing::info;
+
+/// Single entry point for the crawlers and the scheduler.
+#[derive(Debug, Parser)]
+#[command(name = "cution", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Crawl GitHub trending for the configured LANGUAGES.
+    Github,
+    /// Crawl the Hacker News front page.
+    Hn,
+    /// Run every configured crawler once.
+    All,
+    /// Run the long-lived scheduler.
+    Schedule {
+        /// Run the scheduled job body once and exit (also `CUTION_ONCE=true`).
+        #[arg(long)]
+        once: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    common::init_tracing()?;
+    let config = Config::load()?;
+
+    match cli.command {
+        Command::Github => run_one(github::GithubTrendingFetcher::new(&config)?).await,
+        Command::Hn => run_one(hacker_news::HackerNewsCrawler::new(&config)?).await,
+        Command::All => {
+            let report = orchestrator::run(&config).await?;
+            report.into_result()?;
+            info!("All crawlers completed successfully");
+            Ok(())
+        }
+        Command::Schedule { once } => {
+            let once_env = std::env::var("CUTION_ONCE").ok();
+            if once || scheduler::once::once_requested(once_env.as_deref()) {
+                scheduler::app::run_once(config).await
+            } else {
+                scheduler::app::run(config).await
+            }
+        }
+    }
+}
+
+async fn run_one(crawler: impl Crawler + 'static) -> Result<()> {
+    let name = crawler.slug();
+    CrawlerManager::new()
+        .add_crawler(Box::new(crawler))
+        .run_named(name)
+        .await?
+        .into_result()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_subcommands() {
+        let cli = Cli::try_parse_from(["cution", "hn"]).unwrap();
+        assert!(matches!(cli.command, Command::Hn));
+        assert!(Cli::try_parse_from(["cution", "reddit"]).is_err());
+        let cli = Cli::try_parse_from(["cution", "schedule", "--once"]).unwrap();
+        assert!(matches!(cli.command, Command::Schedule { once: true }));
+        assert!(Cli::try_parse_from(["cution"]).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+pub mod models;
+
+use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::http::parse_json;
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult, HttpClient,
+    OutputFormat, Publisher,
+};
+use models::DevtoArticle;
+use std::collections::HashSet;
+use tracing::{info, warn};
+
+const ARTICLES_URL: &str = "https://dev.to/api/articles?top=1&per_page=50";
+/// Section for articles without tags.
+const UNTAGGED: &str = "other";
+
+/// Ranked articles under one tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagGroup {
+    pub tag: String,
+    pub articles: Vec<DevtoArticle>,
+}
+
+/// Publishes the day's top dev.to articles to `{date}/devto.md`, grouped by
+/// tag and ranked by reactions.
+pub struct DevtoCrawler {
+    client: HttpClient,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    tags: Vec<String>,
+    min_reactions: u64,
+    archive: Option<ArchiveWriter>,
+}
+
+impl DevtoCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self {
+            client: ClientFactory::from_config(config).build()?,
+            publisher: Publisher::for_crawler(config, "devto")?,
+            output_formats: config.output_formats_for("devto"),
+            tags: config.devto_tags.clone(),
+            min_reactions: config.devto_min_reactions,
+            archive: ArchiveWriter::from_config(config)?,
+        })
+    }
+
+    async fn fetch(
+        &self,
+        ctx: &CrawlerContext,
+        tag: Option<&str>,
+    ) -> CrawlerResult<Vec<DevtoArticle>> {
+        let url = match tag {
+            Some(tag) => format!("{}&tag={}", ARTICLES_URL, tag),
+            None => ARTICLES_URL.to_string(),
+        };
+        let body = ctx.http_client(&self.client).get_text(&url).await?;
+        parse_json(&url, &body)
+    }
+
+    /// Without `DEVTO_TAGS`, one request grouped by each article's main tag;
+    /// otherwise one request and one section per configured tag. A tag that
+    /// fails is reported and skipped.
+    async fn fetch_groups(&self, ctx: &CrawlerContext) -> CrawlerResult<Vec<TagGroup>> {
+        if self.tags.is_empty() {
+            let articles = self.fetch(ctx, None).await?;
+            return Ok(group_by_primary_tag(self.keep(articles)));
+        }
+        let mut groups = Vec::new();
+        for tag in &self.tags {
+            match self.fetch(ctx, Some(tag)).await {
+                Ok(articles) => groups.push(TagGroup {
+                    tag: tag.clone(),
+                    articles: self.keep(articles),
+                }),
+                Err(e) => {
+                    warn!("Failed to fetch dev.to articles tagged {}: {}", tag, e);
+                    ctx.item_failed(format!("tag {}", tag), &e);
+                }
+            }
+        }
+        Ok(groups)
+    }
+
+    /// The articles with at least `min_reactions`, most reactions first.
+    fn keep(&self, articles: Vec<DevtoArticle>) -> Vec<DevtoArticle> {
+        let mut kept: Vec<_> = articles
+            .into_iter()
+            .filter(|article| article.positive_reactions_count >= self.min_reactions)
+            .collect();
+        kept.sort_by_key(|article| std::cmp::Reverse(article.positive_reactions_count));
+        kept
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let mut groups = self.fetch_groups(ctx).await?;
+        // An article listed under several configured tags stays in the first.
+        let mut seen = HashSet::new();
+        for group in &mut groups {
+            group.articles.retain(|article| seen.insert(article.id));
+        }
+        groups.retain(|group| !group.articles.is_empty());
+        if groups.is_empty() {
+            info!("No dev.to articles above the reaction threshold");
+            return Ok(());
+        }
+
+        let articles: Vec<DevtoArticle> = groups
+            .iter()
+            .flat_map(|group| group.articles.iter().cloned())
+            .collect();
+        let stem = format!("{}/devto", ctx.date);
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_groups_markdown(&groups),
+                &articles,
+            )
+            .await?;
+        info!(
+            "Uploaded {} dev.to articles to {}",
+            articles.len(),
+            written.join(", ")
+        );
+        if let Some(archive) = &self.archive {
+            archive
+                .record(
+                    articles
+                        .into_iter()
+                        .map(|article| article.into_item(ctx.date)),
+                )
+                .await;
+        }
+        Ok(())
+    }
+}
+
+/// Groups ranked `articles` by their main tag, keeping their order within a
+/// group; the group with the most reacted-to article comes first.
+fn group_by_primary_tag(articles: Vec<DevtoArticle>) -> Vec<TagGroup> {
+    let mut groups: Vec<TagGroup> = Vec::new();
+    for article in articles {
+        let tag = article.primary_tag().unwrap_or(UNTAGGED).to_string();
+        match groups.iter_mut().find(|group| group.tag == tag) {
+            Some(group) => group.articles.push(article),
+            None => groups.push(TagGroup {
+                tag,
+                articles: vec![article],
+            }),
+        }
+    }
+    groups
+}
+
+fn format_groups_markdown(groups: &[TagGroup]) -> String {
+    let mut markdown = markdown::section(1, "dev.to Top Articles");
+    for group in groups {
+        markdown.push_str("\n\n");
+        markdown.push_str(&markdown::section(2, &format!("#{}", group.tag)));
+        markdown.push('\n');
+        for (i, article) in group.articles.iter().enumerate() {
+            markdown.push('\n');
+            markdown.push_str(&article.to_markdown_line(i + 1));
+        }
+    }
+    markdown
+}
+
+#[async_trait]
+impl Crawler for DevtoCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("dev.to crawler starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "dev.to"
+    }
+
+    fn slug(&self) -> &'static str {
+        "devto"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if !config.devto_enabled {
+            return Err("DEVTO_ENABLED not set".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(DevtoCrawler {
+    name: "dev.to crawler",
+    slug: "devto",
+    required_env: ["DEVTO_ENABLED"],
+    digest: ("devto", "dev.to"),
+    new: DevtoCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{assert_snapshot, fixture, FixtureDir, MockSupabase, TestConfig};
+
+    fn articles(name: &str) -> Vec<DevtoArticle> {
+        serde_json::from_str(&fixture(&format!("devto/{}.json", name))).unwrap()
+    }
+
+    fn titles(groups: &[TagGroup]) -> Vec<(&str, Vec<&str>)> {
+        groups
+            .iter()
+            .map(|g| {
+                let titles = g.articles.iter().map(|a| a.title.as_str()).collect();
+                (g.tag.as_str(), titles)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn deserializes_the_recorded_listing() {
+        let articles = articles("top");
+        assert_eq!(articles.len(), 5);
+        assert_eq!(
+            articles[0],
+            DevtoArticle {
+                id: 1843201,
+                title: "Building a Tiny Async Runtime in Rust".to_string(),
+                url: "https://dev.to/ferris/building-a-tiny-async-runtime-in-rust-4k2p".to_string(),
+                positive_reactions_count: 212,
+                comments_count: 14,
+                tags: vec!["rust".into(), "async".into(), "tutorial".into()],
+                reading_time_minutes: 9,
+            }
+        );
+        assert_eq!(articles[4].primary_tag(), None);
+    }
+
+    #[tokio::test]
+    async fn groups_by_main_tag_in_reaction_order() {
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .with(|c| c.devto_min_reactions = 10)
+            .build();
+        let crawler = DevtoCrawler::new(&config).unwrap();
+        let groups = group_by_primary_tag(crawler.keep(articles("top")));
+        assert_eq!(
+            titles(&groups),
+            [
+                ("css", vec!["CSS Container Queries Are Finally Here"]),
+                (
+                    "rust",
+                    vec![
+                        "Building a Tiny Async Runtime in Rust",
+                        "Error Handling Patterns with anyhow and thiserror"
+                    ]
+                ),
+                (
+                    "architecture",
+                    vec!["Why Our Team Moved Back to a Monolith"]
+                ),
+            ]
+        );
+        assert_snapshot!("devto.md", format_groups_markdown(&groups));
+    }
+
+    #[tokio::test]
+    async fn fetches_one_section_per_configured_tag() {
+        let fixtures = FixtureDir::new();
+        fixtures.add(
+            &format!("{}&tag=rust", ARTICLES_URL),
+            &fixture("devto/top_rust.json"),
+        );
+        fixtures.add(
+            &format!("{}&tag=webdev", ARTICLES_URL),
+            &fixture("devto/top_webdev.json"),
+        );
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| {
+                c.devto_enabled = true;
+                c.devto_tags = vec!["rust".into(), "webdev".into(), "missing".into()];
+                c.devto_min_reactions = 50;
+            })
+            .build();
+        let date = time::Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        let ctx = CrawlerContext::default().with_date(date);
+        let crawler = DevtoCrawler::new(&config).unwrap();
+        crawler.run_with_context(&ctx).await.unwrap();
+
+        let failed: Vec<String> = ctx.item_errors.take().into_iter().map(|e| e.item).collect();
+        assert_eq!(failed, ["tag missing"]);
+        let markdown = supabase.uploaded("2024-05-10/devto.md").await;
+        assert!(markdown.contains("## #rust\n\n1. [Building a Tiny Async Runtime"));
+        assert!(!markdown.contains("anyhow"), "45 reactions is below 50");
+        let json: Vec<serde_json::Value> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/devto.json").await).unwrap();
+        let ids: Vec<_> = json.iter().map(|a| a["id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, [1843201, 1843377, 1842990]);
+        assert_eq!(json[0]["tags"][0], "rust");
+
+        let articles: Vec<DevtoArticle> =
+            serde_json::from_value(serde_json::Value::Array(json.clone())).unwrap();
+        assert_eq!(articles[0].tags, ["rust", "async", "tutorial"]);
+        assert_eq!(
+            serde_json::to_value(&articles).unwrap(),
+            serde_json::Value::Array(json)
+        );
+    }
+}
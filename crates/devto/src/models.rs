@@ -0,0 +1,73 @@
+use common::archive;
+use common::markdown;
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+/// An article from `/api/articles`, keeping the fields the digest uses.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DevtoArticle {
+    pub id: u64,
+    pub title: String,
+    pub url: String,
+    pub positive_reactions_count: u64,
+    #[serde(default)]
+    pub comments_count: u64,
+    /// A list in our own JSON. The API sends `tags` as one comma-separated
+    /// string, next to a `tag_list` array that is ignored.
+    #[serde(default, deserialize_with = "tag_names")]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub reading_time_minutes: u32,
+}
+
+/// Reads tags written either as a list or as the API's `"rust, async"`.
+fn tag_names<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        List(Vec<String>),
+        Text(String),
+    }
+    Ok(match Tags::deserialize(deserializer)? {
+        Tags::List(tags) => tags,
+        Tags::Text(text) => text
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+    })
+}
+
+impl DevtoArticle {
+    /// The article's first tag, which dev.to treats as the main one.
+    pub fn primary_tag(&self) -> Option<&str> {
+        self.tags.first().map(String::as_str)
+    }
+
+    /// One numbered list item, e.g. `1. [Title](url) · 212 reactions · ...`.
+    pub fn to_markdown_line(&self, rank: usize) -> String {
+        format!(
+            "{}. {} · {} reactions · {} comments · {} min read",
+            rank,
+            markdown::link(&self.title, &self.url),
+            self.positive_reactions_count,
+            self.comments_count,
+            self.reading_time_minutes
+        )
+    }
+}
+
+impl archive::Archivable for DevtoArticle {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "devto".to_string(),
+            date: date.to_string(),
+            url: Some(self.url.clone()),
+            title: self.title.clone(),
+            score: Some(self.positive_reactions_count as f64),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+            id: self.id.to_string(),
+        }
+    }
+}
@@ -0,0 +1,564 @@
+pub mod models;
+
+use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::dedup::{SeenStatus, SeenUrls};
+use common::http::parse_json;
+use common::publish::sha256_hex;
+use common::{
+    markdown, state, ClientFactory, Config, Crawler, CrawlerContext, CrawlerError, CrawlerResult,
+    HttpClient, OutputFormat, Publisher, StateStore,
+};
+use models::{BlueskySearch, MastodonStatus, Post};
+use reqwest::Url;
+use std::collections::HashSet;
+use std::sync::Arc;
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
+use tracing::{info, warn};
+
+const BLUESKY_SEARCH_URL: &str = "https://public.api.bsky.app/xrpc/app.bsky.feed.searchPosts";
+/// Posts per Bluesky page (the API allows up to 100).
+const BLUESKY_PAGE_SIZE: usize = 25;
+/// Statuses per Mastodon page (the API allows up to 40).
+const MASTODON_PAGE_SIZE: usize = 40;
+/// Pages followed per keyword and network.
+const MAX_PAGES: usize = 3;
+/// Posts older than this are left out.
+const MAX_AGE: Duration = Duration::hours(24);
+
+/// One page of Bluesky's search for `keyword`, most liked first.
+fn bluesky_search_url(keyword: &str, since: OffsetDateTime, cursor: Option<&str>) -> String {
+    let since = since.format(&Rfc3339).unwrap_or_default();
+    let limit = BLUESKY_PAGE_SIZE.to_string();
+    let mut params = vec![
+        ("q", keyword),
+        ("sort", "top"),
+        ("since", since.as_str()),
+        ("limit", limit.as_str()),
+    ];
+    if let Some(cursor) = cursor {
+        params.push(("cursor", cursor));
+    }
+    Url::parse_with_params(BLUESKY_SEARCH_URL, &params)
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+/// One page of an instance's public timeline for `hashtag`, older than `max_id`.
+fn mastodon_tag_url(instance: &str, hashtag: &str, max_id: Option<&str>) -> String {
+    let mut url = format!(
+        "{}/api/v1/timelines/tag/{}?limit={}",
+        instance, hashtag, MASTODON_PAGE_SIZE
+    );
+    if let Some(max_id) = max_id {
+        url.push_str(&format!("&max_id={}", max_id));
+    }
+    url
+}
+
+/// The hashtag for a keyword: letters and digits only, lowercased.
+fn hashtag(keyword: &str) -> String {
+    keyword
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Identifies a post by its author and text, so the same words posted again
+/// or cross-posted under another URL count as a repeat.
+fn text_key(post: &Post) -> String {
+    let text = post.text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let digest = sha256_hex(&format!("{}\n{}", post.author, text.to_lowercase()));
+    format!("fediverse:{}", &digest[..16])
+}
+
+/// The posts found under one keyword.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeywordSection {
+    pub keyword: String,
+    pub posts: Vec<Post>,
+}
+
+/// Publishes the day's most liked Bluesky (and Mastodon) posts on each of
+/// `FEDIVERSE_KEYWORDS` to `{date}/fediverse.md`, one section per keyword.
+pub struct FediverseCrawler {
+    client: HttpClient,
+    keywords: Vec<String>,
+    mastodon_instance: Option<String>,
+    min_likes: u64,
+    min_reposts: u64,
+    state: Arc<dyn StateStore>,
+    seen_urls_ttl_days: u32,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    archive: Option<ArchiveWriter>,
+}
+
+impl FediverseCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self {
+            client: ClientFactory::from_config(config).build()?,
+            keywords: config.fediverse_keywords.clone(),
+            mastodon_instance: config.fediverse_mastodon_instance.clone(),
+            min_likes: config.fediverse_min_likes,
+            min_reposts: config.fediverse_min_reposts,
+            state: state::from_config(config)?,
+            seen_urls_ttl_days: config.seen_urls_ttl_days,
+            publisher: Publisher::for_crawler(config, "fediverse")?,
+            output_formats: config.output_formats_for("fediverse"),
+            archive: ArchiveWriter::from_config(config)?,
+        })
+    }
+
+    /// Bluesky posts on `keyword` since the cutoff, following the search
+    /// cursor for up to `MAX_PAGES` pages.
+    async fn search_bluesky(
+        &self,
+        ctx: &CrawlerContext,
+        keyword: &str,
+    ) -> CrawlerResult<Vec<Post>> {
+        let client = ctx.http_client(&self.client);
+        let since = ctx.now() - MAX_AGE;
+        let mut posts = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_PAGES {
+            let url = bluesky_search_url(keyword, since, cursor.as_deref());
+            let page: BlueskySearch = parse_json(&url, &client.get_text(&url).await?)?;
+            let last_page = page.posts.is_empty() || page.cursor.is_none();
+            posts.extend(
+                page.posts
+                    .into_iter()
+                    .map(|post| Post::from_bluesky(keyword, post)),
+            );
+            if last_page {
+                break;
+            }
+            cursor = page.cursor;
+        }
+        Ok(posts)
+    }
+
+    /// Statuses tagged with `keyword`'s hashtag, newest first, paging back
+    /// until they are older than the cutoff or `MAX_PAGES` is reached.
+    async fn search_mastodon(
+        &self,
+        ctx: &CrawlerContext,
+        instance: &str,
+        keyword: &str,
+    ) -> CrawlerResult<Vec<Post>> {
+        let tag = hashtag(keyword);
+        if tag.is_empty() {
+            return Err(CrawlerError::Parse(format!(
+                "\"{}\" does not make a hashtag",
+                keyword
+            )));
+        }
+        let client = ctx.http_client(&self.client);
+        let cutoff = ctx.now() - MAX_AGE;
+        let mut posts: Vec<Post> = Vec::new();
+        let mut max_id: Option<String> = None;
+        for _ in 0..MAX_PAGES {
+            let url = mastodon_tag_url(instance, &tag, max_id.as_deref());
+            let page: Vec<MastodonStatus> = parse_json(&url, &client.get_text(&url).await?)?;
+            let full_page = page.len() == MASTODON_PAGE_SIZE;
+            max_id = page.last().map(|status| status.id.clone());
+            posts.extend(
+                page.into_iter()
+                    .map(|status| Post::from_mastodon(keyword, status)),
+            );
+            let reached_cutoff = posts
+                .last()
+                .and_then(|post| post.created_at)
+                .is_some_and(|created_at| created_at < cutoff);
+            if !full_page || reached_cutoff {
+                break;
+            }
+        }
+        Ok(posts)
+    }
+
+    /// Every network's posts on `keyword` within the age and engagement
+    /// limits, most liked first. Fails only when every network did.
+    async fn fetch_keyword(&self, ctx: &CrawlerContext, keyword: &str) -> CrawlerResult<Vec<Post>> {
+        let mut results = vec![("Bluesky", self.search_bluesky(ctx, keyword).await)];
+        if let Some(instance) = &self.mastodon_instance {
+            results.push((
+                "Mastodon",
+                self.search_mastodon(ctx, instance, keyword).await,
+            ));
+        }
+
+        let cutoff = ctx.now() - MAX_AGE;
+        let mut posts = Vec::new();
+        let mut last_error = None;
+        let attempted = results.len();
+        let mut failed = 0;
+        for (network, result) in results {
+            match result {
+                Ok(found) => posts.extend(found),
+                Err(e) => {
+                    warn!("Failed to search {} for \"{}\": {}", network, keyword, e);
+                    ctx.item_failed(format!("{} \"{}\"", network, keyword), &e);
+                    failed += 1;
+                    last_error = Some(e);
+                }
+            }
+        }
+        if let Some(e) = last_error.filter(|_| failed == attempted) {
+            return Err(e);
+        }
+        posts.retain(|post| {
+            post.likes >= self.min_likes
+                && post.reposts >= self.min_reposts
+                && post
+                    .created_at
+                    .is_none_or(|created_at| created_at >= cutoff)
+        });
+        posts.sort_by(|a, b| b.likes.cmp(&a.likes).then(b.reposts.cmp(&a.reposts)));
+        Ok(posts)
+    }
+
+    /// Leaves out posts already kept under an earlier keyword or published
+    /// on an earlier day, matching by URL or by author and text. The kept
+    /// posts are marked in the returned set, to save once they are published.
+    /// Failing to load the shared seen URLs only costs the check against
+    /// earlier days.
+    async fn dedupe(
+        &self,
+        ctx: &CrawlerContext,
+        sections: &mut [KeywordSection],
+    ) -> Option<SeenUrls> {
+        let mut seen =
+            match SeenUrls::load(self.state.clone(), ctx.date, self.seen_urls_ttl_days).await {
+                Ok(seen) => Some(seen),
+                Err(e) => {
+                    warn!("Could not load seen URLs, publishing repeats: {}", e);
+                    None
+                }
+            };
+        let mut this_run: HashSet<String> = HashSet::new();
+        for section in sections.iter_mut() {
+            section.posts.retain(|post| {
+                let keys = [common::dedup::normalize_url(&post.url), text_key(post)];
+                if keys.iter().any(|key| this_run.contains(key)) {
+                    return false;
+                }
+                this_run.extend(keys.iter().cloned());
+                let Some(seen) = seen.as_mut() else {
+                    return true;
+                };
+                // Mark every key, so either one catches the post next time.
+                let statuses: Vec<SeenStatus> =
+                    keys.iter().map(|key| seen.check_and_mark(key)).collect();
+                let earlier = statuses.into_iter().find_map(|status| match status {
+                    SeenStatus::SeenOn(date) if date < ctx.date => Some(date),
+                    _ => None,
+                });
+                match earlier {
+                    Some(date) => {
+                        info!("Dropping repeat post {} (first seen on {})", post.url, date);
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+        seen
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let mut sections = Vec::new();
+        let mut last_error = None;
+        for keyword in &self.keywords {
+            match self.fetch_keyword(ctx, keyword).await {
+                Ok(posts) => sections.push(KeywordSection {
+                    keyword: keyword.clone(),
+                    posts,
+                }),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        if sections.is_empty() {
+            // Only an error if no keyword could be searched at all.
+            return match last_error {
+                Some(e) => Err(e),
+                None => Ok(()),
+            };
+        }
+        let seen = self.dedupe(ctx, &mut sections).await;
+        sections.retain(|section| !section.posts.is_empty());
+        if sections.is_empty() {
+            info!("No new posts on any keyword within the last {}", MAX_AGE);
+            return Ok(());
+        }
+
+        let posts: Vec<Post> = sections
+            .iter()
+            .flat_map(|section| section.posts.iter().cloned())
+            .collect();
+        let stem = format!("{}/fediverse", ctx.date);
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_sections_markdown(&sections),
+                &posts,
+            )
+            .await?;
+        info!(
+            "Uploaded {} posts on {} keywords to {}",
+            posts.len(),
+            sections.len(),
+            written.join(", ")
+        );
+        // Only once published, so a failed upload leaves the posts new.
+        if let Some(seen) = seen {
+            if let Err(e) = seen.save().await {
+                warn!("Failed to save seen URLs: {}", e);
+            }
+        }
+        if let Some(archive) = &self.archive {
+            archive
+                .record(posts.into_iter().map(|post| post.into_item(ctx.date)))
+                .await;
+        }
+        Ok(())
+    }
+}
+
+fn format_sections_markdown(sections: &[KeywordSection]) -> String {
+    let sections = sections.iter().map(|section| {
+        let posts = section.posts.iter().map(Post::to_markdown_string).collect();
+        (section.keyword.as_str(), posts)
+    });
+    markdown::sectioned("Fediverse", sections, "\n\n")
+}
+
+#[async_trait]
+impl Crawler for FediverseCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Fediverse crawler starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Fediverse"
+    }
+
+    fn slug(&self) -> &'static str {
+        "fediverse"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if !config.fediverse_enabled {
+            return Err("FEDIVERSE_ENABLED not set".to_string());
+        }
+        if config.fediverse_keywords.is_empty() {
+            return Err("FEDIVERSE_ENABLED is set but FEDIVERSE_KEYWORDS is empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(FediverseCrawler {
+    name: "Fediverse crawler",
+    slug: "fediverse",
+    required_env: ["FEDIVERSE_ENABLED"],
+    digest: ("fediverse", "Fediverse"),
+    new: FediverseCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::FixedClock;
+    use test_support::{assert_snapshot, fixture, FixtureDir, MockSupabase, TestConfig};
+    use time::macros::datetime;
+
+    const MASTODON: &str = "https://mastodon.social";
+
+    /// Recorded responses for a run at `now`: two Bluesky pages for `llm`,
+    /// one for `rust`, and the `llm` tag timeline. `rust` has no timeline.
+    fn recorded(now: OffsetDateTime) -> FixtureDir {
+        let fixtures = FixtureDir::new();
+        let since = now - MAX_AGE;
+        fixtures.add(
+            &bluesky_search_url("llm", since, None),
+            &fixture("fediverse/bluesky_llm_1.json"),
+        );
+        fixtures.add(
+            &bluesky_search_url("llm", since, Some("25")),
+            &fixture("fediverse/bluesky_llm_2.json"),
+        );
+        fixtures.add(
+            &bluesky_search_url("rust", since, None),
+            &fixture("fediverse/bluesky_rust_1.json"),
+        );
+        fixtures.add(
+            &mastodon_tag_url(MASTODON, "llm", None),
+            &fixture("fediverse/mastodon_llm.json"),
+        );
+        fixtures
+    }
+
+    fn crawler(
+        supabase: &MockSupabase,
+        fixtures: &FixtureDir,
+        update: impl FnOnce(&mut Config),
+    ) -> FediverseCrawler {
+        let config = TestConfig::new(supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| {
+                c.fediverse_enabled = true;
+                c.fediverse_keywords = vec!["llm".into(), "rust".into()];
+                c.fediverse_mastodon_instance = Some(MASTODON.to_string());
+                update(c);
+            })
+            .build();
+        FediverseCrawler::new(&config).unwrap()
+    }
+
+    fn context(now: OffsetDateTime) -> CrawlerContext {
+        CrawlerContext::with_clock(Arc::new(FixedClock(now)))
+    }
+
+    #[test]
+    fn keywords_become_hashtags() {
+        assert_eq!(hashtag("Open Weights!"), "openweights");
+        assert_eq!(hashtag("LLM"), "llm");
+        assert_eq!(
+            mastodon_tag_url(MASTODON, "llm", Some("112")),
+            "https://mastodon.social/api/v1/timelines/tag/llm?limit=40&max_id=112"
+        );
+        assert!(
+            bluesky_search_url("open weights", datetime!(2024-05-09 12:00 UTC), None)
+                .ends_with("?q=open+weights&sort=top&since=2024-05-09T12%3A00%3A00Z&limit=25")
+        );
+    }
+
+    #[tokio::test]
+    async fn publishes_posts_over_the_thresholds_once() {
+        let now = datetime!(2024-05-10 12:00 UTC);
+        let fixtures = recorded(now);
+        let supabase = MockSupabase::start().await;
+        let ctx = context(now);
+        crawler(&supabase, &fixtures, |_| {})
+            .run_with_context(&ctx)
+            .await
+            .unwrap();
+
+        let failed: Vec<String> = ctx.item_errors.take().into_iter().map(|e| e.item).collect();
+        assert_eq!(failed, ["Mastodon \"rust\""]);
+        let json: Vec<Post> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/fediverse.json").await).unwrap();
+        let kept: Vec<_> = json
+            .iter()
+            .map(|p| (p.keyword.as_str(), p.author.as_str(), p.likes))
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                ("llm", "@alice.bsky.social", 412),
+                ("llm", "@bob.dev", 97),
+                ("llm", "@carol.bsky.social", 61),
+                ("llm", "@erin@fosstodon.org", 44),
+                ("rust", "@dana.rs", 140),
+            ],
+            "the second page is followed; low-liked, old and repeated posts are left out"
+        );
+        assert_eq!(json[0].links, ["https://example.com/release"]);
+        assert_eq!(json[1].links, ["https://blog.bob.dev/spec-decoding"]);
+        assert_eq!(json[3].links, ["https://erin.example/llm-runtimes"]);
+        assert_snapshot!(
+            "fediverse.md",
+            supabase.uploaded("2024-05-10/fediverse.md").await
+        );
+    }
+
+    #[tokio::test]
+    async fn reposts_thresholds_apply_too() {
+        let now = datetime!(2024-05-10 12:00 UTC);
+        let fixtures = recorded(now);
+        let supabase = MockSupabase::start().await;
+        crawler(&supabase, &fixtures, |c| {
+            c.fediverse_keywords = vec!["llm".into()];
+            c.fediverse_min_likes = 0;
+            c.fediverse_min_reposts = 10;
+        })
+        .run_with_context(&context(now))
+        .await
+        .unwrap();
+
+        let json: Vec<Post> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/fediverse.json").await).unwrap();
+        let authors: Vec<_> = json.iter().map(|p| p.author.as_str()).collect();
+        assert_eq!(authors, ["@alice.bsky.social", "@bob.dev"]);
+    }
+
+    #[tokio::test]
+    async fn posts_published_on_an_earlier_day_are_dropped() {
+        let state_db =
+            std::env::temp_dir().join(format!("cution-fediverse-seen-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&state_db);
+        let supabase = MockSupabase::start().await;
+        let first = datetime!(2024-05-10 12:00 UTC);
+        let next = datetime!(2024-05-11 06:00 UTC);
+        let (first_fixtures, next_fixtures) = (recorded(first), recorded(next));
+        let with_state = |c: &mut Config| c.state_db = Some(state_db.clone());
+        crawler(&supabase, &first_fixtures, with_state)
+            .run_with_context(&context(first))
+            .await
+            .unwrap();
+        let rerun = crawler(&supabase, &first_fixtures, with_state);
+        rerun.run_with_context(&context(first)).await.unwrap();
+        crawler(&supabase, &next_fixtures, with_state)
+            .run_with_context(&context(next))
+            .await
+            .unwrap();
+        let _ = std::fs::remove_file(&state_db);
+
+        let again = supabase.uploaded("2024-05-10/fediverse.md").await;
+        assert!(
+            again.contains("@alice.bsky.social"),
+            "a same-day rerun is not a repeat"
+        );
+        assert!(
+            !supabase
+                .uploaded_paths()
+                .await
+                .contains(&"2024-05-11/fediverse.md".to_string()),
+            "every post went out the day before"
+        );
+    }
+
+    #[tokio::test]
+    async fn posts_stay_new_when_the_upload_fails() {
+        let state_db = std::env::temp_dir().join(format!(
+            "cution-fediverse-unsaved-{}.db",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&state_db);
+        let supabase = MockSupabase::start().await;
+        supabase.fail_uploads(401, 1).await;
+        let first = datetime!(2024-05-10 12:00 UTC);
+        let next = datetime!(2024-05-11 06:00 UTC);
+        let (first_fixtures, next_fixtures) = (recorded(first), recorded(next));
+        let with_state = |c: &mut Config| c.state_db = Some(state_db.clone());
+        assert!(crawler(&supabase, &first_fixtures, with_state)
+            .run_with_context(&context(first))
+            .await
+            .is_err());
+        crawler(&supabase, &next_fixtures, with_state)
+            .run_with_context(&context(next))
+            .await
+            .unwrap();
+        let _ = std::fs::remove_file(&state_db);
+
+        let next_day = supabase.uploaded("2024-05-11/fediverse.md").await;
+        assert!(next_day.contains("@alice.bsky.social"));
+    }
+}
@@ -0,0 +1,279 @@
+use common::archive;
+use common::feed::parse_date;
+use common::host_limit::host_of;
+use common::html::{self, TextOptions};
+use common::markdown;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Network {
+    Bluesky,
+    Mastodon,
+}
+
+impl Network {
+    pub fn label(self) -> &'static str {
+        match self {
+            Network::Bluesky => "Bluesky",
+            Network::Mastodon => "Mastodon",
+        }
+    }
+}
+
+/// A post matching one of the watched keywords.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Post {
+    pub network: Network,
+    /// The keyword the post was found under.
+    pub keyword: String,
+    /// `@handle`, or `@user@instance` for a remote Mastodon account.
+    pub author: String,
+    pub author_name: Option<String>,
+    pub url: String,
+    /// Plain text of the post.
+    pub text: String,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub created_at: Option<OffsetDateTime>,
+    pub likes: u64,
+    pub reposts: u64,
+    /// Outbound links, in the order they appear.
+    pub links: Vec<String>,
+}
+
+impl Post {
+    pub fn from_bluesky(keyword: &str, post: BlueskyPost) -> Self {
+        let rkey = post.uri.rsplit('/').next().unwrap_or_default();
+        let url = format!(
+            "https://bsky.app/profile/{}/post/{}",
+            post.author.handle, rkey
+        );
+        let facet_links = post
+            .record
+            .facets
+            .iter()
+            .flat_map(|facet| &facet.features)
+            .filter(|feature| feature.kind == "app.bsky.richtext.facet#link")
+            .filter_map(|feature| feature.uri.clone());
+        let embed_link = post
+            .record
+            .embed
+            .as_ref()
+            .and_then(|embed| embed.external.as_ref())
+            .map(|external| external.uri.clone());
+        Self {
+            network: Network::Bluesky,
+            keyword: keyword.to_string(),
+            author: format!("@{}", post.author.handle),
+            author_name: post
+                .author
+                .display_name
+                .filter(|name| !name.trim().is_empty()),
+            url,
+            text: post.record.text.trim().to_string(),
+            created_at: post.record.created_at.as_deref().and_then(parse_date),
+            likes: post.like_count,
+            reposts: post.repost_count,
+            links: unique(facet_links.chain(embed_link)),
+        }
+    }
+
+    pub fn from_mastodon(keyword: &str, status: MastodonStatus) -> Self {
+        let card_link = status.card.map(|card| card.url);
+        Self {
+            network: Network::Mastodon,
+            keyword: keyword.to_string(),
+            author: format!("@{}", status.account.acct),
+            author_name: Some(status.account.display_name).filter(|name| !name.trim().is_empty()),
+            url: status.url.unwrap_or(status.uri),
+            text: html::to_text(&status.content, &TextOptions::fragment()),
+            created_at: parse_date(&status.created_at),
+            likes: status.favourites_count,
+            reposts: status.reblogs_count,
+            links: unique(content_links(&status.content).into_iter().chain(card_link)),
+        }
+    }
+
+    pub fn to_markdown_string(&self) -> String {
+        let mut markdown = format!(
+            "### {} · {}",
+            markdown::link(&self.author, &self.url),
+            self.network.label()
+        );
+        let mut meta = format!("{} likes · {} reposts", self.likes, self.reposts);
+        if let Some(created_at) = self.created_at {
+            meta.push_str(&format!(" · {}", created_at.date()));
+        }
+        markdown.push_str(&format!("\n\n*{}*", meta));
+        if !self.text.is_empty() {
+            markdown.push_str(&format!("\n\n> {}", markdown::escape(&self.text)));
+        }
+        if !self.links.is_empty() {
+            let links: Vec<String> = self
+                .links
+                .iter()
+                .map(|url| markdown::link(&host_of(url).unwrap_or_else(|| url.clone()), url))
+                .collect();
+            markdown.push_str(&format!("\n\nLinks: {}", links.join(", ")));
+        }
+        markdown
+    }
+}
+
+impl archive::Archivable for Post {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "fediverse".to_string(),
+            date: date.to_string(),
+            url: Some(self.url.clone()),
+            title: format!("{} on {}", self.author, self.network.label()),
+            score: Some(self.likes as f64),
+            id: self.url.clone(),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+        }
+    }
+}
+
+/// Links in a Mastodon post's HTML, leaving out mentions and hashtags.
+fn content_links(content: &str) -> Vec<String> {
+    let Ok(selector) = Selector::parse("a[href]") else {
+        return Vec::new();
+    };
+    Html::parse_fragment(content)
+        .select(&selector)
+        .filter(|a| {
+            let class = a.value().attr("class").unwrap_or_default();
+            !class.contains("mention")
+                && !class.contains("hashtag")
+                && a.value().attr("rel").is_none_or(|rel| !rel.contains("tag"))
+        })
+        .filter_map(|a| a.value().attr("href"))
+        .filter(|href| href.starts_with("http://") || href.starts_with("https://"))
+        .map(str::to_string)
+        .collect()
+}
+
+fn unique(links: impl Iterator<Item = String>) -> Vec<String> {
+    let mut kept: Vec<String> = Vec::new();
+    for link in links {
+        if !kept.contains(&link) {
+            kept.push(link);
+        }
+    }
+    kept
+}
+
+/// `app.bsky.feed.searchPosts`.
+#[derive(Debug, Deserialize)]
+pub struct BlueskySearch {
+    /// Absent on the last page.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    pub posts: Vec<BlueskyPost>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlueskyPost {
+    /// `at://{did}/app.bsky.feed.post/{rkey}`.
+    pub uri: String,
+    pub author: BlueskyAuthor,
+    pub record: BlueskyRecord,
+    #[serde(default)]
+    pub like_count: u64,
+    #[serde(default)]
+    pub repost_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlueskyAuthor {
+    pub handle: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlueskyRecord {
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub facets: Vec<BlueskyFacet>,
+    #[serde(default)]
+    pub embed: Option<BlueskyEmbed>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlueskyFacet {
+    #[serde(default)]
+    pub features: Vec<BlueskyFeature>,
+}
+
+/// A link, mention or tag; only links carry a `uri`.
+#[derive(Debug, Deserialize)]
+pub struct BlueskyFeature {
+    #[serde(rename = "$type")]
+    pub kind: String,
+    #[serde(default)]
+    pub uri: Option<String>,
+}
+
+/// Only link cards (`app.bsky.embed.external`) are used.
+#[derive(Debug, Deserialize)]
+pub struct BlueskyEmbed {
+    #[serde(default)]
+    pub external: Option<BlueskyExternal>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlueskyExternal {
+    pub uri: String,
+}
+
+/// A status from `/api/v1/timelines/tag/{hashtag}`.
+#[derive(Debug, Deserialize)]
+pub struct MastodonStatus {
+    pub id: String,
+    pub created_at: String,
+    /// The status page; remote statuses may only have `uri`.
+    #[serde(default)]
+    pub url: Option<String>,
+    pub uri: String,
+    /// HTML.
+    pub content: String,
+    pub account: MastodonAccount,
+    #[serde(default)]
+    pub favourites_count: u64,
+    #[serde(default)]
+    pub reblogs_count: u64,
+    #[serde(default)]
+    pub card: Option<MastodonCard>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MastodonAccount {
+    pub acct: String,
+    #[serde(default)]
+    pub display_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MastodonCard {
+    pub url: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mastodon_links_skip_mentions_and_hashtags() {
+        let content = r#"<p>New release <a href="https://mastodon.social/tags/llm" class="mention hashtag" rel="tag">#<span>llm</span></a> by <span class="h-card"><a href="https://fosstodon.org/@bob" class="u-url mention">@<span>bob</span></a></span>: <a href="https://example.com/release" rel="nofollow noopener" target="_blank">example.com/release</a></p>"#;
+        assert_eq!(content_links(content), ["https://example.com/release"]);
+    }
+}
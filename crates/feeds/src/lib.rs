@@ -0,0 +1,460 @@
+pub mod models;
+pub mod opml;
+
+use async_trait::async_trait;
+use common::archive::{self, Archivable, ArchiveWriter};
+use common::feed::{self, Feed, FeedEntry};
+use common::http::{Conditional, Validators};
+use common::summary_cache::{self, CachedSummarizer};
+use common::{
+    markdown, state, summarize_long, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult,
+    HttpClient, OutputFormat, Publisher, StateStore, Summarizer, SupabaseStorageClient,
+};
+use models::FeedItem;
+use opml::OpmlFeed;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use time::Duration;
+use tracing::{info, warn};
+
+/// State namespace holding each feed's validators and last entries.
+const STATE_NAMESPACE: &str = "feeds";
+
+/// What is kept of a feed between runs, so a 304 still yields its entries.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CachedFeed {
+    #[serde(default)]
+    validators: Validators,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    entries: Vec<FeedEntry>,
+}
+
+/// The recent entries of one feed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedSection {
+    pub title: String,
+    pub items: Vec<FeedItem>,
+}
+
+/// Where the crawler finds its feeds.
+enum FeedList {
+    /// An OPML subscription list, from a URL or a local path.
+    Opml(String),
+    /// Feeds known up front, such as YouTube's channel upload feeds.
+    Fixed(Vec<OpmlFeed>),
+}
+
+/// Publishes recent entries of every feed in the `FEEDS_OPML` subscription
+/// list to `{date}/feeds.md`, one section per feed. `for_feeds` runs the same
+/// over a fixed list under another slug.
+pub struct FeedsCrawler {
+    client: HttpClient,
+    feeds: FeedList,
+    slug: &'static str,
+    heading: &'static str,
+    max_age: Duration,
+    state: Arc<dyn StateStore>,
+    summarizer: Option<Arc<dyn Summarizer>>,
+    storage: SupabaseStorageClient,
+    summary_ttl_days: u32,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    archive: Option<ArchiveWriter>,
+}
+
+impl FeedsCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        let opml = FeedList::Opml(config.feeds_opml.clone().unwrap_or_default());
+        Ok(Self {
+            max_age: Duration::hours(config.feeds_max_age_hours as i64),
+            summarizer: config.summarizer(config.feeds_summarize)?,
+            ..Self::build(config, opml, "feeds", "Feeds")?
+        })
+    }
+
+    /// A crawler over `feeds` that publishes to `{date}/{slug}.md` under
+    /// `heading`. A feed whose title is its URL takes the title of its
+    /// document instead.
+    pub fn for_feeds(
+        config: &Config,
+        slug: &'static str,
+        heading: &'static str,
+        feeds: Vec<OpmlFeed>,
+    ) -> CrawlerResult<Self> {
+        Self::build(config, FeedList::Fixed(feeds), slug, heading)
+    }
+
+    fn build(
+        config: &Config,
+        feeds: FeedList,
+        slug: &'static str,
+        heading: &'static str,
+    ) -> CrawlerResult<Self> {
+        Ok(Self {
+            client: ClientFactory::from_config(config).build()?,
+            feeds,
+            slug,
+            heading,
+            max_age: Duration::hours(24),
+            state: state::from_config(config)?,
+            summarizer: None,
+            storage: SupabaseStorageClient::from_config(config)?,
+            // An unchanged feed serves the same entries run after run, so
+            // its summaries are cached even without SUMMARY_CACHE_TTL_DAYS.
+            summary_ttl_days: config
+                .summary_cache_ttl_days
+                .unwrap_or(summary_cache::DEFAULT_TTL_DAYS),
+            publisher: Publisher::for_crawler(config, slug)?,
+            output_formats: config.output_formats_for(slug),
+            archive: ArchiveWriter::from_config(config)?,
+        })
+    }
+
+    /// Entries published longer ago than `max_age` are left out.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// The feeds to read: the fixed list, or the subscription list loaded
+    /// from a URL or a local path.
+    async fn load_feeds(&self, ctx: &CrawlerContext) -> CrawlerResult<Vec<OpmlFeed>> {
+        let source = match &self.feeds {
+            FeedList::Fixed(feeds) => return Ok(feeds.clone()),
+            FeedList::Opml(source) => source,
+        };
+        let body = if source.starts_with("http://") || source.starts_with("https://") {
+            ctx.http_client(&self.client).get_text(source).await?
+        } else {
+            tokio::fs::read_to_string(source).await?
+        };
+        let feeds = opml::parse_opml(&body)?;
+        info!("Loaded {} feeds from {}", feeds.len(), source);
+        Ok(feeds)
+    }
+
+    /// The feed, fetched only if it changed since the last run; otherwise
+    /// what was kept from then.
+    async fn fetch_feed(&self, ctx: &CrawlerContext, feed: &OpmlFeed) -> CrawlerResult<Feed> {
+        let cached: CachedFeed = match self.state.get(STATE_NAMESPACE, &feed.xml_url).await {
+            Ok(Some(value)) => serde_json::from_value(value).unwrap_or_default(),
+            Ok(None) => CachedFeed::default(),
+            Err(e) => {
+                warn!("Could not read the cached state of {}: {}", feed.xml_url, e);
+                CachedFeed::default()
+            }
+        };
+        let response = ctx
+            .http_client(&self.client)
+            .get_conditional(&feed.xml_url, &cached.validators)
+            .await?;
+        let (body, validators) = match response {
+            Conditional::NotModified => {
+                info!("{} is unchanged since the last run", feed.title);
+                return Ok(Feed {
+                    title: cached.title,
+                    link: None,
+                    entries: cached.entries,
+                });
+            }
+            Conditional::Modified { body, validators } => (body, validators),
+        };
+        let parsed = feed::parse_feed(&body)?;
+        let fresh = CachedFeed {
+            validators,
+            title: parsed.title.clone(),
+            entries: parsed.entries.clone(),
+        };
+        let value = serde_json::to_value(&fresh).unwrap_or_default();
+        if let Err(e) = self.state.set(STATE_NAMESPACE, &feed.xml_url, value).await {
+            warn!("Could not cache {}: {}", feed.xml_url, e);
+        }
+        Ok(parsed)
+    }
+
+    /// The entries published within `max_age`, newest first, under the
+    /// feed's title; undated entries cannot be placed and are left out.
+    fn recent(&self, ctx: &CrawlerContext, feed: &OpmlFeed, fetched: Feed) -> FeedSection {
+        let title = match fetched.title {
+            Some(title) if feed.title == feed.xml_url && !title.trim().is_empty() => title,
+            _ => feed.title.clone(),
+        };
+        let cutoff = ctx.now() - self.max_age;
+        let mut items: Vec<FeedItem> = fetched
+            .entries
+            .into_iter()
+            .filter(|entry| entry.published.is_some_and(|published| published >= cutoff))
+            .map(|entry| FeedItem::new(&title, entry))
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.published));
+        FeedSection { title, items }
+    }
+
+    /// Fetches every feed and publishes the recent entries. Failing feeds
+    /// are reported and skipped; the run fails only when none could be read.
+    pub async fn publish(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let feeds = self.load_feeds(ctx).await?;
+        let summarizer = self.summarizer.clone().map(|summarizer| {
+            CachedSummarizer::wrap(
+                summarizer,
+                ctx.storage_client(&self.storage),
+                ctx.date,
+                Some(self.summary_ttl_days),
+            )
+        });
+        let mut sections = Vec::new();
+        let mut failed = 0;
+        let mut last_error = None;
+        for feed in &feeds {
+            match self.fetch_feed(ctx, feed).await {
+                Ok(fetched) => {
+                    let mut section = self.recent(ctx, feed, fetched);
+                    if let Some(summarizer) = &summarizer {
+                        summarize_long(ctx, summarizer.as_ref(), &mut section.items).await;
+                    }
+                    if !section.items.is_empty() {
+                        sections.push(section);
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch feed {} ({}): {}",
+                        feed.title, feed.xml_url, e
+                    );
+                    ctx.item_failed(feed.title.clone(), &e);
+                    failed += 1;
+                    last_error = Some(e);
+                }
+            }
+        }
+        if sections.is_empty() {
+            return match last_error {
+                Some(e) if failed == feeds.len() => Err(e),
+                _ => {
+                    info!("No {} entries within the last {}", self.slug, self.max_age);
+                    Ok(())
+                }
+            };
+        }
+
+        let items: Vec<FeedItem> = sections
+            .iter()
+            .flat_map(|section| section.items.iter().cloned())
+            .collect();
+        let stem = format!("{}/{}", ctx.date, self.slug);
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_sections_markdown(self.heading, &sections),
+                &items,
+            )
+            .await?;
+        info!(
+            "Uploaded {} entries from {} feeds to {}",
+            items.len(),
+            sections.len(),
+            written.join(", ")
+        );
+        if let Some(archive) = &self.archive {
+            let records = items.into_iter().map(|item| archive::Item {
+                source: self.slug.to_string(),
+                ..item.into_item(ctx.date)
+            });
+            archive.record(records).await;
+        }
+        Ok(())
+    }
+}
+
+fn format_sections_markdown(heading: &str, sections: &[FeedSection]) -> String {
+    let sections = sections.iter().map(|section| {
+        let items = section
+            .items
+            .iter()
+            .map(FeedItem::to_markdown_string)
+            .collect();
+        (section.title.as_str(), items)
+    });
+    markdown::sectioned(heading, sections, "\n\n")
+}
+
+#[async_trait]
+impl Crawler for FeedsCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Feeds crawler starting up");
+        self.publish(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Feeds"
+    }
+
+    fn slug(&self) -> &'static str {
+        "feeds"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if config.feeds_opml.is_none() {
+            return Err("FEEDS_OPML not set".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(FeedsCrawler {
+    name: "Feeds crawler",
+    slug: "feeds",
+    required_env: ["FEEDS_OPML"],
+    digest: ("feeds", "Feeds"),
+    new: FeedsCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{FixedClock, TruncatingSummarizer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use test_support::{
+        assert_snapshot, fixture, FeedRequest, MockFeeds, MockSupabase, TestConfig,
+    };
+    use time::macros::datetime;
+
+    /// The subscription list and every feed in it: two good ones, one that
+    /// is not a feed and one that is down.
+    async fn subscriptions() -> MockFeeds {
+        let feeds = MockFeeds::start().await;
+        let opml = fixture("feeds/subscriptions.opml").replace("{server}", &feeds.url(""));
+        feeds.file("/subscriptions.opml", &opml).await;
+        feeds
+            .feed("/rust.xml", &fixture("feeds/rust_blog.xml"), "\"rust-1\"")
+            .await;
+        feeds
+            .feed("/notes.atom", &fixture("feeds/notes.atom"), "\"notes-1\"")
+            .await;
+        feeds.file("/broken.xml", "<html><body>Moved").await;
+        feeds.fail("/gone.xml", 500).await;
+        feeds
+    }
+
+    /// Truncates like the default crawler summarizer and counts its calls.
+    #[derive(Default)]
+    struct CountingSummarizer(AtomicUsize);
+
+    #[async_trait]
+    impl Summarizer for CountingSummarizer {
+        async fn summarize(&self, title: &str, content: &str) -> CrawlerResult<String> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            TruncatingSummarizer { max_chars: 60 }
+                .summarize(title, content)
+                .await
+        }
+    }
+
+    fn context() -> CrawlerContext {
+        CrawlerContext::with_clock(Arc::new(FixedClock(datetime!(2024-05-10 12:00 UTC))))
+    }
+
+    async fn crawler(supabase: &MockSupabase, opml: &str) -> FeedsCrawler {
+        let config = TestConfig::new(supabase)
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| c.feeds_opml = Some(opml.to_string()))
+            .build();
+        FeedsCrawler::new(&config)
+            .unwrap()
+            .with_summarizer(Arc::new(TruncatingSummarizer { max_chars: 60 }))
+    }
+
+    #[tokio::test]
+    async fn publishes_recent_entries_and_skips_failing_feeds() {
+        let feeds = subscriptions().await;
+        let supabase = MockSupabase::start().await;
+        let ctx = context();
+        crawler(&supabase, &feeds.url("/subscriptions.opml"))
+            .await
+            .run_with_context(&ctx)
+            .await
+            .unwrap();
+
+        let failed: Vec<String> = ctx.item_errors.take().into_iter().map(|e| e.item).collect();
+        assert_eq!(failed, ["Broken Feed", "Gone & Forgotten"]);
+        let json: Vec<FeedItem> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/feeds.json").await).unwrap();
+        let titles: Vec<_> = json
+            .iter()
+            .map(|i| (i.feed.as_str(), i.title.as_str()))
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                ("Rust Blog", "Faster linking & smaller binaries on nightly"),
+                ("Field Notes", "Profiling async code with tokio-console"),
+            ],
+            "older entries are left out"
+        );
+        assert_snapshot!("feeds.md", supabase.uploaded("2024-05-10/feeds.md").await);
+    }
+
+    #[tokio::test]
+    async fn unchanged_feeds_come_from_the_cache() {
+        let feeds = subscriptions().await;
+        let supabase = MockSupabase::start().await;
+        supabase.keep_uploads().await;
+        let summarizer = Arc::new(CountingSummarizer::default());
+        let crawler = crawler(&supabase, &feeds.url("/subscriptions.opml"))
+            .await
+            .with_summarizer(summarizer.clone());
+        crawler.run_with_context(&context()).await.unwrap();
+        let first = supabase.uploaded("2024-05-10/feeds.md").await;
+        crawler.run_with_context(&context()).await.unwrap();
+        assert_eq!(
+            summarizer.0.load(Ordering::SeqCst),
+            1,
+            "the unchanged entry reuses its cached summary"
+        );
+
+        let rust_requests: Vec<FeedRequest> = feeds
+            .requests()
+            .await
+            .into_iter()
+            .filter(|r| r.path == "/rust.xml")
+            .collect();
+        let sent: Vec<_> = rust_requests
+            .iter()
+            .map(|r| r.if_none_match.as_deref())
+            .collect();
+        assert_eq!(sent, [None, Some("\"rust-1\"")]);
+        assert_eq!(supabase.uploaded("2024-05-10/feeds.md").await, first);
+    }
+
+    #[tokio::test]
+    async fn fails_when_no_feed_can_be_read() {
+        let feeds = MockFeeds::start().await;
+        feeds.fail("/gone.xml", 503).await;
+        let opml = std::env::temp_dir().join(format!("cution-feeds-{}.opml", std::process::id()));
+        std::fs::write(
+            &opml,
+            format!(
+                r#"<opml version="2.0"><body><outline text="Gone" xmlUrl="{}"/></body></opml>"#,
+                feeds.url("/gone.xml")
+            ),
+        )
+        .unwrap();
+        let supabase = MockSupabase::start().await;
+        let result = crawler(&supabase, opml.to_str().unwrap())
+            .await
+            .run_with_context(&context())
+            .await;
+        let _ = std::fs::remove_file(&opml);
+        assert!(result.is_err());
+        assert!(supabase.uploaded_paths().await.is_empty());
+    }
+}
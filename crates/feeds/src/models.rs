@@ -0,0 +1,88 @@
+use common::archive;
+use common::feed::FeedEntry;
+use common::markdown;
+use common::Summarizable;
+use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime};
+
+/// Characters of an entry shown when it has no summary.
+const EXCERPT_CHARS: usize = 280;
+
+/// An entry kept for the digest, with the feed it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FeedItem {
+    /// The feed's title from the OPML file, or from the feed itself.
+    pub feed: String,
+    pub title: String,
+    pub url: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub published: Option<OffsetDateTime>,
+    /// Plain text of the entry.
+    pub content: Option<String>,
+    pub summary: Option<String>,
+}
+
+impl FeedItem {
+    pub fn new(feed: &str, entry: FeedEntry) -> Self {
+        Self {
+            feed: feed.to_string(),
+            title: entry.title,
+            url: entry.url,
+            published: entry.published,
+            content: entry.content,
+            summary: None,
+        }
+    }
+
+    pub fn to_markdown_string(&self) -> String {
+        let title = match &self.url {
+            Some(url) => markdown::link(&self.title, url),
+            None => markdown::escape(&self.title),
+        };
+        let mut markdown = format!("### {}", title);
+        if let Some(published) = self.published {
+            markdown.push_str(&format!("\n\n*{}*", published.date()));
+        }
+        let body = self.summary.clone().or_else(|| {
+            self.content
+                .as_deref()
+                .map(|text| markdown::excerpt(text, EXCERPT_CHARS))
+        });
+        if let Some(body) = body {
+            markdown.push_str("\n\n");
+            markdown.push_str(&body);
+        }
+        markdown
+    }
+}
+
+impl Summarizable for FeedItem {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn text(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+
+    fn set_summary(&mut self, summary: String) {
+        self.summary = Some(summary);
+    }
+}
+
+impl archive::Archivable for FeedItem {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "feeds".to_string(),
+            date: date.to_string(),
+            url: self.url.clone(),
+            title: self.title.clone(),
+            score: None,
+            id: self
+                .url
+                .clone()
+                .unwrap_or_else(|| format!("{}: {}", self.feed, self.title)),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use common::xml::{self, Node};
+use common::{CrawlerError, CrawlerResult};
+
+/// A subscription from an OPML export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpmlFeed {
+    /// The outline's `title`, else its `text`, else the feed URL.
+    pub title: String,
+    pub xml_url: String,
+    pub html_url: Option<String>,
+}
+
+/// Every outline with an `xmlUrl`, folders flattened, in document order.
+/// A feed listed twice is kept once.
+pub fn parse_opml(body: &str) -> CrawlerResult<Vec<OpmlFeed>> {
+    let document = xml::parse(body)?;
+    let root = document.root_element();
+    if !root.has_tag_name("opml") {
+        return Err(CrawlerError::Parse(format!(
+            "not an OPML file (root element <{}>)",
+            root.tag_name().name()
+        )));
+    }
+    let body = xml::child(root, "body")
+        .ok_or_else(|| CrawlerError::Parse("OPML file without a <body>".to_string()))?;
+    let mut feeds = Vec::new();
+    collect(body, &mut feeds);
+    Ok(feeds)
+}
+
+fn collect(parent: Node, feeds: &mut Vec<OpmlFeed>) {
+    for outline in xml::children(parent, "outline") {
+        let xml_url = outline
+            .attribute("xmlUrl")
+            .map(str::trim)
+            .unwrap_or_default();
+        if !xml_url.is_empty() && !feeds.iter().any(|feed| feed.xml_url == xml_url) {
+            let title = [outline.attribute("title"), outline.attribute("text")]
+                .into_iter()
+                .flatten()
+                .map(str::trim)
+                .find(|title| !title.is_empty())
+                .unwrap_or(xml_url);
+            feeds.push(OpmlFeed {
+                title: title.to_string(),
+                xml_url: xml_url.to_string(),
+                html_url: outline
+                    .attribute("htmlUrl")
+                    .filter(|url| !url.is_empty())
+                    .map(str::to_string),
+            });
+        }
+        collect(outline, feeds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::fixture;
+
+    #[test]
+    fn flattens_folders_and_drops_duplicates() {
+        let feeds = parse_opml(&fixture("feeds/subscriptions.opml")).unwrap();
+        let listed: Vec<_> = feeds
+            .iter()
+            .map(|f| (f.title.as_str(), f.xml_url.as_str()))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("Rust Blog", "{server}/rust.xml"),
+                ("Broken Feed", "{server}/broken.xml"),
+                ("Field Notes", "{server}/notes.atom"),
+                ("Gone & Forgotten", "{server}/gone.xml"),
+            ]
+        );
+        assert_eq!(
+            feeds[0].html_url.as_deref(),
+            Some("https://blog.rust-lang.org/")
+        );
+        assert!(parse_opml("<rss><channel/></rss>").is_err());
+    }
+}
@@ -1,109 +1,181 @@
-use common::{Config, Crawler, CrawlerResult, SupabaseStorageClient};
-use time::OffsetDateTime;
-use tracing::{info, warn};
 use async_trait::async_trait;
+use common::archive::{self, Archivable, ArchiveWriter};
+pub use common::config::TrendingRange;
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult, OutputFormat,
+    Publisher, RateLimitedClient, RateLimiter,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use time::Date;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+const GITHUB_TRENDING_URL_FORMAT: &str = "https://github.com/trending/{language}?since={since}";
+const README_URL_FORMAT: &str = "https://raw.githubusercontent.com/{repo}/HEAD/README.md";
+/// Characters of README text kept per repository.
+const README_EXCERPT_LEN: usize = 300;
+/// README downloads in flight across all languages.
+const README_CONCURRENCY: usize = 4;
+/// Requests to GitHub that may go out back to back before the rate applies.
+const GITHUB_BURST: u32 = 4;
 
-pub use self::GithubTrendingFetcher;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Repository {
+    pub name: String,
+    pub description: Option<String>,
+    pub link: String,
+    /// Older snapshots stored this as text such as "1,234".
+    #[serde(deserialize_with = "star_count")]
+    pub stars: u32,
+    /// Stars gained over the trending range ("N stars today").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stars_today: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The configured languages whose trending lists included this repo,
+    /// filled in when the lists are merged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trending_in: Vec<String>,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub is_fork: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readme_excerpt: Option<String>,
+}
+
+impl archive::Archivable for Repository {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "github".to_string(),
+            date: date.to_string(),
+            id: self.name.clone(),
+            title: self.name.clone(),
+            url: Some(self.link.clone()),
+            score: Some(self.stars as f64),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+        }
+    }
+}
 
-const GITHUB_TRENDING_URL_FORMAT: &str = "https://github.com/trending/{language}?since=daily";
-const MARKDOWN_FORMAT: &str =
-    "\n# {title}\n\n**Stars**: {stars}\n\n[View Repository]({link})\n\n{description}\n";
+/// Reads a star count written either as a number or as GitHub's text.
+fn star_count<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stars {
+        Count(u32),
+        Text(String),
+    }
+    Ok(match Stars::deserialize(deserializer)? {
+        Stars::Count(count) => count,
+        Stars::Text(text) => parse_count(&text).unwrap_or(0),
+    })
+}
 
-#[derive(Debug)]
-struct Repository {
-    name: String,
-    description: Option<String>,
-    link: String,
-    stars: String, // Keep as String for direct insertion into markdown
+/// The number in text such as "1,234" or "345 stars today".
+fn parse_count(text: &str) -> Option<u32> {
+    let digits: String = text
+        .split_whitespace()
+        .next()?
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
 }
 
+#[derive(Clone)]
 pub struct GithubTrendingFetcher {
-    http_client: reqwest::Client,
-    supabase_client: SupabaseStorageClient,
+    http_client: RateLimitedClient,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
     languages: Vec<String>,
+    language_concurrency: usize,
+    readme_top_n: usize,
+    exclude_archived: bool,
+    exclude_forks: bool,
+    confirm_top_n: usize,
+    archive: Option<Arc<ArchiveWriter>>,
+    file_per_language: bool,
+    combined_file: bool,
+    since: TrendingRange,
+    spoken_language: Option<String>,
 }
 
 impl GithubTrendingFetcher {
     pub fn new(config: &Config) -> CrawlerResult<Self> {
-        let http_client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
-            .build()
-            .map_err(|e| common::CrawlerError::HttpRequest(e))?;
-        
-        let supabase_client = SupabaseStorageClient::new(
-            &config.supabase.storage_url,
-            &config.supabase.key,
-            &config.supabase.bucket,
-        );
-        
+        let http_client = RateLimitedClient::new(
+            ClientFactory::from_config(config).build()?,
+            RateLimiter::new(config.github_requests_per_sec, GITHUB_BURST),
+        );
+
+        let publisher = Publisher::for_crawler(config, "github")?;
+
         let languages = config.require_languages()?.clone();
-        
+
         Ok(Self {
             http_client,
-            supabase_client,
+            publisher,
+            output_formats: config.output_formats_for("github"),
             languages,
+            language_concurrency: config.github_language_concurrency,
+            readme_top_n: config.github_fetch_readme,
+            exclude_archived: config.github_exclude_archived,
+            exclude_forks: config.github_exclude_forks,
+            confirm_top_n: config.github_confirm_flags_top,
+            archive: ArchiveWriter::from_config(config)?.map(Arc::new),
+            file_per_language: config.github_file_per_language,
+            combined_file: config.github_combined_file,
+            since: config.github_trending_since,
+            spoken_language: None,
         })
     }
 
-    async fn fetch_trending_for_language(
-        &self,
-        language: &str,
-    ) -> CrawlerResult<Vec<Repository>> {
-        let url = if language.is_empty() {
+    /// Fetches this trending range instead of the configured one.
+    pub fn with_since(mut self, since: TrendingRange) -> Self {
+        self.since = since;
+        self
+    }
+
+    /// Only lists repositories in this spoken language code (e.g. `zh`).
+    pub fn with_spoken_language(mut self, code: &str) -> Self {
+        self.spoken_language = Some(code.to_string());
+        self
+    }
+
+    /// The upload stem for `language` (`None` for the combined file).
+    fn output_key(&self, language: Option<&str>) -> String {
+        trending_output_key(language, self.since, self.spoken_language.as_deref())
+    }
+
+    async fn fetch_trending_for_language(&self, language: &str) -> CrawlerResult<Vec<Repository>> {
+        let mut url = if language.is_empty() {
             GITHUB_TRENDING_URL_FORMAT.replace("/{language}", "")
         } else {
             GITHUB_TRENDING_URL_FORMAT.replace("{language}", language)
-        };
+        }
+        .replace("{since}", self.since.as_str());
+        if let Some(code) = &self.spoken_language {
+            url.push_str(&format!("&spoken_language_code={}", code));
+        }
         info!("Fetching trending repositories from: {}", url);
 
-        let response_text = self.http_client.get(&url).send().await
-            .map_err(|e| common::CrawlerError::HttpRequest(e))?
-            .text().await
-            .map_err(|e| common::CrawlerError::HttpRequest(e))?;
-        let document = scraper::Html::parse_document(&response_text);
-
-        let article_selector = scraper::Selector::parse("article.Box-row").map_err(|e| {
-            common::CrawlerError::HtmlParse(format!("Failed to parse article selector: {}", e))
-        })?;
-        let name_selector = scraper::Selector::parse("h2.h3 a")
-            .map_err(|e| common::CrawlerError::HtmlParse(format!("Failed to parse name selector: {}", e)))?;
-        let desc_selector = scraper::Selector::parse("p.col-9").map_err(|e| {
-            common::CrawlerError::HtmlParse(format!("Failed to parse description selector: {}", e))
-        })?;
-        let stars_selector = scraper::Selector::parse("a[href*='/stargazers']")
-            .map_err(|e| common::CrawlerError::HtmlParse(format!("Failed to parse stars selector: {}", e)))?;
-
-        let mut repositories = Vec::new();
-
-        for article in document.select(&article_selector) {
-            let name_element = article.select(&name_selector).next();
-            let repo_name_and_owner = name_element
-                .and_then(|el| el.attr("href"))
-                .map(|href| href.trim_start_matches('/').to_string());
-
-            if repo_name_and_owner.is_none() {
-                warn!("Could not extract repository name and owner from an article. Skipping.");
-                continue;
-            }
-            let full_name = repo_name_and_owner.unwrap();
-
-            let description = article
-                .select(&desc_selector)
-                .next()
-                .map(|p| p.text().collect::<String>().trim().to_string());
-
-            let stars = article
-                .select(&stars_selector)
-                .next()
-                .map(|s| s.text().collect::<String>().trim().replace(',', ""))
-                .unwrap_or_else(|| "0".to_string());
-
-            repositories.push(Repository {
-                name: full_name.clone(),
-                link: format!("https://github.com/{}", full_name),
-                description,
-                stars,
+        let response_text = self.http_client.get_text(&url).await?;
+        let mut repositories = parse_trending(&response_text)?;
+        if self.exclude_archived || self.exclude_forks {
+            self.confirm_repo_flags(&mut repositories).await;
+            let before = repositories.len();
+            repositories.retain(|repo| {
+                !(self.exclude_archived && repo.archived || self.exclude_forks && repo.is_fork)
             });
+            if repositories.len() < before {
+                info!(
+                    "Skipped {} archived/forked repositories",
+                    before - repositories.len()
+                );
+            }
         }
         info!(
             "Found {} repositories for language '{}'",
@@ -117,42 +189,113 @@ impl GithubTrendingFetcher {
         Ok(repositories)
     }
 
-    fn stylize_repository_info(&self, repository: &Repository) -> String {
-        MARKDOWN_FORMAT
-            .replace("{title}", &repository.name)
-            .replace("{stars}", &repository.stars)
-            .replace("{link}", &repository.link)
-            .replace(
-                "{description}",
-                repository
-                    .description
-                    .as_deref()
-                    .unwrap_or("No description provided."),
-            )
+    /// Checks the repository page of the first `confirm_top_n` entries the
+    /// trending markup did not already flag, since it rarely marks archives.
+    async fn confirm_repo_flags(&self, repositories: &mut [Repository]) {
+        for repository in repositories
+            .iter_mut()
+            .filter(|repo| !repo.archived && !repo.is_fork)
+            .take(self.confirm_top_n)
+        {
+            match self.http_client.get_text(&repository.link).await {
+                Ok(html) => {
+                    let (archived, is_fork) = repo_page_flags(&html);
+                    repository.archived = archived;
+                    repository.is_fork = is_fork;
+                }
+                Err(e) => warn!(
+                    "Failed to check {} for archive/fork status: {}",
+                    repository.name, e
+                ),
+            }
+        }
+    }
+
+    /// Fills `readme_excerpt` for the first `readme_top_n` repositories.
+    async fn attach_readme_excerpts(
+        &self,
+        repositories: &mut [Repository],
+        semaphore: Arc<Semaphore>,
+    ) {
+        let mut tasks = JoinSet::new();
+        for (index, repository) in repositories.iter().enumerate().take(self.readme_top_n) {
+            let client = self.http_client.clone();
+            let name = repository.name.clone();
+            let semaphore = semaphore.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.ok()?;
+                let excerpt = fetch_readme_excerpt(&client, &name).await?;
+                Some((index, excerpt))
+            });
+        }
+        while let Some(result) = tasks.join_next().await {
+            if let Ok(Some((index, excerpt))) = result {
+                repositories[index].readme_excerpt = Some(excerpt);
+            }
+        }
     }
 
-    async fn process(&self) -> CrawlerResult<()> {
-        let mut all_markdowns: Vec<String> = Vec::new();
+    /// Publishes `repositories` under `{stem}.{ext}` in every configured format.
+    async fn publish(&self, stem: &str, repositories: &[Repository]) -> CrawlerResult<()> {
+        let render_markdown = || format_repositories_markdown(repositories, self.since);
+
+        info!(
+            "Uploading {} trending repositories to Supabase Storage at {}",
+            repositories.len(),
+            stem
+        );
+        let written = self
+            .publisher
+            .publish_outputs(stem, &self.output_formats, render_markdown, repositories)
+            .await?;
+        info!(
+            "Successfully uploaded trending repositories to {}",
+            written.join(", ")
+        );
+        Ok(())
+    }
+
+    /// A copy using the context's shared client and storage, when it has them.
+    /// The rate limiter is kept, so the copy shares its budget with `self`.
+    fn in_context(&self, ctx: &CrawlerContext) -> Self {
+        let mut fetcher = self.clone();
+        if let Some(client) = &ctx.http {
+            fetcher.http_client =
+                RateLimitedClient::new(client.clone(), self.http_client.limiter().clone());
+        }
+        fetcher.publisher = ctx.publisher(&self.publisher);
+        fetcher
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let date = ctx.date;
+        let mut by_language: Vec<(String, Vec<Repository>)> = Vec::new();
         let mut processed_languages = 0;
 
-        // 各言語のクローリングを並列化
+        // 各言語のクローリングを並列化（同時接続数はセマフォで制限）
+        let semaphore = Arc::new(Semaphore::new(self.language_concurrency));
+        let readme_semaphore = Arc::new(Semaphore::new(README_CONCURRENCY));
         let mut tasks = Vec::new();
         for language in &self.languages {
             let language_clone = language.clone();
             tasks.push(tokio::spawn({
                 let self_clone = self.clone();
+                let semaphore = semaphore.clone();
+                let readme_semaphore = readme_semaphore.clone();
+                let errors = ctx.item_errors.clone();
                 async move {
-                    match self_clone
+                    let permit = semaphore.acquire_owned().await.ok()?;
+                    let result = self_clone
                         .fetch_trending_for_language(&language_clone)
-                        .await
-                    {
-                        Ok(repos) => {
-                            let mut markdown_results = Vec::new();
-                            for repo in repos {
-                                markdown_results.push(self_clone.stylize_repository_info(&repo));
-                            }
-                            if !markdown_results.is_empty() {
-                                Some((language_clone, markdown_results))
+                        .await;
+                    drop(permit);
+                    match result {
+                        Ok(mut repos) => {
+                            self_clone
+                                .attach_readme_excerpts(&mut repos, readme_semaphore)
+                                .await;
+                            if !repos.is_empty() {
+                                Some((language_clone, repos))
                             } else {
                                 None
                             }
@@ -162,6 +305,7 @@ impl GithubTrendingFetcher {
                                 "Failed to fetch trending for language '{}': {}",
                                 language_clone, e
                             );
+                            errors.push(format!("language {}", language_clone), &e);
                             None
                         }
                     }
@@ -170,33 +314,39 @@ impl GithubTrendingFetcher {
         }
 
         // 全てのタスクの結果を集約
-        for task in tasks {
-            if let Ok(Some((language, markdowns))) = task.await {
-                all_markdowns.extend(markdowns);
+        let total = tasks.len();
+        for (done, task) in tasks.into_iter().enumerate() {
+            let result = task.await;
+            ctx.item_processed(self.name(), done + 1, total);
+            if let Ok(Some((language, repos))) = result {
                 processed_languages += 1;
                 info!("Processed language: {}", language);
+                by_language.push((language, repos));
             }
         }
 
-        if processed_languages > 0 && !all_markdowns.is_empty() {
-            let today_str = OffsetDateTime::now_utc().date().to_string(); // YYYY-MM-DD
-            let file_content = all_markdowns.join("\n---\n");
-            let file_path = format!("{}/github-trending.md", today_str);
-
-            info!(
-                "Uploading {} trending repositories to Supabase Storage at {}",
-                all_markdowns.len(),
-                file_path
-            );
-            self
-                .supabase_client
-                .upload_file(&file_path, file_content, "text/markdown")
-                .await
-                .map_err(|e| common::CrawlerError::StorageUpload(e.to_string()))?;
-            info!(
-                "Successfully uploaded trending repositories to {}",
-                file_path
-            );
+        let all_repositories = merge_language_lists(&by_language);
+        if processed_languages > 0 && !all_repositories.is_empty() {
+            if self.file_per_language {
+                for (language, repos) in &by_language {
+                    let language_stem = format!("{}/{}", date, self.output_key(Some(language)));
+                    self.publish(&language_stem, repos).await?;
+                }
+            }
+            if !self.file_per_language || self.combined_file {
+                let stem = format!("{}/{}", date, self.output_key(None));
+                self.publish(&stem, &all_repositories).await?;
+            }
+            if let Some(archive) = &self.archive {
+                archive
+                    .record(
+                        all_repositories
+                            .iter()
+                            .cloned()
+                            .map(|repo| repo.into_item(ctx.date)),
+                    )
+                    .await;
+            }
         } else {
             info!("No trending repositories processed or found.");
         }
@@ -204,18 +354,327 @@ impl GithubTrendingFetcher {
     }
 }
 
+/// One entry per repository across the language lists, most stars first. A
+/// repo in several lists keeps the entry from the first and notes each
+/// language in `trending_in`.
+fn merge_language_lists(by_language: &[(String, Vec<Repository>)]) -> Vec<Repository> {
+    let mut merged: Vec<Repository> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for (language, repositories) in by_language {
+        let label = if language.is_empty() {
+            "overall"
+        } else {
+            language.as_str()
+        };
+        for repository in repositories {
+            let index = *positions.entry(&repository.name).or_insert_with(|| {
+                merged.push(repository.clone());
+                merged.len() - 1
+            });
+            let trending_in = &mut merged[index].trending_in;
+            if !trending_in.iter().any(|l| l == label) {
+                trending_in.push(label.to_string());
+            }
+        }
+    }
+    merged.sort_by(|a, b| b.stars.cmp(&a.stars).then_with(|| a.name.cmp(&b.name)));
+    merged
+}
+
+/// The canonical name for one trending variant, used for its output files and
+/// anything else keyed per variant, so variants never overwrite each other:
+/// `github-trending[-{language}][-{since}][-spoken-{code}]`. The daily range
+/// is left out, keeping `github-trending.md` for the plain daily crawl.
+pub fn trending_output_key(
+    language: Option<&str>,
+    since: TrendingRange,
+    spoken_language: Option<&str>,
+) -> String {
+    let mut key = "github-trending".to_string();
+    if let Some(language) = language {
+        key.push('-');
+        key.push_str(&language_file_name(language));
+    }
+    if since != TrendingRange::Daily {
+        key.push('-');
+        key.push_str(since.as_str());
+    }
+    if let Some(code) = spoken_language.map(str::trim).filter(|c| !c.is_empty()) {
+        key.push_str("-spoken-");
+        key.push_str(&language_file_name(code));
+    }
+    key
+}
+
+/// A filename-safe form of a trending language: `c++` → `cpp`, `c#` → `csharp`,
+/// `Jupyter Notebook` → `jupyter-notebook`. The empty (overall) language is `overall`.
+pub fn language_file_name(language: &str) -> String {
+    let decoded = language
+        .trim()
+        .to_lowercase()
+        .replace("%23", "#")
+        .replace("%2b", "+")
+        .replace("%20", " ");
+    let mut name = String::new();
+    for c in decoded.chars() {
+        match c {
+            '+' => name.push('p'),
+            '#' => name.push_str("sharp"),
+            c if c.is_ascii_alphanumeric() => name.push(c),
+            _ if !name.is_empty() && !name.ends_with('-') => name.push('-'),
+            _ => {}
+        }
+    }
+    let name = name.trim_end_matches('-');
+    if name.is_empty() {
+        "overall".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// The markdown digest of `repositories`, one section each, under a heading
+/// naming the trending range.
+fn format_repositories_markdown(repositories: &[Repository], since: TrendingRange) -> String {
+    let heading = markdown::section(1, &format!("GitHub Trending ({})", since.as_str()));
+    let sections = repositories
+        .iter()
+        .map(|repository| stylize_repository_info(repository, since))
+        .collect::<Vec<_>>()
+        .join("\n---\n");
+    format!("{}\n{}", heading, sections)
+}
+
+fn stylize_repository_info(repository: &Repository, since: TrendingRange) -> String {
+    let mut details = String::new();
+    if let Some(stars_today) = repository.stars_today {
+        details.push_str(&format!(
+            "**{}**: {}\n\n",
+            stars_gained_label(since),
+            stars_today
+        ));
+    }
+    if let Some(language) = &repository.language {
+        details.push_str(&format!("**Language**: {}\n\n", markdown::escape(language)));
+    }
+    // Only worth a line once the repo showed up under more than one language.
+    if repository.trending_in.len() > 1 {
+        details.push_str(&format!(
+            "**Trending in**: {}\n\n",
+            markdown::escape(&repository.trending_in.join(", "))
+        ));
+    }
+    let markdown = format!(
+        "\n{}\n\n**Stars**: {}\n\n{}{}\n\n{}\n",
+        markdown::section(1, &repository.name),
+        repository.stars,
+        details,
+        markdown::link("View Repository", &repository.link),
+        repository
+            .description
+            .as_deref()
+            .unwrap_or("No description provided.")
+    );
+    match &repository.readme_excerpt {
+        Some(excerpt) => format!("{}\n> {}\n", markdown, excerpt),
+        None => markdown,
+    }
+}
+
+fn stars_gained_label(since: TrendingRange) -> &'static str {
+    match since {
+        TrendingRange::Daily => "Stars today",
+        TrendingRange::Weekly => "Stars this week",
+        TrendingRange::Monthly => "Stars this month",
+    }
+}
+
+/// Parses the repositories on a trending page, including the archived/fork
+/// markers GitHub shows on some entries.
+pub fn parse_trending(html: &str) -> CrawlerResult<Vec<Repository>> {
+    let document = scraper::Html::parse_document(html);
+
+    let article_selector = scraper::Selector::parse("article.Box-row").map_err(|e| {
+        common::CrawlerError::HtmlParse(format!("Failed to parse article selector: {}", e))
+    })?;
+    let name_selector = scraper::Selector::parse("h2.h3 a").map_err(|e| {
+        common::CrawlerError::HtmlParse(format!("Failed to parse name selector: {}", e))
+    })?;
+    let desc_selector = scraper::Selector::parse("p.col-9").map_err(|e| {
+        common::CrawlerError::HtmlParse(format!("Failed to parse description selector: {}", e))
+    })?;
+    let stars_selector = scraper::Selector::parse("a[href*='/stargazers']").map_err(|e| {
+        common::CrawlerError::HtmlParse(format!("Failed to parse stars selector: {}", e))
+    })?;
+    let stars_today_selector = scraper::Selector::parse("span.d-inline-block.float-sm-right")
+        .map_err(|e| {
+            common::CrawlerError::HtmlParse(format!("Failed to parse stars today selector: {}", e))
+        })?;
+    let language_selector =
+        scraper::Selector::parse("[itemprop=programmingLanguage]").map_err(|e| {
+            common::CrawlerError::HtmlParse(format!("Failed to parse language selector: {}", e))
+        })?;
+
+    let label_selector = scraper::Selector::parse(".Label").map_err(|e| {
+        common::CrawlerError::HtmlParse(format!("Failed to parse label selector: {}", e))
+    })?;
+    let fork_selector = scraper::Selector::parse("h2 svg.octicon-repo-forked").map_err(|e| {
+        common::CrawlerError::HtmlParse(format!("Failed to parse fork selector: {}", e))
+    })?;
+
+    let mut repositories = Vec::new();
+
+    for article in document.select(&article_selector) {
+        let name_element = article.select(&name_selector).next();
+        let repo_name_and_owner = name_element
+            .and_then(|el| el.attr("href"))
+            .map(|href| href.trim_start_matches('/').to_string());
+
+        if repo_name_and_owner.is_none() {
+            warn!("Could not extract repository name and owner from an article. Skipping.");
+            continue;
+        }
+        let full_name = repo_name_and_owner.unwrap();
+
+        let description = article
+            .select(&desc_selector)
+            .next()
+            .map(|p| p.text().collect::<String>().trim().to_string());
+
+        let stars = article
+            .select(&stars_selector)
+            .next()
+            .and_then(|s| parse_count(&s.text().collect::<String>()))
+            .unwrap_or(0);
+        let stars_today = article
+            .select(&stars_today_selector)
+            .next()
+            .and_then(|s| parse_count(&s.text().collect::<String>()));
+        let language = article
+            .select(&language_selector)
+            .next()
+            .map(|l| l.text().collect::<String>().trim().to_string())
+            .filter(|language| !language.is_empty());
+
+        let archived = article.select(&label_selector).any(|label| {
+            label
+                .text()
+                .collect::<String>()
+                .to_lowercase()
+                .contains("archive")
+        });
+        let is_fork = article.select(&fork_selector).next().is_some()
+            || article.text().any(|text| text.contains("Forked from"));
+
+        repositories.push(Repository {
+            name: full_name.clone(),
+            link: format!("https://github.com/{}", full_name),
+            description,
+            stars,
+            stars_today,
+            language,
+            trending_in: Vec::new(),
+            archived,
+            is_fork,
+            readme_excerpt: None,
+        });
+    }
+    Ok(repositories)
+}
+
+/// Archived and fork status read from a repository's own page.
+pub fn repo_page_flags(html: &str) -> (bool, bool) {
+    let archived =
+        html.contains("This repository has been archived") || html.contains(">Public archive<");
+    let is_fork = html.contains("forked from ");
+    (archived, is_fork)
+}
+
+/// Downloads a repository's README and returns its excerpt; a missing README
+/// (404) or a failed download yields `None`.
+async fn fetch_readme_excerpt(client: &RateLimitedClient, repo: &str) -> Option<String> {
+    let url = README_URL_FORMAT.replace("{repo}", repo);
+    match client.get_text(&url).await {
+        Ok(body) => readme_excerpt(&body, README_EXCERPT_LEN),
+        Err(common::CrawlerError::HttpStatus { status: 404, .. }) => {
+            info!("No README found for {}", repo);
+            None
+        }
+        Err(e) => {
+            warn!("Failed to fetch README for {}: {}", repo, e);
+            None
+        }
+    }
+}
+
+/// Plain prose from the start of a README: headings, badges, images, HTML and
+/// code fences are skipped and the text is cut at a word boundary near `max_chars`.
+pub fn readme_excerpt(markdown: &str, max_chars: usize) -> Option<String> {
+    let mut in_code = false;
+    let mut words: Vec<&str> = Vec::new();
+    for line in markdown.lines().map(str::trim) {
+        if line.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        let skip = in_code
+            || line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with('<')
+            || line.starts_with("![")
+            || line.starts_with("[![")
+            || line.starts_with("---")
+            || line.starts_with('|');
+        if !skip {
+            words.extend(line.split_whitespace());
+        }
+    }
+
+    let mut excerpt = String::new();
+    for word in words {
+        if excerpt.chars().count() + word.chars().count() + 1 > max_chars {
+            excerpt.push('…');
+            break;
+        }
+        if !excerpt.is_empty() {
+            excerpt.push(' ');
+        }
+        excerpt.push_str(word);
+    }
+    (!excerpt.is_empty()).then_some(excerpt)
+}
+
 #[async_trait]
 impl Crawler for GithubTrendingFetcher {
-    async fn run(&self) -> CrawlerResult<()> {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
         info!("GitHub Trending Fetcher starting up");
-        self.process().await
+        self.in_context(ctx).process(ctx).await
     }
 
     fn name(&self) -> &'static str {
         "GitHub Trending"
     }
+
+    fn slug(&self) -> &'static str {
+        "github"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        config.missing_languages().map_or(Ok(()), Err)
+    }
+
+    fn schedule(&self) -> Option<&'static str> {
+        Some("0 0 9 * * *")
+    }
 }
 
+common::register_crawler!(GithubTrendingFetcher {
+    name: "GitHub crawler",
+    slug: "github",
+    required_env: ["LANGUAGES"],
+    digest: ("github-trending", "GitHub Trending"),
+    new: GithubTrendingFetcher::new,
+});
 
 // Backward compatibility function
 pub async fn run_github_crawler() -> anyhow::Result<()> {
@@ -225,3 +684,348 @@ pub async fn run_github_crawler() -> anyhow::Result<()> {
     let crawler = GithubTrendingFetcher::new(&config)?;
     crawler.run().await.map_err(|e| anyhow::anyhow!(e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{assert_snapshot, fixture, FixtureDir, MockSupabase, TestConfig};
+
+    fn repo(name: &str, stars: u32, description: Option<&str>) -> Repository {
+        Repository {
+            name: name.to_string(),
+            description: description.map(str::to_string),
+            link: format!("https://github.com/{}", name),
+            stars,
+            stars_today: None,
+            language: None,
+            trending_in: vec![],
+            archived: false,
+            is_fork: false,
+            readme_excerpt: None,
+        }
+    }
+
+    #[test]
+    fn repository_markdown_matches_snapshots() {
+        let mut with_readme = repo(
+            "BurntSushi/ripgrep",
+            1204,
+            Some("Search | fast, *recursively*"),
+        );
+        with_readme.readme_excerpt = Some("ripgrep is a line-oriented search tool.".to_string());
+        with_readme.stars_today = Some(87);
+        with_readme.language = Some("Rust".to_string());
+        assert_snapshot!(
+            "github-repository.md",
+            stylize_repository_info(&with_readme, TrendingRange::Daily)
+        );
+
+        let digest = format_repositories_markdown(
+            &[
+                with_readme,
+                repo("rust-lang/rust", 250, None),
+                repo("tokio-rs/tokio", 0, Some("")),
+            ],
+            TrendingRange::Daily,
+        );
+        assert_snapshot!("github-trending.md", digest);
+    }
+
+    #[test]
+    fn language_file_names_are_sanitized() {
+        assert_eq!(language_file_name("rust"), "rust");
+        assert_eq!(language_file_name("c++"), "cpp");
+        assert_eq!(language_file_name("C#"), "csharp");
+        assert_eq!(language_file_name("c%23"), "csharp");
+        assert_eq!(language_file_name("Jupyter Notebook"), "jupyter-notebook");
+        assert_eq!(language_file_name("objective-c++"), "objective-cpp");
+        assert_eq!(language_file_name(""), "overall");
+        assert_eq!(language_file_name("../etc"), "etc");
+    }
+
+    #[tokio::test]
+    async fn empty_languages_say_whether_they_were_set() {
+        let supabase = MockSupabase::start().await;
+        let unset = TestConfig::new(&supabase).build();
+        assert_eq!(
+            GithubTrendingFetcher::precheck(&unset),
+            Err("LANGUAGES not set".to_string())
+        );
+        let separators_only = TestConfig::new(&supabase).languages(&[]).build();
+        let reason = GithubTrendingFetcher::precheck(&separators_only).unwrap_err();
+        assert!(reason.contains("set but lists no languages"), "{}", reason);
+        let overall = TestConfig::new(&supabase).languages(&[""]).build();
+        assert_eq!(GithubTrendingFetcher::precheck(&overall), Ok(()));
+    }
+
+    #[test]
+    fn trending_variants_have_distinct_output_keys() {
+        assert_eq!(
+            trending_output_key(None, TrendingRange::Daily, None),
+            "github-trending"
+        );
+        assert_eq!(
+            trending_output_key(Some("c++"), TrendingRange::Daily, None),
+            "github-trending-cpp"
+        );
+
+        let mut keys = Vec::new();
+        for language in [None, Some(""), Some("rust")] {
+            for since in [
+                TrendingRange::Daily,
+                TrendingRange::Weekly,
+                TrendingRange::Monthly,
+            ] {
+                for spoken in [None, Some("zh"), Some("en")] {
+                    keys.push(trending_output_key(language, since, spoken));
+                }
+            }
+        }
+        let total = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), total);
+        assert!(keys.contains(&"github-trending-rust-weekly-spoken-zh".to_string()));
+    }
+
+    #[test]
+    fn repos_in_several_language_lists_are_merged() {
+        let mut from_rust = repo("denoland/deno", 100, Some("from the rust list"));
+        from_rust.stars_today = Some(40);
+        let by_language = vec![
+            (
+                "rust".to_string(),
+                vec![repo("tokio-rs/tokio", 30, None), from_rust],
+            ),
+            (
+                "typescript".to_string(),
+                vec![
+                    repo("denoland/deno", 100, Some("from the typescript list")),
+                    repo("microsoft/vscode", 500, None),
+                ],
+            ),
+            ("".to_string(), vec![repo("tokio-rs/tokio", 30, None)]),
+        ];
+        let merged = merge_language_lists(&by_language);
+        let names: Vec<(&str, Vec<&str>)> = merged
+            .iter()
+            .map(|r| {
+                (
+                    r.name.as_str(),
+                    r.trending_in.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("microsoft/vscode", vec!["typescript"]),
+                ("denoland/deno", vec!["rust", "typescript"]),
+                ("tokio-rs/tokio", vec!["rust", "overall"]),
+            ]
+        );
+        assert_eq!(merged[1].description.as_deref(), Some("from the rust list"));
+        assert_eq!(merged[1].stars_today, Some(40));
+
+        let markdown = format_repositories_markdown(&merged, TrendingRange::Daily);
+        assert_eq!(markdown.matches("# denoland/deno").count(), 1);
+        assert!(markdown.contains("**Trending in**: rust, typescript"));
+        assert!(!markdown.contains("**Trending in**: typescript\n"));
+    }
+
+    #[test]
+    fn archive_envelope_parses_star_counts() {
+        let repo = Repository {
+            name: "rust-lang/rust".to_string(),
+            description: Some("Empowering everyone".to_string()),
+            link: "https://github.com/rust-lang/rust".to_string(),
+            stars: 102_345,
+            stars_today: None,
+            language: None,
+            trending_in: Vec::new(),
+            archived: false,
+            is_fork: false,
+            readme_excerpt: None,
+        };
+        let item = repo.into_item(Date::from_calendar_date(2024, time::Month::May, 10).unwrap());
+        assert_eq!(
+            (item.source.as_str(), item.id.as_str()),
+            ("github", "rust-lang/rust")
+        );
+        assert_eq!(item.score, Some(102_345.0));
+        assert_eq!(item.raw["stars"], 102_345);
+        assert_eq!(item.raw["description"], "Empowering everyone");
+        assert_eq!(item.date, "2024-05-10");
+    }
+
+    #[test]
+    fn snapshots_with_text_star_counts_still_load() {
+        let repo: Repository = serde_json::from_str(
+            r#"{"name":"a/b","description":null,"link":"https://github.com/a/b","stars":"1,234"}"#,
+        )
+        .unwrap();
+        assert_eq!((repo.stars, repo.stars_today), (1234, None));
+    }
+
+    #[test]
+    fn trending_markup_marks_archived_and_forked_repos() {
+        let html = fixture("github/trending-rust.html");
+        let repos = parse_trending(&html).unwrap();
+        let flags: Vec<_> = repos
+            .iter()
+            .map(|r| (r.name.as_str(), r.stars, r.archived, r.is_fork))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                ("rust-lang/rust", 102345, false, false),
+                ("someone/rust", 12, false, true),
+                ("old/tool", 0, true, false),
+            ]
+        );
+        assert_eq!(repos[0].stars_today, Some(1234));
+        assert_eq!(repos[0].language.as_deref(), Some("Rust"));
+        assert_eq!(
+            (repos[1].stars_today, repos[1].language.as_deref()),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn repo_page_reveals_archive_and_fork_status() {
+        assert_eq!(
+            repo_page_flags(
+                r#"<span class="Label">Public archive</span> <span>forked from <a href="/a/b">a/b</a></span>"#
+            ),
+            (true, true)
+        );
+        assert_eq!(repo_page_flags("<p>A fast search tool</p>"), (false, false));
+    }
+
+    #[test]
+    fn repository_markdown_is_unchanged_for_plain_names() {
+        let repository = Repository {
+            name: "rust-lang/rust".to_string(),
+            description: Some("Empowering everyone to build reliable software.".to_string()),
+            link: "https://github.com/rust-lang/rust".to_string(),
+            stars: 102345,
+            stars_today: None,
+            language: None,
+            trending_in: Vec::new(),
+            archived: false,
+            is_fork: false,
+            readme_excerpt: None,
+        };
+        assert_eq!(
+            stylize_repository_info(&repository, TrendingRange::Daily),
+            "\n# rust-lang/rust\n\n**Stars**: 102345\n\n[View Repository](https://github.com/rust-lang/rust)\n\nEmpowering everyone to build reliable software.\n"
+        );
+    }
+
+    #[test]
+    fn readme_excerpt_skips_headers_badges_and_code() {
+        let readme = "# ripgrep\n\n[![CI](https://x/badge.svg)](https://x)\n<p align=\"center\"><img src=\"logo.png\"></p>\n\nripgrep is a line-oriented search tool\nthat recursively searches directories.\n\n```sh\ncargo install ripgrep\n```\n\n## Install\nSee below.\n";
+        assert_eq!(
+            readme_excerpt(readme, 300).as_deref(),
+            Some("ripgrep is a line-oriented search tool that recursively searches directories. See below.")
+        );
+    }
+
+    #[test]
+    fn readme_excerpt_truncates_on_word_boundary() {
+        assert_eq!(
+            readme_excerpt("one two three four", 10).as_deref(),
+            Some("one two…")
+        );
+        assert_eq!(readme_excerpt("# Title only\n![logo](x.png)", 100), None);
+    }
+
+    #[tokio::test]
+    async fn publishes_trending_repositories_from_fixtures() {
+        let fixtures = FixtureDir::new();
+        fixtures.add(
+            "https://github.com/trending/rust?since=daily",
+            &fixture("github/trending-rust.html"),
+        );
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .languages(&["rust"])
+            .fixture_dir(fixtures.path())
+            .output_formats(&[
+                OutputFormat::Markdown,
+                OutputFormat::Html,
+                OutputFormat::Json,
+            ])
+            .with(|config| config.github_exclude_forks = true)
+            .build();
+
+        let date = time::Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        GithubTrendingFetcher::new(&config)
+            .unwrap()
+            .run_with_context(&CrawlerContext::default().with_date(date))
+            .await
+            .unwrap();
+
+        let markdown = supabase.uploaded("2024-05-10/github-trending.md").await;
+        assert_snapshot!("github-trending-fixture.md", markdown);
+        assert!(markdown.contains("rust-lang/rust"));
+        assert!(markdown.contains("old/tool"));
+        assert!(!markdown.contains("someone/rust"), "forks are excluded");
+        let html = supabase.uploaded("2024-05-10/github-trending.html").await;
+        assert!(html.contains("<title>GitHub Trending</title>"));
+        assert!(html.contains("<h1>rust-lang/rust</h1>"));
+        let json: Vec<Repository> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/github-trending.json").await)
+                .unwrap();
+        let repos: Vec<_> = json
+            .iter()
+            .map(|r| (r.name.as_str(), r.stars, r.stars_today, r.archived))
+            .collect();
+        assert_eq!(
+            repos,
+            [
+                ("rust-lang/rust", 102345, Some(1234), false),
+                ("old/tool", 0, None, true)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn weekly_range_is_fetched_and_named_apart() {
+        let fixtures = FixtureDir::new();
+        fixtures.add(
+            "https://github.com/trending/rust?since=weekly",
+            &fixture("github/trending-rust.html"),
+        );
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .languages(&["rust"])
+            .fixture_dir(fixtures.path())
+            .with(|config| config.github_trending_since = TrendingRange::Weekly)
+            .build();
+
+        let date = time::Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        GithubTrendingFetcher::new(&config)
+            .unwrap()
+            .run_with_context(&CrawlerContext::default().with_date(date))
+            .await
+            .unwrap();
+
+        let paths = supabase.uploaded_paths().await;
+        assert!(paths.contains(&"2024-05-10/github-trending-weekly.md".to_string()));
+        assert!(!paths.contains(&"2024-05-10/github-trending.md".to_string()));
+        let markdown = supabase
+            .uploaded("2024-05-10/github-trending-weekly.md")
+            .await;
+        assert!(
+            markdown.starts_with("# GitHub Trending (weekly)\n"),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("**Stars this week**: 1234"),
+            "{}",
+            markdown
+        );
+    }
+}
@@ -1,7 +1,8 @@
 use anyhow::Result;
 
+/// Deprecated shim for `cution github`.
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Call the function defined in lib.rs
+    common::init_tracing()?;
     github::run_github_crawler().await
 }
@@ -1,45 +1,67 @@
 use crate::models::HNItem;
 use anyhow::Result;
-use reqwest::Client;
-use scraper::Html;
+use common::html::{self, TextOptions};
+use common::http::parse_json;
+use common::{CrawlerResult, HttpClient};
 
 #[derive(Clone)]
 pub struct HackerNewsAPI {
-    client: Client,
+    client: HttpClient,
     base_url: String,
 }
 
 impl HackerNewsAPI {
-    pub fn new() -> Self {
-        let client = Client::new();
+    pub fn new() -> CrawlerResult<Self> {
+        Ok(Self::with_client(HttpClient::new()?))
+    }
+
+    pub fn with_client(client: HttpClient) -> Self {
         let base_url = "https://hacker-news.firebaseio.com/v0".to_string();
         Self { client, base_url }
     }
 
+    /// The same API sending requests through `client`.
+    pub fn with_http(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Points the API at another host, e.g. a local mock.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
     pub async fn get_top_stories(&self, limit: usize) -> Result<Vec<u64>> {
         let url = format!("{}/topstories.json", self.base_url);
-        let resp = self.client.get(&url).send().await?;
-        let ids: Vec<u64> = resp.json().await?;
+        let body = self.client.get_text(&url).await?;
+        let ids: Vec<u64> = parse_json(&url, &body)?;
         Ok(ids.into_iter().take(limit).collect())
     }
 
-    pub async fn get_story(&self, story_id: u64) -> Result<HNItem> {
+    /// Fetches one item. A malformed body yields `CrawlerError::Parse` so the
+    /// caller can skip just this story.
+    pub async fn get_story(&self, story_id: u64) -> CrawlerResult<HNItem> {
         let url = format!("{}/item/{}.json", self.base_url, story_id);
-        let resp = self.client.get(&url).send().await?;
-        let item: HNItem = resp.json().await?;
-        Ok(item)
+        let body = self.client.get_text(&url).await?;
+        parse_json(&url, &body)
     }
 
+    /// Plain text of an item's HTML body, keeping paragraph breaks and link URLs.
     pub fn clean_html(&self, html: &str) -> String {
-        Html::parse_fragment(html)
-            .root_element()
-            .text()
-            .collect::<Vec<_>>()
-            .join("")
+        html::to_text(html, &TextOptions::fragment().with_links())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::CrawlerError;
 
-    pub async fn summarize(&self, _api_key: &str, _title: &str, content: &str) -> Result<String> {
-        // TODO: Get summary from LLM
-        Ok(content.chars().take(200).collect::<String>())
+    #[test]
+    fn truncated_item_is_a_parse_error() {
+        let body = r#"{"id": 42, "title": "Show HN: a thing", "sco"#;
+        let err = parse_json::<HNItem>("https://hn.test/item/42.json", body).unwrap_err();
+        assert!(matches!(err, CrawlerError::Parse(ref msg) if msg.contains("Show HN")));
     }
 }
@@ -1,54 +1,164 @@
 pub mod api;
 pub mod models;
 
-pub use self::HackerNewsCrawler;
 use api::HackerNewsAPI;
+use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::dedup::{SeenStatus, SeenUrls, SeenUrlsMode};
+use common::partial::{PartialUploader, RECOVERED_NOTE};
+use common::ranking;
+use common::state::{self, StateStore};
+use common::summary_cache::CachedSummarizer;
+use common::usage;
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult, OutputFormat,
+    Publisher, Summarizer, SupabaseStorageClient,
+};
 use models::StoryData;
-use common::{Config, Crawler, CrawlerResult, SupabaseStorageClient};
-use time::OffsetDateTime;
+use std::sync::Arc;
 use tokio::task::JoinSet;
-use tracing::info;
-use async_trait::async_trait;
+use tracing::{info, warn};
+
+/// Top stories fetched, and the most published.
+const MAX_STORIES: usize = 30;
+/// Points a story needs to be summarized and published.
+const MIN_SCORE: f64 = 20.0;
 
 pub struct HackerNewsCrawler {
     api: HackerNewsAPI,
-    storage_client: SupabaseStorageClient,
-    gemini_api_key: String,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    item_types: Vec<String>,
+    archive: Option<ArchiveWriter>,
+    seen_urls: SeenUrlsMode,
+    seen_urls_ttl_days: u32,
+    /// Only opened when `seen_urls` is on.
+    state: Option<Arc<dyn StateStore>>,
+    storage: SupabaseStorageClient,
+    summary_cache_ttl_days: Option<u32>,
+    partial_upload_every: Option<usize>,
 }
 
 impl HackerNewsCrawler {
     pub fn new(config: &Config) -> CrawlerResult<Self> {
-        let gemini_api_key = config.require_gemini_api_key()?.clone();
-        let storage_client = SupabaseStorageClient::new(
-            &config.supabase.storage_url,
-            &config.supabase.key,
-            &config.supabase.bucket,
-        );
+        config.require_gemini_api_key()?;
+        let publisher = Publisher::for_crawler(config, "hacker_news")?;
 
         Ok(Self {
-            api: HackerNewsAPI::new(),
-            storage_client,
-            gemini_api_key,
+            api: HackerNewsAPI::with_client(ClientFactory::from_config(config).build()?),
+            publisher,
+            output_formats: config.output_formats_for("hacker_news"),
+            item_types: config.hn_item_types.clone(),
+            archive: ArchiveWriter::from_config(config)?,
+            seen_urls: config.hn_seen_urls,
+            seen_urls_ttl_days: config.seen_urls_ttl_days,
+            state: match config.hn_seen_urls {
+                SeenUrlsMode::Off => None,
+                _ => Some(state::from_config(config)?),
+            },
+            storage: SupabaseStorageClient::from_config(config)?,
+            summary_cache_ttl_days: config.summary_cache_ttl_days,
+            partial_upload_every: config.partial_upload_every,
         })
     }
 
-    async fn process_stories(&self) -> CrawlerResult<()> {
-        let story_ids = self.api.get_top_stories(30).await
+    /// Replaces the API client, e.g. with one pointed at a mock server.
+    pub fn with_api(mut self, api: HackerNewsAPI) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// The context's summarizer, behind the persistent summary cache when it
+    /// is enabled.
+    fn summarizer(&self, ctx: &CrawlerContext) -> Arc<dyn Summarizer> {
+        CachedSummarizer::wrap(
+            ctx.summarizer.clone(),
+            ctx.storage_client(&self.storage),
+            ctx.date,
+            self.summary_cache_ttl_days,
+        )
+    }
+
+    /// Annotates or drops stories whose URL went out on an earlier day, and
+    /// records today's new ones in the returned set, which is saved once the
+    /// stories are published.
+    async fn mark_seen(
+        &self,
+        ctx: &CrawlerContext,
+        stories: Vec<StoryData>,
+    ) -> (Vec<StoryData>, Option<SeenUrls>) {
+        let Some(store) = &self.state else {
+            return (stories, None);
+        };
+        let mut seen = match SeenUrls::load(store.clone(), ctx.date, self.seen_urls_ttl_days).await
+        {
+            Ok(seen) => seen,
+            Err(e) => {
+                warn!("Could not load seen URLs, publishing every story: {}", e);
+                return (stories, None);
+            }
+        };
+
+        let mut kept = Vec::with_capacity(stories.len());
+        for mut story in stories {
+            let status = match &story.url {
+                Some(url) => seen.check_and_mark(url),
+                None => SeenStatus::New,
+            };
+            match (status, self.seen_urls) {
+                // Seen earlier today means this is a rerun, not a repeat.
+                (SeenStatus::SeenOn(date), _) if date == ctx.date => kept.push(story),
+                (SeenStatus::SeenOn(date), SeenUrlsMode::Drop) => {
+                    info!(
+                        "Dropping repeat story {} (first seen on {})",
+                        story.title, date
+                    );
+                }
+                (SeenStatus::SeenOn(date), _) => {
+                    story.first_seen = Some(date.to_string());
+                    kept.push(story);
+                }
+                (SeenStatus::New, _) => kept.push(story),
+            }
+        }
+
+        (kept, Some(seen))
+    }
+
+    async fn process_stories(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let api = match &ctx.http {
+            Some(client) => self.api.clone().with_http(client.clone()),
+            None => self.api.clone(),
+        };
+        let story_ids = api
+            .get_top_stories(MAX_STORIES)
+            .await
             .map_err(|e| common::CrawlerError::Api(e.to_string()))?;
         info!("Fetched {} top story IDs", story_ids.len());
 
-        let mut all_stories_markdown: Vec<String> = Vec::new();
-        let mut processed_count = 0;
+        let mut stories: Vec<StoryData> = Vec::new();
 
         let mut tasks = JoinSet::new();
+        let summarizer = self.summarizer(ctx);
 
         for story_id in story_ids {
-            let api = self.api.clone();
-            let gemini_api_key = self.gemini_api_key.clone();
-            tasks.spawn(async move {
+            let api = api.clone();
+            let summarizer = summarizer.clone();
+            let item_types = self.item_types.clone();
+            let errors = ctx.item_errors.clone();
+            // Spawned tasks do not inherit the run's usage attribution.
+            tasks.spawn(usage::attribute(self.slug(), async move {
                 match api.get_story(story_id).await {
                     Ok(item) => {
-                        if item.score < 20 {
+                        if !item.has_type(&item_types) {
+                            info!(
+                                "Skipping {} item: {}",
+                                item.item_type.as_deref().unwrap_or("unknown"),
+                                item.title
+                            );
+                            return None;
+                        }
+                        if (item.score as f64) < MIN_SCORE {
                             return None;
                         }
 
@@ -56,13 +166,15 @@ impl HackerNewsCrawler {
                             Some(html) if (100..10_000).contains(&html.len()) => {
                                 info!("Summarizing story: {}", item.title);
                                 let clean_text = api.clean_html(html);
-                                match api
-                                    .summarize(&gemini_api_key, &item.title, &clean_text)
-                                    .await
-                                {
+                                match summarizer.summarize(&item.title, &clean_text).await {
                                     Ok(summary) => Some(summary),
                                     Err(e) => {
-                                        tracing::warn!("Error summarizing story {}: {}", item.title, e);
+                                        tracing::warn!(
+                                            "Error summarizing story {}: {}",
+                                            item.title,
+                                            e
+                                        );
+                                        errors.push(format!("summary of \"{}\"", item.title), &e);
                                         None
                                     }
                                 }
@@ -70,57 +182,137 @@ impl HackerNewsCrawler {
                             _ => None,
                         };
 
-                        let story_data = StoryData::from_hn_item(item, summary);
-                        Some(story_data.to_markdown_string())
+                        Some(StoryData::from_hn_item(item, summary))
                     }
                     Err(e) => {
                         tracing::warn!("Error fetching story {}: {}", story_id, e);
+                        errors.push(format!("story {}", story_id), &e);
                         None
                     }
                 }
-            });
+            }));
         }
 
+        let publisher = ctx.publisher(&self.publisher);
+        let stem = format!("{}/hacker-news", ctx.date);
+        let mut partial = self
+            .partial_upload_every
+            .map(|every| PartialUploader::new(publisher.storage().clone(), &stem, every));
+        let recovered = match &mut partial {
+            Some(partial) => partial.recover().await.is_some(),
+            None => false,
+        };
+
+        let total = tasks.len();
+        let mut done = 0;
         while let Some(result) = tasks.join_next().await {
-            if let Ok(Some(markdown)) = result {
-                all_stories_markdown.push(markdown);
-                processed_count += 1;
+            done += 1;
+            ctx.item_processed(self.name(), done, total);
+            if let Ok(Some(story)) = result {
+                stories.push(story);
+            }
+            if let Some(partial) = &mut partial {
+                partial.item_done(format_stories_markdown(&stories)).await;
             }
         }
 
-        if processed_count > 0 {
-            let today_str = OffsetDateTime::now_utc().date().to_string();
-            let file_content = all_stories_markdown.join("\n\n---\n\n");
-            let file_path = format!("{}/hacker-news.md", today_str);
+        let (stories, seen) = self.mark_seen(ctx, rank_stories(stories)).await;
 
-            self.storage_client
-                .upload_file(&file_path, file_content, "text/markdown")
-                .await
-                .map_err(|e| common::CrawlerError::StorageUpload(e.to_string()))?;
+        if !stories.is_empty() {
+            let render_markdown = || {
+                let markdown = format_stories_markdown(&stories);
+                if recovered {
+                    format!("{}\n\n{}", RECOVERED_NOTE, markdown)
+                } else {
+                    markdown
+                }
+            };
+
+            let written = publisher
+                .publish_outputs(&stem, &self.output_formats, render_markdown, &stories)
+                .await?;
             info!(
                 "Successfully processed and uploaded {} stories to {}",
-                processed_count, file_path
+                stories.len(),
+                written.join(", ")
             );
+            if let Some(archive) = &self.archive {
+                archive
+                    .record(
+                        stories
+                            .iter()
+                            .cloned()
+                            .map(|story| story.into_item(ctx.date)),
+                    )
+                    .await;
+            }
         } else {
             info!("No stories processed today.");
         }
+        // Only once published, so a failed upload leaves today's stories new.
+        if let Some(seen) = seen {
+            if let Err(e) = seen.save().await {
+                warn!("Failed to save seen URLs: {}", e);
+            }
+        }
+        if let Some(partial) = partial {
+            partial.promote().await;
+        }
 
         Ok(())
     }
 }
 
+/// The stories above `MIN_SCORE`, best first, at most `MAX_STORIES` of them.
+fn rank_stories(stories: Vec<StoryData>) -> Vec<StoryData> {
+    let scored = ranking::score_by(stories, |story| story.score as f64);
+    ranking::top_n(ranking::at_least(scored, MIN_SCORE), MAX_STORIES)
+        .into_iter()
+        .map(|scored| scored.item)
+        .collect()
+}
+
+/// The day's digest: every story's section, separated by rules.
+fn format_stories_markdown(stories: &[StoryData]) -> String {
+    markdown::rule_separated(stories.iter().map(StoryData::to_markdown_string))
+}
+
 #[async_trait]
 impl Crawler for HackerNewsCrawler {
-    async fn run(&self) -> CrawlerResult<()> {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
         info!("Hacker News Fetcher starting up");
-        self.process_stories().await
+        self.process_stories(ctx).await
     }
 
     fn name(&self) -> &'static str {
         "Hacker News"
     }
+
+    fn slug(&self) -> &'static str {
+        "hacker_news"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        config
+            .gemini_api_key
+            .as_ref()
+            .map(|_| ())
+            .ok_or_else(|| "GEMINI_API_KEY not set".to_string())
+    }
+
+    fn schedule(&self) -> Option<&'static str> {
+        Some("0 0 9 * * *")
+    }
 }
 
+common::register_crawler!(HackerNewsCrawler {
+    name: "Hacker News crawler",
+    slug: "hacker_news",
+    required_env: ["GEMINI_API_KEY"],
+    digest: ("hacker-news", "Hacker News"),
+    new: HackerNewsCrawler::new,
+});
+
 // Backward compatibility function
 pub async fn run_hacker_news_crawler() -> anyhow::Result<()> {
     let _ = dotenv::dotenv();
@@ -128,3 +320,217 @@ pub async fn run_hacker_news_crawler() -> anyhow::Result<()> {
     let crawler = HackerNewsCrawler::new(&config)?;
     crawler.run().await.map_err(|e| anyhow::anyhow!(e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{assert_snapshot, fixture, MockHackerNews, MockSupabase, TestConfig};
+
+    fn story(id: u64, title: &str, url: Option<&str>, text: Option<&str>) -> StoryData {
+        StoryData {
+            story_id: id,
+            title: title.to_string(),
+            score: 100 + id as i64,
+            url: url.map(str::to_string),
+            text: text.map(str::to_string),
+            summary: None,
+            first_seen: None,
+        }
+    }
+
+    #[test]
+    fn digest_matches_snapshot() {
+        let mut repeat = story(
+            1,
+            "Rust 2.0 [released]",
+            Some("https://blog.rust-lang.test/2.0"),
+            None,
+        );
+        repeat.first_seen = Some("2024-05-08".to_string());
+        let mut summarized = story(2, "Ask HN: *Your* setup?", None, Some("<p>Long text</p>"));
+        summarized.summary = Some("People share editors & terminals.".to_string());
+        let digest = format_stories_markdown(&[
+            repeat,
+            summarized,
+            story(
+                3,
+                "Show HN: A <tiny> parser",
+                None,
+                Some("<p>Try <a href=\"https://x.test\">it</a></p>"),
+            ),
+            story(4, "Untitled thoughts", None, None),
+        ]);
+        assert_snapshot!("hacker-news.md", digest);
+    }
+
+    #[test]
+    fn stories_are_ranked_by_score_above_the_threshold() {
+        let scored = |id: u64, score: i64| StoryData {
+            score,
+            ..story(id, "t", None, None)
+        };
+        let ranked = rank_stories(vec![
+            scored(1, 50),
+            scored(2, 19),
+            scored(3, 80),
+            scored(4, 50),
+        ]);
+        let ids: Vec<_> = ranked.iter().map(|s| s.story_id).collect();
+        assert_eq!(ids, [3, 1, 4]);
+    }
+
+    #[tokio::test]
+    async fn publishes_top_stories_end_to_end() {
+        let hn = MockHackerNews::start().await;
+        hn.top_stories(&[1, 2, 3, 4, 5]).await;
+        hn.item(
+            1,
+            serde_json::from_str(&fixture("hacker_news/job.json")).unwrap(),
+        )
+        .await;
+        hn.item(
+            2,
+            serde_json::from_str(&fixture("hacker_news/story.json")).unwrap(),
+        )
+        .await;
+        hn.item(
+            3,
+            serde_json::from_str(&fixture("hacker_news/ask.json")).unwrap(),
+        )
+        .await;
+        hn.story(4, "Barely noticed", 3).await;
+        // 5 is unknown and answers `null`, which is skipped like any bad item.
+
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .gemini_api_key("test-key")
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .build();
+        let crawler = HackerNewsCrawler::new(&config)
+            .unwrap()
+            .with_api(HackerNewsAPI::new().unwrap().with_base_url(&hn.base_url()));
+        let date = time::Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        crawler
+            .run_with_context(&CrawlerContext::default().with_date(date))
+            .await
+            .unwrap();
+
+        let json: Vec<StoryData> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/hacker-news.json").await).unwrap();
+        let ids: Vec<u64> = json.iter().map(|s| s.story_id).collect();
+        assert_eq!(ids.len(), 2, "{:?}", ids);
+        assert!(json
+            .iter()
+            .any(|s| s.url.as_deref() == Some("https://blog.rust-lang.test/2.0")));
+
+        let markdown = supabase.uploaded("2024-05-10/hacker-news.md").await;
+        assert!(markdown.contains("# Rust 2.0"));
+        assert!(markdown.contains("[View Link](https://blog.rust-lang.test/2.0)"));
+        assert!(markdown.contains("# Ask HN: What are you building this weekend?"));
+        assert!(!markdown.contains("Acme"), "jobs are filtered by default");
+        assert!(
+            !markdown.contains("Barely noticed"),
+            "low-scoring stories are dropped"
+        );
+    }
+
+    #[tokio::test]
+    async fn stories_published_on_an_earlier_day_are_dropped() {
+        let hn = MockHackerNews::start().await;
+        hn.top_stories(&[2, 3]).await;
+        hn.item(
+            2,
+            serde_json::from_str(&fixture("hacker_news/story.json")).unwrap(),
+        )
+        .await;
+        hn.item(
+            3,
+            serde_json::from_str(&fixture("hacker_news/ask.json")).unwrap(),
+        )
+        .await;
+
+        let state_db =
+            std::env::temp_dir().join(format!("cution-hn-seen-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&state_db);
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .gemini_api_key("test-key")
+            .with(|c| {
+                c.hn_seen_urls = SeenUrlsMode::Drop;
+                c.state_db = Some(state_db.clone());
+            })
+            .build();
+        let crawler = HackerNewsCrawler::new(&config)
+            .unwrap()
+            .with_api(HackerNewsAPI::new().unwrap().with_base_url(&hn.base_url()));
+        for day in [9, 9, 10] {
+            let date = time::Date::from_calendar_date(2024, time::Month::May, day).unwrap();
+            crawler
+                .run_with_context(&CrawlerContext::default().with_date(date))
+                .await
+                .unwrap();
+        }
+        let _ = std::fs::remove_file(&state_db);
+
+        let rerun = supabase.uploaded("2024-05-09/hacker-news.md").await;
+        assert!(
+            rerun.contains("# Rust 2.0"),
+            "a same-day rerun is not a repeat"
+        );
+        let next_day = supabase.uploaded("2024-05-10/hacker-news.md").await;
+        assert!(!next_day.contains("# Rust 2.0"));
+        assert!(
+            next_day.contains("# Ask HN"),
+            "stories without a URL are always kept"
+        );
+    }
+
+    #[tokio::test]
+    async fn partial_digest_is_uploaded_and_replaced() {
+        let hn = MockHackerNews::start().await;
+        hn.top_stories(&[2, 3]).await;
+        hn.item(
+            2,
+            serde_json::from_str(&fixture("hacker_news/story.json")).unwrap(),
+        )
+        .await;
+        hn.item(
+            3,
+            serde_json::from_str(&fixture("hacker_news/ask.json")).unwrap(),
+        )
+        .await;
+
+        let supabase = MockSupabase::start().await;
+        supabase
+            .serve_file(
+                "2024-05-10/hacker-news.partial.md",
+                "# From the crashed run",
+            )
+            .await;
+        let config = TestConfig::new(&supabase)
+            .gemini_api_key("test-key")
+            .with(|c| c.partial_upload_every = Some(1))
+            .build();
+        let crawler = HackerNewsCrawler::new(&config)
+            .unwrap()
+            .with_api(HackerNewsAPI::new().unwrap().with_base_url(&hn.base_url()));
+        let date = time::Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        crawler
+            .run_with_context(&CrawlerContext::default().with_date(date))
+            .await
+            .unwrap();
+
+        let paths = supabase.uploaded_paths().await;
+        let partials = paths
+            .iter()
+            .filter(|p| *p == "2024-05-10/hacker-news.partial.md")
+            .count();
+        assert_eq!(partials, 2, "one upload per story: {:?}", paths);
+        let markdown = supabase.uploaded("2024-05-10/hacker-news.md").await;
+        assert!(markdown.starts_with(RECOVERED_NOTE), "{}", markdown);
+        assert_eq!(
+            supabase.deleted_paths().await,
+            ["2024-05-10/hacker-news.partial.md"]
+        );
+    }
+}
@@ -1,4 +1,10 @@
+use common::archive;
+use common::html::{self, TextOptions};
+use common::markdown;
 use serde::{Deserialize, Serialize};
+use time::Date;
+
+const HN_ITEM_URL_FORMAT: &str = "https://news.ycombinator.com/item?id={id}";
 
 #[derive(Debug, Deserialize)]
 pub struct HNItem {
@@ -7,9 +13,20 @@ pub struct HNItem {
     pub score: i64,
     pub url: Option<String>,
     pub text: Option<String>,
+    /// `story`, `job`, `poll`, `pollopt` or `comment`.
+    #[serde(rename = "type", default)]
+    pub item_type: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl HNItem {
+    /// Whether the item's type is one of `types`; untyped items count as stories.
+    pub fn has_type(&self, types: &[String]) -> bool {
+        let item_type = self.item_type.as_deref().unwrap_or("story");
+        types.iter().any(|t| t.eq_ignore_ascii_case(item_type))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StoryData {
     pub story_id: u64,
     pub title: String,
@@ -17,6 +34,9 @@ pub struct StoryData {
     pub url: Option<String>,
     pub text: Option<String>,
     pub summary: Option<String>,
+    /// Day the story's URL was first published, when it is a repeat.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<String>,
 }
 
 impl StoryData {
@@ -28,6 +48,7 @@ impl StoryData {
             url: item.url,
             text: item.text,
             summary,
+            first_seen: None,
         }
     }
 
@@ -35,21 +56,109 @@ impl StoryData {
         let url_or_summary_or_text = self
             .url
             .as_ref()
-            .map(|u| format!("[View Link]({})", u))
+            .map(|u| markdown::link("View Link", u))
             .or_else(|| self.summary.clone())
             .or_else(|| {
-                self.text.as_ref().map(|t| {
-                    scraper::Html::parse_fragment(t)
-                        .root_element()
-                        .text()
-                        .collect::<String>()
-                })
+                self.text
+                    .as_ref()
+                    .map(|t| html::to_text(t, &TextOptions::fragment().with_links()))
             })
             .unwrap_or_else(|| String::from("No content available."));
 
-        format!(
-            "# {}\n\n**Score**: {}\n\n{}",
-            self.title, self.score, url_or_summary_or_text
-        )
+        let mut markdown = format!(
+            "{}\n\n**Score**: {}\n\n{}",
+            markdown::section(1, &self.title),
+            self.score,
+            url_or_summary_or_text
+        );
+        if let Some(date) = &self.first_seen {
+            markdown.push_str(&format!("\n\n*First seen on {}*", date));
+        }
+        markdown
+    }
+}
+
+impl archive::Archivable for StoryData {
+    fn into_item(self, date: Date) -> archive::Item {
+        let id = self.story_id.to_string();
+        archive::Item {
+            source: "hacker_news".to_string(),
+            date: date.to_string(),
+            url: Some(
+                self.url
+                    .clone()
+                    .unwrap_or_else(|| HN_ITEM_URL_FORMAT.replace("{id}", &id)),
+            ),
+            title: self.title.clone(),
+            score: Some(self.score as f64),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+            id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::archive::Archivable;
+    use test_support::fixture;
+
+    #[test]
+    fn archive_envelope_links_to_the_hn_thread_without_a_url() {
+        let story = StoryData {
+            story_id: 42,
+            title: "Ask HN: Favourite crates?".to_string(),
+            score: 77,
+            url: None,
+            text: Some("<p>Go</p>".to_string()),
+            summary: None,
+            first_seen: None,
+        };
+        let item = story.into_item(time::macros::date!(2024 - 05 - 10));
+        assert_eq!(
+            (item.source.as_str(), item.date.as_str()),
+            ("hacker_news", "2024-05-10")
+        );
+        assert_eq!(item.id, "42");
+        assert_eq!(
+            item.url.as_deref(),
+            Some("https://news.ycombinator.com/item?id=42")
+        );
+        assert_eq!(item.score, Some(77.0));
+        assert_eq!(item.raw["text"], "<p>Go</p>");
+    }
+
+    #[test]
+    fn story_markdown_is_unchanged_for_plain_titles() {
+        let mut story = StoryData {
+            story_id: 1,
+            title: "Show HN: A tiny database".to_string(),
+            score: 120,
+            url: Some("https://example.com/db".to_string()),
+            text: None,
+            summary: None,
+            first_seen: None,
+        };
+        assert_eq!(
+            story.to_markdown_string(),
+            "# Show HN: A tiny database\n\n**Score**: 120\n\n[View Link](https://example.com/db)"
+        );
+
+        story.first_seen = Some("2024-05-08".to_string());
+        assert!(story
+            .to_markdown_string()
+            .ends_with("[View Link](https://example.com/db)\n\n*First seen on 2024-05-08*"));
+    }
+
+    #[test]
+    fn job_items_are_filtered_by_default() {
+        let job: HNItem = serde_json::from_str(&fixture("hacker_news/job.json")).unwrap();
+        let story: HNItem = serde_json::from_str(&fixture("hacker_news/story.json")).unwrap();
+        let stories_only = vec!["story".to_string()];
+
+        assert_eq!(job.item_type.as_deref(), Some("job"));
+        assert!(!job.has_type(&stories_only));
+        assert!(story.has_type(&stories_only));
+        assert!(job.has_type(&["story".to_string(), "job".to_string()]));
     }
 }
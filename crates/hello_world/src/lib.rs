@@ -24,4 +24,4 @@ mod tests {
         assert_eq!(say_hello_to("Rust"), "Hello, Rust!");
         assert_eq!(say_hello_to("世界"), "Hello, 世界!");
     }
-}
\ No newline at end of file
+}
@@ -2,4 +2,4 @@ use hello_world::run_hello_world;
 
 fn main() {
     run_hello_world();
-}
\ No newline at end of file
+}
@@ -0,0 +1,206 @@
+pub mod models;
+
+use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::http::parse_json;
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult, HttpClient,
+    OutputFormat, Publisher,
+};
+use models::LobstersStory;
+use tracing::info;
+
+const HOTTEST_URL: &str = "https://lobste.rs/hottest.json";
+
+/// Publishes the lobste.rs front page to `{date}/lobsters.md`, in the Hacker
+/// News digest's style.
+pub struct LobstersCrawler {
+    client: HttpClient,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    min_score: i64,
+    tags: Vec<String>,
+    archive: Option<ArchiveWriter>,
+}
+
+impl LobstersCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self {
+            client: ClientFactory::from_config(config).build()?,
+            publisher: Publisher::for_crawler(config, "lobsters")?,
+            output_formats: config.output_formats_for("lobsters"),
+            min_score: config.lobsters_min_score,
+            tags: config.lobsters_tags.clone(),
+            archive: ArchiveWriter::from_config(config)?,
+        })
+    }
+
+    async fn fetch_hottest(&self, ctx: &CrawlerContext) -> CrawlerResult<Vec<LobstersStory>> {
+        let body = ctx.http_client(&self.client).get_text(HOTTEST_URL).await?;
+        parse_json(HOTTEST_URL, &body)
+    }
+
+    /// The stories scoring at least `min_score` and, with `LOBSTERS_TAGS`
+    /// set, carrying one of those tags.
+    fn filter(&self, stories: Vec<LobstersStory>) -> Vec<LobstersStory> {
+        stories
+            .into_iter()
+            .filter(|story| story.score >= self.min_score && story.has_any_tag(&self.tags))
+            .collect()
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let fetched = self.fetch_hottest(ctx).await?;
+        let total = fetched.len();
+        let stories = self.filter(fetched);
+        info!("Kept {} of {} Lobsters stories", stories.len(), total);
+        if stories.is_empty() {
+            return Ok(());
+        }
+
+        let stem = format!("{}/lobsters", ctx.date);
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_stories_markdown(&stories),
+                &stories,
+            )
+            .await?;
+        info!(
+            "Uploaded {} Lobsters stories to {}",
+            stories.len(),
+            written.join(", ")
+        );
+        if let Some(archive) = &self.archive {
+            archive
+                .record(
+                    stories
+                        .iter()
+                        .cloned()
+                        .map(|story| story.into_item(ctx.date)),
+                )
+                .await;
+        }
+        Ok(())
+    }
+}
+
+/// Every story's section, separated by rules like the Hacker News digest.
+fn format_stories_markdown(stories: &[LobstersStory]) -> String {
+    markdown::rule_separated(stories.iter().map(LobstersStory::to_markdown_string))
+}
+
+#[async_trait]
+impl Crawler for LobstersCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Lobsters crawler starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Lobsters"
+    }
+
+    fn slug(&self) -> &'static str {
+        "lobsters"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if !config.lobsters_enabled {
+            return Err("LOBSTERS_ENABLED not set".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(LobstersCrawler {
+    name: "Lobsters crawler",
+    slug: "lobsters",
+    required_env: ["LOBSTERS_ENABLED"],
+    digest: ("lobsters", "Lobsters"),
+    new: LobstersCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{assert_snapshot, fixture, FixtureDir, MockSupabase, TestConfig};
+
+    fn stories() -> Vec<LobstersStory> {
+        serde_json::from_str(&fixture("lobsters/hottest.json")).unwrap()
+    }
+
+    #[test]
+    fn deserializes_the_recorded_front_page() {
+        let stories = stories();
+        assert_eq!(stories.len(), 4);
+        let first = &stories[0];
+        assert_eq!(first.short_id, "k3qvxe");
+        assert_eq!(
+            first.url.as_deref(),
+            Some("https://example.dev/posts/allocator")
+        );
+        assert_eq!((first.score, first.comment_count), (42, 17));
+        assert_eq!(first.tags, ["rust", "programming"]);
+        assert_eq!(stories[1].url, None, "text posts have an empty url");
+    }
+
+    #[tokio::test]
+    async fn filters_by_score_and_tags() {
+        let supabase = MockSupabase::start().await;
+        let config = |tags: &[&str]| {
+            TestConfig::new(&supabase)
+                .with(|c| {
+                    c.lobsters_min_score = 10;
+                    c.lobsters_tags = tags.iter().map(|t| t.to_string()).collect();
+                })
+                .build()
+        };
+        let ids = |crawler: LobstersCrawler| {
+            crawler
+                .filter(stories())
+                .into_iter()
+                .map(|s| s.short_id)
+                .collect::<Vec<_>>()
+        };
+        let all = LobstersCrawler::new(&config(&[])).unwrap();
+        assert_eq!(ids(all), ["k3qvxe", "mz8t1a", "r7bn0d"]);
+        let tagged = LobstersCrawler::new(&config(&["Rust", "ai"])).unwrap();
+        assert_eq!(ids(tagged), ["k3qvxe", "r7bn0d"]);
+    }
+
+    #[test]
+    fn markdown_matches_snapshot() {
+        assert_snapshot!("lobsters.md", format_stories_markdown(&stories()[..2]));
+    }
+
+    #[tokio::test]
+    async fn publishes_the_recorded_front_page() {
+        let fixtures = FixtureDir::new();
+        fixtures.add(HOTTEST_URL, &fixture("lobsters/hottest.json"));
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| {
+                c.lobsters_enabled = true;
+                c.lobsters_min_score = 10;
+            })
+            .build();
+        let date = time::Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        LobstersCrawler::new(&config)
+            .unwrap()
+            .run_with_context(&CrawlerContext::default().with_date(date))
+            .await
+            .unwrap();
+
+        let markdown = supabase.uploaded("2024-05-10/lobsters.md").await;
+        assert!(markdown.contains("# Writing a memory allocator in Rust"));
+        assert!(markdown.contains("[17 comments](https://lobste.rs/s/k3qvxe/"));
+        let json: Vec<LobstersStory> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/lobsters.json").await).unwrap();
+        assert_eq!(json.len(), 3, "the 9-point story is below the threshold");
+    }
+}
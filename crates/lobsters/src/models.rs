@@ -0,0 +1,76 @@
+use common::archive;
+use common::markdown;
+use serde::{Deserialize, Deserializer, Serialize};
+use time::Date;
+
+/// One entry of `hottest.json`, keeping the fields the digest uses.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LobstersStory {
+    pub short_id: String,
+    pub title: String,
+    /// `None` for text posts, which the API sends as `""`.
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub url: Option<String>,
+    pub score: i64,
+    pub comment_count: u64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Discussion page on lobste.rs.
+    pub comments_url: String,
+}
+
+fn empty_as_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.filter(|url| !url.trim().is_empty()))
+}
+
+impl LobstersStory {
+    /// Whether the story carries one of `tags`; an empty list allows every story.
+    pub fn has_any_tag(&self, tags: &[String]) -> bool {
+        tags.is_empty()
+            || self
+                .tags
+                .iter()
+                .any(|tag| tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+
+    pub fn to_markdown_string(&self) -> String {
+        let link = self
+            .url
+            .as_ref()
+            .map(|url| format!("{} · ", markdown::link("View Link", url)))
+            .unwrap_or_default();
+        let mut markdown = format!(
+            "{}\n\n**Score**: {}\n\n{}{}",
+            markdown::section(1, &self.title),
+            self.score,
+            link,
+            markdown::link(
+                &format!("{} comments", self.comment_count),
+                &self.comments_url
+            )
+        );
+        if !self.tags.is_empty() {
+            markdown.push_str(&format!("\n\n*Tags: {}*", self.tags.join(", ")));
+        }
+        markdown
+    }
+}
+
+impl archive::Archivable for LobstersStory {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "lobsters".to_string(),
+            date: date.to_string(),
+            url: Some(
+                self.url
+                    .clone()
+                    .unwrap_or_else(|| self.comments_url.clone()),
+            ),
+            title: self.title.clone(),
+            score: Some(self.score as f64),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+            id: self.short_id,
+        }
+    }
+}
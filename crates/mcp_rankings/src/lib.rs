@@ -1,85 +1,104 @@
 pub mod models;
 
-pub use McpRankingsCrawler;
-use models::McpServer;
-use common::{Config, Crawler, CrawlerResult, SupabaseStorageClient};
-use time::OffsetDateTime;
-use tracing::info;
 use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::html;
+use common::relative_time::parse_relative_time;
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult, HttpClient,
+    OutputFormat, Publisher,
+};
+use common::{state, StateStore};
+use models::McpServer;
 use scraper::{Html, Selector};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use time::{Date, OffsetDateTime};
+use tracing::{info, warn};
+
+const MCP_SO_URL: &str = "https://mcp.so";
 
 pub struct McpRankingsCrawler {
-    storage_client: SupabaseStorageClient,
-    client: reqwest::Client,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    client: HttpClient,
+    archive: Option<ArchiveWriter>,
+    state: Arc<dyn StateStore>,
+    min_description_len: usize,
 }
 
 impl McpRankingsCrawler {
     pub fn new(config: &Config) -> CrawlerResult<Self> {
-        let storage_client = SupabaseStorageClient::new(
-            &config.supabase.storage_url,
-            &config.supabase.key,
-            &config.supabase.bucket,
-        );
+        let publisher = Publisher::for_crawler(config, "mcp_rankings")?;
 
-        let client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .build()
-            .map_err(|e| common::CrawlerError::Api(e.to_string()))?;
+        let client = ClientFactory::from_config(config).build()?;
 
         Ok(Self {
-            storage_client,
+            publisher,
+            output_formats: config.output_formats_for("mcp_rankings"),
             client,
+            archive: ArchiveWriter::from_config(config)?,
+            state: state::from_config(config)?,
+            min_description_len: config.ranking_min_description_len,
         })
     }
 
-    async fn fetch_rankings(&self) -> CrawlerResult<Vec<McpServer>> {
-        let url = "https://mcp.so";
-        
-        let response = self.client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| common::CrawlerError::Api(format!("Failed to fetch MCP rankings: {}", e)))?;
+    async fn fetch_rankings(&self, ctx: &CrawlerContext) -> CrawlerResult<Vec<McpServer>> {
+        let html = ctx.http_client(&self.client).get_text(MCP_SO_URL).await?;
 
-        let html = response
-            .text()
-            .await
-            .map_err(|e| common::CrawlerError::Api(format!("Failed to read response: {}", e)))?;
-
-        self.parse_rankings(&html)
+        self.parse_rankings(&html, ctx.now())
     }
 
-    fn parse_rankings(&self, html: &str) -> CrawlerResult<Vec<McpServer>> {
+    fn parse_rankings(&self, html: &str, now: OffsetDateTime) -> CrawlerResult<Vec<McpServer>> {
         let document = Html::parse_document(html);
         let mut servers = Vec::new();
 
-        // This is a placeholder implementation - the actual selectors would need to be 
+        // This is a placeholder implementation - the actual selectors would need to be
         // determined by examining the actual MCP.so page structure
         let row_selector = Selector::parse("tr, .server-row, .mcp-row, .ranking-item")
             .map_err(|e| common::CrawlerError::Parse(format!("Invalid selector: {}", e)))?;
-        
+
         let name_selector = Selector::parse(".server-name, .name, h3, h4, .title")
             .map_err(|e| common::CrawlerError::Parse(format!("Invalid name selector: {}", e)))?;
-        
-        let description_selector = Selector::parse(".description, .desc, p")
-            .map_err(|e| common::CrawlerError::Parse(format!("Invalid description selector: {}", e)))?;
+
+        let description_selector = Selector::parse(".description, .desc, p").map_err(|e| {
+            common::CrawlerError::Parse(format!("Invalid description selector: {}", e))
+        })?;
 
         let stars_selector = Selector::parse(".stars, .star-count, .github-stars")
             .map_err(|e| common::CrawlerError::Parse(format!("Invalid stars selector: {}", e)))?;
 
-        for (index, row) in document.select(&row_selector).enumerate() {
+        let updated_selector = Selector::parse(".updated, .updated-at, time")
+            .map_err(|e| common::CrawlerError::Parse(format!("Invalid updated selector: {}", e)))?;
+
+        let link_selector = Selector::parse("a[href]")
+            .map_err(|e| common::CrawlerError::Parse(format!("Invalid link selector: {}", e)))?;
+
+        // Counted separately: header and skipped rows do not take a rank.
+        let mut rank = 0;
+        for row in document.select(&row_selector) {
+            if html::is_header_row(row) {
+                continue;
+            }
             if let Some(name_elem) = row.select(&name_selector).next() {
                 let name = name_elem.text().collect::<String>().trim().to_string();
-                if !name.is_empty() {
-                    let description = row.select(&description_selector)
+                if !name.is_empty() && !html::looks_like_header(&name) {
+                    let description = row
+                        .select(&description_selector)
                         .next()
                         .map(|elem| elem.text().collect::<String>().trim().to_string())
                         .unwrap_or_default();
+                    if description.chars().count() < self.min_description_len {
+                        info!("Skipping MCP row '{}': description too short", name);
+                        continue;
+                    }
 
-                    let stars = row.select(&stars_selector)
+                    let stars = row
+                        .select(&stars_selector)
                         .next()
                         .and_then(|elem| {
-                            elem.text().collect::<String>()
+                            elem.text()
+                                .collect::<String>()
                                 .chars()
                                 .filter(|c| c.is_ascii_digit())
                                 .collect::<String>()
@@ -88,12 +107,26 @@ impl McpRankingsCrawler {
                         })
                         .unwrap_or(0);
 
+                    // e.g. "updated 2 hours ago"
+                    let updated_at = row.select(&updated_selector).next().and_then(|elem| {
+                        parse_relative_time(&elem.text().collect::<String>(), now)
+                    });
+
+                    let url = row
+                        .select(&link_selector)
+                        .next()
+                        .and_then(|a| a.value().attr("href"))
+                        .and_then(server_url);
+
+                    rank += 1;
                     servers.push(McpServer {
-                        rank: index + 1,
+                        rank,
                         name,
                         description,
                         stars,
-                        fetched_at: OffsetDateTime::now_utc(),
+                        url,
+                        fetched_at: now,
+                        updated_at,
                     });
                 }
             }
@@ -103,52 +136,350 @@ impl McpRankingsCrawler {
         Ok(servers)
     }
 
-    async fn process_rankings(&self) -> CrawlerResult<()> {
-        let servers = self.fetch_rankings().await?;
-        
+    async fn process_rankings(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let servers = self.fetch_rankings(ctx).await?;
+
         if servers.is_empty() {
             info!("No MCP servers found");
             return Ok(());
         }
 
-        let today_str = OffsetDateTime::now_utc().date().to_string();
-        let file_content = self.format_servers_markdown(&servers);
-        let file_path = format!("{}/mcp-rankings.md", today_str);
+        let stem = format!("{}/mcp-rankings", ctx.date);
 
-        self.storage_client
-            .upload_file(&file_path, file_content, "text/markdown")
-            .await
-            .map_err(|e| common::CrawlerError::StorageUpload(e.to_string()))?;
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_servers_markdown(&servers, ctx.date),
+                &servers,
+            )
+            .await?;
 
-        info!("Successfully uploaded {} MCP servers to {}", servers.len(), file_path);
+        info!(
+            "Successfully uploaded {} MCP servers to {}",
+            servers.len(),
+            written.join(", ")
+        );
+        if let Some(archive) = &self.archive {
+            archive
+                .record(
+                    servers
+                        .iter()
+                        .cloned()
+                        .map(|server| server.into_item(ctx.date)),
+                )
+                .await;
+        }
+        self.record_known_servers(&servers).await;
         Ok(())
     }
 
-    fn format_servers_markdown(&self, servers: &[McpServer]) -> String {
-        let mut content = String::new();
-        content.push_str("# MCP Server Rankings\n\n");
-        content.push_str(&format!("*Fetched on {}*\n\n", OffsetDateTime::now_utc().date()));
-        
-        content.push_str("| Rank | Server Name | Description | Stars |\n");
-        content.push_str("|------|-------------|-------------|-------|\n");
-        
-        for server in servers {
-            content.push_str(&format!("| {} | {} | {} | {} |\n", 
-                server.rank, server.name, server.description, server.stars));
+    /// Adds the servers to the `known_servers` state and logs the ones never
+    /// seen before.
+    async fn record_known_servers(&self, servers: &[McpServer]) {
+        let known = match self.state.get("mcp_rankings", "known_servers").await {
+            Ok(known) => known,
+            Err(e) => {
+                warn!("Failed to read known MCP servers: {}", e);
+                return;
+            }
+        };
+        let names = new_server_names(known.as_ref(), servers);
+        let all: Vec<&str> = servers.iter().map(|s| s.name.as_str()).collect();
+        if let Err(e) = self
+            .state
+            .merge("mcp_rankings", "known_servers", json!(all))
+            .await
+        {
+            warn!("Failed to update known MCP servers: {}", e);
+            return;
         }
-        
-        content
+        if known.is_some() && !names.is_empty() {
+            info!("{} new MCP server(s): {}", names.len(), names.join(", "));
+        }
+    }
+}
+
+/// `href` resolved against mcp.so; `None` for anchors and unparsable links.
+fn server_url(href: &str) -> Option<String> {
+    if href.trim().is_empty() || href.starts_with('#') {
+        return None;
     }
+    let url = reqwest::Url::parse(MCP_SO_URL)
+        .ok()?
+        .join(href.trim())
+        .ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+/// Names of `servers` missing from the `known` list.
+fn new_server_names(known: Option<&Value>, servers: &[McpServer]) -> Vec<String> {
+    let known: Vec<&str> = known
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    servers
+        .iter()
+        .filter(|s| !known.contains(&s.name.as_str()))
+        .map(|s| s.name.clone())
+        .collect()
+}
+
+fn format_servers_markdown(servers: &[McpServer], date: Date) -> String {
+    let mut table = markdown::Table::new();
+    table
+        .add_column("Rank")
+        .add_column("Server Name")
+        .add_column("Description")
+        .add_column("Stars");
+    for server in servers {
+        let name = match &server.url {
+            Some(url) => markdown::link(&server.name, url),
+            None => markdown::escape(&server.name),
+        };
+        table.add_markdown_row([
+            server.rank.to_string(),
+            name,
+            markdown::escape(&server.description),
+            server.stars.to_string(),
+        ]);
+    }
+    format!(
+        "{}\n\n*Fetched on {}*\n\n{}",
+        markdown::section(1, "MCP Server Rankings"),
+        date,
+        table.render()
+    )
 }
 
 #[async_trait]
 impl Crawler for McpRankingsCrawler {
-    async fn run(&self) -> CrawlerResult<()> {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
         info!("MCP Rankings Crawler starting up");
-        self.process_rankings().await
+        self.process_rankings(ctx).await
     }
 
     fn name(&self) -> &'static str {
         "MCP Rankings"
     }
-}
\ No newline at end of file
+
+    fn slug(&self) -> &'static str {
+        "mcp_rankings"
+    }
+
+    fn schedule(&self) -> Option<&'static str> {
+        Some("0 0 * * * *")
+    }
+}
+
+common::register_crawler!(McpRankingsCrawler {
+    name: "MCP Rankings",
+    slug: "mcp_rankings",
+    required_env: [],
+    digest: ("mcp-rankings", "MCP Server Rankings"),
+    new: McpRankingsCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{assert_snapshot, fixture, FixtureDir, MockSupabase, TestConfig};
+
+    const FETCHED_AT: OffsetDateTime = time::macros::datetime!(2024-05-10 09:00 UTC);
+
+    #[test]
+    fn rankings_markdown_matches_snapshot() {
+        let mut filesystem = McpServer::new(
+            1,
+            "filesystem".to_string(),
+            "Secure file operations".to_string(),
+            1204,
+        );
+        filesystem.url = Some("https://mcp.so/server/filesystem".to_string());
+        let servers = vec![
+            filesystem,
+            McpServer::new(
+                2,
+                "shell".to_string(),
+                "run a | b\npipelines".to_string(),
+                5,
+            ),
+            McpServer::new(
+                3,
+                "日本語".to_string(),
+                "Unicode *names* & `code`".to_string(),
+                0,
+            ),
+        ];
+        assert_snapshot!(
+            "mcp-rankings.md",
+            format_servers_markdown(&servers, FETCHED_AT.date())
+        );
+    }
+
+    #[test]
+    fn archive_envelope_scores_by_stars() {
+        let item = McpServer::new(1, "filesystem".to_string(), "Files".to_string(), 950)
+            .into_item(FETCHED_AT.date());
+        assert_eq!(
+            (item.source.as_str(), item.id.as_str()),
+            ("mcp_rankings", "filesystem")
+        );
+        assert_eq!(item.score, Some(950.0));
+        assert_eq!(item.raw["description"], "Files");
+    }
+
+    #[test]
+    fn new_servers_are_those_not_known() {
+        let servers = vec![
+            McpServer::new(1, "filesystem".to_string(), String::new(), 1),
+            McpServer::new(2, "github".to_string(), String::new(), 1),
+        ];
+        assert_eq!(
+            new_server_names(Some(&json!(["filesystem", "slack"])), &servers),
+            vec!["github"]
+        );
+        assert_eq!(
+            new_server_names(None, &servers),
+            vec!["filesystem", "github"]
+        );
+    }
+
+    /// Cell text per row, ignoring column padding and rule width.
+    fn cells(markdown: &str) -> Vec<Vec<String>> {
+        markdown
+            .lines()
+            .map(|line| {
+                line.split('|')
+                    .map(|cell| cell.trim().to_string())
+                    .map(|cell| {
+                        if cell.chars().all(|c| c == '-') {
+                            "-".to_string()
+                        } else {
+                            cell
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn markdown_matches_previous_table_for_plain_text() {
+        let date = Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        let servers = vec![
+            McpServer::new(
+                1,
+                "filesystem".to_string(),
+                "Secure file operations".to_string(),
+                1200,
+            ),
+            McpServer::new(
+                2,
+                "github".to_string(),
+                "Repository management".to_string(),
+                800,
+            ),
+        ];
+        let before = "# MCP Server Rankings\n\n*Fetched on 2024-05-10*\n\n\
+            | Rank | Server Name | Description | Stars |\n\
+            |------|-------------|-------------|-------|\n\
+            | 1 | filesystem | Secure file operations | 1200 |\n\
+            | 2 | github | Repository management | 800 |\n";
+        let after = format_servers_markdown(&servers, date);
+        assert_eq!(cells(&after), cells(before));
+        assert!(after.starts_with("# MCP Server Rankings\n\n*Fetched on 2024-05-10*\n\n| Rank |"));
+    }
+
+    #[test]
+    fn pipes_in_descriptions_stay_in_their_cell() {
+        let date = Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        let servers = vec![McpServer::new(
+            1,
+            "shell".to_string(),
+            "run a | b\npipelines".to_string(),
+            5,
+        )];
+        let markdown = format_servers_markdown(&servers, date);
+        assert!(markdown.contains("| run a \\| b pipelines |"));
+    }
+
+    #[tokio::test]
+    async fn header_and_undescribed_rows_are_dropped() {
+        let supabase = MockSupabase::start().await;
+        let crawler = McpRankingsCrawler::new(&TestConfig::new(&supabase).build()).unwrap();
+        let servers = crawler
+            .parse_rankings(&fixture("mcp_rankings/with-header.html"), FETCHED_AT)
+            .unwrap();
+        let names: Vec<&str> = servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["filesystem", "github"]);
+        assert_eq!(servers[0].stars, 1204);
+
+        let strict = TestConfig::new(&supabase)
+            .with(|config| config.ranking_min_description_len = 40)
+            .build();
+        let servers = McpRankingsCrawler::new(&strict)
+            .unwrap()
+            .parse_rankings(&fixture("mcp_rankings/with-header.html"), FETCHED_AT)
+            .unwrap();
+        assert_eq!(servers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn ranks_count_servers_not_rows() {
+        let supabase = MockSupabase::start().await;
+        let crawler = McpRankingsCrawler::new(&TestConfig::new(&supabase).build()).unwrap();
+        let servers = crawler
+            .parse_rankings(&fixture("mcp_rankings/ranked.html"), FETCHED_AT)
+            .unwrap();
+        let ranks: Vec<(usize, &str)> = servers.iter().map(|s| (s.rank, s.name.as_str())).collect();
+        assert_eq!(
+            ranks,
+            vec![(1, "filesystem"), (2, "github"), (3, "postgres")]
+        );
+        let urls: Vec<Option<&str>> = servers.iter().map(|s| s.url.as_deref()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                Some("https://mcp.so/server/filesystem"),
+                Some("https://github.com/modelcontextprotocol/servers"),
+                None,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn csv_has_one_row_per_server_with_json_columns() {
+        let fixtures = FixtureDir::new();
+        fixtures.add("https://mcp.so", &fixture("mcp_rankings/with-header.html"));
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Csv, OutputFormat::Json])
+            .build();
+        let date = Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        McpRankingsCrawler::new(&config)
+            .unwrap()
+            .run_with_context(&CrawlerContext::default().with_date(date))
+            .await
+            .unwrap();
+
+        let csv = supabase.uploaded("2024-05-10/mcp-rankings.csv").await;
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("rank,name,description,stars,"));
+        let (_rank, row) = lines[1].split_once(',').unwrap();
+        assert!(row.starts_with(
+            "filesystem,Secure file operations with configurable access controls,1204,"
+        ));
+        assert_eq!(lines.len(), 3);
+
+        let json: Vec<McpServer> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/mcp-rankings.json").await).unwrap();
+        let servers: Vec<_> = json
+            .iter()
+            .map(|s| (s.rank, s.name.as_str(), s.stars))
+            .collect();
+        assert_eq!(servers, [(1, "filesystem", 1204), (2, "github", 812)]);
+    }
+}
@@ -1,5 +1,6 @@
+use common::archive;
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpServer {
@@ -7,8 +8,18 @@ pub struct McpServer {
     pub name: String,
     pub description: String,
     pub stars: u32,
+    /// The server's page, when its row links to one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
     #[serde(with = "time::serde::iso8601")]
     pub fetched_at: OffsetDateTime,
+    /// When the site says the server was last updated, if it shows that.
+    #[serde(
+        default,
+        with = "time::serde::iso8601::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub updated_at: Option<OffsetDateTime>,
 }
 
 impl McpServer {
@@ -18,7 +29,23 @@ impl McpServer {
             name,
             description,
             stars,
+            url: None,
             fetched_at: OffsetDateTime::now_utc(),
+            updated_at: None,
         }
     }
-}
\ No newline at end of file
+}
+
+impl archive::Archivable for McpServer {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "mcp_rankings".to_string(),
+            date: date.to_string(),
+            id: self.name.clone(),
+            title: self.name.clone(),
+            url: self.url.clone(),
+            score: Some(self.stars as f64),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use common::{HttpClient, RetryPolicy};
+use serde_json::{json, Value};
+
+use crate::{post_json, truncate, Level, Notification, Notifier};
+
+/// Discord's embed limits.
+const TITLE_LIMIT: usize = 256;
+const DESCRIPTION_LIMIT: usize = 4096;
+const MAX_FIELDS: usize = 25;
+const FIELD_NAME_LIMIT: usize = 256;
+const FIELD_VALUE_LIMIT: usize = 1024;
+/// Discord's limit for the title, description and fields of an embed together.
+const EMBED_LIMIT: usize = 6000;
+
+/// Posts to a Discord webhook as a single embed.
+pub struct DiscordNotifier {
+    client: HttpClient,
+    url: String,
+    retry: RetryPolicy,
+}
+
+impl DiscordNotifier {
+    pub fn new(client: HttpClient, url: &str) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+fn color(level: Level) -> u32 {
+    match level {
+        Level::Info => 0x3498db,
+        Level::Warning => 0xf1c40f,
+        Level::Error => 0xe74c3c,
+    }
+}
+
+/// The webhook body for `msg`. Fields that would take the embed past
+/// `EMBED_LIMIT` are shortened or left out.
+pub fn payload(msg: &Notification) -> Value {
+    let title = truncate(&msg.title, TITLE_LIMIT);
+    let description = truncate(&msg.body, DESCRIPTION_LIMIT);
+    let mut left = EMBED_LIMIT - title.chars().count() - description.chars().count();
+    let mut fields: Vec<Value> = Vec::new();
+    for (name, value) in msg.fields.iter().take(MAX_FIELDS) {
+        let name = truncate(name, FIELD_NAME_LIMIT);
+        let name_len = name.chars().count();
+        if name_len >= left {
+            break;
+        }
+        let value = truncate(value, FIELD_VALUE_LIMIT.min(left - name_len));
+        left -= name_len + value.chars().count();
+        fields.push(json!({
+            "name": name,
+            "value": value,
+            "inline": true,
+        }));
+    }
+    json!({
+        "embeds": [{
+            "title": title,
+            "description": description,
+            "color": color(msg.level),
+            "fields": fields,
+        }]
+    })
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    async fn send(&self, msg: &Notification) {
+        post_json(
+            &self.client,
+            &self.retry,
+            "Discord",
+            &self.url,
+            &payload(msg),
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sample;
+
+    #[test]
+    fn payload_snapshot() {
+        assert_eq!(
+            serde_json::to_string_pretty(&payload(&sample())).unwrap(),
+            r#"{
+  "embeds": [
+    {
+      "color": 15158332,
+      "description": "*2* of 5 crawlers failed",
+      "fields": [
+        {
+          "inline": true,
+          "name": "Failed",
+          "value": "github, openrouter"
+        },
+        {
+          "inline": true,
+          "name": "Duration",
+          "value": "42s"
+        }
+      ],
+      "title": "Crawl failed"
+    }
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn fields_are_capped() {
+        let mut msg = Notification::new(Level::Warning, "t", "b");
+        for i in 0..30 {
+            msg = msg.with_field(format!("f{}", i), "v".repeat(20));
+        }
+        let payload = payload(&msg);
+        let fields = payload["embeds"][0]["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), MAX_FIELDS);
+    }
+
+    #[test]
+    fn embed_stays_within_the_total_limit() {
+        let mut msg = Notification::new(Level::Error, "t".repeat(300), "b".repeat(5000));
+        for i in 0..10 {
+            msg = msg.with_field(format!("f{}", i), "v".repeat(2000));
+        }
+        let payload = payload(&msg);
+        let embed = &payload["embeds"][0];
+        let fields = embed["fields"].as_array().unwrap();
+        let chars = |value: &Value| value.as_str().unwrap().chars().count();
+        let total = chars(&embed["title"])
+            + chars(&embed["description"])
+            + fields
+                .iter()
+                .map(|f| chars(&f["name"]) + chars(&f["value"]))
+                .sum::<usize>();
+        assert_eq!(total, EMBED_LIMIT);
+        assert_eq!(chars(&fields[0]["value"]), FIELD_VALUE_LIMIT);
+        assert_eq!(
+            fields.len(),
+            2,
+            "the second field is shortened, the rest left out"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Outgoing notifications (Slack, Discord or any JSON webhook).
+//!
+//! Sending is best effort: `Notifier::send` never fails, it retries transient
+//! errors and logs what could not be delivered, so a broken webhook can never
+//! fail a crawl.
+
+pub mod discord;
+pub mod slack;
+pub mod webhook;
+
+use async_trait::async_trait;
+use common::{
+    retry_async, ClientFactory, Config, CrawlerError, CrawlerResult, HttpClient, RetryPolicy,
+};
+use serde_json::Value;
+use tracing::{info, warn};
+
+pub use discord::DiscordNotifier;
+pub use slack::SlackNotifier;
+pub use webhook::WebhookNotifier;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// A message with a title, a markdown body and optional key/value fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub level: Level,
+    pub title: String,
+    pub body: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl Notification {
+    pub fn new(level: Level, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            level,
+            title: title.into(),
+            body: body.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn with_field(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Delivers `msg`. Failures are logged, never returned.
+    async fn send(&self, msg: &Notification);
+}
+
+/// Sends every notification to all of its notifiers concurrently.
+#[derive(Default)]
+pub struct MultiNotifier {
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl MultiNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_notifier(mut self, notifier: Box<dyn Notifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.notifiers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifiers.is_empty()
+    }
+
+    /// One notifier per `NOTIFY_*_URL` that is set; empty when none are.
+    pub fn from_config(config: &Config) -> CrawlerResult<Self> {
+        let client = ClientFactory::from_config(config).build()?;
+        let mut notifiers = Self::new();
+        if let Some(url) = &config.notify_slack_url {
+            notifiers = notifiers.add_notifier(Box::new(SlackNotifier::new(client.clone(), url)));
+        }
+        if let Some(url) = &config.notify_discord_url {
+            notifiers = notifiers.add_notifier(Box::new(DiscordNotifier::new(client.clone(), url)));
+        }
+        if let Some(url) = &config.notify_webhook_url {
+            notifiers = notifiers.add_notifier(Box::new(WebhookNotifier::new(client, url)));
+        }
+        info!("Configured {} notifier(s)", notifiers.len());
+        Ok(notifiers)
+    }
+}
+
+#[async_trait]
+impl Notifier for MultiNotifier {
+    async fn send(&self, msg: &Notification) {
+        futures::future::join_all(self.notifiers.iter().map(|n| n.send(msg))).await;
+    }
+}
+
+/// Cuts `text` to at most `max` characters, ending in `…` when shortened.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+/// POSTs `payload` as JSON, retrying transient failures, and logs the outcome.
+pub(crate) async fn post_json(
+    client: &HttpClient,
+    policy: &RetryPolicy,
+    backend: &str,
+    url: &str,
+    payload: &Value,
+) {
+    let body = payload.to_string();
+    let result = retry_async(policy, CrawlerError::is_transient, |_| {
+        let request = client
+            .inner()
+            .post(url)
+            .header("Content-Type", "application/json")
+            .body(body.clone());
+        common::http::send_text(request, url)
+    })
+    .await;
+    if let Err(e) = result {
+        warn!("Failed to send {} notification: {}", backend, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Notifier for Recorder {
+        async fn send(&self, msg: &Notification) {
+            self.0.lock().unwrap().push(msg.title.clone());
+        }
+    }
+
+    pub(crate) fn sample() -> Notification {
+        Notification::new(Level::Error, "Crawl failed", "*2* of 5 crawlers failed")
+            .with_field("Failed", "github, openrouter")
+            .with_field("Duration", "42s")
+    }
+
+    #[test]
+    fn truncate_marks_cut_text() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("abcdefghij", 5), "abcd…");
+        assert_eq!(truncate("ééééé", 3), "éé…");
+    }
+
+    #[tokio::test]
+    async fn multi_notifier_fans_out() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let notifiers = MultiNotifier::new()
+            .add_notifier(Box::new(Recorder(seen.clone())))
+            .add_notifier(Box::new(Recorder(seen.clone())));
+        notifiers.send(&sample()).await;
+        assert_eq!(*seen.lock().unwrap(), vec!["Crawl failed", "Crawl failed"]);
+    }
+
+    #[tokio::test]
+    async fn unreachable_webhook_is_not_an_error() {
+        let notifier = WebhookNotifier::new(HttpClient::new().unwrap(), "http://127.0.0.1:9/hook")
+            .with_retry(RetryPolicy::none());
+        // Completing without a panic is the contract.
+        notifier.send(&sample()).await;
+    }
+}
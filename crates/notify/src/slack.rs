@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use common::{HttpClient, RetryPolicy};
+use serde_json::{json, Value};
+
+use crate::{post_json, truncate, Level, Notification, Notifier};
+
+/// Slack's limit for the text of a header block.
+const HEADER_LIMIT: usize = 150;
+/// Slack's limit for the text of a section block.
+const SECTION_LIMIT: usize = 3000;
+/// Slack's limits for the fields of a section block.
+const MAX_FIELDS: usize = 10;
+const FIELD_LIMIT: usize = 2000;
+
+/// Posts to a Slack incoming webhook using Block Kit.
+pub struct SlackNotifier {
+    client: HttpClient,
+    url: String,
+    retry: RetryPolicy,
+}
+
+impl SlackNotifier {
+    pub fn new(client: HttpClient, url: &str) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+fn emoji(level: Level) -> &'static str {
+    match level {
+        Level::Info => ":information_source:",
+        Level::Warning => ":warning:",
+        Level::Error => ":rotating_light:",
+    }
+}
+
+/// The webhook body for `msg`. `text` is the fallback shown in notifications.
+pub fn payload(msg: &Notification) -> Value {
+    let title = truncate(&format!("{} {}", emoji(msg.level), msg.title), HEADER_LIMIT);
+    let mut blocks = vec![json!({
+        "type": "header",
+        "text": { "type": "plain_text", "text": title, "emoji": true },
+    })];
+    if !msg.body.is_empty() {
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": truncate(&msg.body, SECTION_LIMIT) },
+        }));
+    }
+    if !msg.fields.is_empty() {
+        let fields: Vec<Value> = msg
+            .fields
+            .iter()
+            .take(MAX_FIELDS)
+            .map(|(name, value)| {
+                json!({
+                    "type": "mrkdwn",
+                    "text": truncate(&format!("*{}*\n{}", name, value), FIELD_LIMIT),
+                })
+            })
+            .collect();
+        blocks.push(json!({ "type": "section", "fields": fields }));
+    }
+    json!({ "text": title, "blocks": blocks })
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    async fn send(&self, msg: &Notification) {
+        post_json(&self.client, &self.retry, "Slack", &self.url, &payload(msg)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sample;
+
+    #[test]
+    fn payload_snapshot() {
+        assert_eq!(
+            serde_json::to_string_pretty(&payload(&sample())).unwrap(),
+            r#"{
+  "blocks": [
+    {
+      "text": {
+        "emoji": true,
+        "text": ":rotating_light: Crawl failed",
+        "type": "plain_text"
+      },
+      "type": "header"
+    },
+    {
+      "text": {
+        "text": "*2* of 5 crawlers failed",
+        "type": "mrkdwn"
+      },
+      "type": "section"
+    },
+    {
+      "fields": [
+        {
+          "text": "*Failed*\ngithub, openrouter",
+          "type": "mrkdwn"
+        },
+        {
+          "text": "*Duration*\n42s",
+          "type": "mrkdwn"
+        }
+      ],
+      "type": "section"
+    }
+  ],
+  "text": ":rotating_light: Crawl failed"
+}"#
+        );
+    }
+
+    #[test]
+    fn long_bodies_are_truncated() {
+        let msg = Notification::new(Level::Info, "t".repeat(500), "b".repeat(10_000));
+        let payload = payload(&msg);
+        let header = payload["blocks"][0]["text"]["text"].as_str().unwrap();
+        let body = payload["blocks"][1]["text"]["text"].as_str().unwrap();
+        assert_eq!(header.chars().count(), HEADER_LIMIT);
+        assert_eq!(body.chars().count(), SECTION_LIMIT);
+        assert!(body.ends_with('…'));
+    }
+}
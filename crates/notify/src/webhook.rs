@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use common::{HttpClient, RetryPolicy};
+use serde_json::{json, Value};
+
+use crate::{post_json, truncate, Notification, Notifier};
+
+/// Longest title or body sent to a generic webhook.
+const TEXT_LIMIT: usize = 16_000;
+
+/// POSTs notifications as plain JSON to any endpoint.
+pub struct WebhookNotifier {
+    client: HttpClient,
+    url: String,
+    retry: RetryPolicy,
+}
+
+impl WebhookNotifier {
+    pub fn new(client: HttpClient, url: &str) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+pub fn payload(msg: &Notification) -> Value {
+    let fields: Vec<Value> = msg
+        .fields
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": truncate(value, TEXT_LIMIT) }))
+        .collect();
+    json!({
+        "level": msg.level.as_str(),
+        "title": truncate(&msg.title, TEXT_LIMIT),
+        "body": truncate(&msg.body, TEXT_LIMIT),
+        "fields": fields,
+    })
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn send(&self, msg: &Notification) {
+        post_json(
+            &self.client,
+            &self.retry,
+            "webhook",
+            &self.url,
+            &payload(msg),
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sample;
+
+    #[test]
+    fn payload_snapshot() {
+        assert_eq!(
+            payload(&sample()).to_string(),
+            r#"{"body":"*2* of 5 crawlers failed","fields":[{"name":"Failed","value":"github, openrouter"},{"name":"Duration","value":"42s"}],"level":"error","title":"Crawl failed"}"#
+        );
+    }
+}
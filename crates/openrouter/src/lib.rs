@@ -1,83 +1,105 @@
 pub mod models;
 
-pub use OpenRouterCrawler;
-use models::ModelRanking;
-use common::{Config, Crawler, CrawlerResult, SupabaseStorageClient};
-use time::OffsetDateTime;
-use tracing::info;
 use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::html;
+use common::http::parse_json;
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult, HttpClient,
+    OutputFormat, Publisher,
+};
+use models::{ApiModel, ApiModels, ModelRanking};
 use scraper::{Html, Selector};
+use time::{Date, OffsetDateTime};
+use tracing::info;
+
+const RANKINGS_URL: &str = "https://openrouter.ai/rankings";
+const MODELS_API_URL: &str = "https://openrouter.ai/api/v1/models";
 
 pub struct OpenRouterCrawler {
-    storage_client: SupabaseStorageClient,
-    client: reqwest::Client,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    client: HttpClient,
+    archive: Option<ArchiveWriter>,
+    use_api: bool,
 }
 
 impl OpenRouterCrawler {
     pub fn new(config: &Config) -> CrawlerResult<Self> {
-        let storage_client = SupabaseStorageClient::new(
-            &config.supabase.storage_url,
-            &config.supabase.key,
-            &config.supabase.bucket,
-        );
+        let publisher = Publisher::for_crawler(config, "openrouter")?;
 
-        let client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .build()
-            .map_err(|e| common::CrawlerError::Api(e.to_string()))?;
+        let client = ClientFactory::from_config(config).build()?;
 
         Ok(Self {
-            storage_client,
+            publisher,
+            output_formats: config.output_formats_for("openrouter"),
             client,
+            archive: ArchiveWriter::from_config(config)?,
+            use_api: config.openrouter_use_api,
         })
     }
 
-    async fn fetch_rankings(&self) -> CrawlerResult<Vec<ModelRanking>> {
-        let url = "https://openrouter.ai/rankings";
-        
-        let response = self.client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| common::CrawlerError::Api(format!("Failed to fetch OpenRouter rankings: {}", e)))?;
+    async fn fetch_rankings(&self, ctx: &CrawlerContext) -> CrawlerResult<Vec<ModelRanking>> {
+        if self.use_api {
+            return self.fetch_rankings_via_api(ctx).await;
+        }
+        let html = ctx.http_client(&self.client).get_text(RANKINGS_URL).await?;
 
-        let html = response
-            .text()
-            .await
-            .map_err(|e| common::CrawlerError::Api(format!("Failed to read response: {}", e)))?;
+        self.parse_rankings(&html, ctx.now())
+    }
 
-        self.parse_rankings(&html)
+    /// Models from the public models API, which needs no key.
+    async fn fetch_rankings_via_api(
+        &self,
+        ctx: &CrawlerContext,
+    ) -> CrawlerResult<Vec<ModelRanking>> {
+        let body = ctx
+            .http_client(&self.client)
+            .get_text(MODELS_API_URL)
+            .await?;
+        let models: ApiModels = parse_json(MODELS_API_URL, &body)?;
+        let rankings = rank_api_models(models.data, ctx.now());
+        info!("Read {} models from the OpenRouter API", rankings.len());
+        Ok(rankings)
     }
 
-    fn parse_rankings(&self, html: &str) -> CrawlerResult<Vec<ModelRanking>> {
+    fn parse_rankings(&self, html: &str, now: OffsetDateTime) -> CrawlerResult<Vec<ModelRanking>> {
         let document = Html::parse_document(html);
         let mut rankings = Vec::new();
 
-        // This is a placeholder implementation - the actual selectors would need to be 
+        // This is a placeholder implementation - the actual selectors would need to be
         // determined by examining the actual OpenRouter rankings page structure
         let row_selector = Selector::parse("tr, .ranking-row, .model-row")
             .map_err(|e| common::CrawlerError::Parse(format!("Invalid selector: {}", e)))?;
-        
+
         let name_selector = Selector::parse(".model-name, .name, h3, h4")
             .map_err(|e| common::CrawlerError::Parse(format!("Invalid name selector: {}", e)))?;
-        
+
         let score_selector = Selector::parse(".score, .rating, .points")
             .map_err(|e| common::CrawlerError::Parse(format!("Invalid score selector: {}", e)))?;
 
-        for (index, row) in document.select(&row_selector).enumerate() {
+        let mut rank = 0;
+        for row in document.select(&row_selector) {
+            if html::is_header_row(row) {
+                continue;
+            }
             if let Some(name_elem) = row.select(&name_selector).next() {
                 let name = name_elem.text().collect::<String>().trim().to_string();
-                if !name.is_empty() {
-                    let score = row.select(&score_selector)
+                if !name.is_empty() && !html::looks_like_header(&name) {
+                    let score = row
+                        .select(&score_selector)
                         .next()
                         .and_then(|elem| elem.text().collect::<String>().trim().parse::<f64>().ok())
                         .unwrap_or(0.0);
 
+                    rank += 1;
                     rankings.push(ModelRanking {
-                        rank: index + 1,
+                        rank,
                         name,
                         score,
-                        fetched_at: OffsetDateTime::now_utc(),
+                        prompt_price: None,
+                        completion_price: None,
+                        fetched_at: now,
                     });
                 }
             }
@@ -87,52 +109,291 @@ impl OpenRouterCrawler {
         Ok(rankings)
     }
 
-    async fn process_rankings(&self) -> CrawlerResult<()> {
-        let rankings = self.fetch_rankings().await?;
-        
+    async fn process_rankings(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let rankings = self.fetch_rankings(ctx).await?;
+
         if rankings.is_empty() {
             info!("No OpenRouter rankings found");
             return Ok(());
         }
 
-        let today_str = OffsetDateTime::now_utc().date().to_string();
-        let file_content = self.format_rankings_markdown(&rankings);
-        let file_path = format!("{}/openrouter-rankings.md", today_str);
+        let stem = format!("{}/openrouter-rankings", ctx.date);
 
-        self.storage_client
-            .upload_file(&file_path, file_content, "text/markdown")
-            .await
-            .map_err(|e| common::CrawlerError::StorageUpload(e.to_string()))?;
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_rankings_markdown(&rankings, ctx.date),
+                &rankings,
+            )
+            .await?;
 
-        info!("Successfully uploaded {} OpenRouter rankings to {}", rankings.len(), file_path);
+        info!(
+            "Successfully uploaded {} OpenRouter rankings to {}",
+            rankings.len(),
+            written.join(", ")
+        );
+        if let Some(archive) = &self.archive {
+            archive
+                .record(
+                    rankings
+                        .iter()
+                        .cloned()
+                        .map(|model| model.into_item(ctx.date)),
+                )
+                .await;
+        }
         Ok(())
     }
+}
+
+/// The API has no popularity figure, so models are ranked by context length,
+/// longest first, with ties broken by id to keep the order stable.
+fn rank_api_models(mut models: Vec<ApiModel>, now: OffsetDateTime) -> Vec<ModelRanking> {
+    models.retain(|model| !model.id.trim().is_empty());
+    models.sort_by(|a, b| {
+        b.context_length
+            .cmp(&a.context_length)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    models
+        .into_iter()
+        .enumerate()
+        .map(|(index, model)| ModelRanking {
+            rank: index + 1,
+            score: model.context_length.unwrap_or(0) as f64 / 1000.0,
+            prompt_price: model.pricing.prompt_per_million(),
+            completion_price: model.pricing.completion_per_million(),
+            name: model.id,
+            fetched_at: now,
+        })
+        .collect()
+}
+
+fn format_rankings_markdown(rankings: &[ModelRanking], date: Date) -> String {
+    let mut table = markdown::Table::new();
+    table
+        .add_column("Rank")
+        .add_column("Model Name")
+        .add_column("Score")
+        .add_column("Prompt $/M")
+        .add_column("Completion $/M");
+    for ranking in rankings {
+        table.add_row([
+            ranking.rank.to_string(),
+            ranking.name.clone(),
+            format!("{:.2}", ranking.score),
+            format_price(ranking.prompt_price),
+            format_price(ranking.completion_price),
+        ]);
+    }
+    format!(
+        "{}\n\n*Fetched on {}*\n\n{}",
+        markdown::section(1, "OpenRouter Model Rankings"),
+        date,
+        table.render()
+    )
+}
 
-    fn format_rankings_markdown(&self, rankings: &[ModelRanking]) -> String {
-        let mut content = String::new();
-        content.push_str("# OpenRouter Model Rankings\n\n");
-        content.push_str(&format!("*Fetched on {}*\n\n", OffsetDateTime::now_utc().date()));
-        
-        content.push_str("| Rank | Model Name | Score |\n");
-        content.push_str("|------|------------|-------|\n");
-        
-        for ranking in rankings {
-            content.push_str(&format!("| {} | {} | {:.2} |\n", 
-                ranking.rank, ranking.name, ranking.score));
+/// `$3.00`, with more places for sub-cent prices (`$0.075`); `-` when unknown.
+fn format_price(price: Option<f64>) -> String {
+    match price {
+        Some(price) if price > 0.0 && price < 0.1 => {
+            let precise = format!("{:.4}", price);
+            format!("${}", precise.trim_end_matches('0'))
         }
-        
-        content
+        Some(price) => format!("${:.2}", price),
+        None => "-".to_string(),
     }
 }
 
 #[async_trait]
 impl Crawler for OpenRouterCrawler {
-    async fn run(&self) -> CrawlerResult<()> {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
         info!("OpenRouter Crawler starting up");
-        self.process_rankings().await
+        self.process_rankings(ctx).await
     }
 
     fn name(&self) -> &'static str {
         "OpenRouter"
     }
-}
\ No newline at end of file
+
+    fn slug(&self) -> &'static str {
+        "openrouter"
+    }
+
+    fn schedule(&self) -> Option<&'static str> {
+        Some("0 0 * * * *")
+    }
+}
+
+common::register_crawler!(OpenRouterCrawler {
+    name: "OpenRouter crawler",
+    slug: "openrouter",
+    required_env: [],
+    digest: ("openrouter-rankings", "OpenRouter Rankings"),
+    new: OpenRouterCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{assert_snapshot, fixture, FixtureDir, MockSupabase, TestConfig};
+
+    const FETCHED_AT: OffsetDateTime = time::macros::datetime!(2024-05-10 09:00 UTC);
+
+    #[test]
+    fn rankings_markdown_matches_snapshot() {
+        let mut sonnet = ModelRanking::new(1, "anthropic/claude-sonnet-4".to_string(), 98.456);
+        (sonnet.prompt_price, sonnet.completion_price) = (Some(3.0), Some(15.0));
+        let mut llama = ModelRanking::new(2, "meta|llama".to_string(), 0.0);
+        (llama.prompt_price, llama.completion_price) = (Some(0.075), Some(0.0));
+        let rankings = vec![
+            sonnet,
+            llama,
+            ModelRanking::new(10, "google/gemini-2.5-pro".to_string(), 100.0),
+        ];
+        assert_snapshot!(
+            "openrouter-rankings.md",
+            format_rankings_markdown(&rankings, FETCHED_AT.date())
+        );
+    }
+
+    #[test]
+    fn archive_envelope_uses_the_run_date() {
+        let mut model = ModelRanking::new(3, "openai/gpt-4o".to_string(), 88.5);
+        model.fetched_at = time::macros::datetime!(2024-05-11 00:01 UTC);
+        let item = model.into_item(time::macros::date!(2024 - 05 - 10));
+        assert_eq!(item.date, "2024-05-10");
+        assert_eq!(
+            (item.id.as_str(), item.score),
+            ("openai/gpt-4o", Some(88.5))
+        );
+        assert_eq!(item.raw["rank"], 3);
+    }
+
+    #[test]
+    fn markdown_matches_previous_table_for_plain_names() {
+        let date = Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        let rankings = vec![
+            ModelRanking::new(1, "anthropic/claude-sonnet-4".to_string(), 98.5),
+            ModelRanking::new(2, "google/gemini-2.5-pro".to_string(), 91.0),
+        ];
+        // Previously: "| 1 | anthropic/claude-sonnet-4 | 98.50 |" with an unpadded rule.
+        // Scraped rankings carry no prices, so those columns show "-".
+        assert_eq!(
+            format_rankings_markdown(&rankings, date),
+            "# OpenRouter Model Rankings\n\n*Fetched on 2024-05-10*\n\n\
+             | Rank | Model Name                | Score | Prompt $/M | Completion $/M |\n\
+             | ---- | ------------------------- | ----- | ---------- | -------------- |\n\
+             | 1    | anthropic/claude-sonnet-4 | 98.50 | -          | -              |\n\
+             | 2    | google/gemini-2.5-pro     | 91.00 | -          | -              |\n"
+        );
+    }
+
+    #[test]
+    fn prices_are_per_million_tokens() {
+        assert_eq!(format_price(Some(3.0)), "$3.00");
+        assert_eq!(format_price(Some(0.075)), "$0.075");
+        assert_eq!(format_price(Some(0.0)), "$0.00");
+        assert_eq!(format_price(None), "-");
+
+        let saved = r#"{"rank":1,"name":"openai/gpt-4o","score":88.5,"fetched_at":"+002024-05-10T09:00:00.000000000Z"}"#;
+        let model: ModelRanking = serde_json::from_str(saved).unwrap();
+        assert_eq!((model.prompt_price, model.completion_price), (None, None));
+    }
+
+    #[tokio::test]
+    async fn header_rows_are_not_models() {
+        let supabase = MockSupabase::start().await;
+        let crawler = OpenRouterCrawler::new(&TestConfig::new(&supabase).build()).unwrap();
+        let rankings = crawler
+            .parse_rankings(&fixture("openrouter/with-header.html"), FETCHED_AT)
+            .unwrap();
+        let names: Vec<&str> = rankings.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["anthropic/claude-sonnet-4", "google/gemini-2.5-pro"]
+        );
+        assert_eq!(
+            (rankings[0].rank, rankings[1].rank),
+            (1, 2),
+            "the header takes no rank"
+        );
+        assert_eq!(rankings[0].score, 98.5);
+        assert_eq!(
+            rankings[0].fetched_at, FETCHED_AT,
+            "stamped by the context's clock"
+        );
+    }
+
+    #[tokio::test]
+    async fn api_models_are_ranked_by_context_length() {
+        let fixtures = FixtureDir::new();
+        fixtures.add(MODELS_API_URL, &fixture("openrouter/models.json"));
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Json])
+            .build();
+        let ctx = CrawlerContext::with_clock(std::sync::Arc::new(common::FixedClock(FETCHED_AT)));
+        OpenRouterCrawler::new(&config)
+            .unwrap()
+            .run_with_context(&ctx)
+            .await
+            .unwrap();
+
+        let rankings: Vec<ModelRanking> = serde_json::from_str(
+            &supabase
+                .uploaded("2024-05-10/openrouter-rankings.json")
+                .await,
+        )
+        .unwrap();
+        let ranked: Vec<(usize, &str, f64)> = rankings
+            .iter()
+            .map(|r| (r.rank, r.name.as_str(), r.score))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (1, "google/gemini-2.5-pro", 1048.576),
+                (2, "anthropic/claude-3.5-haiku", 200.0),
+                (3, "anthropic/claude-sonnet-4", 200.0),
+                (4, "openai/gpt-4o", 128.0),
+                (5, "acme/no-context", 0.0),
+            ]
+        );
+        let prices: Vec<(Option<f64>, Option<f64>)> = rankings
+            .iter()
+            .map(|r| (r.prompt_price, r.completion_price))
+            .collect();
+        assert_eq!(prices[2], (Some(3.0), Some(15.0)));
+        assert_eq!(prices[4], (None, None));
+    }
+
+    #[tokio::test]
+    async fn scrapes_the_rankings_page_when_the_api_is_off() {
+        let fixtures = FixtureDir::new();
+        fixtures.add(RANKINGS_URL, &fixture("openrouter/with-header.html"));
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Json])
+            .with(|config| config.openrouter_use_api = false)
+            .build();
+        OpenRouterCrawler::new(&config)
+            .unwrap()
+            .run_with_context(&CrawlerContext::default().with_date(FETCHED_AT.date()))
+            .await
+            .unwrap();
+
+        let rankings: Vec<ModelRanking> = serde_json::from_str(
+            &supabase
+                .uploaded("2024-05-10/openrouter-rankings.json")
+                .await,
+        )
+        .unwrap();
+        assert_eq!(rankings.len(), 2);
+    }
+}
@@ -1,11 +1,20 @@
+use common::archive;
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelRanking {
     pub rank: usize,
     pub name: String,
+    /// The rankings page's score, or the context length in thousands of
+    /// tokens for models read from the API.
     pub score: f64,
+    /// USD per million prompt tokens, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_price: Option<f64>,
+    /// USD per million completion tokens, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_price: Option<f64>,
     #[serde(with = "time::serde::iso8601")]
     pub fetched_at: OffsetDateTime,
 }
@@ -16,7 +25,68 @@ impl ModelRanking {
             rank,
             name,
             score,
+            prompt_price: None,
+            completion_price: None,
             fetched_at: OffsetDateTime::now_utc(),
         }
     }
-}
\ No newline at end of file
+}
+
+impl archive::Archivable for ModelRanking {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "openrouter".to_string(),
+            date: date.to_string(),
+            id: self.name.clone(),
+            title: self.name.clone(),
+            url: None,
+            score: Some(self.score),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+        }
+    }
+}
+
+/// `GET /api/v1/models`.
+#[derive(Debug, Deserialize)]
+pub struct ApiModels {
+    pub data: Vec<ApiModel>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiModel {
+    /// `{vendor}/{model}`, as shown on the rankings page.
+    pub id: String,
+    /// Display name, e.g. "Anthropic: Claude Sonnet 4".
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub pricing: ApiPricing,
+    #[serde(default)]
+    pub context_length: Option<u64>,
+}
+
+/// USD per token, as decimal strings.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiPricing {
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub completion: Option<String>,
+}
+
+impl ApiPricing {
+    pub fn prompt_per_million(&self) -> Option<f64> {
+        per_million(self.prompt.as_deref())
+    }
+
+    pub fn completion_per_million(&self) -> Option<f64> {
+        per_million(self.completion.as_deref())
+    }
+}
+
+/// A per-token price scaled to a million tokens. Negative prices mark
+/// routers whose price depends on the model picked, so they count as unknown.
+fn per_million(price: Option<&str>) -> Option<f64> {
+    let price: f64 = price?.trim().parse().ok()?;
+    (price >= 0.0).then_some(price * 1_000_000.0)
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use common::{Config, RunReport, SupabaseStorageClient};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// Name of the checksums file inside each `{date}/` folder.
+pub const CHECKSUMS_FILE: &str = "CHECKSUMS.txt";
+
+/// Uploads `{date}/CHECKSUMS.txt` listing the SHA-256 of every file the run
+/// wrote under the run's folder, and with `CUTION_CHECKSUMS_KEY` set, a hex
+/// HMAC-SHA256 of that file as `{date}/CHECKSUMS.txt.sig`.
+pub async fn publish(config: &Config, report: &RunReport) -> Result<String> {
+    let storage = SupabaseStorageClient::from_config(config)?;
+    let date = report.date().to_string();
+    let body = render(report, &date);
+    let path = format!("{}/{}", date, CHECKSUMS_FILE);
+    if let Some(key) = &config.checksums_key {
+        storage
+            .upload_file(&format!("{}.sig", path), sign(key, &body), "text/plain")
+            .await?;
+    }
+    let files = body.lines().count();
+    storage.upload_file(&path, body, "text/plain").await?;
+    info!("Uploaded {} ({} file(s))", path, files);
+    Ok(path)
+}
+
+/// `sha256sum` style lines (`{hash}  {name}`, sorted by name) for the files in
+/// `report` under `{date}/`, named relative to it so `sha256sum -c` can check
+/// a downloaded copy of the folder.
+pub fn render(report: &RunReport, date: &str) -> String {
+    let prefix = format!("{}/", date);
+    let files: BTreeMap<&str, &str> = report
+        .outcomes
+        .iter()
+        .flat_map(|outcome| &outcome.checksums)
+        .filter_map(|(path, hash)| Some((path.strip_prefix(&prefix)?, hash.as_str())))
+        .collect();
+    files
+        .into_iter()
+        .map(|(name, hash)| format!("{}  {}\n", hash, name))
+        .collect()
+}
+
+/// Hex HMAC-SHA256 of `body` under `key`.
+pub fn sign(key: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::CrawlerOutcome;
+
+    fn outcome(checksums: &[(&str, &str)]) -> CrawlerOutcome {
+        CrawlerOutcome {
+            uploaded: checksums.iter().map(|(p, _)| p.to_string()).collect(),
+            checksums: checksums
+                .iter()
+                .map(|(p, h)| (p.to_string(), h.to_string()))
+                .collect(),
+            ..test_support::outcome("GitHub Trending")
+        }
+    }
+
+    #[test]
+    fn lists_todays_files_sorted_by_name() {
+        let report = RunReport {
+            outcomes: vec![
+                outcome(&[
+                    ("2024-05-10/hacker-news.md", "bb"),
+                    ("archive/2024/05.ndjson", "cc"),
+                ]),
+                outcome(&[("2024-05-10/github-trending.md", "aa")]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&report, "2024-05-10"),
+            "aa  github-trending.md\nbb  hacker-news.md\n"
+        );
+        assert_eq!(render(&RunReport::default(), "2024-05-10"), "");
+    }
+
+    #[test]
+    fn signature_is_rfc_4231_hmac_sha256() {
+        // RFC 4231 test case 2.
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use async_trait::async_trait;
+use common::dedup::parse_date;
+use common::summary_cache::{self, CACHE_PREFIX};
+use common::{Config, Crawler, CrawlerContext, CrawlerError, CrawlerResult, SupabaseStorageClient};
+use std::sync::OnceLock;
+use time::{Date, Duration};
+use tracing::info;
+
+/// Paths never deleted, matched at the start of the path or of any segment:
+/// history, state snapshots and the weekly roll-ups filed under a Monday.
+pub const KEEP: &[&str] = &["history/", "state/", "weekly-review"];
+
+/// The `YYYY-MM-DD` a path starts with, as in `2024-05-10/hacker-news.md`.
+pub fn path_date(path: &str) -> Option<Date> {
+    let head = path.get(..10)?;
+    match path.as_bytes().get(10) {
+        None | Some(b'/' | b'-' | b'_' | b'.') => parse_date(head),
+        Some(_) => None,
+    }
+}
+
+/// Whether `path` is dated before `cutoff`.
+fn is_expired(path: &str, cutoff: Date) -> bool {
+    path_date(path).is_some_and(|date| date < cutoff)
+}
+
+pub fn is_kept(path: &str) -> bool {
+    KEEP.iter()
+        .any(|keep| path.starts_with(keep) || path.contains(&format!("/{}", keep)))
+}
+
+/// What a cleanup removes, given every file under the expired date folders.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CleanupPlan {
+    pub delete: Vec<String>,
+    /// Expired paths spared by `KEEP`.
+    pub kept: Vec<String>,
+}
+
+/// Sorts the dated paths from before `cutoff` into deletions and keeps;
+/// everything else is left alone.
+pub fn plan(paths: &[String], cutoff: Date) -> CleanupPlan {
+    let mut plan = CleanupPlan::default();
+    for path in paths {
+        if !is_expired(path, cutoff) {
+            continue;
+        }
+        if is_kept(path) {
+            plan.kept.push(path.clone());
+        } else {
+            plan.delete.push(path.clone());
+        }
+    }
+    plan
+}
+
+/// The log lines for `plan`, listing every path a dry run would delete.
+pub fn report(plan: &CleanupPlan, cutoff: Date, dry_run: bool) -> String {
+    let verb = if dry_run { "Would delete" } else { "Deleting" };
+    let mut out = format!(
+        "{} {} file(s) dated before {} ({} kept by the keep-list)",
+        verb,
+        plan.delete.len(),
+        cutoff,
+        plan.kept.len()
+    );
+    if dry_run {
+        for path in &plan.delete {
+            out.push_str("\n  ");
+            out.push_str(path);
+        }
+    }
+    out
+}
+
+/// Deletes files under date folders older than `CUTION_RETENTION_DAYS`,
+/// except those matching `KEEP`, and summary cache entries past their TTL.
+pub struct CleanupCrawler {
+    storage: SupabaseStorageClient,
+    retention_days: u32,
+    /// Cached summaries last written longer ago than this are never reused.
+    summary_ttl_days: u32,
+    dry_run: bool,
+}
+
+impl CleanupCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        let retention_days = config.retention_days.ok_or_else(|| {
+            CrawlerError::Config(anyhow::anyhow!("CUTION_RETENTION_DAYS not set"))
+        })?;
+        Ok(Self {
+            storage: SupabaseStorageClient::from_config(config)?,
+            retention_days,
+            summary_ttl_days: config
+                .summary_cache_ttl_days
+                .unwrap_or(summary_cache::DEFAULT_TTL_DAYS),
+            dry_run: config.retention_dry_run,
+        })
+    }
+
+    /// Summary cache entries last written before `cutoff`. An entry is only
+    /// rewritten when it is summarized again, so one this old has outlived
+    /// its TTL and no crawler will read it.
+    async fn list_stale_summaries(
+        storage: &SupabaseStorageClient,
+        cutoff: Date,
+    ) -> CrawlerResult<Vec<String>> {
+        let objects = storage
+            .list_objects(CACHE_PREFIX)
+            .await
+            .map_err(|e| CrawlerError::Api(e.to_string()))?;
+        Ok(objects
+            .into_iter()
+            .filter(|object| object.size.is_some())
+            .filter(|object| object.updated.is_some_and(|updated| updated < cutoff))
+            .map(|object| format!("{}/{}", CACHE_PREFIX, object.name))
+            .collect())
+    }
+
+    /// Every file under the top-level entries dated before `cutoff`.
+    async fn list_expired(
+        storage: &SupabaseStorageClient,
+        cutoff: Date,
+    ) -> CrawlerResult<Vec<String>> {
+        let list = |prefix: String| async move {
+            storage
+                .list_objects(&prefix)
+                .await
+                .map_err(|e| CrawlerError::Api(e.to_string()))
+        };
+        let mut files = Vec::new();
+        let mut folders = Vec::new();
+        for object in list(String::new()).await? {
+            if !is_expired(&object.name, cutoff) {
+                continue;
+            }
+            match object.size {
+                Some(_) => files.push(object.name),
+                None => folders.push(object.name),
+            }
+        }
+        while let Some(folder) = folders.pop() {
+            for object in list(folder.clone()).await? {
+                let path = format!("{}/{}", folder, object.name);
+                match object.size {
+                    Some(_) => files.push(path),
+                    None => folders.push(path),
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+#[async_trait]
+impl Crawler for CleanupCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let storage = ctx.storage_client(&self.storage);
+        let cutoff = ctx.date - Duration::days(self.retention_days as i64);
+        let mut plan = plan(&Self::list_expired(&storage, cutoff).await?, cutoff);
+        info!("{}", report(&plan, cutoff, self.dry_run));
+        let summary_cutoff = ctx.date - Duration::days(self.summary_ttl_days as i64);
+        let stale = Self::list_stale_summaries(&storage, summary_cutoff).await?;
+        info!(
+            "{} {} summary cache entr{} last written before {}",
+            if self.dry_run {
+                "Would delete"
+            } else {
+                "Deleting"
+            },
+            stale.len(),
+            if stale.len() == 1 { "y" } else { "ies" },
+            summary_cutoff
+        );
+        plan.delete.extend(stale);
+        if self.dry_run || plan.delete.is_empty() {
+            return Ok(());
+        }
+        storage
+            .delete_files(&plan.delete)
+            .await
+            .map_err(|e| CrawlerError::Api(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "Retention Cleanup"
+    }
+
+    fn slug(&self) -> &'static str {
+        "cleanup"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if config.retention_days.is_none() {
+            return Err("CUTION_RETENTION_DAYS not set".to_string());
+        }
+        Ok(())
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        static DEPENDS_ON: OnceLock<Vec<&'static str>> = OnceLock::new();
+        DEPENDS_ON.get_or_init(|| crate::source_slugs_and(&["digest", "html_index"]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn listing() -> Vec<String> {
+        [
+            "2024-03-01/hacker-news.md",
+            "2024-03-01/runs/120000-usage.json",
+            "2024-03-04/weekly-review.md",
+            "2024-03-05/history/github.json",
+            "2024-05-01/hacker-news.md",
+            "archive/2024/03.ndjson",
+            "state/seen_urls.json",
+            "index.html",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn reads_the_leading_date() {
+        assert_eq!(path_date("2024-05-10/a.md"), Some(date!(2024 - 05 - 10)));
+        assert_eq!(path_date("2024-05-10"), Some(date!(2024 - 05 - 10)));
+        assert_eq!(
+            path_date("2024-05-10-notes.md"),
+            Some(date!(2024 - 05 - 10))
+        );
+        assert_eq!(path_date("2024-05-100/a.md"), None);
+        assert_eq!(path_date("2024-13-01/a.md"), None);
+        assert_eq!(path_date("archive/2024/05.ndjson"), None);
+    }
+
+    #[test]
+    fn keep_list_matches_prefixes_and_segments() {
+        assert!(is_kept("state/seen_urls.json"));
+        assert!(is_kept("2024-03-05/history/github.json"));
+        assert!(is_kept("2024-03-04/weekly-review.html"));
+        assert!(!is_kept("2024-03-01/hacker-news.md"));
+        assert!(!is_kept("2024-03-01/my-history.md"));
+    }
+
+    #[test]
+    fn plan_deletes_only_expired_unkept_files() {
+        let plan = plan(&listing(), date!(2024 - 04 - 10));
+        assert_eq!(
+            plan.delete,
+            [
+                "2024-03-01/hacker-news.md",
+                "2024-03-01/runs/120000-usage.json"
+            ]
+        );
+        assert_eq!(
+            plan.kept,
+            [
+                "2024-03-04/weekly-review.md",
+                "2024-03-05/history/github.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn dry_run_report_lists_the_paths() {
+        let cutoff = date!(2024 - 04 - 10);
+        let plan = plan(&listing(), cutoff);
+        assert_eq!(
+            report(&plan, cutoff, true),
+            "Would delete 2 file(s) dated before 2024-04-10 (2 kept by the keep-list)\n  \
+             2024-03-01/hacker-news.md\n  2024-03-01/runs/120000-usage.json"
+        );
+        assert!(!report(&plan, cutoff, false).contains('\n'));
+    }
+
+    #[tokio::test]
+    async fn deletes_expired_files_from_storage() {
+        let dir = std::env::temp_dir().join(format!("cution-cleanup-{}", std::process::id()));
+        let storage = SupabaseStorageClient::local(&dir);
+        for path in listing() {
+            storage
+                .upload_file(&path, "x".to_string(), "text/plain")
+                .await
+                .unwrap();
+        }
+        let crawler = CleanupCrawler {
+            storage: storage.clone(),
+            retention_days: 30,
+            summary_ttl_days: 7,
+            dry_run: false,
+        };
+        let ctx = CrawlerContext::default().with_date(date!(2024 - 05 - 10));
+        crawler.run_with_context(&ctx).await.unwrap();
+
+        let exists = |path: &str| dir.join(path).exists();
+        assert!(!exists("2024-03-01/hacker-news.md"));
+        assert!(!exists("2024-03-01/runs/120000-usage.json"));
+        assert!(exists("2024-03-04/weekly-review.md"));
+        assert!(exists("2024-03-05/history/github.json"));
+        assert!(exists("2024-05-01/hacker-news.md"));
+        assert!(exists("state/seen_urls.json"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn prunes_summaries_past_their_ttl() {
+        let dir =
+            std::env::temp_dir().join(format!("cution-cleanup-summaries-{}", std::process::id()));
+        let storage = SupabaseStorageClient::local(&dir);
+        for name in ["old", "fresh"] {
+            storage
+                .upload_file(
+                    &format!("summaries/{}.txt", name),
+                    "x".to_string(),
+                    "text/plain",
+                )
+                .await
+                .unwrap();
+        }
+        let ten_days_ago =
+            std::time::SystemTime::now() - std::time::Duration::from_secs(10 * 86_400);
+        std::fs::File::options()
+            .write(true)
+            .open(dir.join("summaries/old.txt"))
+            .unwrap()
+            .set_modified(ten_days_ago)
+            .unwrap();
+        let crawler = CleanupCrawler {
+            storage,
+            retention_days: 30,
+            summary_ttl_days: 7,
+            dry_run: false,
+        };
+        let today = time::OffsetDateTime::now_utc().date();
+        let ctx = CrawlerContext::default().with_date(today);
+        crawler.run_with_context(&ctx).await.unwrap();
+
+        assert!(!dir.join("summaries/old.txt").exists());
+        assert!(dir.join("summaries/fresh.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
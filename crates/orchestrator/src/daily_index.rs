@@ -0,0 +1,29 @@
+use anyhow::Result;
+use common::index::{build_index, objects_from_report, INDEX_FILE};
+use common::{Config, RunReport, SupabaseStorageClient};
+use tracing::{info, warn};
+
+/// Lists the run's folder and uploads `{date}/README.md` describing it and the
+/// run. If the folder cannot be listed the index is built from the paths the
+/// report says were uploaded.
+pub async fn publish(config: &Config, report: &RunReport) -> Result<String> {
+    let storage = SupabaseStorageClient::from_config(config)?;
+    let date = report.date().to_string();
+    let objects = match storage.list_objects(&date).await {
+        Ok(objects) => objects,
+        Err(e) => {
+            warn!(
+                "Failed to list {}, indexing uploaded paths only: {}",
+                date, e
+            );
+            objects_from_report(report, &date)
+        }
+    };
+
+    let path = format!("{}/{}", date, INDEX_FILE);
+    storage
+        .upload_file(&path, build_index(&objects, report), "text/markdown")
+        .await?;
+    info!("Uploaded {} ({} file(s))", path, objects.len());
+    Ok(path)
+}
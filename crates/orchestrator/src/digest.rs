@@ -0,0 +1,302 @@
+use async_trait::async_trait;
+use common::output::load_markdown;
+use common::registry::CrawlerDescriptor;
+use common::{
+    markdown, Config, Crawler, CrawlerContext, CrawlerResult, OutputFormat, Publisher, Summarizer,
+    SupabaseStorageClient, TruncatingSummarizer,
+};
+use std::sync::{Arc, OnceLock};
+use tracing::{info, warn};
+
+/// The `SOURCES` with a digest section, in order except that the Best of the
+/// Day, itself a summary of the others, leads.
+fn digest_sources() -> Vec<&'static CrawlerDescriptor> {
+    let mut sources: Vec<_> = crate::SOURCES
+        .iter()
+        .filter(|source| source.digest.is_some())
+        .collect();
+    sources.sort_by_key(|source| source.slug != best_of_day::DESCRIPTOR.slug);
+    sources
+}
+
+/// Lines of each section passed to the summarizer for the TL;DR.
+const OUTLINE_LINES: usize = 8;
+
+/// One source's markdown in the digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestSection {
+    pub title: String,
+    pub markdown: String,
+}
+
+/// Meta-crawler that concatenates the day's markdown outputs into
+/// `{date}/digest.md`, optionally headed by a one-paragraph TL;DR.
+pub struct DigestBuilder {
+    publisher: Publisher,
+    formats: Vec<OutputFormat>,
+    summarizer: Option<Arc<dyn Summarizer>>,
+}
+
+impl DigestBuilder {
+    /// With `DIGEST_TLDR` the TL;DR comes from Gemini when `GEMINI_API_KEY` is
+    /// set, and is the outline's first characters otherwise.
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        let summarizer = if config.digest_tldr {
+            Some(config.gemini_summarizer()?.unwrap_or_else(|| {
+                Arc::new(TruncatingSummarizer { max_chars: 600 }) as Arc<dyn Summarizer>
+            }))
+        } else {
+            None
+        };
+        Ok(Self {
+            publisher: Publisher::for_crawler(config, "digest")?,
+            formats: config.document_formats_for("digest"),
+            summarizer,
+        })
+    }
+
+    /// Replaces the summarizer used for the TL;DR.
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    async fn load_sections(
+        storage: &SupabaseStorageClient,
+        date: &str,
+    ) -> CrawlerResult<Vec<DigestSection>> {
+        let mut sections = Vec::new();
+        for entry in digest_sources()
+            .into_iter()
+            .filter_map(|source| source.digest)
+        {
+            let stem = format!("{}/{}", date, entry.output);
+            if let Some(markdown) = load_markdown(storage, &stem).await? {
+                sections.push(DigestSection {
+                    title: entry.title.to_string(),
+                    markdown,
+                });
+            }
+        }
+        Ok(sections)
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let date = ctx.date.to_string();
+        let publisher = ctx.publisher(&self.publisher);
+        let sections = Self::load_sections(publisher.storage(), &date).await?;
+        if sections.is_empty() {
+            info!("No markdown outputs for {}; skipping the digest", date);
+            return Ok(());
+        }
+
+        let tldr = match &self.summarizer {
+            Some(summarizer) => tldr(summarizer.as_ref(), &sections).await,
+            None => None,
+        };
+        let written = publisher
+            .publish_outputs::<(), _>(
+                &format!("{}/digest", date),
+                &self.formats,
+                || render_digest(&date, tldr.as_deref(), &sections),
+                &[],
+            )
+            .await?;
+        info!(
+            "Uploaded digest of {} section(s) to {}",
+            sections.len(),
+            written.join(", ")
+        );
+        Ok(())
+    }
+}
+
+/// The text the TL;DR is generated from: each section's title followed by its
+/// first few non-empty lines, with heading markers and table rules dropped.
+pub fn outline(sections: &[DigestSection]) -> String {
+    let mut out = String::new();
+    for section in sections {
+        out.push_str(&section.title);
+        out.push('\n');
+        section
+            .markdown
+            .lines()
+            .map(|line| line.trim().trim_start_matches('#').trim())
+            .filter(|line| !line.is_empty() && !line.trim_matches(['|', '-', ' ']).is_empty())
+            .take(OUTLINE_LINES)
+            .for_each(|line| {
+                out.push_str(line);
+                out.push('\n');
+            });
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+/// Summarizes the sections into one paragraph. Summarizer errors and empty
+/// results leave the digest without a TL;DR.
+pub async fn tldr(summarizer: &dyn Summarizer, sections: &[DigestSection]) -> Option<String> {
+    match summarizer
+        .summarize("Daily digest", &outline(sections))
+        .await
+    {
+        Ok(summary) => {
+            let paragraph = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+            (!paragraph.is_empty()).then_some(paragraph)
+        }
+        Err(e) => {
+            warn!("Omitting the digest TL;DR: {}", e);
+            None
+        }
+    }
+}
+
+/// Pushes every heading in `markdown` one level down so it nests under the
+/// section heading.
+fn demote_headings(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        if !in_code && line.starts_with('#') && !line.starts_with("######") {
+            out.push('#');
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+pub fn render_digest(date: &str, tldr: Option<&str>, sections: &[DigestSection]) -> String {
+    let mut out = markdown::section(1, &format!("Daily Digest {}", date));
+    out.push_str("\n\n");
+    if let Some(tldr) = tldr {
+        out.push_str(&format!("**TL;DR:** {}\n\n", markdown::escape(tldr)));
+    }
+    for section in sections {
+        out.push_str(&markdown::section(2, &section.title));
+        out.push_str("\n\n");
+        out.push_str(demote_headings(section.markdown.trim()).trim_end());
+        out.push_str("\n\n");
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+#[async_trait]
+impl Crawler for DigestBuilder {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Daily Digest"
+    }
+
+    fn slug(&self) -> &'static str {
+        "digest"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if !config.daily_digest {
+            return Err("DAILY_DIGEST not set".to_string());
+        }
+        Ok(())
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        static DEPENDS_ON: OnceLock<Vec<&'static str>> = OnceLock::new();
+        DEPENDS_ON.get_or_init(|| digest_sources().iter().map(|source| source.slug).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::CrawlerError;
+    use test_support::{MockSupabase, TestConfig};
+
+    struct Failing;
+
+    #[async_trait]
+    impl Summarizer for Failing {
+        async fn summarize(&self, _title: &str, _content: &str) -> CrawlerResult<String> {
+            Err(CrawlerError::Api("quota exceeded".to_string()))
+        }
+    }
+
+    fn sections() -> Vec<DigestSection> {
+        vec![
+            DigestSection {
+                title: "GitHub Trending".to_string(),
+                markdown: "# Trending\n\n| Name | Stars |\n| ---- | ----- |\n| rust | 10 |\n"
+                    .to_string(),
+            },
+            DigestSection {
+                title: "Hacker News".to_string(),
+                markdown: "## Rust 2.0\nA new edition.\n".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn every_source_with_a_section_is_read_best_of_day_first() {
+        let outputs: Vec<&str> = digest_sources()
+            .iter()
+            .filter_map(|source| source.digest)
+            .map(|entry| entry.output)
+            .collect();
+        assert_eq!(outputs[0], "best-of-day");
+        assert!(outputs.contains(&"arxiv"));
+        assert!(!outputs.contains(&"weekly-review"));
+    }
+
+    #[test]
+    fn digest_nests_sources_under_the_tldr() {
+        assert_eq!(
+            render_digest("2024-05-10", Some("Rust everywhere\ntoday."), &sections()),
+            "# Daily Digest 2024-05-10\n\n\
+             **TL;DR:** Rust everywhere today.\n\n\
+             ## GitHub Trending\n\n\
+             ## Trending\n\n\
+             | Name | Stars |\n| ---- | ----- |\n| rust | 10 |\n\n\
+             ## Hacker News\n\n\
+             ### Rust 2.0\nA new edition.\n"
+        );
+        assert_eq!(
+            outline(&sections()),
+            "GitHub Trending\nTrending\n| Name | Stars |\n| rust | 10 |\n\nHacker News\nRust 2.0\nA new edition."
+        );
+    }
+
+    #[tokio::test]
+    async fn tldr_falls_back_to_truncation_without_a_gemini_key() {
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .with(|c| {
+                c.daily_digest = true;
+                c.digest_tldr = true;
+            })
+            .build();
+        let summarizer = DigestBuilder::new(&config).unwrap().summarizer.unwrap();
+        let long = "word ".repeat(200);
+        assert_eq!(summarizer.summarize("t", &long).await.unwrap().len(), 600);
+
+        let keyed = TestConfig::new(&supabase)
+            .gemini_api_key("key")
+            .with(|c| c.digest_tldr = true)
+            .build();
+        assert!(DigestBuilder::new(&keyed).unwrap().summarizer.is_some());
+    }
+
+    #[tokio::test]
+    async fn summarizer_failure_omits_the_tldr() {
+        assert_eq!(tldr(&Failing, &sections()).await, None);
+        let summary = tldr(&TruncatingSummarizer { max_chars: 24 }, &sections()).await;
+        assert_eq!(summary.as_deref(), Some("GitHub Trending Trending"));
+        assert!(!render_digest("2024-05-10", None, &sections()).contains("TL;DR"));
+    }
+}
@@ -0,0 +1,313 @@
+//! Post-run embeddings of the day's archived items, upserted into a pgvector
+//! table for semantic search over past digests.
+
+use anyhow::Result;
+use common::archive::{archive_path, Item};
+use common::{
+    ClientFactory, Config, GeminiClient, RunReport, SupabaseStorageClient, SupabaseTableClient,
+};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+const TABLE: &str = "item_embeddings";
+/// Texts per `batchEmbedContents` request, which accepts at most 100.
+const BATCH_SIZE: usize = 100;
+/// Longest text sent for a single item.
+const MAX_TEXT_CHARS: usize = 2000;
+
+/// Items in the NDJSON `archive` seen on `date`; malformed lines are skipped.
+pub fn day_items(archive: &str, date: &str) -> Vec<Item> {
+    archive
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Item>(line).ok())
+        .filter(|item| item.date == date)
+        .collect()
+}
+
+/// The title, followed by the item's summary or description when it has one.
+pub fn embedding_text(item: &Item) -> String {
+    let detail = ["summary", "description"]
+        .iter()
+        .find_map(|key| item.raw.get(*key).and_then(Value::as_str))
+        .map(str::trim)
+        .filter(|text| !text.is_empty());
+    let text = match detail {
+        Some(detail) => format!("{}\n\n{}", item.title, detail),
+        None => item.title.clone(),
+    };
+    text.chars().take(MAX_TEXT_CHARS).collect()
+}
+
+/// pgvector's text form, e.g. `[0.5,-1]`.
+fn vector_literal(values: &[f32]) -> String {
+    let parts: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", parts.join(","))
+}
+
+/// The table row for `item`, keyed by `{source}:{date}:{id}`.
+pub fn row(item: &Item, content: &str, embedding: &[f32], model: &str) -> Value {
+    json!({
+        "id": format!("{}:{}:{}", item.source, item.date, item.id),
+        "source": item.source,
+        "date": item.date,
+        "item_id": item.id,
+        "title": item.title,
+        "url": item.url,
+        "content": content,
+        "model": model,
+        "embedding": vector_literal(embedding),
+    })
+}
+
+/// Embeds items with Gemini and upserts them in batches.
+pub struct Embedder {
+    client: GeminiClient,
+    table: SupabaseTableClient,
+    model: String,
+    dimensions: usize,
+}
+
+impl Embedder {
+    pub fn new(
+        client: GeminiClient,
+        table: SupabaseTableClient,
+        model: &str,
+        dimensions: usize,
+    ) -> Self {
+        Self {
+            client: client.with_model(model),
+            table,
+            model: model.to_string(),
+            dimensions,
+        }
+    }
+
+    /// One embedding per text, or `None` where it failed or had the wrong
+    /// length. A failed batch is retried one text at a time so a single bad
+    /// item does not lose the rest.
+    pub async fn embed_batch(&self, texts: &[String]) -> Vec<Option<Vec<f32>>> {
+        let vectors = match self.client.embed(texts).await {
+            Ok(vectors) => vectors.into_iter().map(Some).collect(),
+            Err(e) => {
+                warn!(
+                    "Embedding a batch of {} failed, retrying one by one: {}",
+                    texts.len(),
+                    e
+                );
+                let mut vectors = Vec::with_capacity(texts.len());
+                for text in texts {
+                    match self.client.embed(std::slice::from_ref(text)).await {
+                        Ok(mut single) => vectors.push(single.pop()),
+                        Err(e) => {
+                            warn!("Skipping an item that could not be embedded: {}", e);
+                            vectors.push(None);
+                        }
+                    }
+                }
+                vectors
+            }
+        };
+        vectors
+            .into_iter()
+            .map(|vector| {
+                vector.filter(|v| {
+                    let valid = v.len() == self.dimensions;
+                    if !valid {
+                        warn!(
+                            "Skipping a {}-dimensional embedding, expected {}",
+                            v.len(),
+                            self.dimensions
+                        );
+                    }
+                    valid
+                })
+            })
+            .collect()
+    }
+
+    /// Embeds and upserts `items`, returning how many rows were written.
+    pub async fn upsert_items(&self, items: &[Item]) -> usize {
+        let mut written = 0;
+        for batch in items.chunks(BATCH_SIZE) {
+            let texts: Vec<String> = batch.iter().map(embedding_text).collect();
+            let vectors = self.embed_batch(&texts).await;
+            let rows: Vec<Value> = batch
+                .iter()
+                .zip(&texts)
+                .zip(vectors)
+                .filter_map(|((item, text), vector)| {
+                    vector.map(|v| row(item, text, &v, &self.model))
+                })
+                .collect();
+            match self.table.upsert(TABLE, "id", &rows).await {
+                Ok(()) => written += rows.len(),
+                Err(e) => warn!("Failed to upsert {} embedding(s): {}", rows.len(), e),
+            }
+        }
+        written
+    }
+}
+
+/// Embeds the items archived on the run's date and returns how many rows were
+/// upserted.
+pub async fn publish(config: &Config, report: &RunReport) -> Result<usize> {
+    let api_key = config.require_gemini_api_key()?;
+    let date = report.date().to_string();
+    let Some(path) = archive_path(&date) else {
+        anyhow::bail!("No archive path for {}", date);
+    };
+    let storage = SupabaseStorageClient::from_config(config)?;
+    let Some(archive) = storage.download_file(&path).await? else {
+        info!("No {} yet; nothing to embed", path);
+        return Ok(0);
+    };
+    let items = day_items(&archive, &date);
+
+    let client = GeminiClient::new(ClientFactory::from_config(config).build()?, api_key);
+    let embedder = Embedder::new(
+        client,
+        SupabaseTableClient::from_config(config)?,
+        &config.embedding_model,
+        config.embedding_dimensions,
+    );
+    let written = embedder.upsert_items(&items).await;
+    info!(
+        "Upserted {} of {} embedding(s) into {}",
+        written,
+        items.len(),
+        TABLE
+    );
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::HttpClient;
+    use test_support::{MockGemini, MockSupabase};
+
+    fn item(id: usize) -> Item {
+        Item {
+            source: "hacker_news".to_string(),
+            date: "2024-05-10".to_string(),
+            id: id.to_string(),
+            title: format!("Story {}", id),
+            url: Some(format!("https://example.com/{}", id)),
+            score: Some(1.0),
+            raw: json!({ "summary": "A summary." }),
+        }
+    }
+
+    fn embedder(gemini: &MockGemini, supabase: &MockSupabase, dimensions: usize) -> Embedder {
+        Embedder::new(
+            gemini.client(),
+            SupabaseTableClient::new(HttpClient::new().unwrap(), &supabase.url(), "key"),
+            "text-embedding-004",
+            dimensions,
+        )
+    }
+
+    #[test]
+    fn reads_the_days_items() {
+        let archive = format!(
+            "{}\nnot json\n{}\n",
+            serde_json::to_string(&item(1)).unwrap(),
+            serde_json::to_string(&Item {
+                date: "2024-05-09".to_string(),
+                ..item(2)
+            })
+            .unwrap()
+        );
+        assert_eq!(day_items(&archive, "2024-05-10"), vec![item(1)]);
+        assert_eq!(embedding_text(&item(1)), "Story 1\n\nA summary.");
+        let bare = Item {
+            raw: json!({ "description": "  " }),
+            ..item(3)
+        };
+        assert_eq!(embedding_text(&bare), "Story 3");
+    }
+
+    #[tokio::test]
+    async fn batches_requests_and_upserts() {
+        let items: Vec<Item> = (0..150).map(item).collect();
+        let gemini = MockGemini::start().await;
+        gemini.respond_with_embeddings(3).await;
+        let supabase = MockSupabase::start().await;
+
+        let written = embedder(&gemini, &supabase, 3).upsert_items(&items).await;
+        assert_eq!(written, 150);
+
+        let gemini_requests = gemini.requests().await;
+        let sizes: Vec<usize> = gemini_requests
+            .iter()
+            .map(|r| r["requests"].as_array().unwrap().len())
+            .collect();
+        assert_eq!(sizes, vec![100, 50]);
+        assert_eq!(
+            gemini_requests[1]["requests"][0],
+            json!({
+                "model": "models/text-embedding-004",
+                "content": { "parts": [{ "text": "Story 100\n\nA summary." }] }
+            })
+        );
+
+        let upserts = supabase.upserts().await;
+        assert_eq!(upserts.len(), 2);
+        let first = &upserts[0];
+        assert_eq!(first.table, "item_embeddings");
+        assert_eq!(first.on_conflict.as_deref(), Some("id"));
+        assert_eq!(first.prefer, "resolution=merge-duplicates,return=minimal");
+        assert_eq!(first.rows.as_array().unwrap().len(), 100);
+        assert_eq!(
+            first.rows[1],
+            json!({
+                "id": "hacker_news:2024-05-10:1",
+                "source": "hacker_news",
+                "date": "2024-05-10",
+                "item_id": "1",
+                "title": "Story 1",
+                "url": "https://example.com/1",
+                "content": "Story 1\n\nA summary.",
+                "model": "text-embedding-004",
+                "embedding": "[0.5,0.5,0.5]",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_batches_fall_back_to_single_items() {
+        let items: Vec<Item> = (0..3).map(item).collect();
+        let gemini = MockGemini::start().await;
+        gemini.respond_with_embeddings(3).await;
+        // The batch fails, then so does the first item on its own.
+        gemini.fail_embeddings(500, 1).await;
+        gemini.fail_embeddings(400, 1).await;
+        let supabase = MockSupabase::start().await;
+
+        let written = embedder(&gemini, &supabase, 3).upsert_items(&items).await;
+        assert_eq!(written, 2);
+        assert_eq!(gemini.requests().await.len(), 4);
+        let upserts = supabase.upserts().await;
+        let ids: Vec<&str> = upserts[0]
+            .rows
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| row["item_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, ["1", "2"]);
+    }
+
+    #[tokio::test]
+    async fn vectors_of_the_wrong_size_are_not_written() {
+        let gemini = MockGemini::start().await;
+        gemini.respond_with_embeddings(2).await;
+        let supabase = MockSupabase::start().await;
+
+        let written = embedder(&gemini, &supabase, 3)
+            .upsert_items(&[item(0)])
+            .await;
+        assert_eq!(written, 0);
+        assert!(supabase.upserts().await.is_empty());
+    }
+}
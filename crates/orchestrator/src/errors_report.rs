@@ -0,0 +1,133 @@
+use anyhow::Result;
+use common::{Config, CrawlerOutcome, ItemError, OutputFormat, RunReport, SupabaseStorageClient};
+use serde::Serialize;
+use tracing::info;
+
+/// One crawler's entry in `errors.json`; crawlers without errors are left out.
+#[derive(Debug, Serialize)]
+struct CrawlerErrors<'a> {
+    name: &'a str,
+    /// Why the whole crawler failed, if it did.
+    error: Option<&'a str>,
+    items: &'a [ItemError],
+}
+
+fn with_errors(report: &RunReport) -> impl Iterator<Item = &CrawlerOutcome> {
+    report
+        .outcomes
+        .iter()
+        .filter(|o| o.error.is_some() || !o.item_errors.is_empty())
+}
+
+fn render_json(date: &str, report: &RunReport) -> Result<String> {
+    let crawlers: Vec<_> = with_errors(report)
+        .map(|o| CrawlerErrors {
+            name: &o.name,
+            error: o.error.as_deref(),
+            items: &o.item_errors,
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(
+        &serde_json::json!({ "date": date, "crawlers": crawlers }),
+    )?)
+}
+
+fn render_markdown(date: &str, report: &RunReport) -> String {
+    let mut out = format!("# Errors for {}\n", date);
+    let mut any = false;
+    for outcome in with_errors(report) {
+        any = true;
+        out.push_str(&format!("\n## {}\n\n", outcome.name));
+        if let Some(error) = &outcome.error {
+            out.push_str(&format!("**Failed:** {}\n\n", error));
+        }
+        for item in &outcome.item_errors {
+            out.push_str(&format!("- {}: {}\n", item.item, item.error));
+        }
+    }
+    if !any {
+        out.push_str("\nNo errors in this run.\n");
+    }
+    out
+}
+
+/// Uploads `{date}/errors.md` and/or `errors.json` listing the crawlers that
+/// failed and the items each one skipped. Written even for a clean run, so a
+/// rerun replaces an earlier run's list.
+pub async fn publish(config: &Config, report: &RunReport) -> Result<Vec<String>> {
+    let storage = SupabaseStorageClient::from_config(config)?;
+    let date = report.date().to_string();
+    let mut paths = Vec::new();
+    for format in &config.errors_file {
+        let body = match format {
+            OutputFormat::Json => render_json(&date, report)?,
+            _ => render_markdown(&date, report),
+        };
+        let path = format!("{}/errors.{}", date, format.extension());
+        storage
+            .upload_file(&path, body, format.content_type())
+            .await?;
+        paths.push(path);
+    }
+    let items: usize = report.outcomes.iter().map(|o| o.item_errors.len()).sum();
+    info!(
+        "Uploaded {} ({} failed crawler(s), {} item error(s))",
+        paths.join(", "),
+        report.error_count(),
+        items
+    );
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(name: &str, error: Option<&str>, items: &[(&str, &str)]) -> CrawlerOutcome {
+        CrawlerOutcome {
+            error: error.map(str::to_string),
+            item_errors: items
+                .iter()
+                .map(|(item, error)| ItemError {
+                    item: item.to_string(),
+                    error: error.to_string(),
+                })
+                .collect(),
+            ..test_support::outcome(name)
+        }
+    }
+
+    fn report() -> RunReport {
+        RunReport {
+            outcomes: vec![
+                outcome("GitHub Trending", None, &[]),
+                outcome("Hacker News", None, &[("story 42", "HTTP 503")]),
+                outcome("OpenRouter", Some("timeout"), &[]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn markdown_lists_failed_crawlers_and_items() {
+        assert_eq!(
+            render_markdown("2024-05-10", &report()),
+            "# Errors for 2024-05-10\n\n\
+             ## Hacker News\n\n- story 42: HTTP 503\n\n\
+             ## OpenRouter\n\n**Failed:** timeout\n\n"
+        );
+        assert!(render_markdown("2024-05-10", &RunReport::default())
+            .ends_with("No errors in this run.\n"));
+    }
+
+    #[test]
+    fn json_skips_crawlers_without_errors() {
+        let json: serde_json::Value =
+            serde_json::from_str(&render_json("2024-05-10", &report()).unwrap()).unwrap();
+        let crawlers = json["crawlers"].as_array().unwrap();
+        assert_eq!(crawlers.len(), 2);
+        assert_eq!(crawlers[0]["items"][0]["item"], "story 42");
+        assert_eq!(crawlers[1]["error"], "timeout");
+        assert_eq!(crawlers[1]["items"], serde_json::json!([]));
+    }
+}
@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use common::index::{display_title, split_output_name};
+use common::{Config, Crawler, CrawlerContext, CrawlerError, CrawlerResult, SupabaseStorageClient};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+pub const INDEX_PATH: &str = "index.html";
+/// Most recent date folders listed on the page.
+const RECENT_DAYS: usize = 14;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:48rem;margin:2rem auto;padding:0 1rem;line-height:1.5}\
+h2{border-bottom:1px solid #ddd;padding-bottom:.25rem}a{color:#0366d6}";
+
+/// The files found in one `{date}/` folder.
+#[derive(Debug, Clone)]
+pub struct DayListing {
+    pub date: String,
+    pub files: Vec<String>,
+}
+
+/// Uploads a static `index.html` linking the recent outputs in the bucket, so
+/// it can be browsed through the bucket's public URL.
+pub struct HtmlIndexBuilder {
+    storage: SupabaseStorageClient,
+}
+
+impl HtmlIndexBuilder {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self {
+            storage: SupabaseStorageClient::from_config(config)?,
+        })
+    }
+
+    async fn list_days(storage: &SupabaseStorageClient) -> CrawlerResult<Vec<DayListing>> {
+        let mut dates: Vec<String> = storage
+            .list_files("")
+            .await
+            .map_err(|e| CrawlerError::Api(e.to_string()))?
+            .into_iter()
+            .filter(|name| is_date(name))
+            .collect();
+        dates.sort_unstable_by(|a, b| b.cmp(a));
+        dates.truncate(RECENT_DAYS);
+
+        let mut days = Vec::new();
+        for date in dates {
+            match storage.list_files(&date).await {
+                Ok(files) => days.push(DayListing { date, files }),
+                Err(e) => warn!("Failed to list {}: {}", date, e),
+            }
+        }
+        Ok(days)
+    }
+}
+
+#[async_trait]
+impl Crawler for HtmlIndexBuilder {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let storage = ctx.storage_client(&self.storage);
+        let days = Self::list_days(&storage).await?;
+        info!("Writing {} with {} day(s)", INDEX_PATH, days.len());
+        storage
+            .upload_file(INDEX_PATH, render_index(&days), "text/html; charset=utf-8")
+            .await
+            .map_err(|e| CrawlerError::StorageUpload(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "HTML Index"
+    }
+
+    fn slug(&self) -> &'static str {
+        "html_index"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if !config.generate_html_index {
+            return Err("GENERATE_HTML_INDEX not set".to_string());
+        }
+        Ok(())
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        static DEPENDS_ON: OnceLock<Vec<&'static str>> = OnceLock::new();
+        DEPENDS_ON.get_or_init(|| crate::source_slugs_and(&["digest"]))
+    }
+}
+
+fn is_date(name: &str) -> bool {
+    name.len() == 10
+        && name.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the index page: one section per day, one line per crawler output
+/// with a link for each format it was written in.
+pub fn render_index(days: &[DayListing]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>Cution</title>\n<style>{}</style>\n</head>\n<body>\n<h1>Cution</h1>\n",
+        STYLE
+    );
+    if days.is_empty() {
+        html.push_str("<p>No outputs yet.</p>\n");
+    }
+    for day in days {
+        let mut outputs: BTreeMap<&str, Vec<(&str, &str)>> = BTreeMap::new();
+        for file in &day.files {
+            if let Some((stem, ext)) = split_output_name(file) {
+                outputs.entry(stem).or_default().push((ext, file.as_str()));
+            }
+        }
+        if outputs.is_empty() {
+            continue;
+        }
+        html.push_str(&format!("<h2>{}</h2>\n<ul>\n", escape_html(&day.date)));
+        for (stem, mut files) in outputs {
+            files.sort_unstable();
+            let links = files
+                .iter()
+                .map(|(ext, file)| {
+                    format!(
+                        "<a href=\"{}/{}\">{}</a>",
+                        escape_html(&day.date),
+                        escape_html(file),
+                        escape_html(ext)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" · ");
+            html.push_str(&format!(
+                "<li><strong>{}</strong> {}</li>\n",
+                escape_html(&display_title(stem)),
+                links
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_formats_by_crawler_and_skips_archive_hashes() {
+        let days = vec![DayListing {
+            date: "2024-05-10".to_string(),
+            files: vec![
+                "hacker-news.md".to_string(),
+                "github-trending-latest.md".to_string(),
+                "github-trending-0123456789ab.md".to_string(),
+                "hacker-news.json".to_string(),
+                "rust".to_string(),
+            ],
+        }];
+        let html = render_index(&days);
+        let body = &html[html.find("<h1>").unwrap()..];
+        assert_eq!(
+            body,
+            "<h1>Cution</h1>\n<h2>2024-05-10</h2>\n<ul>\n\
+             <li><strong>GitHub Trending</strong> <a href=\"2024-05-10/github-trending-latest.md\">md</a></li>\n\
+             <li><strong>Hacker News</strong> <a href=\"2024-05-10/hacker-news.json\">json</a> · <a href=\"2024-05-10/hacker-news.md\">md</a></li>\n\
+             </ul>\n</body>\n</html>\n"
+        );
+    }
+
+    #[test]
+    fn only_date_folders_are_days() {
+        assert!(is_date("2024-05-10"));
+        assert!(!is_date("state"));
+        assert!(!is_date("index.html"));
+        assert!(!is_date("2024_05_10"));
+    }
+}
@@ -0,0 +1,331 @@
+pub mod checksums;
+pub mod cleanup;
+pub mod daily_index;
+pub mod digest;
+pub mod embeddings;
+pub mod errors_report;
+pub mod html_index;
+pub mod request_log;
+pub mod staleness;
+pub mod translate;
+pub mod usage_report;
+
+use anyhow::Result;
+use common::registry::CrawlerDescriptor;
+use common::usage;
+use common::{Config, Crawler, CrawlerContext, CrawlerManager, CrawlerResult, RunReport};
+use tracing::{info, warn};
+
+/// The source crates' crawlers, in registration order.
+pub const SOURCES: &[CrawlerDescriptor] = &[
+    github::DESCRIPTOR,
+    hacker_news::DESCRIPTOR,
+    lobsters::DESCRIPTOR,
+    product_hunt::DESCRIPTOR,
+    reddit::DESCRIPTOR,
+    arxiv::DESCRIPTOR,
+    papers_with_code::DESCRIPTOR,
+    devto::DESCRIPTOR,
+    feeds::DESCRIPTOR,
+    registries::DESCRIPTOR,
+    youtube::DESCRIPTOR,
+    fediverse::DESCRIPTOR,
+    xai_search::DESCRIPTOR,
+    custom_site::DESCRIPTOR,
+    openrouter::DESCRIPTOR,
+    mcp_rankings::DESCRIPTOR,
+    best_of_day::DESCRIPTOR,
+    rollup::DESCRIPTOR,
+];
+
+/// The slugs of `SOURCES` followed by `extra`, for a meta-crawler that runs
+/// after every source.
+pub(crate) fn source_slugs_and(extra: &[&'static str]) -> Vec<&'static str> {
+    SOURCES
+        .iter()
+        .map(|source| source.slug)
+        .chain(extra.iter().copied())
+        .collect()
+}
+
+/// Registers every crawler whose `precheck` passes, logging why the others
+/// were skipped.
+pub fn build_manager(config: &Config) -> CrawlerManager {
+    if config.audit_http {
+        request_log::enable();
+    }
+
+    // Meta-crawlers come last; the manager runs them after the sources they read
+    SOURCES
+        .iter()
+        .fold(Registry::new(config), Registry::add_source)
+        .add("daily digest", digest::DigestBuilder::new)
+        .add("HTML index", html_index::HtmlIndexBuilder::new)
+        .add("retention cleanup", cleanup::CleanupCrawler::new)
+        .manager
+}
+
+/// The context every crawler runs with: summaries come from Gemini when
+/// `GEMINI_API_KEY` is set and are truncated excerpts otherwise.
+pub fn run_context(config: &Config) -> CrawlerContext {
+    let context = CrawlerContext::default();
+    match config.gemini_summarizer() {
+        Ok(Some(summarizer)) => context.with_summarizer(summarizer),
+        Ok(None) => context,
+        Err(e) => {
+            warn!(
+                "Failed to create the Gemini summarizer, truncating instead: {}",
+                e
+            );
+            context
+        }
+    }
+}
+
+/// Adds crawlers to a manager only when their `precheck` passes.
+struct Registry<'a> {
+    config: &'a Config,
+    manager: CrawlerManager,
+}
+
+impl<'a> Registry<'a> {
+    fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            manager: CrawlerManager::new()
+                .with_crawler_timeout(config.crawler_timeout.unwrap_or_default())
+                .with_context(run_context(config)),
+        }
+    }
+
+    fn add_source(mut self, source: &CrawlerDescriptor) -> Self {
+        if let Err(reason) = (source.precheck)(self.config) {
+            info!("Skipping {}: {}", source.name, reason);
+            return self;
+        }
+        match (source.build)(self.config) {
+            Ok(Some(crawler)) => self.manager = self.manager.add_crawler(crawler),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to create {}: {}", source.name, e),
+        }
+        self
+    }
+
+    fn add<C, F>(mut self, label: &str, build: F) -> Self
+    where
+        C: Crawler + 'static,
+        F: FnOnce(&Config) -> CrawlerResult<C>,
+    {
+        if let Err(reason) = C::precheck(self.config) {
+            info!("Skipping {}: {}", label, reason);
+            return self;
+        }
+        match build(self.config) {
+            Ok(crawler) => self.manager = self.manager.add_crawler(Box::new(crawler)),
+            Err(e) => warn!("Failed to create {}: {}", label, e),
+        }
+        self
+    }
+}
+
+/// Runs all configured crawlers in-process and returns the per-crawler report.
+///
+/// Crawler failures are recorded in the report rather than returned as an error;
+/// callers decide whether a partial failure is fatal.
+pub async fn run(config: &Config) -> Result<RunReport> {
+    before_run(config).await;
+    let manager = build_manager(config);
+    let report = manager.run_with_report().await;
+    after_run(config, &report).await;
+    Ok(report)
+}
+
+/// Steps that must happen before any crawler runs. Failures are logged.
+pub async fn before_run(config: &Config) {
+    if let Err(e) = usage_report::prepare(config).await {
+        warn!("Failed to load this month's LLM spending: {}", e);
+    }
+}
+
+/// Steps that need the finished run's report. Failures are logged, never
+/// returned, so they cannot fail an otherwise successful run.
+pub async fn after_run(config: &Config, report: &RunReport) {
+    if config.daily_index {
+        if let Err(e) = daily_index::publish(config, report).await {
+            warn!("Failed to publish daily index: {}", e);
+        }
+    }
+    if let Some(days) = config.stale_after_days {
+        match staleness::check(config, report, days).await {
+            Ok(stale) => staleness::report_stale(&stale),
+            Err(e) => warn!("Failed to check for stale outputs: {}", e),
+        }
+    }
+    if config.embeddings {
+        if let Err(e) = usage::attribute("embeddings", embeddings::publish(config, report)).await {
+            warn!("Failed to embed archived items: {}", e);
+        }
+    }
+    if let Some(language) = &config.translate_to {
+        if let Err(e) =
+            usage::attribute("translate", translate::publish(config, report, language)).await
+        {
+            warn!("Failed to translate outputs: {}", e);
+        }
+    }
+    if config.checksums {
+        if let Err(e) = checksums::publish(config, report).await {
+            warn!("Failed to publish checksums: {}", e);
+        }
+    }
+    if !config.errors_file.is_empty() {
+        if let Err(e) = errors_report::publish(config, report).await {
+            warn!("Failed to publish the errors file: {}", e);
+        }
+    }
+    // After the LLM-using steps, so their calls are counted
+    publish_run_logs(config).await;
+}
+
+/// The settings turning on `after_run` steps, by environment variable. These
+/// steps need the whole run's report, so per-crawler schedules skip them.
+pub fn after_run_settings(config: &Config) -> Vec<&'static str> {
+    [
+        (config.daily_index, "DAILY_INDEX"),
+        (config.stale_after_days.is_some(), "STALE_AFTER_DAYS"),
+        (config.embeddings, "CUTION_EMBEDDINGS"),
+        (config.translate_to.is_some(), "CUTION_TRANSLATE_TO"),
+        (config.checksums, "CUTION_CHECKSUMS"),
+        (!config.errors_file.is_empty(), "CUTION_ERRORS_FILE"),
+    ]
+    .into_iter()
+    .filter_map(|(on, key)| on.then_some(key))
+    .collect()
+}
+
+/// Uploads the run's LLM usage and, with `CUTION_AUDIT_HTTP`, its request
+/// log, draining both for the next run. Runs that only execute some crawlers
+/// call this instead of `after_run`.
+pub async fn publish_run_logs(config: &Config) {
+    if let Err(e) = usage_report::publish(config).await {
+        warn!("Failed to publish LLM usage: {}", e);
+    }
+    // Last, so the requests of the steps above are included
+    if config.audit_http {
+        if let Err(e) = request_log::publish(config).await {
+            warn!("Failed to upload the request log: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{MockSupabase, TestConfig};
+
+    #[test]
+    fn every_source_is_registered() {
+        let slugs: Vec<_> = SOURCES.iter().map(|s| s.slug).collect();
+        assert_eq!(
+            slugs,
+            [
+                "github",
+                "hacker_news",
+                "lobsters",
+                "product_hunt",
+                "reddit",
+                "arxiv",
+                "papers_with_code",
+                "devto",
+                "feeds",
+                "registries",
+                "youtube",
+                "fediverse",
+                "xai_search",
+                "custom_site",
+                "openrouter",
+                "mcp_rankings",
+                "best_of_day",
+                "weekly_rollup"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn after_run_settings_lists_the_enabled_steps() {
+        let supabase = MockSupabase::start().await;
+        assert!(after_run_settings(&TestConfig::new(&supabase).build()).is_empty());
+        let config = TestConfig::new(&supabase)
+            .with(|c| {
+                c.daily_index = true;
+                c.checksums = true;
+            })
+            .build();
+        assert_eq!(
+            after_run_settings(&config),
+            ["DAILY_INDEX", "CUTION_CHECKSUMS"]
+        );
+    }
+
+    #[tokio::test]
+    async fn crawlers_summarize_by_truncation_without_a_gemini_key() {
+        let supabase = MockSupabase::start().await;
+        let context = run_context(&TestConfig::new(&supabase).build());
+        let long = "word ".repeat(100);
+        assert_eq!(
+            context
+                .summarizer
+                .summarize("t", &long)
+                .await
+                .unwrap()
+                .len(),
+            200
+        );
+    }
+
+    #[tokio::test]
+    async fn gating_follows_the_required_env() {
+        let supabase = MockSupabase::start().await;
+        let bare = TestConfig::new(&supabase).build();
+        for source in SOURCES {
+            let built = (source.build)(&bare).unwrap();
+            assert_eq!(
+                built.is_none(),
+                !source.required_env.is_empty(),
+                "{}",
+                source.slug
+            );
+            if let Err(reason) = (source.precheck)(&bare) {
+                for env in source.required_env {
+                    assert!(reason.contains(env), "{}: {}", source.slug, reason);
+                }
+            }
+        }
+
+        let full = TestConfig::new(&supabase)
+            .languages(&["rust"])
+            .gemini_api_key("key")
+            .with(|c| {
+                c.product_hunt_token = Some("token".to_string());
+                c.reddit_subreddits = vec!["rust".to_string()];
+                c.best_of_day = true;
+                c.weekly_rollup = true;
+                c.lobsters_enabled = true;
+                c.papers_with_code = true;
+                c.devto_enabled = true;
+                c.feeds_opml = Some("subscriptions.opml".to_string());
+                c.registries_enabled = true;
+                c.youtube_channels = vec!["UCaYhcUwRBNscFNUKTjgPFiA".to_string()];
+                c.arxiv_ids = vec!["2401.01234".to_string()];
+                c.fediverse_enabled = true;
+                c.fediverse_keywords = vec!["llm".to_string()];
+                c.xai_api_key = Some("xai-key".to_string());
+                c.custom_site_urls = vec!["https://example.com".to_string()];
+            })
+            .build();
+        for source in SOURCES {
+            let crawler = (source.build)(&full).unwrap().expect(source.slug);
+            assert_eq!(crawler.slug(), source.slug);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use common::audit::AuditLog;
+use common::{Config, SupabaseStorageClient};
+use time::{OffsetDateTime, Time};
+use tracing::info;
+
+/// Turns on the process-wide request log; nothing is recorded until then.
+pub fn enable() {
+    AuditLog::global().enable();
+}
+
+/// Drains the request log into `{date}/runs/{run_id}-requests.json`, where the
+/// run id is the UTC time the run finished.
+pub async fn publish(config: &Config) -> Result<String> {
+    let snapshot = AuditLog::global().take();
+    let now = OffsetDateTime::now_utc();
+    let path = run_path(&now.date().to_string(), now.time(), "requests");
+    let body = serde_json::to_string_pretty(&snapshot)?;
+    SupabaseStorageClient::from_config(config)?
+        .upload_file(&path, body, "application/json")
+        .await?;
+    info!(
+        "Uploaded {} ({} request(s), {} dropped)",
+        path,
+        snapshot.requests.len(),
+        snapshot.dropped
+    );
+    Ok(path)
+}
+
+/// `{date}/runs/{run_id}-{kind}.json`, where the run id is the finish time.
+pub(crate) fn run_path(date: &str, finished: Time, kind: &str) -> String {
+    format!(
+        "{}/runs/{:02}{:02}{:02}-{}.json",
+        date,
+        finished.hour(),
+        finished.minute(),
+        finished.second(),
+        kind
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_is_named_after_the_finish_time() {
+        let finished = Time::from_hms(9, 5, 30).unwrap();
+        assert_eq!(
+            run_path("2024-05-10", finished, "requests"),
+            "2024-05-10/runs/090530-requests.json"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use common::publish::content_hash;
+use common::{Config, CrawlerResult, RunReport, SupabaseStorageClient};
+use time::{Date, Duration, OffsetDateTime};
+use tracing::{debug, warn};
+
+/// A file whose content matched the same file in each of the previous days.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleOutput {
+    pub crawler: String,
+    pub file: String,
+    /// Consecutive previous days with identical content.
+    pub days: usize,
+}
+
+/// The files each successful crawler wrote under `{today}/`, as
+/// (crawler, file name). `-latest` copies are skipped: in archive mode the
+/// hashed file next to them holds the same content.
+pub fn checked_files(report: &RunReport, today: &str) -> Vec<(String, String)> {
+    let prefix = format!("{}/", today);
+    let mut files = Vec::new();
+    for outcome in report.outcomes.iter().filter(|o| o.is_success()) {
+        for path in &outcome.uploaded {
+            let Some(file) = path.strip_prefix(&prefix) else {
+                continue;
+            };
+            let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+            if !stem.ends_with("-latest") {
+                files.push((outcome.name.clone(), file.to_string()));
+            }
+        }
+    }
+    files
+}
+
+/// How many of the `previous` hashes (most recent first) match `today` before
+/// the first missing or different one.
+pub fn unchanged_days(today: &str, previous: &[Option<String>]) -> usize {
+    previous
+        .iter()
+        .take_while(|hash| hash.as_deref() == Some(today))
+        .count()
+}
+
+/// Compares each file written today with the same file on the previous
+/// `max_days` days and returns those identical on all of them.
+pub async fn check(
+    config: &Config,
+    report: &RunReport,
+    max_days: usize,
+) -> CrawlerResult<Vec<StaleOutput>> {
+    let storage = SupabaseStorageClient::from_config(config)?;
+    let today = OffsetDateTime::now_utc().date();
+    let mut stale = Vec::new();
+    for (crawler, file) in checked_files(report, &today.to_string()) {
+        let Some(current) = download_hash(&storage, today, &file).await else {
+            continue;
+        };
+        let mut previous = Vec::with_capacity(max_days);
+        for days_ago in 1..=max_days as i64 {
+            let hash = download_hash(&storage, today - Duration::days(days_ago), &file).await;
+            let stop = hash.as_deref() != Some(current.as_str());
+            previous.push(hash);
+            if stop {
+                break;
+            }
+        }
+        let days = unchanged_days(&current, &previous);
+        if days >= max_days {
+            stale.push(StaleOutput {
+                crawler,
+                file,
+                days,
+            });
+        }
+    }
+    Ok(stale)
+}
+
+/// Hash of `content` without the lines naming `date`, so headings and
+/// timestamps that change every day do not hide unchanged data.
+pub fn dateless_hash(content: &str, date: Date) -> String {
+    let date = date.to_string();
+    let kept: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.contains(&date))
+        .collect();
+    content_hash(&kept.join("\n"))
+}
+
+async fn download_hash(storage: &SupabaseStorageClient, date: Date, file: &str) -> Option<String> {
+    let path = format!("{}/{}", date, file);
+    match storage.download_file(&path).await {
+        Ok(content) => content.map(|content| dateless_hash(&content, date)),
+        Err(e) => {
+            debug!("Could not read {} for the staleness check: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Logs a warning for every stale output.
+pub fn report_stale(stale: &[StaleOutput]) {
+    for output in stale {
+        warn!(
+            "{} looks stale: {} has not changed for {} day(s)",
+            output.crawler, output.file, output.days
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::CrawlerOutcome;
+    use time::macros::date;
+
+    #[test]
+    fn counts_consecutive_matching_days() {
+        let same = Some("abc".to_string());
+        let other = Some("def".to_string());
+        assert_eq!(
+            unchanged_days("abc", &[same.clone(), same.clone(), same.clone()]),
+            3
+        );
+        assert_eq!(
+            unchanged_days("abc", &[same.clone(), None, same.clone()]),
+            1
+        );
+        assert_eq!(unchanged_days("abc", &[other, same]), 0);
+        assert_eq!(unchanged_days("abc", &[]), 0);
+    }
+
+    #[test]
+    fn dated_lines_do_not_count_as_changes() {
+        let day = |date: Date, body: &str| {
+            dateless_hash(
+                &format!(
+                    "# Daily Digest {}\n\n{}\nFetched at {}T06:00:00Z",
+                    date, body, date
+                ),
+                date,
+            )
+        };
+        let (first, next) = (date!(2024 - 05 - 09), date!(2024 - 05 - 10));
+        assert_eq!(day(first, "| rust | 10 |"), day(next, "| rust | 10 |"));
+        assert_ne!(day(first, "| rust | 10 |"), day(next, "| rust | 11 |"));
+    }
+
+    #[test]
+    fn checks_todays_files_from_successful_crawlers() {
+        let outcome = |name: &str, error: Option<&str>, uploaded: &[&str]| CrawlerOutcome {
+            name: name.to_string(),
+            duration: std::time::Duration::ZERO,
+            error: error.map(str::to_string),
+            uploaded: uploaded.iter().map(|p| p.to_string()).collect(),
+            unchanged: vec![],
+            checksums: vec![],
+            item_errors: vec![],
+        };
+        let report = RunReport {
+            outcomes: vec![
+                outcome(
+                    "GitHub Trending",
+                    None,
+                    &[
+                        "2024-05-10/github-trending-0123456789ab.md",
+                        "2024-05-10/github-trending-latest.md",
+                        "weekly/2024-05-06/github.md",
+                    ],
+                ),
+                outcome("Hacker News", Some("boom"), &["2024-05-10/hacker-news.md"]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            checked_files(&report, "2024-05-10"),
+            vec![(
+                "GitHub Trending".to_string(),
+                "github-trending-0123456789ab.md".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! Post-run translation of markdown outputs into `{date}/{stem}.{lang}.md`.
+//!
+//! Files are split on their `---` rules so each request stays small, and
+//! everything that must survive verbatim (fenced and inline code, link
+//! targets, bare URLs) is swapped for a `⟦n⟧` placeholder before a section is
+//! sent and put back afterwards.
+
+use anyhow::Result;
+use common::output::load_markdown;
+use common::{
+    ClientFactory, Config, CrawlerResult, GeminiClient, RunReport, SupabaseStorageClient,
+    Translator,
+};
+use tracing::{info, warn};
+
+/// The stem of `source`'s markdown output, taken from its digest entry in
+/// `SOURCES`.
+pub fn file_stem(source: &str) -> Option<&'static str> {
+    crate::SOURCES
+        .iter()
+        .find(|descriptor| descriptor.slug == source)
+        .and_then(|descriptor| descriptor.digest)
+        .map(|entry| entry.output)
+}
+
+/// Markdown up to a `---` rule, and the rule line itself (empty after the
+/// last section).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub body: String,
+    pub separator: String,
+}
+
+/// Tracks whether a line is inside a fenced code block.
+#[derive(Default)]
+struct Fence(Option<String>);
+
+impl Fence {
+    /// Feeds `line` and returns whether it belongs to a code block, counting
+    /// the opening and closing fences.
+    fn consume(&mut self, line: &str) -> bool {
+        let trimmed = line.trim();
+        match &self.0 {
+            Some(marker) => {
+                if trimmed.starts_with(marker.as_str())
+                    && trimmed
+                        .trim_start_matches(marker.as_str())
+                        .trim()
+                        .is_empty()
+                {
+                    self.0 = None;
+                }
+                true
+            }
+            None => {
+                for ch in ['`', '~'] {
+                    let run = trimmed.chars().take_while(|c| *c == ch).count();
+                    if run >= 3 {
+                        self.0 = Some(ch.to_string().repeat(run));
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+/// Splits `markdown` on lines that are exactly `---`, ignoring any inside
+/// fenced code. `join_sections` reverses it byte for byte.
+pub fn split_sections(markdown: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut body = String::new();
+    let mut fence = Fence::default();
+    for line in markdown.split_inclusive('\n') {
+        let in_code = fence.consume(line);
+        if !in_code && line.trim_end() == "---" {
+            sections.push(Section {
+                body: std::mem::take(&mut body),
+                separator: line.to_string(),
+            });
+        } else {
+            body.push_str(line);
+        }
+    }
+    sections.push(Section {
+        body,
+        separator: String::new(),
+    });
+    sections
+}
+
+pub fn join_sections(sections: &[Section]) -> String {
+    sections
+        .iter()
+        .flat_map(|s| [s.body.as_str(), s.separator.as_str()])
+        .collect()
+}
+
+fn placeholder(index: usize) -> String {
+    format!("⟦{}⟧", index)
+}
+
+/// Text with its untranslatable spans replaced by placeholders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Protected {
+    pub text: String,
+    pub spans: Vec<String>,
+}
+
+impl Protected {
+    fn hold(&mut self, span: &str) {
+        self.text.push_str(&placeholder(self.spans.len()));
+        self.spans.push(span.to_string());
+    }
+
+    /// Whether anything besides placeholders, whitespace and punctuation is left.
+    pub fn has_text(&self) -> bool {
+        let mut rest = self.text.clone();
+        for index in 0..self.spans.len() {
+            rest = rest.replace(&placeholder(index), "");
+        }
+        rest.chars().any(char::is_alphabetic)
+    }
+}
+
+/// Replaces fenced code blocks, inline code, link targets, autolinks and bare
+/// URLs with placeholders.
+pub fn protect(text: &str) -> Protected {
+    let mut out = Protected {
+        text: String::new(),
+        spans: Vec::new(),
+    };
+    let mut fence = Fence::default();
+    let mut block = String::new();
+    for line in text.split_inclusive('\n') {
+        let was_open = fence.is_open();
+        if fence.consume(line) {
+            block.push_str(line);
+            if was_open && !fence.is_open() {
+                flush_block(&mut out, &mut block);
+            }
+            continue;
+        }
+        protect_line(&mut out, line);
+    }
+    if !block.is_empty() {
+        flush_block(&mut out, &mut block);
+    }
+    out
+}
+
+/// Holds a code block, keeping its final newline outside the placeholder.
+fn flush_block(out: &mut Protected, block: &mut String) {
+    let code = std::mem::take(block);
+    let trimmed = code.strip_suffix('\n').unwrap_or(&code);
+    out.hold(trimmed);
+    if trimmed.len() < code.len() {
+        out.text.push('\n');
+    }
+}
+
+fn protect_line(out: &mut Protected, line: &str) {
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &line[i..];
+        if rest.starts_with('`') {
+            let ticks = rest.chars().take_while(|c| *c == '`').count();
+            let marker = &rest[..ticks];
+            if let Some(end) = rest[ticks..].find(marker) {
+                let len = ticks + end + ticks;
+                out.hold(&rest[..len]);
+                i += len;
+                continue;
+            }
+        } else if let Some(target) = rest.strip_prefix("](") {
+            if let Some(len) = link_target_len(target) {
+                out.text.push_str("](");
+                out.hold(&target[..len]);
+                out.text.push(')');
+                i += 2 + len + 1;
+                continue;
+            }
+        } else if rest.starts_with("<http") {
+            if let Some(end) = rest.find('>') {
+                out.hold(&rest[..=end]);
+                i += end + 1;
+                continue;
+            }
+        } else if (rest.starts_with("http://") || rest.starts_with("https://"))
+            && !line[..i].ends_with(|c: char| c.is_alphanumeric())
+        {
+            let len = bare_url_len(rest);
+            out.hold(&rest[..len]);
+            i += len;
+            continue;
+        }
+        let ch = rest.chars().next().expect("non-empty");
+        out.text.push(ch);
+        i += ch.len_utf8();
+    }
+}
+
+/// Length of a link target up to its unbalanced closing `)`.
+fn link_target_len(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, ch) in s.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            '\n' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn bare_url_len(s: &str) -> usize {
+    let end = s
+        .find(|c: char| c.is_whitespace() || matches!(c, ')' | ']' | '>' | '|'))
+        .unwrap_or(s.len());
+    s[..end]
+        .trim_end_matches(['.', ',', ';', ':', '!', '?'])
+        .len()
+}
+
+/// Puts the spans back, or `None` if the translation lost or repeated a
+/// placeholder.
+pub fn restore(text: &str, spans: &[String]) -> Option<String> {
+    let mut out = text.to_string();
+    for (index, span) in spans.iter().enumerate() {
+        let marker = placeholder(index);
+        if out.matches(&marker).count() != 1 {
+            return None;
+        }
+        out = out.replacen(&marker, span, 1);
+    }
+    Some(out)
+}
+
+/// Translates one section, keeping its surrounding whitespace. A translation
+/// that mangles the placeholders leaves the section as it was.
+async fn translate_section(
+    translator: &dyn Translator,
+    body: &str,
+    language: &str,
+) -> CrawlerResult<String> {
+    let core = body.trim();
+    let protected = protect(core);
+    if !protected.has_text() {
+        return Ok(body.to_string());
+    }
+    let translated = translator.translate(&protected.text, language).await?;
+    let Some(restored) = restore(translated.trim(), &protected.spans) else {
+        warn!("Translation dropped protected content; keeping a section untranslated");
+        return Ok(body.to_string());
+    };
+    let start = body.len() - body.trim_start().len();
+    let end = body.trim_end().len();
+    Ok(format!("{}{}{}", &body[..start], restored, &body[end..]))
+}
+
+/// Translates `markdown` section by section and reassembles it.
+pub async fn translate_markdown(
+    translator: &dyn Translator,
+    markdown: &str,
+    language: &str,
+) -> CrawlerResult<String> {
+    let mut sections = split_sections(markdown);
+    for section in &mut sections {
+        section.body = translate_section(translator, &section.body, language).await?;
+    }
+    Ok(join_sections(&sections))
+}
+
+/// Translates the run's output of each `CUTION_TRANSLATE_SOURCES` crawler and
+/// returns the uploaded paths. A source that fails is logged and skipped.
+pub async fn publish(config: &Config, report: &RunReport, language: &str) -> Result<Vec<String>> {
+    let api_key = config.require_gemini_api_key()?;
+    let client = ClientFactory::from_config(config).build()?;
+    let translator = GeminiClient::new(client, api_key);
+    let storage = SupabaseStorageClient::from_config(config)?;
+    let date = report.date().to_string();
+
+    let mut uploaded = Vec::new();
+    for source in &config.translate_sources {
+        let Some(stem) = file_stem(source) else {
+            warn!(
+                "No markdown output known for '{}'; not translating it",
+                source
+            );
+            continue;
+        };
+        let path = format!("{}/{}.md", date, stem);
+        let Some(markdown) = load_markdown(&storage, &format!("{}/{}", date, stem)).await? else {
+            info!("No {} for {}; nothing to translate", path, source);
+            continue;
+        };
+
+        let translated = match translate_markdown(&translator, &markdown, language).await {
+            Ok(translated) => translated,
+            Err(e) => {
+                warn!("Failed to translate {}: {}", path, e);
+                continue;
+            }
+        };
+        let target = format!("{}/{}.{}.md", date, stem, language);
+        storage
+            .upload_file(&target, translated, "text/markdown")
+            .await?;
+        info!("Uploaded {}", target);
+        uploaded.push(target);
+    }
+    Ok(uploaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    const MIXED: &str = "# Trending\n\nSee [the repo](https://github.com/a/b_(c)) and `cargo run`.\n\n---\n\n```rust\nfn main() {}\n---\n```\n\nDocs: https://docs.rs/x. Also <https://example.com/y>.\n---\n| Name | Stars |\n|---|---|\n| tokio | 10 |\n";
+
+    /// Uppercases everything, mimicking a translator that touches every word.
+    struct Shouting;
+
+    #[async_trait]
+    impl Translator for Shouting {
+        async fn translate(&self, text: &str, _language: &str) -> CrawlerResult<String> {
+            Ok(format!("  {}\n", text.to_uppercase()))
+        }
+    }
+
+    /// Uppercases, but drops everything after the first placeholder.
+    struct Lossy;
+
+    #[async_trait]
+    impl Translator for Lossy {
+        async fn translate(&self, text: &str, _language: &str) -> CrawlerResult<String> {
+            Ok(text.split('⟦').next().unwrap_or_default().to_uppercase())
+        }
+    }
+
+    #[test]
+    fn stems_come_from_the_digest_entries() {
+        assert_eq!(file_stem("github"), Some("github-trending"));
+        assert_eq!(file_stem("papers_with_code"), Some("papers-with-code"));
+        assert_eq!(file_stem("custom_site"), None);
+        assert_eq!(file_stem("unknown"), None);
+    }
+
+    #[test]
+    fn splits_on_rules_outside_code_and_round_trips() {
+        let sections = split_sections(MIXED);
+        assert_eq!(sections.len(), 3);
+        assert!(sections[1].body.contains("fn main() {}\n---\n```"));
+        assert_eq!(sections[0].separator, "---\n");
+        assert_eq!(sections[2].separator, "");
+        assert_eq!(join_sections(&sections), MIXED);
+
+        let no_rules = "plain text without a trailing newline";
+        assert_eq!(join_sections(&split_sections(no_rules)), no_rules);
+        assert_eq!(split_sections("a\n--- \nb").len(), 2);
+        assert_eq!(split_sections("| a |\n|---|\n").len(), 1);
+    }
+
+    #[test]
+    fn protects_code_links_and_urls() {
+        let protected = protect(MIXED);
+        assert_eq!(
+            protected.spans,
+            vec![
+                "https://github.com/a/b_(c)",
+                "`cargo run`",
+                "```rust\nfn main() {}\n---\n```",
+                "https://docs.rs/x",
+                "<https://example.com/y>",
+            ]
+        );
+        assert!(protected.text.starts_with(
+            "# Trending\n\nSee [the repo](⟦0⟧) and ⟦1⟧.\n\n---\n\n⟦2⟧\n\nDocs: ⟦3⟧. Also ⟦4⟧."
+        ));
+        assert_eq!(
+            restore(&protected.text, &protected.spans).as_deref(),
+            Some(MIXED)
+        );
+    }
+
+    #[test]
+    fn unterminated_fence_and_unmatched_backtick() {
+        let protected = protect("odd ` tick\n```\nno end");
+        assert_eq!(protected.text, "odd ` tick\n⟦0⟧");
+        assert_eq!(protected.spans, vec!["```\nno end"]);
+        assert!(protected.has_text());
+        assert!(!protect("```\ncode\n```\n").has_text());
+    }
+
+    #[test]
+    fn restore_rejects_lost_or_duplicated_placeholders() {
+        let spans = vec!["`x`".to_string()];
+        assert_eq!(restore("use ⟦0⟧", &spans).as_deref(), Some("use `x`"));
+        assert_eq!(restore("use it", &spans), None);
+        assert_eq!(restore("⟦0⟧ ⟦0⟧", &spans), None);
+    }
+
+    #[tokio::test]
+    async fn translation_leaves_code_and_urls_untouched() {
+        let out = translate_markdown(&Shouting, MIXED, "ja").await.unwrap();
+        assert_eq!(
+            out,
+            "# TRENDING\n\nSEE [THE REPO](https://github.com/a/b_(c)) AND `cargo run`.\n\n---\n\n```rust\nfn main() {}\n---\n```\n\nDOCS: https://docs.rs/x. ALSO <https://example.com/y>.\n---\n| NAME | STARS |\n|---|---|\n| TOKIO | 10 |\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn mangled_sections_stay_in_the_original() {
+        let out = translate_markdown(&Lossy, MIXED, "ja").await.unwrap();
+        assert_eq!(
+            out,
+            MIXED
+                .replace("| Name | Stars |", "| NAME | STARS |")
+                .replace("tokio", "TOKIO")
+        );
+    }
+}
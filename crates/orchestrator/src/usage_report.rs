@@ -0,0 +1,71 @@
+use anyhow::Result;
+use common::usage::{self, UsageSummary};
+use common::{state, Config, SupabaseStorageClient};
+use serde::Serialize;
+use time::OffsetDateTime;
+use tracing::info;
+
+use crate::request_log;
+
+/// What `{date}/runs/{run_id}-usage.json` holds.
+#[derive(Debug, Serialize)]
+struct Manifest {
+    month: String,
+    /// Spending recorded for the month, including this run.
+    month_cost_usd: f64,
+    budget_usd: Option<f64>,
+    #[serde(flatten)]
+    usage: UsageSummary,
+}
+
+/// Hands `LLM_PRICES` to the shared tracker and, with `CUTION_LLM_BUDGET_USD`
+/// set, the month's spending so far, so clients stop calling once it is spent.
+pub async fn prepare(config: &Config) -> Result<()> {
+    let tracker = usage::global();
+    tracker.set_prices(config.llm_prices.clone());
+    if let Some(budget) = config.llm_budget_usd {
+        let month = usage::month_key(OffsetDateTime::now_utc().date());
+        let spent = usage::month_spent(state::from_config(config)?.as_ref(), &month).await?;
+        info!(
+            "LLM budget: ${:.2} of ${:.2} spent in {}",
+            spent, budget, month
+        );
+        tracker.set_budget(budget, spent);
+    }
+    Ok(())
+}
+
+/// Drains the run's LLM usage, adds its cost to the month's running total and
+/// uploads both as `{date}/runs/{run_id}-usage.json`. Runs without LLM calls
+/// upload nothing.
+pub async fn publish(config: &Config) -> Result<Option<String>> {
+    let summary = usage::global().take();
+    if summary.is_empty() {
+        return Ok(None);
+    }
+    let now = OffsetDateTime::now_utc();
+    let month = usage::month_key(now.date());
+    let store = state::from_config(config)?;
+    let month_cost_usd =
+        usage::add_month_spent(store.as_ref(), &month, summary.total_cost_usd).await?;
+
+    let path = request_log::run_path(&now.date().to_string(), now.time(), "usage");
+    let manifest = Manifest {
+        month,
+        month_cost_usd,
+        budget_usd: config.llm_budget_usd,
+        usage: summary,
+    };
+    SupabaseStorageClient::from_config(config)?
+        .upload_file(
+            &path,
+            serde_json::to_string_pretty(&manifest)?,
+            "application/json",
+        )
+        .await?;
+    info!(
+        "Uploaded {} (${:.4} this run, {} call(s) skipped over budget)",
+        path, manifest.usage.total_cost_usd, manifest.usage.skipped_calls
+    );
+    Ok(Some(path))
+}
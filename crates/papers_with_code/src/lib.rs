@@ -0,0 +1,363 @@
+pub mod models;
+
+use arxiv::ArxivId;
+use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::http::parse_json;
+use common::output::load_markdown;
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult, HttpClient,
+    OutputFormat, Publisher,
+};
+use models::{ApiPaper, ApiRepository, ApiTask, Page, PwcPaper};
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use tracing::{info, warn};
+
+const API_URL: &str = "https://paperswithcode.com/api/v1";
+/// Pages of the paper list read at most, whatever `PAPERS_WITH_CODE_TOP_N` says.
+const MAX_PAGES: usize = 5;
+
+/// Publishes the most starred papers on Papers with Code to
+/// `{date}/papers-with-code.md`, marking the ones the day's arXiv digest
+/// also covers.
+pub struct PapersWithCodeCrawler {
+    client: HttpClient,
+    base_url: String,
+    top_n: usize,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    archive: Option<ArchiveWriter>,
+}
+
+impl PapersWithCodeCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self {
+            client: ClientFactory::from_config(config).build()?,
+            base_url: API_URL.to_string(),
+            top_n: config.papers_with_code_top_n,
+            publisher: Publisher::for_crawler(config, "papers_with_code")?,
+            output_formats: config.output_formats_for("papers_with_code"),
+            archive: ArchiveWriter::from_config(config)?,
+        })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, ctx: &CrawlerContext, url: &str) -> CrawlerResult<T> {
+        let body = ctx.http_client(&self.client).get_text(url).await?;
+        parse_json(url, &body)
+    }
+
+    /// The first `top_n` papers by stars, following `next` links.
+    async fn fetch_papers(&self, ctx: &CrawlerContext) -> CrawlerResult<Vec<ApiPaper>> {
+        let mut papers = Vec::new();
+        let mut next = Some(format!("{}/papers/?ordering=-github_stars", self.base_url));
+        let mut pages = 0;
+        while let Some(url) = next.take() {
+            let page: Page<ApiPaper> = self.get(ctx, &url).await?;
+            papers.extend(page.results);
+            pages += 1;
+            if papers.len() < self.top_n && pages < MAX_PAGES {
+                next = page.next;
+            }
+        }
+        papers.truncate(self.top_n);
+        Ok(papers)
+    }
+
+    /// Adds repository stars and tasks to `paper`. A paper whose details
+    /// cannot be read is listed without them.
+    async fn with_details(&self, ctx: &CrawlerContext, paper: ApiPaper) -> PwcPaper {
+        let base = format!("{}/papers/{}", self.base_url, paper.id);
+        let repositories = self
+            .details::<ApiRepository>(ctx, &paper.id, &format!("{}/repositories/", base))
+            .await;
+        let tasks = self
+            .details::<ApiTask>(ctx, &paper.id, &format!("{}/tasks/", base))
+            .await;
+        PwcPaper::new(paper, &repositories, tasks)
+    }
+
+    async fn details<T: DeserializeOwned>(
+        &self,
+        ctx: &CrawlerContext,
+        paper: &str,
+        url: &str,
+    ) -> Vec<T> {
+        match self.get::<Page<T>>(ctx, url).await {
+            Ok(page) => page.results,
+            Err(e) => {
+                warn!("Failed to fetch {}: {}", url, e);
+                ctx.item_failed(paper, &e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// The arXiv ids mentioned in today's `{date}/arxiv.md` (or its `-latest`
+    /// copy in archive mode), if there is one.
+    async fn arxiv_digest_ids(&self, publisher: &Publisher, date: &str) -> HashSet<ArxivId> {
+        let stem = format!("{}/arxiv", date);
+        match load_markdown(publisher.storage(), &stem).await {
+            Ok(Some(markdown)) => ArxivId::find_all(&markdown).into_iter().collect(),
+            Ok(None) => HashSet::new(),
+            Err(e) => {
+                warn!("Could not read {}.md for arXiv cross-links: {}", stem, e);
+                HashSet::new()
+            }
+        }
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let listed = self.fetch_papers(ctx).await?;
+        if listed.is_empty() {
+            info!("No Papers with Code papers found");
+            return Ok(());
+        }
+        let mut papers = Vec::with_capacity(listed.len());
+        for paper in listed {
+            papers.push(self.with_details(ctx, paper).await);
+        }
+        papers.sort_by_key(|paper| std::cmp::Reverse(paper.stars));
+
+        let publisher = ctx.publisher(&self.publisher);
+        let date = ctx.date.to_string();
+        let in_digest = self.arxiv_digest_ids(&publisher, &date).await;
+        for paper in &mut papers {
+            paper.in_arxiv_digest = paper
+                .arxiv_id
+                .as_ref()
+                .is_some_and(|id| in_digest.contains(id));
+        }
+
+        let stem = format!("{}/papers-with-code", date);
+        let written = publisher
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_papers_markdown(&papers),
+                &papers,
+            )
+            .await?;
+        info!(
+            "Uploaded {} Papers with Code papers to {}",
+            papers.len(),
+            written.join(", ")
+        );
+        if let Some(archive) = &self.archive {
+            archive
+                .record(papers.into_iter().map(|paper| paper.into_item(ctx.date)))
+                .await;
+        }
+        Ok(())
+    }
+}
+
+fn format_papers_markdown(papers: &[PwcPaper]) -> String {
+    format!(
+        "{}\n\n{}",
+        markdown::section(1, "Papers with Code: Most Starred"),
+        markdown::rule_separated(papers.iter().map(PwcPaper::to_markdown_string))
+    )
+}
+
+#[async_trait]
+impl Crawler for PapersWithCodeCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Papers with Code crawler starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Papers with Code"
+    }
+
+    fn slug(&self) -> &'static str {
+        "papers_with_code"
+    }
+
+    /// Runs after the arXiv digest so it can link to it.
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["arxiv"]
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if !config.papers_with_code {
+            return Err("PAPERS_WITH_CODE not set".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(PapersWithCodeCrawler {
+    name: "Papers with Code crawler",
+    slug: "papers_with_code",
+    required_env: ["PAPERS_WITH_CODE"],
+    digest: ("papers-with-code", "Papers with Code"),
+    new: PapersWithCodeCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{assert_snapshot, fixture, FixtureDir, MockSupabase, TestConfig};
+    use time::macros::date;
+
+    /// The recorded API responses, saved under the URLs the crawler requests.
+    fn recorded_api() -> FixtureDir {
+        let fixtures = FixtureDir::new();
+        let papers = format!("{}/papers/", API_URL);
+        fixtures.add(
+            &format!("{}?ordering=-github_stars", papers),
+            &fixture("papers_with_code/papers_page1.json"),
+        );
+        fixtures.add(
+            &format!("{}?ordering=-github_stars&page=2", papers),
+            &fixture("papers_with_code/papers_page2.json"),
+        );
+        for (id, name) in [
+            ("attention-is-all-you-need", "attention"),
+            ("llama-open-and-efficient-foundation-language-1", "llama"),
+            ("sparse-routing-for-workshop-models", "sparse"),
+        ] {
+            for kind in ["repositories", "tasks"] {
+                let file = if kind == "tasks" { "tasks" } else { "repos" };
+                fixtures.add(
+                    &format!("{}{}/{}/", papers, id, kind),
+                    &fixture(&format!("papers_with_code/{}_{}.json", file, name)),
+                );
+            }
+        }
+        fixtures
+    }
+
+    #[test]
+    fn maps_the_recorded_responses() {
+        let page: Page<ApiPaper> =
+            serde_json::from_str(&fixture("papers_with_code/papers_page1.json")).unwrap();
+        assert_eq!(page.results.len(), 2);
+        assert!(page.next.unwrap().ends_with("&page=2"));
+        let repos: Page<ApiRepository> =
+            serde_json::from_str(&fixture("papers_with_code/repos_attention.json")).unwrap();
+        let tasks: Page<ApiTask> =
+            serde_json::from_str(&fixture("papers_with_code/tasks_attention.json")).unwrap();
+
+        let paper = PwcPaper::new(page.results[0].clone(), &repos.results, tasks.results);
+        assert_eq!(paper.arxiv_id, ArxivId::parse("1706.03762"));
+        assert_eq!(paper.stars, 131000, "the most starred repository wins");
+        assert_eq!(
+            paper.repository.as_deref(),
+            Some("https://github.com/huggingface/transformers")
+        );
+        assert_eq!(paper.tasks, ["Machine Translation", "Language Modelling"]);
+        assert_eq!(
+            paper.url,
+            "https://paperswithcode.com/paper/attention-is-all-you-need"
+        );
+
+        let page2: Page<ApiPaper> =
+            serde_json::from_str(&fixture("papers_with_code/papers_page2.json")).unwrap();
+        let unlisted = PwcPaper::new(page2.results[0].clone(), &[], Vec::new());
+        assert_eq!((unlisted.arxiv_id, unlisted.stars), (None, 0));
+    }
+
+    #[tokio::test]
+    async fn pages_until_top_n_and_links_the_arxiv_digest() {
+        let fixtures = recorded_api();
+        let supabase = MockSupabase::start().await;
+        supabase
+            .serve_file(
+                "2024-05-10/arxiv.md",
+                "# arXiv\n\n[LLaMA](https://arxiv.org/abs/2302.13971v1)",
+            )
+            .await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| c.papers_with_code = true)
+            .build();
+        let ctx = CrawlerContext::default().with_date(date!(2024 - 05 - 10));
+        PapersWithCodeCrawler::new(&config)
+            .unwrap()
+            .run_with_context(&ctx)
+            .await
+            .unwrap();
+
+        assert!(ctx.item_errors.take().is_empty());
+        let json: Vec<PwcPaper> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/papers-with-code.json").await)
+                .unwrap();
+        let summary: Vec<_> = json
+            .iter()
+            .map(|p| (p.title.as_str(), p.stars, p.in_arxiv_digest))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Attention Is All You Need", 131000, false),
+                (
+                    "LLaMA: Open and Efficient Foundation Language Models",
+                    55000,
+                    true
+                ),
+                ("Sparse Routing for Workshop Models", 0, false),
+            ]
+        );
+        assert_snapshot!(
+            "papers-with-code.md",
+            supabase.uploaded("2024-05-10/papers-with-code.md").await
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_the_latest_arxiv_digest_in_archive_mode() {
+        let supabase = MockSupabase::start().await;
+        supabase
+            .serve_file(
+                "2024-05-10/arxiv.md",
+                "[old](https://arxiv.org/abs/1706.03762)",
+            )
+            .await;
+        supabase
+            .serve_file(
+                "2024-05-10/arxiv-latest.md",
+                "[LLaMA](https://arxiv.org/abs/2302.13971v1)",
+            )
+            .await;
+        let config = TestConfig::new(&supabase)
+            .with(|c| c.papers_with_code = true)
+            .build();
+        let crawler = PapersWithCodeCrawler::new(&config).unwrap();
+        let ids = crawler
+            .arxiv_digest_ids(&crawler.publisher, "2024-05-10")
+            .await;
+        assert_eq!(ids, HashSet::from([ArxivId::parse("2302.13971").unwrap()]));
+    }
+
+    #[tokio::test]
+    async fn stops_paging_once_top_n_is_reached() {
+        let fixtures = recorded_api();
+        // Reading this page would fail the run.
+        fixtures.add(
+            &format!("{}/papers/?ordering=-github_stars&page=2", API_URL),
+            "not json",
+        );
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Json])
+            .with(|c| {
+                c.papers_with_code = true;
+                c.papers_with_code_top_n = 2;
+            })
+            .build();
+        PapersWithCodeCrawler::new(&config)
+            .unwrap()
+            .run_with_context(&CrawlerContext::default().with_date(date!(2024 - 05 - 10)))
+            .await
+            .unwrap();
+        let json: Vec<PwcPaper> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/papers-with-code.json").await)
+                .unwrap();
+        assert_eq!(json.len(), 2);
+    }
+}
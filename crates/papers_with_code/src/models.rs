@@ -0,0 +1,114 @@
+use arxiv::ArxivId;
+use common::archive;
+use common::markdown;
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+const PAPER_URL: &str = "https://paperswithcode.com/paper";
+
+/// One page of a list endpoint; `next` is the full URL of the following page.
+#[derive(Debug, Deserialize)]
+pub struct Page<T> {
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default = "Vec::new")]
+    pub results: Vec<T>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiPaper {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub arxiv_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiRepository {
+    pub url: String,
+    #[serde(default)]
+    pub stars: u64,
+    #[serde(default)]
+    pub is_official: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiTask {
+    pub name: String,
+}
+
+/// A trending paper with the stars of its most starred repository.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PwcPaper {
+    pub title: String,
+    pub arxiv_id: Option<ArxivId>,
+    pub stars: u64,
+    /// The paper's page on paperswithcode.com.
+    pub url: String,
+    /// The most starred implementation.
+    pub repository: Option<String>,
+    pub tasks: Vec<String>,
+    /// Whether the day's arXiv digest covers the paper too.
+    #[serde(default)]
+    pub in_arxiv_digest: bool,
+}
+
+impl PwcPaper {
+    pub fn new(paper: ApiPaper, repositories: &[ApiRepository], tasks: Vec<ApiTask>) -> Self {
+        let best = repositories
+            .iter()
+            .max_by_key(|repo| (repo.stars, repo.is_official));
+        Self {
+            url: format!("{}/{}", PAPER_URL, paper.id),
+            arxiv_id: paper.arxiv_id.as_deref().and_then(ArxivId::parse),
+            title: paper.title,
+            stars: best.map_or(0, |repo| repo.stars),
+            repository: best.map(|repo| repo.url.clone()),
+            tasks: tasks.into_iter().map(|task| task.name).collect(),
+            in_arxiv_digest: false,
+        }
+    }
+
+    pub fn to_markdown_string(&self) -> String {
+        let mut links = vec![markdown::link("Papers with Code", &self.url)];
+        if let Some(id) = &self.arxiv_id {
+            links.push(markdown::link(&format!("arXiv:{}", id), &id.abs_url()));
+        }
+        if let Some(repository) = &self.repository {
+            links.push(markdown::link("Code", repository));
+        }
+        let mut markdown = format!(
+            "{}\n\n**Stars**: {}\n\n{}",
+            markdown::section(2, &self.title),
+            self.stars,
+            links.join(" · ")
+        );
+        if !self.tasks.is_empty() {
+            markdown.push_str(&format!(
+                "\n\n*Tasks: {}*",
+                markdown::escape(&self.tasks.join(", "))
+            ));
+        }
+        if self.in_arxiv_digest {
+            markdown.push_str("\n\nAlso in today's [arXiv digest](arxiv.md)");
+        }
+        markdown
+    }
+}
+
+impl archive::Archivable for PwcPaper {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "papers_with_code".to_string(),
+            date: date.to_string(),
+            url: Some(self.url.clone()),
+            title: self.title.clone(),
+            score: Some(self.stars as f64),
+            id: self
+                .arxiv_id
+                .as_ref()
+                .map_or_else(|| self.url.clone(), ToString::to_string),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+        }
+    }
+}
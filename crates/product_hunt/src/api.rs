@@ -0,0 +1,227 @@
+use crate::models::{GraphQlResponse, PhPost, PostsData};
+use common::http::{parse_json, send_text};
+use common::{CrawlerError, CrawlerResult, HttpClient};
+use serde_json::{json, Value};
+use time::Date;
+
+pub const API_URL: &str = "https://api.producthunt.com/v2/api/graphql";
+/// Largest page the API serves.
+const MAX_PAGE_SIZE: usize = 20;
+
+const POSTS_QUERY: &str = "query Posts($first: Int!, $after: String, $postedAfter: DateTime, $postedBefore: DateTime, $order: PostsOrder) {
+  posts(first: $first, after: $after, postedAfter: $postedAfter, postedBefore: $postedBefore, order: $order) {
+    edges {
+      node {
+        id
+        name
+        tagline
+        votesCount
+        url
+        topics(first: 5) { edges { node { name } } }
+      }
+    }
+    pageInfo { hasNextPage endCursor }
+  }
+}";
+
+/// One page of the `posts` query: the posts launched on `day`, most voted
+/// first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostsQuery {
+    pub day: Date,
+    pub first: usize,
+    pub after: Option<String>,
+}
+
+impl PostsQuery {
+    pub fn new(day: Date, first: usize) -> Self {
+        Self {
+            day,
+            first: first.clamp(1, MAX_PAGE_SIZE),
+            after: None,
+        }
+    }
+
+    /// The next page, starting after `cursor`.
+    pub fn after(mut self, cursor: impl Into<String>) -> Self {
+        self.after = Some(cursor.into());
+        self
+    }
+
+    /// The JSON body to POST.
+    pub fn to_body(&self) -> Value {
+        let next_day = self.day.next_day().unwrap_or(self.day);
+        json!({
+            "query": POSTS_QUERY,
+            "variables": {
+                "first": self.first,
+                "after": self.after,
+                "postedAfter": format!("{}T00:00:00Z", self.day),
+                "postedBefore": format!("{}T00:00:00Z", next_day),
+                "order": "VOTES",
+            },
+        })
+    }
+}
+
+/// Client for the Product Hunt v2 GraphQL API.
+#[derive(Clone)]
+pub struct ProductHuntApi {
+    client: HttpClient,
+    token: String,
+    url: String,
+}
+
+impl ProductHuntApi {
+    pub fn new(client: HttpClient, token: &str) -> Self {
+        Self {
+            client,
+            token: token.to_string(),
+            url: API_URL.to_string(),
+        }
+    }
+
+    /// Points the API at another endpoint, e.g. a local mock.
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    /// The `limit` most voted posts launched on `day`, following the cursor
+    /// until enough posts were read or the last page.
+    pub async fn top_posts(&self, day: Date, limit: usize) -> CrawlerResult<Vec<PhPost>> {
+        let mut posts = Vec::new();
+        let mut query = PostsQuery::new(day, limit);
+        while posts.len() < limit {
+            let page = self.posts_page(&query).await?.posts;
+            posts.extend(page.edges.into_iter().map(|edge| PhPost::from(edge.node)));
+            match page.page_info.end_cursor {
+                Some(cursor) if page.page_info.has_next_page => {
+                    query = PostsQuery::new(day, limit.saturating_sub(posts.len())).after(cursor)
+                }
+                _ => break,
+            }
+        }
+        posts.truncate(limit);
+        Ok(posts)
+    }
+
+    async fn posts_page(&self, query: &PostsQuery) -> CrawlerResult<PostsData> {
+        let request = self
+            .client
+            .inner()
+            .post(&self.url)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/json")
+            .json(&query.to_body());
+        let body = send_text(request, &self.url).await.map_err(|e| match e {
+            CrawlerError::HttpStatus { status: 401, .. } => {
+                CrawlerError::Api("Product Hunt rejected PRODUCT_HUNT_TOKEN (HTTP 401)".to_string())
+            }
+            e => e,
+        })?;
+        let response: GraphQlResponse<PostsData> = parse_json(&self.url, &body)?;
+        if !response.errors.is_empty() {
+            let messages: Vec<_> = response.errors.into_iter().map(|e| e.message).collect();
+            return Err(CrawlerError::Api(format!(
+                "Product Hunt query failed: {}",
+                messages.join("; ")
+            )));
+        }
+        response
+            .data
+            .ok_or_else(|| CrawlerError::Parse("Product Hunt response has no data".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::MockProductHunt;
+    use time::macros::date;
+
+    fn api(mock: &MockProductHunt) -> ProductHuntApi {
+        ProductHuntApi::new(HttpClient::new().unwrap(), "test-token").with_url(&mock.url())
+    }
+
+    #[test]
+    fn query_covers_the_whole_day() {
+        let body = PostsQuery::new(date!(2024 - 05 - 09), 50)
+            .after("Mg")
+            .to_body();
+        assert!(body["query"].as_str().unwrap().contains("votesCount"));
+        assert_eq!(
+            body["variables"],
+            json!({
+                "first": 20,
+                "after": "Mg",
+                "postedAfter": "2024-05-09T00:00:00Z",
+                "postedBefore": "2024-05-10T00:00:00Z",
+                "order": "VOTES",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn follows_the_cursor_until_the_limit() {
+        let mock = MockProductHunt::start().await;
+        mock.page(None, &[("1", 300), ("2", 250)], Some("c2")).await;
+        mock.page(Some("c2"), &[("3", 200), ("4", 150)], Some("c4"))
+            .await;
+        mock.page(Some("c4"), &[("5", 100)], None).await;
+
+        let posts = api(&mock)
+            .top_posts(date!(2024 - 05 - 09), 3)
+            .await
+            .unwrap();
+        assert_eq!(
+            posts.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(),
+            ["1", "2", "3"]
+        );
+        assert_eq!(posts[0].topics, ["Developer Tools"]);
+
+        let requests = mock.requests().await;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["variables"]["first"], 3);
+        assert_eq!(requests[1]["variables"]["after"], "c2");
+        assert_eq!(requests[1]["variables"]["first"], 1);
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_last_page() {
+        let mock = MockProductHunt::start().await;
+        mock.page(None, &[("1", 10)], None).await;
+        let posts = api(&mock)
+            .top_posts(date!(2024 - 05 - 09), 10)
+            .await
+            .unwrap();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(mock.requests().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn invalid_token_is_an_api_error() {
+        let mock = MockProductHunt::start().await;
+        mock.reject_token().await;
+        let err = api(&mock)
+            .top_posts(date!(2024 - 05 - 09), 10)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, CrawlerError::Api(ref msg) if msg.contains("PRODUCT_HUNT_TOKEN")),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn graphql_errors_fail_the_query() {
+        let mock = MockProductHunt::start().await;
+        mock.graphql_error("Field 'votesCount' doesn't exist").await;
+        let err = api(&mock)
+            .top_posts(date!(2024 - 05 - 09), 10)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("votesCount"), "{}", err);
+    }
+}
@@ -0,0 +1,181 @@
+pub mod api;
+pub mod models;
+
+use api::ProductHuntApi;
+use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult, OutputFormat,
+    Publisher,
+};
+use models::PhPost;
+use time::Date;
+use tracing::info;
+
+/// Publishes the previous day's most voted Product Hunt launches to
+/// `{date}/product-hunt.md`.
+pub struct ProductHuntCrawler {
+    api: ProductHuntApi,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    top_n: usize,
+    archive: Option<ArchiveWriter>,
+}
+
+impl ProductHuntCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        let client = ClientFactory::from_config(config).build()?;
+        let token = config.product_hunt_token.as_deref().unwrap_or_default();
+        Ok(Self {
+            api: ProductHuntApi::new(client, token),
+            publisher: Publisher::for_crawler(config, "product_hunt")?,
+            output_formats: config.output_formats_for("product_hunt"),
+            top_n: config.product_hunt_top_n,
+            archive: ArchiveWriter::from_config(config)?,
+        })
+    }
+
+    pub fn with_api(mut self, api: ProductHuntApi) -> Self {
+        self.api = api;
+        self
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let day = ctx.date.previous_day().unwrap_or(ctx.date);
+        let posts = self.api.top_posts(day, self.top_n).await?;
+        if posts.is_empty() {
+            info!("No Product Hunt launches found for {}", day);
+            return Ok(());
+        }
+
+        let stem = format!("{}/product-hunt", ctx.date);
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_posts_markdown(&posts, day),
+                &posts,
+            )
+            .await?;
+        info!(
+            "Uploaded {} Product Hunt launches to {}",
+            posts.len(),
+            written.join(", ")
+        );
+        if let Some(archive) = &self.archive {
+            archive
+                .record(posts.iter().cloned().map(|post| post.into_item(ctx.date)))
+                .await;
+        }
+        Ok(())
+    }
+}
+
+fn format_posts_markdown(posts: &[PhPost], day: Date) -> String {
+    let sections: Vec<String> = posts
+        .iter()
+        .enumerate()
+        .map(|(i, post)| post.to_markdown_string(i + 1))
+        .collect();
+    format!(
+        "{}\n\n{}",
+        markdown::section(1, &format!("Product Hunt: Top Launches of {}", day)),
+        sections.join("\n\n---\n\n")
+    )
+}
+
+#[async_trait]
+impl Crawler for ProductHuntCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Product Hunt crawler starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Product Hunt"
+    }
+
+    fn slug(&self) -> &'static str {
+        "product_hunt"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if config.product_hunt_token.is_none() {
+            return Err("PRODUCT_HUNT_TOKEN not set".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(ProductHuntCrawler {
+    name: "Product Hunt crawler",
+    slug: "product_hunt",
+    required_env: ["PRODUCT_HUNT_TOKEN"],
+    digest: ("product-hunt", "Product Hunt"),
+    new: ProductHuntCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::HttpClient;
+    use test_support::{assert_snapshot, MockProductHunt, MockSupabase, TestConfig};
+    use time::macros::date;
+
+    #[test]
+    fn markdown_matches_snapshot() {
+        let posts = [
+            PhPost {
+                id: "1".to_string(),
+                name: "Shipyard".to_string(),
+                tagline: "Preview environments for every pull request".to_string(),
+                votes: 512,
+                topics: vec!["Developer Tools".to_string(), "GitHub".to_string()],
+                url: "https://www.producthunt.com/posts/shipyard".to_string(),
+            },
+            PhPost {
+                id: "2".to_string(),
+                name: "Quiet_Notes".to_string(),
+                tagline: "Notes that stay out of your way".to_string(),
+                votes: 230,
+                topics: Vec::new(),
+                url: "https://www.producthunt.com/posts/quiet-notes".to_string(),
+            },
+        ];
+        assert_snapshot!(
+            "product-hunt.md",
+            format_posts_markdown(&posts, date!(2024 - 05 - 09))
+        );
+    }
+
+    #[tokio::test]
+    async fn publishes_the_previous_days_launches() {
+        let mock = MockProductHunt::start().await;
+        mock.page(None, &[("1", 300), ("2", 250)], None).await;
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| c.product_hunt_token = Some("test-token".to_string()))
+            .build();
+        let api =
+            ProductHuntApi::new(HttpClient::new().unwrap(), "test-token").with_url(&mock.url());
+        ProductHuntCrawler::new(&config)
+            .unwrap()
+            .with_api(api)
+            .run_with_context(&CrawlerContext::default().with_date(date!(2024 - 05 - 10)))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            mock.requests().await[0]["variables"]["postedAfter"],
+            "2024-05-09T00:00:00Z"
+        );
+        let markdown = supabase.uploaded("2024-05-10/product-hunt.md").await;
+        assert!(markdown.starts_with("# Product Hunt: Top Launches of 2024-05-09"));
+        assert!(markdown.contains("## 1. Launch 1"));
+        let json: Vec<PhPost> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/product-hunt.json").await).unwrap();
+        assert_eq!(json.iter().map(|p| p.votes).collect::<Vec<_>>(), [300, 250]);
+    }
+}
@@ -0,0 +1,125 @@
+use common::archive;
+use common::markdown;
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+/// A launch from the day's top posts.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PhPost {
+    pub id: String,
+    pub name: String,
+    pub tagline: String,
+    pub votes: u64,
+    pub topics: Vec<String>,
+    /// The post's page on producthunt.com.
+    pub url: String,
+}
+
+impl PhPost {
+    pub fn to_markdown_string(&self, rank: usize) -> String {
+        let mut markdown = format!(
+            "{}\n\n{}\n\n**Votes**: {} · {}",
+            markdown::section(2, &format!("{}. {}", rank, self.name)),
+            markdown::escape(&self.tagline),
+            self.votes,
+            markdown::link("View on Product Hunt", &self.url)
+        );
+        if !self.topics.is_empty() {
+            markdown.push_str(&format!(
+                "\n\n*Topics: {}*",
+                markdown::escape(&self.topics.join(", "))
+            ));
+        }
+        markdown
+    }
+}
+
+impl From<PostNode> for PhPost {
+    fn from(node: PostNode) -> Self {
+        Self {
+            id: node.id,
+            name: node.name,
+            tagline: node.tagline,
+            votes: node.votes_count,
+            topics: node
+                .topics
+                .edges
+                .into_iter()
+                .map(|edge| edge.node.name)
+                .collect(),
+            url: node.url,
+        }
+    }
+}
+
+impl archive::Archivable for PhPost {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "product_hunt".to_string(),
+            date: date.to_string(),
+            url: Some(self.url.clone()),
+            title: self.name.clone(),
+            score: Some(self.votes as f64),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+            id: self.id,
+        }
+    }
+}
+
+/// Envelope of every GraphQL response. A rejected query still answers 200,
+/// with `errors` set and `data` missing.
+#[derive(Debug, Deserialize)]
+pub struct GraphQlResponse<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphQlError {
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostsData {
+    pub posts: Connection<PostNode>,
+}
+
+/// A Relay-style connection: one page of edges plus the cursor to the next.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection<T> {
+    pub edges: Vec<Edge<T>>,
+    #[serde(default)]
+    pub page_info: PageInfo,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Edge<T> {
+    pub node: T,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    #[serde(default)]
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostNode {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub tagline: String,
+    pub votes_count: u64,
+    pub url: String,
+    pub topics: Connection<TopicNode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopicNode {
+    pub name: String,
+}
@@ -0,0 +1,414 @@
+pub mod models;
+
+use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::http::parse_json;
+use common::{
+    markdown, retry_async, summarize_long, ClientFactory, Config, Crawler, CrawlerContext,
+    CrawlerError, CrawlerResult, HttpClient, OutputFormat, Publisher, RateLimiter, RetryPolicy,
+    Summarizer,
+};
+use models::{Listing, RawPost, RedditPost};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const REDDIT_URL: &str = "https://www.reddit.com";
+/// Reddit asks API clients to identify themselves; generic browser agents
+/// are throttled much harder.
+const USER_AGENT: &str = concat!(
+    "rust:cution:v",
+    env!("CARGO_PKG_VERSION"),
+    " (daily subreddit digest; +https://github.com/yusa-n/cution)"
+);
+/// Unauthenticated clients get about ten requests a minute.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(6);
+
+/// Publishes each `REDDIT_SUBREDDITS` subreddit's top posts of the day to
+/// `{date}/reddit.md`, one section per subreddit.
+pub struct RedditCrawler {
+    client: HttpClient,
+    limiter: RateLimiter,
+    base_url: String,
+    retry: RetryPolicy,
+    subreddits: Vec<String>,
+    min_score: i64,
+    flairs: Vec<String>,
+    summarizer: Option<Arc<dyn Summarizer>>,
+    publisher: Publisher,
+    output_formats: Vec<OutputFormat>,
+    archive: Option<ArchiveWriter>,
+}
+
+impl RedditCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        let user_agent = config
+            .http_user_agent
+            .clone()
+            .unwrap_or_else(|| USER_AGENT.to_string());
+        let client = ClientFactory::from_config(config)
+            .with_user_agent(user_agent)
+            .build()?;
+        Ok(Self {
+            client,
+            limiter: RateLimiter::every(REQUEST_INTERVAL),
+            base_url: REDDIT_URL.to_string(),
+            retry: RetryPolicy {
+                base_delay: REQUEST_INTERVAL,
+                max_delay: Duration::from_secs(60),
+                ..RetryPolicy::default()
+            },
+            subreddits: config.reddit_subreddits.clone(),
+            min_score: config.reddit_min_score,
+            flairs: config.reddit_flairs.clone(),
+            summarizer: config.summarizer(config.reddit_summarize)?,
+            publisher: Publisher::for_crawler(config, "reddit")?,
+            output_formats: config.output_formats_for("reddit"),
+            archive: ArchiveWriter::from_config(config)?,
+        })
+    }
+
+    /// Points the crawler at another host, e.g. a local mock.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Spaces subreddit requests `interval` apart instead.
+    pub fn with_request_interval(mut self, interval: Duration) -> Self {
+        self.limiter = RateLimiter::every(interval);
+        self
+    }
+
+    /// How a rate-limited (429) request is retried.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// The subreddit's top posts of the last day, 429s retried with backoff.
+    async fn fetch_top(&self, subreddit: &str) -> CrawlerResult<Vec<RawPost>> {
+        let url = format!("{}/r/{}/top.json?t=day&limit=50", self.base_url, subreddit);
+        let body = retry_async(
+            &self.retry,
+            |e: &CrawlerError| matches!(e, CrawlerError::RateLimited { .. }),
+            |_| async {
+                self.limiter.acquire().await;
+                self.client.get_text(&url).await
+            },
+        )
+        .await?;
+        let listing: Listing = parse_json(&url, &body)?;
+        Ok(listing.data.children.into_iter().map(|t| t.data).collect())
+    }
+
+    /// The posts worth listing: not removed, scoring at least `min_score` and,
+    /// with `REDDIT_FLAIRS` set, carrying a matching flair.
+    fn filter(&self, posts: Vec<RawPost>) -> Vec<RedditPost> {
+        posts
+            .into_iter()
+            .filter(|post| !post.is_removed() && post.score >= self.min_score)
+            .filter(|post| self.flair_matches(post.link_flair_text.as_deref()))
+            .map(RedditPost::from)
+            .collect()
+    }
+
+    /// Flairs match case-insensitively on a substring, so `educational`
+    /// matches "🧠 educational".
+    fn flair_matches(&self, flair: Option<&str>) -> bool {
+        if self.flairs.is_empty() {
+            return true;
+        }
+        let Some(flair) = flair else {
+            return false;
+        };
+        let flair = flair.to_lowercase();
+        self.flairs
+            .iter()
+            .any(|wanted| flair.contains(wanted.as_str()))
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let mut sections = Vec::new();
+        let mut last_error = None;
+        for subreddit in &self.subreddits {
+            match self.fetch_top(subreddit).await {
+                Ok(raw) => {
+                    let total = raw.len();
+                    let mut posts = self.filter(raw);
+                    info!(
+                        "Kept {} of {} posts from r/{}",
+                        posts.len(),
+                        total,
+                        subreddit
+                    );
+                    if let Some(summarizer) = &self.summarizer {
+                        summarize_long(ctx, summarizer.as_ref(), &mut posts).await;
+                    }
+                    sections.push(SubredditSection {
+                        subreddit: subreddit.clone(),
+                        posts,
+                    });
+                }
+                Err(e) => {
+                    warn!("Failed to fetch r/{}: {}", subreddit, e);
+                    ctx.item_failed(format!("r/{}", subreddit), &e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        if sections.is_empty() {
+            return match last_error {
+                Some(e) => Err(e),
+                None => Ok(()),
+            };
+        }
+
+        let posts: Vec<RedditPost> = sections
+            .iter()
+            .flat_map(|section| section.posts.iter().cloned())
+            .collect();
+        let stem = format!("{}/reddit", ctx.date);
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_sections_markdown(&sections),
+                &posts,
+            )
+            .await?;
+        info!(
+            "Uploaded {} Reddit posts from {} subreddits to {}",
+            posts.len(),
+            sections.len(),
+            written.join(", ")
+        );
+        if let Some(archive) = &self.archive {
+            archive
+                .record(posts.into_iter().map(|post| post.into_item(ctx.date)))
+                .await;
+        }
+        Ok(())
+    }
+}
+
+/// One subreddit's kept posts.
+struct SubredditSection {
+    subreddit: String,
+    posts: Vec<RedditPost>,
+}
+
+fn format_sections_markdown(sections: &[SubredditSection]) -> String {
+    let sections = sections.iter().map(|section| {
+        let posts: Vec<String> = if section.posts.is_empty() {
+            vec!["*No posts passed the filters today.*".to_string()]
+        } else {
+            section
+                .posts
+                .iter()
+                .map(RedditPost::to_markdown_string)
+                .collect()
+        };
+        (format!("r/{}", section.subreddit), posts)
+    });
+    markdown::sectioned("Reddit Digest", sections, "\n\n---\n\n")
+}
+
+#[async_trait]
+impl Crawler for RedditCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Reddit crawler starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Reddit"
+    }
+
+    fn slug(&self) -> &'static str {
+        "reddit"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if config.reddit_subreddits.is_empty() {
+            return Err("REDDIT_SUBREDDITS not set".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(RedditCrawler {
+    name: "Reddit crawler",
+    slug: "reddit",
+    required_env: ["REDDIT_SUBREDDITS"],
+    digest: ("reddit", "Reddit"),
+    new: RedditCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{
+        assert_snapshot, fixture, MockGemini, MockReddit, MockSupabase, TestConfig,
+    };
+    use time::macros::date;
+
+    fn raw_posts() -> Vec<RawPost> {
+        let listing: Listing = serde_json::from_str(&fixture("reddit/rust_top.json")).unwrap();
+        listing.data.children.into_iter().map(|t| t.data).collect()
+    }
+
+    fn crawler(config: &Config, reddit: &MockReddit) -> RedditCrawler {
+        RedditCrawler::new(config)
+            .unwrap()
+            .with_base_url(&reddit.base_url())
+            .with_request_interval(Duration::from_millis(1))
+            .with_retry(RetryPolicy {
+                base_delay: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            })
+    }
+
+    #[test]
+    fn maps_the_recorded_listing() {
+        let raw = raw_posts();
+        assert_eq!(raw.len(), 6);
+        let removed: Vec<_> = raw
+            .iter()
+            .filter(|p| p.is_removed())
+            .map(|p| p.id.as_str())
+            .collect();
+        assert_eq!(removed, ["1cns0a", "1cnt4b"]);
+
+        let link = RedditPost::from(raw[0].clone());
+        assert_eq!(
+            link.permalink,
+            "https://www.reddit.com/r/rust/comments/1cnq2x/announcing_rust_1780/"
+        );
+        assert_eq!(
+            link.url.as_deref(),
+            Some("https://blog.rust-lang.org/2024/05/02/Rust-1.78.0.html")
+        );
+        assert_eq!(link.text, None);
+
+        let self_post = RedditPost::from(raw[1].clone());
+        assert_eq!(self_post.url, None, "self-posts link to their own comments");
+        let text = self_post.text.unwrap();
+        assert!(
+            text.starts_with("I have been rewriting our ingestion service"),
+            "{}",
+            text
+        );
+        assert!(text.contains("our shadow traffic notes (https://example.dev/shadow-traffic)"));
+        assert!(!text.contains("&lt;") && !text.contains("<p>"));
+
+        let short = RedditPost::from(raw[4].clone());
+        assert_eq!(
+            short.text.as_deref(),
+            Some("Which crate do you use for async retries & backoff?")
+        );
+        assert_eq!(
+            RedditPost::from(raw[3].clone()).author,
+            None,
+            "deleted account"
+        );
+    }
+
+    #[tokio::test]
+    async fn filters_by_score_and_flair() {
+        let supabase = MockSupabase::start().await;
+        let reddit = MockReddit::start().await;
+        let ids = |flairs: &[&str]| {
+            let config = TestConfig::new(&supabase)
+                .with(|c| {
+                    c.reddit_subreddits = vec!["rust".to_string()];
+                    c.reddit_flairs = flairs.iter().map(|f| f.to_string()).collect();
+                })
+                .build();
+            crawler(&config, &reddit)
+                .filter(raw_posts())
+                .into_iter()
+                .map(|p| p.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&[]), ["1cnq2x", "1cnr7k", "1cnu9c"]);
+        assert_eq!(ids(&["educational", "official blog"]), ["1cnq2x", "1cnr7k"]);
+    }
+
+    #[tokio::test]
+    async fn publishes_a_section_per_subreddit_and_retries_429s() {
+        let supabase = MockSupabase::start().await;
+        let reddit = MockReddit::start().await;
+        reddit.top("rust", &fixture("reddit/rust_top.json")).await;
+        reddit.rate_limit("rust", 2).await;
+        reddit
+            .top("LocalLLaMA", r#"{"kind":"Listing","data":{"children":[]}}"#)
+            .await;
+        let gemini = MockGemini::start().await;
+        gemini
+            .respond_with_text("A team's notes on moving a Go service to Rust.")
+            .await;
+        let config = TestConfig::new(&supabase)
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| c.reddit_subreddits = vec!["rust".to_string(), "LocalLLaMA".to_string()])
+            .build();
+        crawler(&config, &reddit)
+            .with_summarizer(Arc::new(gemini.client()))
+            .run_with_context(&CrawlerContext::default().with_date(date!(2024 - 05 - 10)))
+            .await
+            .unwrap();
+
+        let requests = reddit.requests().await;
+        assert_eq!(requests.len(), 4, "two 429s, the retry, then LocalLLaMA");
+        assert!(requests
+            .iter()
+            .all(|r| r.user_agent.starts_with("rust:cution:v")));
+        assert_eq!(
+            requests[0].path_and_query,
+            "/r/rust/top.json?t=day&limit=50"
+        );
+        assert_eq!(gemini.requests().await.len(), 1, "only the long self-post");
+
+        let markdown = supabase.uploaded("2024-05-10/reddit.md").await;
+        assert_snapshot!("reddit.md", markdown);
+        let json: Vec<RedditPost> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/reddit.json").await).unwrap();
+        assert_eq!(json.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn a_rate_limited_subreddit_does_not_stop_the_others() {
+        let supabase = MockSupabase::start().await;
+        let reddit = MockReddit::start().await;
+        reddit.rate_limit("rust", 10).await;
+        reddit.top("rust", &fixture("reddit/rust_top.json")).await;
+        reddit
+            .top("programming", &fixture("reddit/rust_top.json"))
+            .await;
+        let config = TestConfig::new(&supabase)
+            .with(|c| c.reddit_subreddits = vec!["rust".to_string(), "programming".to_string()])
+            .build();
+        let ctx = CrawlerContext::default().with_date(date!(2024 - 05 - 10));
+        crawler(&config, &reddit)
+            .run_with_context(&ctx)
+            .await
+            .unwrap();
+
+        let errors = ctx.item_errors.take();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].item, "r/rust");
+        assert!(
+            errors[0].error.contains("Rate limited"),
+            "{}",
+            errors[0].error
+        );
+        let markdown = supabase.uploaded("2024-05-10/reddit.md").await;
+        assert!(markdown.contains("## r/programming") && !markdown.contains("## r/rust"));
+    }
+}
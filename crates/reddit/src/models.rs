@@ -0,0 +1,176 @@
+use common::archive;
+use common::html::{self, TextOptions};
+use common::markdown;
+use common::Summarizable;
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+const REDDIT_URL: &str = "https://www.reddit.com";
+/// Characters of a self-post shown when it has no summary.
+const EXCERPT_CHARS: usize = 280;
+
+/// The `top.json` listing of a subreddit.
+#[derive(Debug, Deserialize)]
+pub struct Listing {
+    pub data: ListingData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListingData {
+    #[serde(default)]
+    pub children: Vec<Thing>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Thing {
+    pub data: RawPost,
+}
+
+/// A post as the listing sends it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawPost {
+    pub id: String,
+    pub subreddit: String,
+    pub title: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    pub score: i64,
+    #[serde(default)]
+    pub num_comments: u64,
+    pub permalink: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub is_self: bool,
+    #[serde(default)]
+    pub selftext: String,
+    /// The self-post's HTML, itself HTML-escaped.
+    #[serde(default)]
+    pub selftext_html: Option<String>,
+    #[serde(default)]
+    pub link_flair_text: Option<String>,
+    /// Set once a moderator, admin or the author took the post down.
+    #[serde(default)]
+    pub removed_by_category: Option<String>,
+}
+
+impl RawPost {
+    /// Whether the post was removed or deleted; its title and text are gone
+    /// or about to be.
+    pub fn is_removed(&self) -> bool {
+        self.removed_by_category.is_some()
+            || matches!(self.selftext.trim(), "[removed]" | "[deleted]")
+    }
+
+    /// Plain text of a self-post, links kept as `text (url)`.
+    pub fn text(&self) -> Option<String> {
+        let text = match &self.selftext_html {
+            // The first pass only decodes the escaped markup.
+            Some(escaped) => html::to_text(
+                &html::to_text(escaped, &TextOptions::fragment()),
+                &TextOptions::fragment().with_links(),
+            ),
+            None => self.selftext.trim().to_string(),
+        };
+        (self.is_self && !text.is_empty()).then_some(text)
+    }
+}
+
+/// A post kept for the digest.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RedditPost {
+    pub id: String,
+    pub subreddit: String,
+    pub title: String,
+    /// `None` when the account was deleted.
+    pub author: Option<String>,
+    pub score: i64,
+    pub num_comments: u64,
+    /// The comments page.
+    pub permalink: String,
+    /// The linked page; `None` for self-posts.
+    pub url: Option<String>,
+    pub flair: Option<String>,
+    /// Plain text of a self-post.
+    pub text: Option<String>,
+    pub summary: Option<String>,
+}
+
+impl From<RawPost> for RedditPost {
+    fn from(raw: RawPost) -> Self {
+        let text = raw.text();
+        Self {
+            permalink: format!("{}{}", REDDIT_URL, raw.permalink),
+            url: raw.url.filter(|_| !raw.is_self),
+            author: raw.author.filter(|a| a != "[deleted]"),
+            flair: raw
+                .link_flair_text
+                .map(|f| f.trim().to_string())
+                .filter(|f| !f.is_empty()),
+            id: raw.id,
+            subreddit: raw.subreddit,
+            title: raw.title,
+            score: raw.score,
+            num_comments: raw.num_comments,
+            text,
+            summary: None,
+        }
+    }
+}
+
+impl RedditPost {
+    pub fn to_markdown_string(&self) -> String {
+        let mut meta = format!(
+            "**Score**: {} · {}",
+            self.score,
+            markdown::link(&format!("{} comments", self.num_comments), &self.permalink)
+        );
+        if let Some(flair) = &self.flair {
+            meta.push_str(&format!(" · *{}*", markdown::escape(flair)));
+        }
+        let body = self
+            .url
+            .as_ref()
+            .map(|url| markdown::link("View Link", url))
+            .or_else(|| self.summary.clone())
+            .or_else(|| {
+                self.text
+                    .as_deref()
+                    .map(|text| markdown::excerpt(text, EXCERPT_CHARS))
+            });
+        let mut markdown = format!("{}\n\n{}", markdown::section(3, &self.title), meta);
+        if let Some(body) = body {
+            markdown.push_str("\n\n");
+            markdown.push_str(&body);
+        }
+        markdown
+    }
+}
+
+impl Summarizable for RedditPost {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    fn set_summary(&mut self, summary: String) {
+        self.summary = Some(summary);
+    }
+}
+
+impl archive::Archivable for RedditPost {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "reddit".to_string(),
+            date: date.to_string(),
+            url: Some(self.url.clone().unwrap_or_else(|| self.permalink.clone())),
+            title: self.title.clone(),
+            score: Some(self.score as f64),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+            id: self.id,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Each run's stats are kept at `{date}/history/registries.json`; the file
+//! from a week earlier gives every package its week-over-week change.
+
+use crate::models::{PackageStat, PackageTrend, Registry};
+use common::{CrawlerError, CrawlerResult, SupabaseStorageClient};
+use std::collections::{HashMap, HashSet};
+use time::{Date, Duration};
+use tracing::{info, warn};
+
+pub fn history_path(date: Date) -> String {
+    format!("{}/history/registries.json", date)
+}
+
+/// The stats recorded a week before `date`; empty when there are none or
+/// they cannot be read, which only costs the deltas.
+pub async fn load_last_week(storage: &SupabaseStorageClient, date: Date) -> Vec<PackageStat> {
+    let path = history_path(date - Duration::weeks(1));
+    match storage.download_file(&path).await {
+        Ok(Some(body)) => serde_json::from_str(&body).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable registry history {}: {}", path, e);
+            Vec::new()
+        }),
+        Ok(None) => {
+            info!("No registry history at {}; publishing without deltas", path);
+            Vec::new()
+        }
+        Err(e) => {
+            warn!("Failed to download registry history {}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Records `stats` with one entry per registry and name. A crate listed both
+/// among the most downloaded and the newest has the same recent downloads in
+/// each, so a single entry gives both sections their delta next week.
+pub async fn save(
+    storage: &SupabaseStorageClient,
+    date: Date,
+    stats: &[PackageStat],
+) -> CrawlerResult<()> {
+    let mut seen = HashSet::new();
+    let stats: Vec<&PackageStat> = stats
+        .iter()
+        .filter(|stat| seen.insert((stat.registry, stat.name.as_str())))
+        .collect();
+    let json = serde_json::to_string_pretty(&stats)
+        .map_err(|e| CrawlerError::Parse(format!("Failed to serialize JSON: {}", e)))?;
+    storage
+        .upload_file(&history_path(date), json, "application/json")
+        .await?;
+    Ok(())
+}
+
+/// `stats` with the change in downloads since `last_week`, matched by
+/// registry and name.
+pub fn with_deltas(stats: Vec<PackageStat>, last_week: &[PackageStat]) -> Vec<PackageTrend> {
+    let previous: HashMap<(Registry, &str), u64> = last_week
+        .iter()
+        .map(|stat| ((stat.registry, stat.name.as_str()), stat.downloads_recent))
+        .collect();
+    stats
+        .into_iter()
+        .map(|stat| {
+            let weekly_delta = previous
+                .get(&(stat.registry, stat.name.as_str()))
+                .map(|&before| stat.downloads_recent as i64 - before as i64);
+            PackageTrend { stat, weekly_delta }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(registry: Registry, name: &str, downloads_recent: u64) -> PackageStat {
+        PackageStat {
+            registry,
+            name: name.to_string(),
+            downloads_recent,
+            version: None,
+            description: None,
+            repo_url: None,
+        }
+    }
+
+    #[test]
+    fn deltas_match_on_registry_and_name() {
+        let last_week = [
+            stat(Registry::CratesIo, "serde", 1_000),
+            stat(Registry::CratesIo, "tokio", 900),
+            stat(Registry::Npm, "serde", 5),
+        ];
+        let trends = with_deltas(
+            vec![
+                stat(Registry::CratesIo, "serde", 1_250),
+                stat(Registry::CratesIo, "tokio", 600),
+                stat(Registry::Npm, "react", 80),
+            ],
+            &last_week,
+        );
+        let deltas: Vec<_> = trends.iter().map(|t| t.weekly_delta).collect();
+        assert_eq!(deltas, [Some(250), Some(-300), None]);
+    }
+
+    #[test]
+    fn history_is_filed_under_the_run_date() {
+        let date = Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        assert_eq!(history_path(date), "2024-05-10/history/registries.json");
+        assert_eq!(
+            history_path(date - Duration::weeks(1)),
+            "2024-05-03/history/registries.json"
+        );
+    }
+}
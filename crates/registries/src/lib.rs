@@ -0,0 +1,396 @@
+pub mod history;
+pub mod models;
+
+use async_trait::async_trait;
+use common::archive::{Archivable, ArchiveWriter};
+use common::http::parse_json;
+use common::{
+    markdown, ClientFactory, Config, Crawler, CrawlerContext, CrawlerResult, HttpClient,
+    OutputFormat, Publisher, SupabaseStorageClient,
+};
+use models::{CratesPage, CratesSummary, NpmDownloads, NpmManifest, PackageStat, PackageTrend};
+use tracing::{info, warn};
+
+const CRATES_URL: &str = "https://crates.io/api/v1/crates?sort=recent-downloads&per_page=50";
+const CRATES_SUMMARY_URL: &str = "https://crates.io/api/v1/summary";
+const NPM_DOWNLOADS_URL: &str = "https://api.npmjs.org/downloads/point/last-week";
+const NPM_REGISTRY_URL: &str = "https://registry.npmjs.org";
+
+/// Each section of `{date}/registries.md`, with week-over-week deltas.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sections {
+    /// crates.io's most downloaded crates of the last 90 days.
+    pub top_crates: Vec<PackageTrend>,
+    pub new_crates: Vec<PackageTrend>,
+    /// The npm watchlist, most downloaded first.
+    pub npm: Vec<PackageTrend>,
+}
+
+impl Sections {
+    fn packages(&self) -> impl Iterator<Item = &PackageTrend> {
+        self.top_crates
+            .iter()
+            .chain(&self.new_crates)
+            .chain(&self.npm)
+    }
+}
+
+/// Publishes the most downloaded crates, the newest crates and the npm
+/// watchlist's weekly downloads to `{date}/registries.md`.
+pub struct RegistriesCrawler {
+    client: HttpClient,
+    publisher: Publisher,
+    storage: SupabaseStorageClient,
+    output_formats: Vec<OutputFormat>,
+    npm_watchlist: Vec<String>,
+    archive: Option<ArchiveWriter>,
+}
+
+impl RegistriesCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self {
+            client: ClientFactory::from_config(config).build()?,
+            publisher: Publisher::for_crawler(config, "registries")?,
+            storage: SupabaseStorageClient::from_config(config)?,
+            output_formats: config.output_formats_for("registries"),
+            npm_watchlist: config.npm_watchlist.clone(),
+            archive: ArchiveWriter::from_config(config)?,
+        })
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        ctx: &CrawlerContext,
+        url: &str,
+    ) -> CrawlerResult<T> {
+        let body = ctx.http_client(&self.client).get_text(url).await?;
+        parse_json(url, &body)
+    }
+
+    async fn fetch_npm(&self, ctx: &CrawlerContext, name: &str) -> CrawlerResult<PackageStat> {
+        let downloads: NpmDownloads = self
+            .get(ctx, &format!("{}/{}", NPM_DOWNLOADS_URL, name))
+            .await?;
+        let manifest: NpmManifest = self
+            .get(ctx, &format!("{}/{}/latest", NPM_REGISTRY_URL, name))
+            .await?;
+        Ok(PackageStat::from_npm(name, downloads, manifest))
+    }
+
+    /// The current stats of every section. Only the crates.io listing is
+    /// required; the new crates and each watched npm package are reported
+    /// and skipped when they fail.
+    async fn fetch_stats(
+        &self,
+        ctx: &CrawlerContext,
+    ) -> CrawlerResult<(Vec<PackageStat>, Vec<PackageStat>, Vec<PackageStat>)> {
+        let page: CratesPage = self.get(ctx, CRATES_URL).await?;
+        let top_crates = page.crates.into_iter().map(PackageStat::from).collect();
+
+        let new_crates = match self.get::<CratesSummary>(ctx, CRATES_SUMMARY_URL).await {
+            Ok(summary) => summary
+                .new_crates
+                .into_iter()
+                .map(PackageStat::from)
+                .collect(),
+            Err(e) => {
+                warn!("Failed to fetch new crates: {}", e);
+                ctx.item_failed("crates.io new crates", &e);
+                Vec::new()
+            }
+        };
+
+        let mut npm = Vec::new();
+        for name in &self.npm_watchlist {
+            match self.fetch_npm(ctx, name).await {
+                Ok(stat) => npm.push(stat),
+                Err(e) => {
+                    warn!("Failed to fetch npm package {}: {}", name, e);
+                    ctx.item_failed(format!("npm {}", name), &e);
+                }
+            }
+        }
+        npm.sort_by_key(|stat: &PackageStat| std::cmp::Reverse(stat.downloads_recent));
+        Ok((top_crates, new_crates, npm))
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let (top_crates, new_crates, npm) = self.fetch_stats(ctx).await?;
+        let storage = ctx.storage_client(&self.storage);
+        let last_week = history::load_last_week(&storage, ctx.date).await;
+        let sections = Sections {
+            top_crates: history::with_deltas(top_crates, &last_week),
+            new_crates: history::with_deltas(new_crates, &last_week),
+            npm: history::with_deltas(npm, &last_week),
+        };
+
+        let packages: Vec<PackageTrend> = sections.packages().cloned().collect();
+        let stats: Vec<PackageStat> = packages.iter().map(|p| p.stat.clone()).collect();
+        history::save(&storage, ctx.date, &stats).await?;
+
+        let stem = format!("{}/registries", ctx.date);
+        let written = ctx
+            .publisher(&self.publisher)
+            .publish_outputs(
+                &stem,
+                &self.output_formats,
+                || format_markdown(&sections),
+                &packages,
+            )
+            .await?;
+        info!(
+            "Uploaded {} registry packages to {}",
+            packages.len(),
+            written.join(", ")
+        );
+        if let Some(archive) = &self.archive {
+            archive
+                .record(
+                    packages
+                        .into_iter()
+                        .map(|package| package.into_item(ctx.date)),
+                )
+                .await;
+        }
+        Ok(())
+    }
+}
+
+fn format_markdown(sections: &Sections) -> String {
+    fn list(markdown: &mut String, packages: &[PackageTrend]) {
+        for (i, package) in packages.iter().enumerate() {
+            markdown.push('\n');
+            markdown.push_str(&package.to_markdown_line(i + 1));
+        }
+    }
+
+    let mut markdown = markdown::section(1, "Package Registries");
+    markdown.push_str("\n\n");
+    markdown.push_str(&markdown::section(2, "crates.io"));
+    markdown.push_str("\n\nMost downloaded over the last 90 days.\n");
+    list(&mut markdown, &sections.top_crates);
+    if !sections.new_crates.is_empty() {
+        markdown.push_str("\n\n");
+        markdown.push_str(&markdown::section(3, "New crates"));
+        markdown.push('\n');
+        list(&mut markdown, &sections.new_crates);
+    }
+    if !sections.npm.is_empty() {
+        markdown.push_str("\n\n");
+        markdown.push_str(&markdown::section(2, "npm"));
+        markdown.push_str("\n\nDownloads over the last week.\n");
+        list(&mut markdown, &sections.npm);
+    }
+    markdown
+}
+
+#[async_trait]
+impl Crawler for RegistriesCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Registries crawler starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Package Registries"
+    }
+
+    fn slug(&self) -> &'static str {
+        "registries"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if !config.registries_enabled {
+            return Err("REGISTRIES_ENABLED not set".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(RegistriesCrawler {
+    name: "Registries crawler",
+    slug: "registries",
+    required_env: ["REGISTRIES_ENABLED"],
+    digest: ("registries", "Package Registries"),
+    new: RegistriesCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::Registry;
+    use test_support::{assert_snapshot, fixture, FixtureDir, MockSupabase, TestConfig};
+
+    fn fixtures() -> FixtureDir {
+        let fixtures = FixtureDir::new();
+        fixtures.add(CRATES_URL, &fixture("registries/crates.json"));
+        fixtures.add(CRATES_SUMMARY_URL, &fixture("registries/summary.json"));
+        for (name, file) in [("react", "react"), ("@types/node", "types_node")] {
+            fixtures.add(
+                &format!("{}/{}", NPM_DOWNLOADS_URL, name),
+                &fixture(&format!("registries/npm_{}_downloads.json", file)),
+            );
+            fixtures.add(
+                &format!("{}/{}/latest", NPM_REGISTRY_URL, name),
+                &fixture(&format!("registries/npm_{}_latest.json", file)),
+            );
+        }
+        fixtures
+    }
+
+    #[test]
+    fn deserializes_the_recorded_responses() {
+        let page: CratesPage = serde_json::from_str(&fixture("registries/crates.json")).unwrap();
+        let top: Vec<PackageStat> = page.crates.into_iter().map(PackageStat::from).collect();
+        assert_eq!(top.len(), 3);
+        assert_eq!(
+            top[0],
+            PackageStat {
+                registry: Registry::CratesIo,
+                name: "syn".to_string(),
+                downloads_recent: 58_012_448,
+                version: Some("2.0.66".to_string()),
+                description: Some("Parser for Rust source code".to_string()),
+                repo_url: Some("https://github.com/dtolnay/syn".to_string()),
+            }
+        );
+
+        let summary: CratesSummary =
+            serde_json::from_str(&fixture("registries/summary.json")).unwrap();
+        let fresh = PackageStat::from(summary.new_crates.into_iter().next().unwrap());
+        assert_eq!(fresh.downloads_recent, 0, "not counted yet");
+        assert_eq!(
+            fresh.version.as_deref(),
+            Some("0.1.0-alpha.1"),
+            "no stable release"
+        );
+
+        let stat = PackageStat::from_npm(
+            "react",
+            serde_json::from_str(&fixture("registries/npm_react_downloads.json")).unwrap(),
+            serde_json::from_str(&fixture("registries/npm_react_latest.json")).unwrap(),
+        );
+        assert_eq!(stat.downloads_recent, 25_104_553);
+        assert_eq!(
+            stat.repo_url.as_deref(),
+            Some("https://github.com/facebook/react")
+        );
+    }
+
+    #[tokio::test]
+    async fn publishes_sections_with_weekly_deltas() {
+        let fixtures = fixtures();
+        let supabase = MockSupabase::start().await;
+        supabase
+            .serve_file(
+                "2024-05-03/history/registries.json",
+                &fixture("registries/history.json"),
+            )
+            .await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| {
+                c.registries_enabled = true;
+                c.npm_watchlist = vec!["@types/node".into(), "react".into(), "left-pad-2".into()];
+            })
+            .build();
+        let date = time::Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        let ctx = CrawlerContext::default().with_date(date);
+        let crawler = RegistriesCrawler::new(&config).unwrap();
+        crawler.run_with_context(&ctx).await.unwrap();
+
+        let failed: Vec<String> = ctx.item_errors.take().into_iter().map(|e| e.item).collect();
+        assert_eq!(failed, ["npm left-pad-2"]);
+        assert_snapshot!(
+            "registries.md",
+            supabase.uploaded("2024-05-10/registries.md").await
+        );
+
+        let json: Vec<serde_json::Value> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/registries.json").await).unwrap();
+        let npm: Vec<_> = json
+            .iter()
+            .filter(|p| p["registry"] == "npm")
+            .map(|p| (p["name"].as_str().unwrap(), p["weekly_delta"].clone()))
+            .collect();
+        assert_eq!(
+            npm,
+            [
+                ("@types/node", serde_json::json!(-512_330)),
+                ("react", serde_json::Value::Null)
+            ]
+        );
+
+        let history: Vec<PackageStat> = serde_json::from_str(
+            &supabase
+                .uploaded("2024-05-10/history/registries.json")
+                .await,
+        )
+        .unwrap();
+        assert_eq!(history.len(), json.len());
+        assert_eq!(history[0].name, "syn");
+    }
+
+    #[tokio::test]
+    async fn an_empty_watchlist_leaves_out_npm() {
+        let fixtures = fixtures();
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .with(|c| c.registries_enabled = true)
+            .build();
+        let crawler = RegistriesCrawler::new(&config).unwrap();
+        let ctx = CrawlerContext::default();
+        crawler.run_with_context(&ctx).await.unwrap();
+
+        assert!(ctx.item_errors.take().is_empty());
+        let markdown = supabase
+            .uploaded(&format!("{}/registries.md", ctx.date))
+            .await;
+        assert!(markdown.contains("### New crates"));
+        assert!(!markdown.contains("## npm"));
+        assert!(!markdown.contains("last week"), "no history, no deltas");
+    }
+
+    #[tokio::test]
+    async fn a_crate_in_both_crates_io_sections_is_recorded_once() {
+        let fixtures = fixtures();
+        let mut summary: serde_json::Value =
+            serde_json::from_str(&fixture("registries/summary.json")).unwrap();
+        summary["new_crates"][0]["name"] = "tokio".into();
+        summary["new_crates"][0]["recent_downloads"] = 23_911_420.into();
+        fixtures.add(CRATES_SUMMARY_URL, &summary.to_string());
+        let supabase = MockSupabase::start().await;
+        supabase
+            .serve_file(
+                "2024-05-03/history/registries.json",
+                &fixture("registries/history.json"),
+            )
+            .await;
+        let config = TestConfig::new(&supabase)
+            .fixture_dir(fixtures.path())
+            .with(|c| c.registries_enabled = true)
+            .build();
+        let date = time::Date::from_calendar_date(2024, time::Month::May, 10).unwrap();
+        let ctx = CrawlerContext::default().with_date(date);
+        let crawler = RegistriesCrawler::new(&config).unwrap();
+        crawler.run_with_context(&ctx).await.unwrap();
+
+        let history: Vec<PackageStat> = serde_json::from_str(
+            &supabase
+                .uploaded("2024-05-10/history/registries.json")
+                .await,
+        )
+        .unwrap();
+        let tokio: Vec<_> = history.iter().filter(|s| s.name == "tokio").collect();
+        assert_eq!(tokio.len(), 1);
+        let markdown = supabase.uploaded("2024-05-10/registries.md").await;
+        assert_eq!(
+            markdown.matches("(+0 on last week's 90-day total)").count(),
+            2,
+            "{}",
+            markdown
+        );
+    }
+}
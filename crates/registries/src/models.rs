@@ -0,0 +1,214 @@
+use common::archive;
+use common::markdown;
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Registry {
+    CratesIo,
+    Npm,
+}
+
+impl Registry {
+    pub fn label(self) -> &'static str {
+        match self {
+            Registry::CratesIo => "crates.io",
+            Registry::Npm => "npm",
+        }
+    }
+}
+
+/// A package and its recent downloads: the last 90 days on crates.io, the
+/// last week on npm.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PackageStat {
+    pub registry: Registry,
+    pub name: String,
+    pub downloads_recent: u64,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub repo_url: Option<String>,
+}
+
+impl PackageStat {
+    pub fn from_npm(name: &str, downloads: NpmDownloads, manifest: NpmManifest) -> Self {
+        Self {
+            registry: Registry::Npm,
+            name: name.to_string(),
+            downloads_recent: downloads.downloads,
+            version: Some(manifest.version),
+            description: manifest.description.map(|d| d.trim().to_string()),
+            repo_url: manifest.repository.as_ref().map(NpmRepository::web_url),
+        }
+    }
+
+    /// The package's page on its registry.
+    pub fn page_url(&self) -> String {
+        match self.registry {
+            Registry::CratesIo => format!("https://crates.io/crates/{}", self.name),
+            Registry::Npm => format!("https://www.npmjs.com/package/{}", self.name),
+        }
+    }
+}
+
+/// A package with the change in its recent downloads since the last week's run.
+/// On crates.io that is the difference between two 90-day totals, not the
+/// downloads gained in the week, so it is labelled as such.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PackageTrend {
+    #[serde(flatten)]
+    pub stat: PackageStat,
+    /// `None` when the package was not in last week's history.
+    pub weekly_delta: Option<i64>,
+}
+
+impl PackageTrend {
+    /// One numbered list item, e.g.
+    /// `1. [serde](…) 1.0.203 · 28512331 downloads (+1204118 on last week's 90-day total) · [repo](…) — …`.
+    pub fn to_markdown_line(&self, rank: usize) -> String {
+        let stat = &self.stat;
+        let mut line = format!("{}. {}", rank, markdown::link(&stat.name, &stat.page_url()));
+        if let Some(version) = &stat.version {
+            line.push_str(&format!(" {}", markdown::escape(version)));
+        }
+        line.push_str(&format!(" · {} downloads", stat.downloads_recent));
+        if let Some(delta) = self.weekly_delta {
+            let compared_to = match stat.registry {
+                Registry::CratesIo => "on last week's 90-day total",
+                Registry::Npm => "vs last week",
+            };
+            line.push_str(&format!(" ({:+} {})", delta, compared_to));
+        }
+        if let Some(repo_url) = &stat.repo_url {
+            line.push_str(&format!(" · {}", markdown::link("repo", repo_url)));
+        }
+        if let Some(description) = stat.description.as_deref().filter(|d| !d.trim().is_empty()) {
+            line.push_str(&format!(" — {}", markdown::escape(description)));
+        }
+        line
+    }
+}
+
+impl archive::Archivable for PackageTrend {
+    fn into_item(self, date: Date) -> archive::Item {
+        archive::Item {
+            source: "registries".to_string(),
+            date: date.to_string(),
+            url: Some(self.stat.page_url()),
+            title: self.stat.name.clone(),
+            score: Some(self.stat.downloads_recent as f64),
+            id: format!("{}:{}", self.stat.registry.label(), self.stat.name),
+            raw: serde_json::to_value(&self).unwrap_or_default(),
+        }
+    }
+}
+
+/// A page of `/api/v1/crates`.
+#[derive(Debug, Deserialize)]
+pub struct CratesPage {
+    pub crates: Vec<ApiCrate>,
+}
+
+/// `/api/v1/summary`, of which only the newest crates are used.
+#[derive(Debug, Deserialize)]
+pub struct CratesSummary {
+    pub new_crates: Vec<ApiCrate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiCrate {
+    pub name: String,
+    pub description: Option<String>,
+    /// Missing for crates too new to have been counted.
+    #[serde(default)]
+    pub recent_downloads: Option<u64>,
+    #[serde(default)]
+    pub max_stable_version: Option<String>,
+    pub newest_version: String,
+    pub repository: Option<String>,
+}
+
+impl From<ApiCrate> for PackageStat {
+    fn from(krate: ApiCrate) -> Self {
+        Self {
+            registry: Registry::CratesIo,
+            name: krate.name,
+            downloads_recent: krate.recent_downloads.unwrap_or(0),
+            version: Some(krate.max_stable_version.unwrap_or(krate.newest_version)),
+            description: krate.description.map(|d| d.trim().to_string()),
+            repo_url: krate.repository,
+        }
+    }
+}
+
+/// `api.npmjs.org/downloads/point/last-week/{package}`.
+#[derive(Debug, Deserialize)]
+pub struct NpmDownloads {
+    pub downloads: u64,
+}
+
+/// `registry.npmjs.org/{package}/latest`.
+#[derive(Debug, Deserialize)]
+pub struct NpmManifest {
+    pub version: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub repository: Option<NpmRepository>,
+}
+
+/// npm accepts a bare string or an object with a `url`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum NpmRepository {
+    Url(String),
+    Object { url: String },
+}
+
+impl NpmRepository {
+    /// A browsable URL: `git+https://…/x.git` and `github:owner/repo` become
+    /// `https://github.com/…` style links.
+    pub fn web_url(&self) -> String {
+        let raw = match self {
+            NpmRepository::Url(url) | NpmRepository::Object { url } => url.trim(),
+        };
+        let url = raw.trim_start_matches("git+").trim_end_matches(".git");
+        if let Some(path) = url.strip_prefix("github:") {
+            return format!("https://github.com/{}", path);
+        }
+        if let Some(rest) = url.strip_prefix("git://") {
+            return format!("https://{}", rest);
+        }
+        if let Some(rest) = url.strip_prefix("ssh://git@") {
+            return format!("https://{}", rest);
+        }
+        if !url.contains("://") && url.matches('/').count() == 1 {
+            return format!("https://github.com/{}", url);
+        }
+        url.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_npm_repository_urls() {
+        let web = |raw: &str| NpmRepository::Url(raw.to_string()).web_url();
+        assert_eq!(
+            web("git+https://github.com/facebook/react.git"),
+            "https://github.com/facebook/react"
+        );
+        assert_eq!(web("github:vitejs/vite"), "https://github.com/vitejs/vite");
+        assert_eq!(
+            web("expressjs/express"),
+            "https://github.com/expressjs/express"
+        );
+        assert_eq!(
+            web("git://github.com/lodash/lodash.git"),
+            "https://github.com/lodash/lodash"
+        );
+    }
+}
@@ -0,0 +1,554 @@
+use async_trait::async_trait;
+use common::output::load_items;
+use common::{
+    markdown, Config, Crawler, CrawlerContext, CrawlerResult, OutputFormat, Publisher,
+    SupabaseStorageClient,
+};
+use futures::future::join_all;
+use github::Repository;
+use hacker_news::models::StoryData;
+use mcp_rankings::models::McpServer;
+use openrouter::models::ModelRanking;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use time::{Date, Duration};
+use tracing::{info, warn};
+
+const HN_ITEM_URL_FORMAT: &str = "https://news.ycombinator.com/item?id={id}";
+
+/// One source's daily outputs for the week, oldest first, and the days it
+/// published nothing.
+#[derive(Debug, Clone)]
+pub struct Snapshots<T> {
+    pub days: Vec<(Date, Vec<T>)>,
+    pub missing: Vec<Date>,
+}
+
+impl<T> Default for Snapshots<T> {
+    fn default() -> Self {
+        Self {
+            days: Vec::new(),
+            missing: Vec::new(),
+        }
+    }
+}
+
+impl<T> Snapshots<T> {
+    fn first(&self) -> Option<&[T]> {
+        self.days.first().map(|(_, items)| items.as_slice())
+    }
+
+    fn last(&self) -> Option<&[T]> {
+        self.days.last().map(|(_, items)| items.as_slice())
+    }
+}
+
+/// Everything the roll-up reads for one week.
+#[derive(Debug, Clone, Default)]
+pub struct WeekArtifacts {
+    pub github: Snapshots<Repository>,
+    pub hacker_news: Snapshots<StoryData>,
+    pub openrouter: Snapshots<ModelRanking>,
+    pub mcp: Snapshots<McpServer>,
+}
+
+/// A repository and how often it trended during the week.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrendingLeader {
+    pub name: String,
+    pub link: String,
+    pub days: usize,
+    /// Highest star count seen during the week.
+    pub stars: u64,
+}
+
+/// A model's OpenRouter rank at the start and end of the week.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mover {
+    pub name: String,
+    pub from_rank: usize,
+    pub to_rank: usize,
+}
+
+impl Mover {
+    /// Places gained; negative when the model dropped.
+    pub fn change(&self) -> i64 {
+        self.from_rank as i64 - self.to_rank as i64
+    }
+}
+
+/// Repositories that trended on the most days, ties broken by stars.
+pub fn trending_leaders(github: &Snapshots<Repository>, n: usize) -> Vec<TrendingLeader> {
+    let mut leaders: HashMap<&str, TrendingLeader> = HashMap::new();
+    for (_, repositories) in &github.days {
+        let mut seen_today = HashSet::new();
+        for repo in repositories {
+            let leader = leaders.entry(&repo.name).or_insert_with(|| TrendingLeader {
+                name: repo.name.clone(),
+                link: repo.link.clone(),
+                days: 0,
+                stars: 0,
+            });
+            // A repo trending in several languages counts once per day.
+            if seen_today.insert(repo.name.as_str()) {
+                leader.days += 1;
+            }
+            leader.stars = leader.stars.max(u64::from(repo.stars));
+        }
+    }
+    let mut leaders: Vec<_> = leaders.into_values().collect();
+    leaders.sort_by(|a, b| {
+        b.days
+            .cmp(&a.days)
+            .then(b.stars.cmp(&a.stars))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    leaders.truncate(n);
+    leaders
+}
+
+/// The highest-scoring stories of the week, each counted once at its best score.
+pub fn top_stories(hacker_news: &Snapshots<StoryData>, n: usize) -> Vec<StoryData> {
+    let mut best: HashMap<u64, &StoryData> = HashMap::new();
+    for story in hacker_news.days.iter().flat_map(|(_, stories)| stories) {
+        best.entry(story.story_id)
+            .and_modify(|current| {
+                if story.score > current.score {
+                    *current = story;
+                }
+            })
+            .or_insert(story);
+    }
+    let mut stories: Vec<StoryData> = best.into_values().cloned().collect();
+    stories.sort_by(|a, b| b.score.cmp(&a.score).then(a.story_id.cmp(&b.story_id)));
+    stories.truncate(n);
+    stories
+}
+
+/// Models whose rank changed most between the first and last snapshot of the
+/// week. Models missing from either snapshot are left out.
+pub fn openrouter_movers(openrouter: &Snapshots<ModelRanking>, n: usize) -> Vec<Mover> {
+    let (Some(first), Some(last)) = (openrouter.first(), openrouter.last()) else {
+        return Vec::new();
+    };
+    let start: HashMap<&str, usize> = first.iter().map(|m| (m.name.as_str(), m.rank)).collect();
+    let mut movers: Vec<Mover> = last
+        .iter()
+        .filter_map(|model| {
+            let from_rank = *start.get(model.name.as_str())?;
+            (from_rank != model.rank).then(|| Mover {
+                name: model.name.clone(),
+                from_rank,
+                to_rank: model.rank,
+            })
+        })
+        .collect();
+    movers.sort_by(|a, b| {
+        b.change()
+            .abs()
+            .cmp(&a.change().abs())
+            .then(b.change().cmp(&a.change()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    movers.truncate(n);
+    movers
+}
+
+/// Servers in the week's last snapshot that were not in its first, by stars.
+pub fn new_mcp_servers(mcp: &Snapshots<McpServer>, n: usize) -> Vec<McpServer> {
+    if mcp.days.len() < 2 {
+        return Vec::new();
+    }
+    let (Some(first), Some(last)) = (mcp.first(), mcp.last()) else {
+        return Vec::new();
+    };
+    let known: HashSet<&str> = first.iter().map(|s| s.name.as_str()).collect();
+    let mut servers: Vec<McpServer> = last
+        .iter()
+        .filter(|server| !known.contains(server.name.as_str()))
+        .cloned()
+        .collect();
+    servers.sort_by(|a, b| b.stars.cmp(&a.stars).then_with(|| a.name.cmp(&b.name)));
+    servers.truncate(n);
+    servers
+}
+
+/// Writes the note under a section heading about days without data. Returns
+/// false when the source has no data at all, so the section can stop there.
+fn push_coverage<T>(out: &mut String, snapshots: &Snapshots<T>) -> bool {
+    if snapshots.days.is_empty() {
+        out.push_str("_No data this week._\n\n");
+        return false;
+    }
+    if !snapshots.missing.is_empty() {
+        let days: Vec<String> = snapshots.missing.iter().map(Date::to_string).collect();
+        out.push_str(&format!("_No data for {}._\n\n", days.join(", ")));
+    }
+    true
+}
+
+fn push_list(out: &mut String, lines: Vec<String>, empty: &str) {
+    if lines.is_empty() {
+        out.push_str(empty);
+        out.push('\n');
+    }
+    for (index, line) in lines.iter().enumerate() {
+        out.push_str(&format!("{}. {}\n", index + 1, line));
+    }
+    out.push('\n');
+}
+
+/// Renders the week in review with one section per source, `top_n` entries each.
+pub fn format_rollup(week_start: Date, artifacts: &WeekArtifacts, top_n: usize) -> String {
+    let mut out = markdown::section(1, &format!("Week in Review: {}", week_start));
+    out.push_str("\n\n");
+
+    out.push_str(&markdown::section(2, "GitHub Trending Leaders"));
+    out.push_str("\n\n");
+    if push_coverage(&mut out, &artifacts.github) {
+        let lines = trending_leaders(&artifacts.github, top_n)
+            .iter()
+            .map(|leader| {
+                format!(
+                    "{} — trending {} day(s), {} stars",
+                    markdown::link(&leader.name, &leader.link),
+                    leader.days,
+                    leader.stars
+                )
+            })
+            .collect();
+        push_list(&mut out, lines, "No trending repositories.");
+    }
+
+    out.push_str(&markdown::section(2, "Top Hacker News Stories"));
+    out.push_str("\n\n");
+    if push_coverage(&mut out, &artifacts.hacker_news) {
+        let lines = top_stories(&artifacts.hacker_news, top_n)
+            .iter()
+            .map(|story| {
+                let url = story.url.clone().unwrap_or_else(|| {
+                    HN_ITEM_URL_FORMAT.replace("{id}", &story.story_id.to_string())
+                });
+                format!(
+                    "{} — {} points",
+                    markdown::link(&story.title, &url),
+                    story.score
+                )
+            })
+            .collect();
+        push_list(&mut out, lines, "No stories.");
+    }
+
+    out.push_str(&markdown::section(2, "OpenRouter Movers"));
+    out.push_str("\n\n");
+    if push_coverage(&mut out, &artifacts.openrouter) {
+        let lines = openrouter_movers(&artifacts.openrouter, top_n)
+            .iter()
+            .map(|mover| {
+                format!(
+                    "{} — #{} → #{} ({:+})",
+                    markdown::escape(&mover.name),
+                    mover.from_rank,
+                    mover.to_rank,
+                    mover.change()
+                )
+            })
+            .collect();
+        push_list(&mut out, lines, "No rank changes.");
+    }
+
+    out.push_str(&markdown::section(2, "New MCP Servers"));
+    out.push_str("\n\n");
+    if push_coverage(&mut out, &artifacts.mcp) {
+        let lines = new_mcp_servers(&artifacts.mcp, top_n)
+            .iter()
+            .map(|server| {
+                format!(
+                    "{} — {} stars: {}",
+                    markdown::escape(&server.name),
+                    server.stars,
+                    markdown::escape(&server.description)
+                )
+            })
+            .collect();
+        push_list(&mut out, lines, "No new servers.");
+    }
+
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// The Monday starting the week that contains `date`.
+pub fn week_start(date: Date) -> Date {
+    date - Duration::days(date.weekday().number_days_from_monday() as i64)
+}
+
+/// Meta-crawler combining the week's GitHub, Hacker News, OpenRouter and MCP
+/// outputs into `{week-start}/weekly-review.md`. It rewrites the document on
+/// each run, so mid-week it covers the days so far.
+pub struct WeeklyRollupCrawler {
+    publisher: Publisher,
+    formats: Vec<OutputFormat>,
+    top_n: usize,
+}
+
+impl WeeklyRollupCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self {
+            publisher: Publisher::for_crawler(config, "weekly_rollup")?,
+            formats: config.document_formats_for("weekly_rollup"),
+            top_n: config.weekly_rollup_top_n,
+        })
+    }
+
+    async fn load_week<T: DeserializeOwned>(
+        storage: &SupabaseStorageClient,
+        stem: &str,
+        days: &[Date],
+    ) -> Snapshots<T> {
+        let loads = days.iter().map(|&day| async move {
+            (day, load_items(storage, &format!("{}/{}", day, stem)).await)
+        });
+        let mut snapshots = Snapshots::default();
+        for (day, loaded) in join_all(loads).await {
+            match loaded {
+                Ok(Some(items)) => snapshots.days.push((day, items)),
+                Ok(None) => snapshots.missing.push(day),
+                Err(e) => {
+                    warn!("Skipping {}/{} in the weekly roll-up: {}", day, stem, e);
+                    snapshots.missing.push(day);
+                }
+            }
+        }
+        snapshots
+    }
+
+    async fn process(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        let today = ctx.date;
+        let publisher = ctx.publisher(&self.publisher);
+        let storage = publisher.storage();
+        let start = week_start(today);
+        let mut days = Vec::new();
+        let mut day = start;
+        while day <= today {
+            days.push(day);
+            day += Duration::days(1);
+        }
+
+        let (github, hacker_news, openrouter, mcp) = futures::join!(
+            Self::load_week(storage, "github-trending", &days),
+            Self::load_week(storage, "hacker-news", &days),
+            Self::load_week(storage, "openrouter-rankings", &days),
+            Self::load_week(storage, "mcp-rankings", &days),
+        );
+        let artifacts = WeekArtifacts {
+            github,
+            hacker_news,
+            openrouter,
+            mcp,
+        };
+
+        let stem = format!("{}/weekly-review", start);
+        let written = publisher
+            .publish_outputs::<(), _>(
+                &stem,
+                &self.formats,
+                || format_rollup(start, &artifacts, self.top_n),
+                &[],
+            )
+            .await?;
+        info!("Uploaded weekly review to {}", written.join(", "));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Crawler for WeeklyRollupCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("Weekly roll-up starting up");
+        self.process(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "Weekly Roll-up"
+    }
+
+    fn slug(&self) -> &'static str {
+        "weekly_rollup"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if !config.weekly_rollup {
+            return Err("WEEKLY_ROLLUP not set".to_string());
+        }
+        Ok(())
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &["github", "hacker_news", "openrouter", "mcp_rankings"]
+    }
+}
+
+common::register_crawler!(WeeklyRollupCrawler {
+    name: "Weekly Roll-up crawler",
+    slug: "weekly_rollup",
+    required_env: ["WEEKLY_ROLLUP"],
+    new: WeeklyRollupCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{repo, story};
+    use time::macros::date;
+
+    fn models(names: &[&str]) -> Vec<ModelRanking> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ModelRanking::new(i + 1, name.to_string(), 0.0))
+            .collect()
+    }
+
+    fn servers(entries: &[(&str, u32)]) -> Vec<McpServer> {
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, (name, stars))| {
+                McpServer::new(i + 1, name.to_string(), format!("{} server", name), *stars)
+            })
+            .collect()
+    }
+
+    fn artifacts() -> WeekArtifacts {
+        let mon = date!(2024 - 05 - 06);
+        let tue = date!(2024 - 05 - 07);
+        let wed = date!(2024 - 05 - 08);
+        WeekArtifacts {
+            github: Snapshots {
+                days: vec![
+                    (mon, vec![repo("a/fast", 1200), repo("b/new", 90)]),
+                    (
+                        wed,
+                        vec![
+                            repo("a/fast", 1500),
+                            repo("c/big", 9000),
+                            repo("a/fast", 1500),
+                        ],
+                    ),
+                ],
+                missing: vec![tue],
+            },
+            hacker_news: Snapshots {
+                days: vec![
+                    (mon, vec![story(1, "Rust 2.0", 100), story(2, "Ask HN", 50)]),
+                    (
+                        tue,
+                        vec![story(1, "Rust 2.0", 420), story(3, "Show HN", 300)],
+                    ),
+                ],
+                missing: vec![wed],
+            },
+            openrouter: Snapshots {
+                days: vec![
+                    (mon, models(&["gpt", "claude", "gemini", "llama"])),
+                    (
+                        wed,
+                        models(&["claude", "gpt", "llama", "gemini", "mistral"]),
+                    ),
+                ],
+                missing: vec![tue],
+            },
+            mcp: Snapshots {
+                days: vec![],
+                missing: vec![mon, tue, wed],
+            },
+        }
+    }
+
+    #[test]
+    fn selectors_pick_the_weeks_leaders() {
+        let week = artifacts();
+
+        let leaders = trending_leaders(&week.github, 2);
+        assert_eq!(
+            leaders
+                .iter()
+                .map(|l| (l.name.as_str(), l.days, l.stars))
+                .collect::<Vec<_>>(),
+            vec![("a/fast", 2, 1500), ("c/big", 1, 9000)]
+        );
+
+        let stories = top_stories(&week.hacker_news, 3);
+        assert_eq!(
+            stories
+                .iter()
+                .map(|s| (s.story_id, s.score))
+                .collect::<Vec<_>>(),
+            vec![(1, 420), (3, 300), (2, 50)]
+        );
+
+        let movers = openrouter_movers(&week.openrouter, 10);
+        assert_eq!(
+            movers
+                .iter()
+                .map(|m| (m.name.as_str(), m.change()))
+                .collect::<Vec<_>>(),
+            vec![("claude", 1), ("llama", 1), ("gemini", -1), ("gpt", -1)]
+        );
+
+        let mcp = Snapshots {
+            days: vec![
+                (date!(2024 - 05 - 06), servers(&[("fs", 10)])),
+                (
+                    date!(2024 - 05 - 08),
+                    servers(&[("fs", 12), ("git", 5), ("web", 50)]),
+                ),
+            ],
+            missing: vec![],
+        };
+        let new: Vec<_> = new_mcp_servers(&mcp, 10)
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(new, vec!["web", "git"]);
+        assert!(new_mcp_servers(
+            &Snapshots {
+                days: mcp.days[..1].to_vec(),
+                missing: vec![]
+            },
+            10
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn rollup_notes_missing_days_and_sources() {
+        assert_eq!(
+            format_rollup(date!(2024 - 05 - 06), &artifacts(), 2),
+            "# Week in Review: 2024-05-06\n\n\
+             ## GitHub Trending Leaders\n\n\
+             _No data for 2024-05-07._\n\n\
+             1. [a/fast](https://github.com/a/fast) — trending 2 day(s), 1500 stars\n\
+             2. [c/big](https://github.com/c/big) — trending 1 day(s), 9000 stars\n\n\
+             ## Top Hacker News Stories\n\n\
+             _No data for 2024-05-08._\n\n\
+             1. [Rust 2.0](https://news.ycombinator.com/item?id=1) — 420 points\n\
+             2. [Show HN](https://news.ycombinator.com/item?id=3) — 300 points\n\n\
+             ## OpenRouter Movers\n\n\
+             _No data for 2024-05-07._\n\n\
+             1. claude — #2 → #1 (+1)\n\
+             2. llama — #4 → #3 (+1)\n\n\
+             ## New MCP Servers\n\n\
+             _No data this week._\n"
+        );
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        assert_eq!(week_start(date!(2024 - 05 - 06)), date!(2024 - 05 - 06));
+        assert_eq!(week_start(date!(2024 - 05 - 12)), date!(2024 - 05 - 06));
+        assert_eq!(week_start(date!(2024 - 05 - 08)), date!(2024 - 05 - 06));
+    }
+}
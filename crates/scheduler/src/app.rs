@@ -0,0 +1,319 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use common::{Config, SupabaseStorageClient};
+use notify::{MultiNotifier, Notifier};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::retry::RetryOptions;
+use crate::scheduler::JobStats;
+use crate::shutdown::{self, PendingSends};
+use crate::startup::{self, StartupOptions};
+use crate::status::SchedulerStatus;
+use crate::status::{self as status_server, StatusServerOptions};
+use crate::{heartbeat, job, once, schedule, DailyScheduler};
+
+/// Registers all configured jobs and runs the scheduler until Ctrl+C or SIGTERM.
+pub async fn run(config: Config) -> Result<()> {
+    run_until(config, shutdown::signal()).await
+}
+
+/// Like [`run`], but shuts down gracefully once `shutdown` completes.
+pub async fn run_until(config: Config, shutdown: impl Future<Output = ()>) -> Result<()> {
+    let storage = SupabaseStorageClient::from_config(&config)?;
+    let startup_options = StartupOptions::from_env()?;
+    let status_options = StatusServerOptions::from_env()?;
+    let drain_timeout = schedule::drain_timeout_from_env()?;
+    let heartbeat_interval = heartbeat::interval_from_env()?;
+    let shutdown_grace = shutdown::grace_from_env()?;
+    let notifier = notifier_from_config(&config)?;
+    let pending = PendingSends::default();
+    let mut scheduler = DailyScheduler::new().await?;
+    scheduler.set_retry_options(RetryOptions::from_env()?);
+    scheduler.set_jitter_window(schedule::jitter_window_from_env()?);
+    let mut registered: Vec<(String, Vec<String>)> = Vec::new();
+
+    let schedules = schedule::schedules_from_env()?;
+    if let Some(overrides) = schedule::crawler_schedules_from_env()? {
+        let skipped = orchestrator::after_run_settings(&config);
+        if !skipped.is_empty() {
+            warn!(
+                "Per-crawler schedules run no post-run steps; ignoring {}",
+                skipped.join(", ")
+            );
+        }
+        let manager = Arc::new(orchestrator::build_manager(&config));
+        for crawler_job in schedule::plan_crawler_jobs(&manager, &overrides, &schedules)? {
+            if registered
+                .iter()
+                .any(|(name, _)| name == &crawler_job.crawler)
+            {
+                scheduler
+                    .add_trigger(&crawler_job.crawler, &crawler_job.cron)
+                    .await?;
+            } else {
+                let config = config.clone();
+                let manager = manager.clone();
+                let storage = storage.clone();
+                let name = crawler_job.crawler.clone();
+                let notifier = notifier.clone();
+                let pending = pending.clone();
+                scheduler
+                    .add_cron_job(&crawler_job.crawler, &crawler_job.cron, move || {
+                        let config = config.clone();
+                        let manager = manager.clone();
+                        let storage = storage.clone();
+                        let name = name.clone();
+                        let notifier = notifier.clone();
+                        let pending = pending.clone();
+                        async move {
+                            let result = job::run_single_crawler(&config, &manager, &name).await;
+                            notify_result(&pending, notifier, &name, &result);
+                            result?;
+                            record_success(&storage, &name).await;
+                            Ok(())
+                        }
+                    })
+                    .await?;
+            }
+            log_next_fire_times(
+                &format!("{} ({})", crawler_job.crawler, crawler_job.cron),
+                &crawler_job.cron,
+            )?;
+            register(&mut registered, &crawler_job.crawler, &crawler_job.cron);
+        }
+    } else {
+        let (first, extra) = schedules.split_first().context("no schedules configured")?;
+        let config = config.clone();
+        let storage = storage.clone();
+        let notifier = notifier.clone();
+        let pending = pending.clone();
+        scheduler
+            .add_cron_job(DAILY_JOB, first, move || {
+                let config = config.clone();
+                let storage = storage.clone();
+                let notifier = notifier.clone();
+                let pending = pending.clone();
+                async move {
+                    let result = job::run_daily_crawlers(&config).await;
+                    notify_result(&pending, notifier, DAILY_JOB, &result);
+                    result?;
+                    record_success(&storage, DAILY_JOB).await;
+                    Ok(())
+                }
+            })
+            .await?;
+        for cron_expression in extra {
+            scheduler.add_trigger(DAILY_JOB, cron_expression).await?;
+        }
+        for cron_expression in &schedules {
+            log_next_fire_times(cron_expression, cron_expression)?;
+            register(&mut registered, DAILY_JOB, cron_expression);
+        }
+    }
+
+    let scheduler = Arc::new(scheduler);
+    let serving_status = status_options.is_some();
+    if let Some(options) = status_options {
+        let status = scheduler.status_handle();
+        tokio::spawn(async move {
+            if let Err(e) = status_server::serve(options, status).await {
+                warn!("Status listener stopped: {}", e);
+            }
+        });
+    }
+    if let Some(interval) = heartbeat_interval {
+        tokio::spawn(heartbeat::run(
+            storage.clone(),
+            scheduler.status_handle(),
+            interval,
+        ));
+    }
+    scheduler.start().await?;
+    run_startup_jobs(&scheduler, &storage, &startup_options, &registered).await;
+
+    info!("Press Ctrl+C (or send SIGTERM) to stop the scheduler");
+
+    // Handle graceful shutdown
+    tokio::select! {
+        _ = scheduler.run_forever() => {
+            info!("Scheduler stopped");
+        }
+        _ = shutdown => {
+            info!("Shutting down...");
+            scheduler.drain(drain_timeout).await?;
+            let status = scheduler.status_handle();
+            let final_heartbeat = heartbeat_interval.map(|_| async move {
+                heartbeat::upload(&storage, &status, &heartbeat::hostname()).await
+            });
+            shutdown::flush(&pending, shutdown_grace, final_heartbeat, serving_status).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// One-shot mode: runs all crawlers once with the retry policy, notifies and
+/// records the last successful run like a scheduled run, flushes pending
+/// notifications and a final heartbeat, and returns the run's error, if any.
+pub async fn run_once(config: Config) -> Result<()> {
+    let storage = SupabaseStorageClient::from_config(&config)?;
+    let retry = RetryOptions::from_env()?;
+    let heartbeat_enabled = heartbeat::interval_from_env()?.is_some();
+    let shutdown_grace = shutdown::grace_from_env()?;
+    let notifier = notifier_from_config(&config)?;
+    let pending = PendingSends::default();
+    let status = SchedulerStatus::shared(Utc::now());
+    let stats = JobStats::default();
+
+    let result = once::run_once(DAILY_JOB, &retry, &stats, || async {
+        if let Ok(mut status) = status.write() {
+            status.record_start(DAILY_JOB, Utc::now());
+        }
+        let result = job::run_daily_crawlers(&config).await;
+        if let Ok(mut status) = status.write() {
+            let outcome = result
+                .as_ref()
+                .map(|_| ())
+                .map_err(|e| anyhow::anyhow!("{:#}", e));
+            status.record_finish(DAILY_JOB, Utc::now(), &outcome);
+        }
+        result
+    })
+    .await;
+
+    notify_result(&pending, notifier, DAILY_JOB, &result);
+    if result.is_ok() {
+        record_success(&storage, DAILY_JOB).await;
+    }
+    let final_heartbeat = heartbeat_enabled
+        .then_some(async { heartbeat::upload(&storage, &status, &heartbeat::hostname()).await });
+    shutdown::flush(&pending, shutdown_grace, final_heartbeat, false).await;
+    result.map(|_| ())
+}
+
+/// Job name in daily mode; `--once` uses it too so both share the same state file.
+const DAILY_JOB: &str = "crawlers";
+
+fn register(registered: &mut Vec<(String, Vec<String>)>, name: &str, cron: &str) {
+    match registered.iter_mut().find(|(n, _)| n == name) {
+        Some((_, crons)) => crons.push(cron.to_string()),
+        None => registered.push((name.to_string(), vec![cron.to_string()])),
+    }
+}
+
+/// Kicks off run-on-start and catch-up executions in the background.
+async fn run_startup_jobs(
+    scheduler: &Arc<DailyScheduler>,
+    storage: &SupabaseStorageClient,
+    options: &StartupOptions,
+    registered: &[(String, Vec<String>)],
+) {
+    for (name, crons) in registered {
+        let run_now = if options.run_on_start {
+            info!(
+                "Job {}: running once at startup (CUTION_RUN_ON_START)",
+                name
+            );
+            true
+        } else if options.catch_up {
+            match catch_up_due(storage, options, name, crons).await {
+                Ok(due) => due,
+                Err(e) => {
+                    warn!("Job {}: could not evaluate catch-up: {}", name, e);
+                    false
+                }
+            }
+        } else {
+            false
+        };
+
+        if run_now {
+            let scheduler = scheduler.clone();
+            let name = name.clone();
+            tokio::spawn(async move {
+                scheduler.run_now(&name).await;
+            });
+        }
+    }
+}
+
+async fn catch_up_due(
+    storage: &SupabaseStorageClient,
+    options: &StartupOptions,
+    name: &str,
+    crons: &[String],
+) -> Result<bool> {
+    let last_success = startup::load_last_success(storage, name).await?;
+    let missed = startup::missed_trigger(crons, last_success, Utc::now(), options.catch_up_window)?;
+    if let Some(trigger) = missed {
+        info!(
+            "Job {}: trigger at {} was missed (last success: {}), catching up now",
+            name,
+            trigger.to_rfc3339(),
+            last_success
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_else(|| "never".to_string())
+        );
+    }
+    Ok(missed.is_some())
+}
+
+/// The configured notifiers, or `None` when there are none.
+fn notifier_from_config(config: &Config) -> Result<Option<Arc<dyn Notifier>>> {
+    Ok(Some(MultiNotifier::from_config(config)?)
+        .filter(|n| !n.is_empty())
+        .map(|n| Arc::new(n) as Arc<dyn Notifier>))
+}
+
+/// Sends the job's result to the notifiers, if any, without waiting for it.
+fn notify_result(
+    pending: &PendingSends,
+    notifier: Option<Arc<dyn Notifier>>,
+    name: &str,
+    result: &Result<common::RunReport>,
+) {
+    if let Some(notifier) = notifier {
+        pending.notify(notifier, job::run_notification(name, result));
+    }
+}
+
+async fn record_success(storage: &SupabaseStorageClient, name: &str) {
+    if let Err(e) = startup::save_last_success(storage, name, Utc::now()).await {
+        warn!("Job {}: failed to persist last successful run: {}", name, e);
+    }
+}
+
+fn log_next_fire_times(label: &str, cron_expression: &str) -> Result<()> {
+    let upcoming = schedule::next_fire_times(cron_expression, Utc::now(), 3)?;
+    info!(
+        "Schedule '{}' next fires at: {}",
+        label,
+        upcoming
+            .iter()
+            .map(|t| t.to_rfc3339())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use test_support::{MockSupabase, TestConfig};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn run_keeps_scheduling_until_shutdown() {
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase).build();
+
+        let started = Instant::now();
+        run_until(config, tokio::time::sleep(Duration::from_secs(2)))
+            .await
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use common::SupabaseStorageClient;
+use serde::Serialize;
+use std::env;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::status::{LastRun, SharedStatus, StatusReport};
+
+pub const HEARTBEAT_PATH: &str = "state/scheduler-heartbeat.json";
+
+/// Longest wait between heartbeat attempts while storage keeps failing.
+const MAX_BACKOFF: Duration = Duration::from_secs(4 * 60 * 60);
+
+/// Reads `CUTION_HEARTBEAT_MIN` (default 15); `0` disables the heartbeat.
+pub fn interval_from_env() -> Result<Option<Duration>> {
+    let minutes = match env::var("CUTION_HEARTBEAT_MIN") {
+        Ok(v) => v.trim().parse::<u64>().with_context(|| {
+            format!(
+                "CUTION_HEARTBEAT_MIN must be a non-negative integer, got '{}'",
+                v
+            )
+        })?,
+        Err(_) => 15,
+    };
+    Ok((minutes > 0).then(|| Duration::from_secs(minutes * 60)))
+}
+
+/// Contents of `state/scheduler-heartbeat.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Heartbeat {
+    pub timestamp: DateTime<Utc>,
+    pub hostname: String,
+    pub jobs: Vec<HeartbeatJob>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HeartbeatJob {
+    pub name: String,
+    pub crons: Vec<String>,
+    pub last_run: LastRun,
+}
+
+impl Heartbeat {
+    pub fn from_report(report: &StatusReport, hostname: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            timestamp: now,
+            hostname: hostname.to_string(),
+            jobs: report
+                .jobs
+                .iter()
+                .map(|job| HeartbeatJob {
+                    name: job.name.clone(),
+                    crons: job.crons.clone(),
+                    last_run: job.last_run.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Delay until the next heartbeat: the regular interval, doubled for every
+/// consecutive failure up to `MAX_BACKOFF`.
+pub fn next_delay(interval: Duration, consecutive_failures: u32) -> Duration {
+    let factor = 2u32.saturating_pow(consecutive_failures.min(16));
+    interval
+        .saturating_mul(factor)
+        .min(MAX_BACKOFF.max(interval))
+}
+
+pub fn hostname() -> String {
+    env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Builds the heartbeat from `status` and uploads it once.
+pub async fn upload(
+    storage: &SupabaseStorageClient,
+    status: &SharedStatus,
+    hostname: &str,
+) -> Result<()> {
+    let now = Utc::now();
+    // The stale threshold does not matter for the heartbeat payload.
+    let report = status
+        .read()
+        .map_err(|_| anyhow::anyhow!("status lock poisoned"))?
+        .report(now, chrono::Duration::zero());
+    let body = serde_json::to_string_pretty(&Heartbeat::from_report(&report, hostname, now))?;
+    storage
+        .upload_file(HEARTBEAT_PATH, body, "application/json")
+        .await
+}
+
+/// Uploads a heartbeat every `interval` forever. Failures are logged and
+/// back off; they never end the loop.
+pub async fn run(storage: SupabaseStorageClient, status: SharedStatus, interval: Duration) {
+    let hostname = hostname();
+    let mut failures: u32 = 0;
+    loop {
+        match upload(&storage, &status, &hostname).await {
+            Ok(()) => {
+                if failures > 0 {
+                    info!(
+                        "Heartbeat upload recovered after {} failed attempt(s)",
+                        failures
+                    );
+                }
+                failures = 0;
+            }
+            Err(e) => {
+                failures = failures.saturating_add(1);
+                warn!(
+                    "Heartbeat upload failed ({} in a row), next attempt in {:?}: {}",
+                    failures,
+                    next_delay(interval, failures),
+                    e
+                );
+            }
+        }
+        tokio::time::sleep(next_delay(interval, failures)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::SchedulerStatus;
+    use chrono::TimeZone;
+
+    #[test]
+    fn heartbeat_payload_lists_jobs_and_outcomes() {
+        let t = |m| Utc.with_ymd_and_hms(2024, 5, 10, 9, m, 0).unwrap();
+        let mut status = SchedulerStatus::new(t(0));
+        status.register_job("github", "0 0 9 * * *");
+        status.register_job("openrouter", "0 0 * * * *");
+        status.record_start("github", t(0));
+        status.record_finish("github", t(3), &Ok(()));
+
+        let report = status.report(t(5), chrono::Duration::minutes(5));
+        let json = serde_json::to_value(Heartbeat::from_report(&report, "worker-1", t(5))).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "timestamp": "2024-05-10T09:05:00Z",
+                "hostname": "worker-1",
+                "jobs": [
+                    {
+                        "name": "github",
+                        "crons": ["0 0 9 * * *"],
+                        "last_run": {
+                            "started_at": "2024-05-10T09:00:00Z",
+                            "finished_at": "2024-05-10T09:03:00Z",
+                            "outcome": "success",
+                            "error": null
+                        }
+                    },
+                    {
+                        "name": "openrouter",
+                        "crons": ["0 0 * * * *"],
+                        "last_run": {
+                            "started_at": null,
+                            "finished_at": null,
+                            "outcome": null,
+                            "error": null
+                        }
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_after_failures_and_caps() {
+        let interval = Duration::from_secs(15 * 60);
+        assert_eq!(next_delay(interval, 0), interval);
+        assert_eq!(next_delay(interval, 1), Duration::from_secs(30 * 60));
+        assert_eq!(next_delay(interval, 2), Duration::from_secs(60 * 60));
+        assert_eq!(next_delay(interval, 10), MAX_BACKOFF);
+        assert_eq!(next_delay(interval, u32::MAX), MAX_BACKOFF);
+        // An interval longer than the cap is never shortened.
+        let daily = Duration::from_secs(24 * 60 * 60);
+        assert_eq!(next_delay(daily, 3), daily);
+    }
+}
@@ -0,0 +1,201 @@
+use anyhow::Result;
+use common::{Config, CrawlerManager, RunReport};
+use notify::{Level, Notification};
+use tracing::{info, warn};
+
+/// Job body executed on every scheduled trigger: runs the orchestrator in-process.
+pub async fn run_daily_crawlers(config: &Config) -> Result<RunReport> {
+    info!("Starting daily crawlers execution");
+    orchestrator::before_run(config).await;
+    let report = orchestrator::build_manager(config).run_with_report().await;
+    orchestrator::after_run(config, &report).await;
+    check_report(report)
+}
+
+/// Runs the given manager and turns a report with failed crawlers into an error,
+/// so the scheduler logs it as a failed job.
+pub async fn execute(manager: CrawlerManager) -> Result<RunReport> {
+    check_report(manager.run_with_report().await)
+}
+
+/// Job body for per-crawler schedules: runs only the named crawler, with the
+/// LLM budget loaded beforehand and the run's usage published afterwards.
+pub async fn run_single_crawler(
+    config: &Config,
+    manager: &CrawlerManager,
+    name: &str,
+) -> Result<RunReport> {
+    info!("Starting scheduled run of {}", name);
+    orchestrator::before_run(config).await;
+    let report = manager.run_named(name).await;
+    orchestrator::publish_run_logs(config).await;
+    check_report(report?)
+}
+
+/// What to tell the configured notifiers once job `name` has run.
+pub fn run_notification(name: &str, result: &Result<RunReport>) -> Notification {
+    match result {
+        Ok(report) => Notification::new(
+            Level::Info,
+            format!("Job {} finished", name),
+            format!("{} crawler(s) succeeded", report.success_count()),
+        ),
+        Err(e) => Notification::new(Level::Error, format!("Job {} failed", name), e.to_string()),
+    }
+}
+
+fn check_report(report: RunReport) -> Result<RunReport> {
+    for outcome in report.failed() {
+        warn!(
+            "Crawler {} failed after {:?}: {}",
+            outcome.name,
+            outcome.duration,
+            outcome.error.as_deref().unwrap_or_default()
+        );
+    }
+
+    if !report.is_success() {
+        anyhow::bail!(
+            "Daily crawlers failed: {} of {} crawlers failed",
+            report.error_count(),
+            report.outcomes.len()
+        );
+    }
+
+    info!(
+        "Daily crawlers completed successfully ({} crawlers)",
+        report.outcomes.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use common::usage;
+    use common::{Crawler, CrawlerContext, CrawlerError, CrawlerResult};
+    use test_support::{MockSupabase, TestConfig};
+
+    struct StubCrawler {
+        name: &'static str,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Crawler for StubCrawler {
+        async fn run_with_context(&self, _ctx: &CrawlerContext) -> CrawlerResult<()> {
+            if self.fail {
+                return Err(CrawlerError::Api("stub failure".to_string()));
+            }
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_returns_report_for_successful_run() {
+        let manager = CrawlerManager::new()
+            .add_crawler(Box::new(StubCrawler {
+                name: "a",
+                fail: false,
+            }))
+            .add_crawler(Box::new(StubCrawler {
+                name: "b",
+                fail: false,
+            }));
+
+        let report = execute(manager).await.unwrap();
+        assert_eq!(report.success_count(), 2);
+        assert!(report.is_success());
+    }
+
+    #[tokio::test]
+    async fn execute_fails_when_any_crawler_fails() {
+        let manager = CrawlerManager::new()
+            .add_crawler(Box::new(StubCrawler {
+                name: "ok",
+                fail: false,
+            }))
+            .add_crawler(Box::new(StubCrawler {
+                name: "broken",
+                fail: true,
+            }));
+
+        let err = execute(manager).await.unwrap_err();
+        assert!(err.to_string().contains("1 of 2 crawlers failed"));
+    }
+
+    #[tokio::test]
+    async fn run_single_crawler_runs_only_named_crawler() {
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase).build();
+        let manager = CrawlerManager::new()
+            .add_crawler(Box::new(StubCrawler {
+                name: "ok",
+                fail: false,
+            }))
+            .add_crawler(Box::new(StubCrawler {
+                name: "broken",
+                fail: true,
+            }));
+
+        let report = run_single_crawler(&config, &manager, "ok").await.unwrap();
+        assert_eq!(report.outcomes.len(), 1);
+        assert_eq!(report.outcomes[0].name, "ok");
+        assert!(run_single_crawler(&config, &manager, "missing")
+            .await
+            .is_err());
+    }
+
+    struct LlmCrawler;
+
+    #[async_trait]
+    impl Crawler for LlmCrawler {
+        async fn run_with_context(&self, _ctx: &CrawlerContext) -> CrawlerResult<()> {
+            usage::global().record("gemini-2.0-flash", 1_000, 100);
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            "llm"
+        }
+    }
+
+    #[tokio::test]
+    async fn single_crawler_runs_publish_their_llm_usage() {
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase).build();
+        let manager = CrawlerManager::new().add_crawler(Box::new(LlmCrawler));
+
+        run_single_crawler(&config, &manager, "llm").await.unwrap();
+        assert!(usage::global().summary().is_empty());
+        let paths = supabase.uploaded_paths().await;
+        assert!(
+            paths.iter().any(|p| p.ends_with("-usage.json")),
+            "{:?}",
+            paths
+        );
+    }
+
+    #[tokio::test]
+    async fn notification_reports_the_job_result() {
+        let manager = CrawlerManager::new().add_crawler(Box::new(StubCrawler {
+            name: "broken",
+            fail: true,
+        }));
+        let failed = run_notification("crawlers", &execute(manager).await);
+        assert_eq!(
+            (failed.level, failed.title.as_str()),
+            (Level::Error, "Job crawlers failed")
+        );
+        assert!(failed.body.contains("1 of 1 crawlers failed"));
+
+        let ok = run_notification("github", &Ok(RunReport::default()));
+        assert_eq!(ok.level, Level::Info);
+        assert_eq!(ok.body, "0 crawler(s) succeeded");
+    }
+}
@@ -1,3 +1,12 @@
+pub mod app;
+pub mod heartbeat;
+pub mod job;
+pub mod once;
+pub mod retry;
+pub mod schedule;
 pub mod scheduler;
+pub mod shutdown;
+pub mod startup;
+pub mod status;
 
-pub use scheduler::DailyScheduler;
\ No newline at end of file
+pub use scheduler::DailyScheduler;
@@ -0,0 +1,139 @@
+use anyhow::Result;
+use common::{retry_async, RetryPolicy};
+use std::fmt;
+use std::future::Future;
+use tracing::info;
+
+use crate::retry::RetryOptions;
+use crate::scheduler::{run_exclusive, JobStats};
+
+/// Whether the `CUTION_ONCE` value asks for one-shot mode; `--once` is parsed
+/// by the command line.
+pub fn once_requested(env_value: Option<&str>) -> bool {
+    env_value
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Returned when the overlap guard reports another execution in progress.
+#[derive(Debug)]
+pub struct AlreadyRunning(pub String);
+
+impl fmt::Display for AlreadyRunning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "job {} is already running", self.0)
+    }
+}
+
+impl std::error::Error for AlreadyRunning {}
+
+/// Runs `job_fn` once under the overlap guard, retrying failures according to
+/// `retry` (a fixed delay between attempts). The final error is returned so the
+/// process can exit non-zero.
+pub async fn run_once<T, F, Fut>(
+    name: &str,
+    retry: &RetryOptions,
+    stats: &JobStats,
+    job_fn: F,
+) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let delay = retry.delay.to_std().unwrap_or_default();
+    let policy = RetryPolicy {
+        max_attempts: retry.attempts.saturating_add(1),
+        base_delay: delay,
+        max_delay: delay,
+        jitter: 0.0,
+    };
+    info!(
+        "Running job {} once (up to {} attempts)",
+        name, policy.max_attempts
+    );
+    retry_async(
+        &policy,
+        |e: &anyhow::Error| !e.is::<AlreadyRunning>(),
+        |_| async {
+            match run_exclusive(name, stats, &job_fn).await {
+                Some(result) => result,
+                None => Err(AlreadyRunning(name.to_string()).into()),
+            }
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    fn retry(attempts: u32) -> RetryOptions {
+        RetryOptions {
+            attempts,
+            delay: ChronoDuration::minutes(30),
+        }
+    }
+
+    #[test]
+    fn once_flag_from_env() {
+        assert!(once_requested(Some("true")));
+        assert!(once_requested(Some(" YES ")));
+        assert!(!once_requested(Some("no")));
+        assert!(!once_requested(None));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn transient_failure_is_retried_after_delay() {
+        let calls = AtomicU32::new(0);
+        let stats = JobStats::default();
+        let started = tokio::time::Instant::now();
+        let result = run_once("crawlers", &retry(2), &stats, || async {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                anyhow::bail!("network down");
+            }
+            Ok(())
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(started.elapsed(), Duration::from_secs(30 * 60));
+        assert_eq!(stats.executions(), 2);
+        assert!(!stats.is_running());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exhausted_retries_return_last_error() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = run_once("crawlers", &retry(2), &JobStats::default(), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            anyhow::bail!("Daily crawlers failed: 1 of 3 crawlers failed")
+        })
+        .await;
+        assert!(result.unwrap_err().to_string().contains("1 of 3"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn held_guard_fails_without_running_or_retrying() {
+        let stats = JobStats::default();
+        let calls = AtomicU32::new(0);
+        // Hold the guard as if a previous execution were still in flight.
+        let outer = run_exclusive("crawlers", &stats, || async {
+            let inner = run_once("crawlers", &retry(2), &stats, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+            .await;
+            assert!(inner.unwrap_err().is::<AlreadyRunning>());
+            Ok(())
+        })
+        .await;
+        assert!(matches!(outer, Some(Ok(()))));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(stats.skipped_runs(), 1);
+    }
+}
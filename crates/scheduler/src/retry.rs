@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use common::Clock;
+use std::env;
+
+/// `clock`'s current time as the chrono type the scheduler works in.
+pub fn now(clock: &dyn Clock) -> DateTime<Utc> {
+    DateTime::from(std::time::SystemTime::from(clock.now()))
+}
+
+/// One-shot retries after a failed scheduled run.
+#[derive(Debug, Clone)]
+pub struct RetryOptions {
+    /// Retries after the initial failure (`CUTION_RETRY_ATTEMPTS`).
+    pub attempts: u32,
+    /// Wait before each retry (`CUTION_RETRY_DELAY_MIN`).
+    pub delay: Duration,
+}
+
+impl Default for RetryOptions {
+    fn default() -> Self {
+        Self {
+            attempts: 2,
+            delay: Duration::minutes(30),
+        }
+    }
+}
+
+impl RetryOptions {
+    /// No retries; failed runs wait for the next trigger.
+    pub fn disabled() -> Self {
+        Self {
+            attempts: 0,
+            ..Self::default()
+        }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let attempts = match env::var("CUTION_RETRY_ATTEMPTS") {
+            Ok(v) => v.trim().parse::<u32>().with_context(|| {
+                format!(
+                    "CUTION_RETRY_ATTEMPTS must be a non-negative integer, got '{}'",
+                    v
+                )
+            })?,
+            Err(_) => defaults.attempts,
+        };
+        let delay = match env::var("CUTION_RETRY_DELAY_MIN") {
+            Ok(v) => v
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .filter(|m| *m > 0)
+                .map(Duration::minutes)
+                .with_context(|| {
+                    format!(
+                        "CUTION_RETRY_DELAY_MIN must be a positive integer, got '{}'",
+                        v
+                    )
+                })?,
+            Err(_) => defaults.delay,
+        };
+        Ok(Self { attempts, delay })
+    }
+}
+
+/// A retry that should fire at `at`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannedRetry {
+    /// 1-based retry number.
+    pub attempt: u32,
+    pub of: u32,
+    pub at: DateTime<Utc>,
+}
+
+/// Plans the next retry after `retries_done` retries have already failed, or
+/// `None` when the budget is spent.
+pub fn plan_retry(
+    options: &RetryOptions,
+    retries_done: u32,
+    clock: &dyn Clock,
+) -> Option<PlannedRetry> {
+    if retries_done >= options.attempts {
+        return None;
+    }
+    Some(PlannedRetry {
+        attempt: retries_done + 1,
+        of: options.attempts,
+        at: now(clock) + options.delay,
+    })
+}
+
+/// A retry is dropped once any newer execution of the job has started, which
+/// `executions` (the job's run counter) reveals.
+pub fn retry_still_wanted(executions_at_failure: u64, executions_now: u64) -> bool {
+    executions_now == executions_at_failure
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use common::FixedClock;
+    use time::macros::datetime;
+
+    #[test]
+    fn retries_are_spaced_by_delay_until_budget_is_spent() {
+        let clock = FixedClock(datetime!(2024-05-10 9:00:05 UTC));
+        let options = RetryOptions::default();
+
+        assert_eq!(
+            plan_retry(&options, 0, &clock),
+            Some(PlannedRetry {
+                attempt: 1,
+                of: 2,
+                at: Utc.with_ymd_and_hms(2024, 5, 10, 9, 30, 5).unwrap(),
+            })
+        );
+        assert_eq!(plan_retry(&options, 1, &clock).map(|r| r.attempt), Some(2));
+        assert_eq!(plan_retry(&options, 2, &clock), None);
+        assert_eq!(plan_retry(&RetryOptions::disabled(), 0, &clock), None);
+    }
+
+    #[test]
+    fn newer_run_cancels_pending_retry() {
+        assert!(retry_still_wanted(3, 3));
+        assert!(!retry_still_wanted(3, 4));
+    }
+}
@@ -0,0 +1,377 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use common::CrawlerManager;
+use cron::Schedule;
+use std::env;
+use std::str::FromStr;
+
+pub const CRON_FORMAT_HINT: &str =
+    "expected 6 fields 'sec min hour day-of-month month day-of-week', e.g. '0 0 9 * * *'";
+
+const CRON_FIELDS: usize = 6;
+
+/// Reads the configured schedules from `CUTION_CRON`, falling back to a daily
+/// job at `CUTION_RUN_HOUR`:`CUTION_RUN_MINUTE` (09:00 UTC by default).
+pub fn schedules_from_env() -> Result<Vec<String>> {
+    if let Ok(raw) = env::var("CUTION_CRON") {
+        if !raw.trim().is_empty() {
+            return parse_cron_list(&raw).context("Invalid CUTION_CRON");
+        }
+    }
+
+    let hour = parse_env_u32("CUTION_RUN_HOUR", 9)?;
+    let minute = parse_env_u32("CUTION_RUN_MINUTE", 0)?;
+    Ok(vec![daily_cron(hour, minute)?])
+}
+
+fn parse_env_u32(key: &str, default: u32) -> Result<u32> {
+    match env::var(key) {
+        Ok(v) => v
+            .trim()
+            .parse()
+            .with_context(|| format!("{} must be a non-negative integer, got '{}'", key, v)),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Builds the cron expression for a daily run at `hour`:`minute` UTC.
+pub fn daily_cron(hour: u32, minute: u32) -> Result<String> {
+    if hour > 23 {
+        anyhow::bail!("hour must be between 0 and 23, got {}", hour);
+    }
+    if minute > 59 {
+        anyhow::bail!("minute must be between 0 and 59, got {}", minute);
+    }
+    Ok(format!("0 {} {} * * *", minute, hour))
+}
+
+/// Splits a list of cron expressions separated by `;` or newlines.
+///
+/// Commas are valid inside any cron field (`0 0 9,21 * * Mon,Wed,Fri`), so they
+/// cannot separate expressions.
+pub fn parse_cron_list(raw: &str) -> Result<Vec<String>> {
+    let mut expressions = Vec::new();
+    for expression in raw
+        .split([';', '\n'])
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+    {
+        validate_cron(expression)?;
+        expressions.push(expression.to_string());
+    }
+    if expressions.is_empty() {
+        anyhow::bail!("no cron expressions given ({})", CRON_FORMAT_HINT);
+    }
+    Ok(expressions)
+}
+
+/// Parses a 6-field cron expression, returning an error that shows the expected format.
+pub fn validate_cron(expression: &str) -> Result<Schedule> {
+    let fields = expression.split_whitespace().count();
+    if fields != CRON_FIELDS {
+        anyhow::bail!(
+            "invalid cron expression '{}': found {} fields, {}",
+            expression,
+            fields,
+            CRON_FORMAT_HINT
+        );
+    }
+    Schedule::from_str(expression).map_err(|e| {
+        anyhow::anyhow!(
+            "invalid cron expression '{}': {} ({})",
+            expression,
+            e,
+            CRON_FORMAT_HINT
+        )
+    })
+}
+
+/// A cron job that runs a single crawler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrawlerJob {
+    pub crawler: String,
+    pub cron: String,
+}
+
+/// Reads per-crawler schedule overrides.
+///
+/// Returns `None` unless `CUTION_SCHEDULES` is set or
+/// `CUTION_PER_CRAWLER_SCHEDULES=true` asks for the crawlers' own metadata.
+pub fn crawler_schedules_from_env() -> Result<Option<Vec<(String, String)>>> {
+    if let Ok(raw) = env::var("CUTION_SCHEDULES") {
+        if !raw.trim().is_empty() {
+            return parse_schedule_map(&raw)
+                .context("Invalid CUTION_SCHEDULES")
+                .map(Some);
+        }
+    }
+    let per_crawler = env::var("CUTION_PER_CRAWLER_SCHEDULES")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false);
+    Ok(per_crawler.then(Vec::new))
+}
+
+/// Parses a `name=cron;name=cron` map, e.g. `github=0 0 9 * * *;openrouter=0 0 * * * *`.
+pub fn parse_schedule_map(raw: &str) -> Result<Vec<(String, String)>> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for entry in raw.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, cron) = entry
+            .split_once('=')
+            .with_context(|| format!("expected 'crawler=cron', got '{}'", entry))?;
+        let (name, cron) = (name.trim(), cron.trim());
+        if name.is_empty() {
+            anyhow::bail!("missing crawler name in '{}'", entry);
+        }
+        if entries.iter().any(|(existing, _)| existing == name) {
+            anyhow::bail!("crawler '{}' is scheduled more than once", name);
+        }
+        validate_cron(cron)?;
+        entries.push((name.to_string(), cron.to_string()));
+    }
+    Ok(entries)
+}
+
+/// Resolves one job per crawler: an explicit override wins, then the crawler's
+/// own `schedule()` metadata, then the global `default` schedules.
+///
+/// Fails when an override names a crawler that is not registered.
+pub fn plan_crawler_jobs(
+    manager: &CrawlerManager,
+    overrides: &[(String, String)],
+    default: &[String],
+) -> Result<Vec<CrawlerJob>> {
+    for (name, _) in overrides {
+        if manager.find(name).is_none() {
+            let known = manager.crawlers().map(|c| c.slug()).collect::<Vec<_>>();
+            anyhow::bail!(
+                "unknown crawler '{}' in schedule map (known: {})",
+                name,
+                known.join(", ")
+            );
+        }
+    }
+
+    let mut jobs = Vec::new();
+    for crawler in manager.crawlers() {
+        let overridden = overrides
+            .iter()
+            .find(|(name, _)| name == crawler.slug() || name == crawler.name())
+            .map(|(_, cron)| cron.clone());
+        let crons = match overridden.or_else(|| crawler.schedule().map(str::to_string)) {
+            Some(cron) => vec![cron],
+            None => default.to_vec(),
+        };
+        for cron in crons {
+            validate_cron(&cron)
+                .with_context(|| format!("invalid schedule for {}", crawler.slug()))?;
+            jobs.push(CrawlerJob {
+                crawler: crawler.slug().to_string(),
+                cron,
+            });
+        }
+    }
+    Ok(jobs)
+}
+
+/// Reads `CUTION_SCHEDULE_JITTER_SECS` (default 0, i.e. no jitter).
+pub fn jitter_window_from_env() -> Result<u64> {
+    parse_env_u64("CUTION_SCHEDULE_JITTER_SECS", 0)
+}
+
+/// Reads `CUTION_DRAIN_TIMEOUT_SECS`: how long shutdown waits for running jobs (default 300).
+pub fn drain_timeout_from_env() -> Result<std::time::Duration> {
+    parse_env_u64("CUTION_DRAIN_TIMEOUT_SECS", 300).map(std::time::Duration::from_secs)
+}
+
+pub(crate) fn parse_env_u64(key: &str, default: u64) -> Result<u64> {
+    match env::var(key) {
+        Ok(v) => v
+            .trim()
+            .parse()
+            .with_context(|| format!("{} must be a non-negative integer, got '{}'", key, v)),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Deterministic start delay for job `name` within a `window_secs` window.
+///
+/// Uses FNV-1a rather than `DefaultHasher` so offsets stay the same across
+/// builds and restarts.
+pub fn jitter_offset(name: &str, window_secs: u64) -> std::time::Duration {
+    if window_secs == 0 {
+        return std::time::Duration::ZERO;
+    }
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    std::time::Duration::from_secs(hash % window_secs)
+}
+
+/// Returns the next `count` fire times of `expression` strictly after `after`.
+pub fn next_fire_times(
+    expression: &str,
+    after: DateTime<Utc>,
+    count: usize,
+) -> Result<Vec<DateTime<Utc>>> {
+    let schedule = validate_cron(expression)?;
+    Ok(schedule.after(&after).take(count).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use common::{Crawler, CrawlerContext, CrawlerResult};
+
+    struct StubCrawler {
+        slug: &'static str,
+        schedule: Option<&'static str>,
+    }
+
+    #[async_trait]
+    impl Crawler for StubCrawler {
+        async fn run_with_context(&self, _ctx: &CrawlerContext) -> CrawlerResult<()> {
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            self.slug
+        }
+
+        fn schedule(&self) -> Option<&'static str> {
+            self.schedule
+        }
+    }
+
+    fn manager() -> CrawlerManager {
+        CrawlerManager::new()
+            .add_crawler(Box::new(StubCrawler {
+                slug: "github",
+                schedule: Some("0 0 9 * * *"),
+            }))
+            .add_crawler(Box::new(StubCrawler {
+                slug: "openrouter",
+                schedule: Some("0 0 * * * *"),
+            }))
+            .add_crawler(Box::new(StubCrawler {
+                slug: "plain",
+                schedule: None,
+            }))
+    }
+
+    #[test]
+    fn validate_cron_accepts_six_fields() {
+        assert!(validate_cron("0 0 9 * * *").is_ok());
+        assert!(validate_cron("0 */15 * * * Mon-Fri").is_ok());
+    }
+
+    #[test]
+    fn validate_cron_rejects_wrong_field_count_with_hint() {
+        let err = validate_cron("0 9 * * *").unwrap_err().to_string();
+        assert!(err.contains("found 5 fields"));
+        assert!(err.contains(CRON_FORMAT_HINT));
+    }
+
+    #[test]
+    fn validate_cron_rejects_out_of_range_values() {
+        assert!(validate_cron("0 90 25 * * *").is_err());
+    }
+
+    #[test]
+    fn parse_cron_list_keeps_commas_inside_fields() {
+        let list = parse_cron_list("0 0 9,21 * * *; 0 30 12 * * Sun").unwrap();
+        assert_eq!(list, vec!["0 0 9,21 * * *", "0 30 12 * * Sun"]);
+    }
+
+    #[test]
+    fn parse_cron_list_keeps_day_of_week_lists() {
+        let list = parse_cron_list("0 0 9 * * Mon,Wed,Fri\n0 0 21 * * Sat,Sun").unwrap();
+        assert_eq!(list, vec!["0 0 9 * * Mon,Wed,Fri", "0 0 21 * * Sat,Sun"]);
+    }
+
+    #[test]
+    fn parse_cron_list_rejects_incomplete_trailing_expression() {
+        assert!(parse_cron_list("0 0 9 * * *; 0 0").is_err());
+        assert!(parse_cron_list(" ; ").is_err());
+    }
+
+    #[test]
+    fn daily_cron_validates_ranges() {
+        assert_eq!(daily_cron(9, 0).unwrap(), "0 0 9 * * *");
+        assert!(daily_cron(24, 0).is_err());
+        assert!(daily_cron(9, 60).is_err());
+    }
+
+    #[test]
+    fn next_fire_times_are_computed_after_reference() {
+        let after = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        let times = next_fire_times("0 0 9 * * *", after, 3).unwrap();
+        assert_eq!(
+            times,
+            vec![
+                Utc.with_ymd_and_hms(2024, 1, 2, 9, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 3, 9, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 4, 9, 0, 0).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn jitter_offset_is_stable_and_within_window() {
+        assert_eq!(jitter_offset("github", 0), std::time::Duration::ZERO);
+        let github = jitter_offset("github", 300);
+        assert_eq!(github, jitter_offset("github", 300));
+        assert!(github < std::time::Duration::from_secs(300));
+        assert_eq!(github, std::time::Duration::from_secs(186));
+        assert_ne!(
+            jitter_offset("github", 300),
+            jitter_offset("openrouter", 300)
+        );
+    }
+
+    #[test]
+    fn parse_schedule_map_splits_entries() {
+        let map = parse_schedule_map("github=0 0 9 * * *; openrouter=0 0 * * * *;").unwrap();
+        assert_eq!(
+            map,
+            vec![
+                ("github".to_string(), "0 0 9 * * *".to_string()),
+                ("openrouter".to_string(), "0 0 * * * *".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_schedule_map_rejects_bad_entries() {
+        assert!(parse_schedule_map("github").is_err());
+        assert!(parse_schedule_map("github=0 9 * * *").is_err());
+        assert!(parse_schedule_map("a=0 0 9 * * *;a=0 0 10 * * *").is_err());
+    }
+
+    #[test]
+    fn plan_uses_override_then_metadata_then_default() {
+        let overrides = vec![("openrouter".to_string(), "0 30 * * * *".to_string())];
+        let default = vec!["0 0 12 * * *".to_string()];
+        let jobs = plan_crawler_jobs(&manager(), &overrides, &default).unwrap();
+        let pairs: Vec<_> = jobs
+            .iter()
+            .map(|j| (j.crawler.as_str(), j.cron.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("github", "0 0 9 * * *"),
+                ("openrouter", "0 30 * * * *"),
+                ("plain", "0 0 12 * * *"),
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_rejects_unknown_crawler_names() {
+        let overrides = vec![("lobsters".to_string(), "0 0 9 * * *".to_string())];
+        let err = plan_crawler_jobs(&manager(), &overrides, &[]).unwrap_err();
+        assert!(err.to_string().contains("unknown crawler 'lobsters'"));
+    }
+}
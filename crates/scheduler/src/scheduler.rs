@@ -1,46 +1,537 @@
-use anyhow::Result;
-use tokio_cron_scheduler::{JobScheduler, Job};
-use tracing::{info, error};
-use time::OffsetDateTime;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use common::{Clock, SystemClock};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio_cron_scheduler::{Job, JobScheduler};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::retry::{self, RetryOptions};
+use crate::schedule;
+use crate::status::{SchedulerStatus, SharedStatus};
+
+/// Execution counters for a named job, shared by every trigger of that job.
+#[derive(Debug, Default)]
+pub struct JobStats {
+    running: AtomicBool,
+    executions: AtomicU64,
+    skipped_runs: AtomicU64,
+}
+
+impl JobStats {
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    pub fn executions(&self) -> u64 {
+        self.executions.load(Ordering::SeqCst)
+    }
+
+    pub fn skipped_runs(&self) -> u64 {
+        self.skipped_runs.load(Ordering::SeqCst)
+    }
+}
+
+/// Point-in-time view of a job's counters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobStatus {
+    pub name: String,
+    pub running: bool,
+    pub executions: u64,
+    pub skipped_runs: u64,
+}
+
+/// Clears the running flag even if the job panics.
+struct RunningGuard<'a>(&'a JobStats);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Runs `job_fn` unless a previous execution sharing `stats` is still in progress.
+///
+/// Returns `None` when the trigger was skipped.
+pub async fn run_exclusive<T, F, Fut>(name: &str, stats: &JobStats, job_fn: F) -> Option<Result<T>>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    if stats
+        .running
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        let skipped = stats.skipped_runs.fetch_add(1, Ordering::SeqCst) + 1;
+        warn!(
+            "Job {}: previous run still in progress, skipping ({} skipped so far)",
+            name, skipped
+        );
+        return None;
+    }
+
+    let _guard = RunningGuard(stats);
+    stats.executions.fetch_add(1, Ordering::SeqCst);
+    Some(job_fn().await)
+}
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+type JobFn = Arc<dyn Fn() -> JobFuture + Send + Sync>;
+
+/// Everything needed to execute one named job, shared by its cron triggers,
+/// `run_now` and pending retries.
+struct JobRunner {
+    name: String,
+    stats: Arc<JobStats>,
+    status: SharedStatus,
+    job_fn: JobFn,
+    retry: RetryOptions,
+    clock: Arc<dyn Clock>,
+    /// Delay applied to cron-triggered runs so jobs sharing a trigger are staggered.
+    jitter: std::time::Duration,
+    /// Set once shutdown starts; no new executions begin afterwards.
+    draining: Arc<AtomicBool>,
+}
+
+impl JobRunner {
+    /// Entry point for cron triggers: waits out the job's jitter, then runs it.
+    async fn run_scheduled(self: &Arc<Self>) -> Option<Result<()>> {
+        if !self.jitter.is_zero() {
+            info!(
+                "Job {}: delaying start by {}s (schedule jitter)",
+                self.name,
+                self.jitter.as_secs()
+            );
+            tokio::time::sleep(self.jitter).await;
+        }
+        self.run().await
+    }
+
+    /// Runs the job and, if it fails, schedules retries in the background.
+    async fn run(self: &Arc<Self>) -> Option<Result<()>> {
+        let result = self.run_tracked().await;
+        if matches!(result, Some(Err(_))) {
+            self.clone().spawn_retries();
+        }
+        result
+    }
+
+    /// `run_exclusive` plus bookkeeping in the shared status.
+    async fn run_tracked(&self) -> Option<Result<()>> {
+        if self.draining.load(Ordering::SeqCst) {
+            info!(
+                "Job {}: scheduler is shutting down, not starting",
+                self.name
+            );
+            return None;
+        }
+        let result = run_exclusive(&self.name, &self.stats, || {
+            if let Ok(mut status) = self.status.write() {
+                status.record_start(&self.name, retry::now(self.clock.as_ref()));
+            }
+            (self.job_fn)()
+        })
+        .await;
+        if let Ok(mut status) = self.status.write() {
+            match &result {
+                Some(result) => {
+                    status.record_finish(&self.name, retry::now(self.clock.as_ref()), result)
+                }
+                None => status.record_skip(&self.name),
+            }
+        }
+        log_outcome(&self.name, result.as_ref());
+        result
+    }
+
+    fn spawn_retries(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let name = &self.name;
+            let mut retries_done = 0;
+            let mut executions = self.stats.executions();
+            while let Some(planned) =
+                retry::plan_retry(&self.retry, retries_done, self.clock.as_ref())
+            {
+                info!(
+                    "Job {}: retry {}/{} scheduled for {}",
+                    name,
+                    planned.attempt,
+                    planned.of,
+                    planned.at.to_rfc3339()
+                );
+                if let Ok(mut status) = self.status.write() {
+                    status.record_retry_scheduled(name, planned.attempt, planned.of, planned.at);
+                }
+                let wait = (planned.at - retry::now(self.clock.as_ref()))
+                    .to_std()
+                    .unwrap_or_default();
+                tokio::time::sleep(wait).await;
+                if let Ok(mut status) = self.status.write() {
+                    status.clear_pending_retry(name);
+                }
+
+                if !retry::retry_still_wanted(executions, self.stats.executions()) {
+                    info!(
+                        "Job {}: a newer run started, dropping retry {}/{}",
+                        name, planned.attempt, planned.of
+                    );
+                    return;
+                }
+                info!(
+                    "Job {}: running retry {}/{}",
+                    name, planned.attempt, planned.of
+                );
+                match self.run_tracked().await {
+                    Some(Err(_)) => {
+                        retries_done += 1;
+                        executions = self.stats.executions();
+                    }
+                    _ => return,
+                }
+            }
+            if retries_done > 0 {
+                warn!(
+                    "Job {}: giving up after {} failed retries",
+                    name, retries_done
+                );
+            }
+        });
+    }
+}
+
+fn log_outcome(name: &str, result: Option<&Result<()>>) {
+    match result {
+        Some(Ok(())) => info!("Scheduled job {} completed successfully", name),
+        Some(Err(e)) => error!("Scheduled job {} failed: {}", name, e),
+        None => {}
+    }
+}
+
+/// Identifies one scheduled trigger of a named job.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JobHandle {
+    pub id: Uuid,
+    pub name: String,
+}
+
+/// A scheduled trigger as reported by `list_jobs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub cron: String,
+    pub next_fire: Option<DateTime<Utc>>,
+}
+
+struct Trigger {
+    id: Uuid,
+    name: String,
+    cron: String,
+}
 
 pub struct DailyScheduler {
     scheduler: JobScheduler,
+    jobs: HashMap<String, Arc<JobRunner>>,
+    triggers: Vec<Trigger>,
+    status: SharedStatus,
+    retry: RetryOptions,
+    clock: Arc<dyn Clock>,
+    jitter_window_secs: u64,
+    draining: Arc<AtomicBool>,
+}
+
+/// Polls until none of `jobs` is running or `timeout` elapses, returning the
+/// names still running at the deadline.
+pub async fn wait_for_idle(
+    jobs: &[(String, Arc<JobStats>)],
+    timeout: std::time::Duration,
+) -> Vec<String> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let running: Vec<String> = jobs
+            .iter()
+            .filter(|(_, stats)| stats.is_running())
+            .map(|(name, _)| name.clone())
+            .collect();
+        if running.is_empty() || tokio::time::Instant::now() >= deadline {
+            return running;
+        }
+        tokio::time::sleep_until(deadline.min(tokio::time::Instant::now() + DRAIN_POLL_INTERVAL))
+            .await;
+    }
 }
 
+const DRAIN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
 impl DailyScheduler {
     pub async fn new() -> Result<Self> {
         let scheduler = JobScheduler::new().await?;
-        
+
         Ok(Self {
             scheduler,
+            jobs: HashMap::new(),
+            triggers: Vec::new(),
+            status: SchedulerStatus::shared(Utc::now()),
+            retry: RetryOptions::disabled(),
+            clock: Arc::new(SystemClock),
+            jitter_window_secs: 0,
+            draining: Arc::new(AtomicBool::new(false)),
         })
     }
 
-    pub async fn add_daily_job<F, Fut>(&mut self, hour: u32, minute: u32, job_fn: F) -> Result<()>
+    /// Staggers jobs registered after this call by a per-name offset in `0..window_secs`.
+    pub fn set_jitter_window(&mut self, window_secs: u64) {
+        self.jitter_window_secs = window_secs;
+    }
+
+    /// Retry policy for failed runs of jobs registered after this call.
+    pub fn set_retry_options(&mut self, retry: RetryOptions) {
+        self.retry = retry;
+    }
+
+    /// Handle to the state served by the status listener.
+    pub fn status_handle(&self) -> SharedStatus {
+        self.status.clone()
+    }
+
+    /// Counters for every registered job name, sorted by name.
+    pub fn status(&self) -> Vec<JobStatus> {
+        let mut status: Vec<JobStatus> = self
+            .jobs
+            .iter()
+            .map(|(name, entry)| JobStatus {
+                name: name.clone(),
+                running: entry.stats.is_running(),
+                executions: entry.stats.executions(),
+                skipped_runs: entry.stats.skipped_runs(),
+            })
+            .collect();
+        status.sort_by(|a, b| a.name.cmp(&b.name));
+        status
+    }
+
+    /// Registers a new job `name` running daily at `hour`:`minute` UTC.
+    ///
+    /// Fails on out-of-range times and when `name` is already registered.
+    pub async fn add_daily_job<F, Fut>(
+        &mut self,
+        name: &str,
+        hour: u32,
+        minute: u32,
+        job_fn: F,
+    ) -> Result<JobHandle>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<()>> + Send + 'static,
+    {
+        let cron_expression = schedule::daily_cron(hour, minute)?;
+        self.add_cron_job(name, &cron_expression, job_fn).await
+    }
+
+    /// Registers a new job `name` running `job_fn` on `cron_expression`.
+    ///
+    /// Fails when `name` is already registered; use `add_trigger` to give an
+    /// existing job more schedules.
+    pub async fn add_cron_job<F, Fut>(
+        &mut self,
+        name: &str,
+        cron_expression: &str,
+        job_fn: F,
+    ) -> Result<JobHandle>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<()>> + Send + 'static,
+    {
+        schedule::validate_cron(cron_expression)?;
+        if self.jobs.contains_key(name) {
+            anyhow::bail!("job '{}' is already registered", name);
+        }
+        let runner = self.new_runner(name, job_fn, Arc::new(JobStats::default()));
+        self.jobs.insert(name.to_string(), runner.clone());
+        self.schedule_trigger(runner, cron_expression).await
+    }
+
+    /// Runs the registered job `name` on `cron_expression` as well. Triggers
+    /// of one job never overlap: one that fires while a previous execution is
+    /// running is skipped. Registering the same cron twice is an error.
+    pub async fn add_trigger(&mut self, name: &str, cron_expression: &str) -> Result<JobHandle> {
+        schedule::validate_cron(cron_expression)?;
+        let runner = self
+            .jobs
+            .get(name)
+            .cloned()
+            .with_context(|| format!("job '{}' is not registered", name))?;
+        if self
+            .triggers
+            .iter()
+            .any(|t| t.name == name && t.cron == cron_expression)
+        {
+            anyhow::bail!(
+                "job '{}' is already scheduled with '{}'",
+                name,
+                cron_expression
+            );
+        }
+        self.schedule_trigger(runner, cron_expression).await
+    }
+
+    /// Replaces every trigger of `name` with a single `cron_expression` running
+    /// `job_fn`, keeping the job's counters and overlap guard.
+    pub async fn replace_job<F, Fut>(
+        &mut self,
+        name: &str,
+        cron_expression: &str,
+        job_fn: F,
+    ) -> Result<JobHandle>
     where
         F: Fn() -> Fut + Send + Sync + 'static,
         Fut: std::future::Future<Output = Result<()>> + Send + 'static,
     {
-        let cron_expression = format!("0 {} {} * * *", minute, hour);
-        info!("Scheduling daily job with cron: {}", cron_expression);
+        schedule::validate_cron(cron_expression)?;
+        let existing: Vec<JobHandle> = self
+            .triggers
+            .iter()
+            .filter(|t| t.name == name)
+            .map(|t| JobHandle {
+                id: t.id,
+                name: t.name.clone(),
+            })
+            .collect();
+        for handle in &existing {
+            self.remove_trigger(handle).await?;
+        }
+        let stats = self
+            .jobs
+            .remove(name)
+            .map(|runner| runner.stats.clone())
+            .unwrap_or_default();
+        let runner = self.new_runner(name, job_fn, stats);
+        self.jobs.insert(name.to_string(), runner.clone());
+        info!("Replaced job {} ({} old trigger(s))", name, existing.len());
+        self.schedule_trigger(runner, cron_expression).await
+    }
+
+    /// Unschedules the trigger behind `handle`. The job itself is dropped once
+    /// its last trigger is gone.
+    pub async fn remove_job(&mut self, handle: &JobHandle) -> Result<()> {
+        self.remove_trigger(handle).await?;
+        if !self.triggers.iter().any(|t| t.name == handle.name) {
+            self.jobs.remove(&handle.name);
+        }
+        Ok(())
+    }
+
+    /// Every scheduled trigger with its next (jittered) fire time, sorted by name then cron.
+    pub fn list_jobs(&self) -> Vec<JobInfo> {
+        let now = Utc::now();
+        let mut jobs: Vec<JobInfo> = self
+            .triggers
+            .iter()
+            .map(|t| JobInfo {
+                id: t.id,
+                name: t.name.clone(),
+                cron: t.cron.clone(),
+                next_fire: schedule::next_fire_times(&t.cron, now, 1)
+                    .ok()
+                    .and_then(|times| times.into_iter().next())
+                    .map(|fire| fire + self.jitter_of(&t.name)),
+            })
+            .collect();
+        jobs.sort_by(|a, b| (&a.name, &a.cron).cmp(&(&b.name, &b.cron)));
+        jobs
+    }
+
+    fn jitter_of(&self, name: &str) -> chrono::Duration {
+        self.jobs
+            .get(name)
+            .and_then(|runner| chrono::Duration::from_std(runner.jitter).ok())
+            .unwrap_or_else(chrono::Duration::zero)
+    }
 
+    fn new_runner<F, Fut>(&self, name: &str, job_fn: F, stats: Arc<JobStats>) -> Arc<JobRunner>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<()>> + Send + 'static,
+    {
         let job_fn = Arc::new(job_fn);
-        let job = Job::new_async(&cron_expression, move |_uuid, _l| {
-            let job_fn = job_fn.clone();
+        Arc::new(JobRunner {
+            name: name.to_string(),
+            stats,
+            status: self.status.clone(),
+            job_fn: Arc::new(move || -> JobFuture { Box::pin(job_fn()) }),
+            retry: self.retry.clone(),
+            clock: self.clock.clone(),
+            jitter: schedule::jitter_offset(name, self.jitter_window_secs),
+            draining: self.draining.clone(),
+        })
+    }
+
+    async fn schedule_trigger(
+        &mut self,
+        runner: Arc<JobRunner>,
+        cron_expression: &str,
+    ) -> Result<JobHandle> {
+        info!(
+            "Scheduling job {} with cron: {}",
+            runner.name, cron_expression
+        );
+        let name = runner.name.clone();
+        let jitter = runner.jitter;
+        let job = Job::new_async(cron_expression, move |_uuid, _l| {
+            let runner = runner.clone();
             Box::pin(async move {
-                info!("Executing scheduled job at {}", OffsetDateTime::now_utc());
-                match job_fn().await {
-                    Ok(()) => info!("Scheduled job completed successfully"),
-                    Err(e) => error!("Scheduled job failed: {}", e),
-                }
+                info!(
+                    "Executing scheduled job {} at {}",
+                    runner.name,
+                    OffsetDateTime::now_utc()
+                );
+                runner.run_scheduled().await;
             })
         })?;
 
-        self.scheduler.add(job).await?;
+        let id = self.scheduler.add(job).await?;
+        if let Ok(mut status) = self.status.write() {
+            status.register_job(&name, cron_expression);
+            status.set_jitter(&name, jitter);
+        }
+        self.triggers.push(Trigger {
+            id,
+            name: name.clone(),
+            cron: cron_expression.to_string(),
+        });
+        Ok(JobHandle { id, name })
+    }
+
+    async fn remove_trigger(&mut self, handle: &JobHandle) -> Result<()> {
+        let index = self
+            .triggers
+            .iter()
+            .position(|t| t.id == handle.id)
+            .with_context(|| format!("job '{}' ({}) is not scheduled", handle.name, handle.id))?;
+        self.scheduler.remove(&handle.id).await?;
+        let trigger = self.triggers.remove(index);
+        if let Ok(mut status) = self.status.write() {
+            status.unregister_job(&trigger.name, &trigger.cron);
+        }
+        info!("Removed job {} ({})", trigger.name, trigger.cron);
         Ok(())
     }
 
+    /// Runs the job registered under `name` immediately, honoring the overlap guard.
+    ///
+    /// Returns `None` when no such job exists or the trigger was skipped.
+    pub async fn run_now(&self, name: &str) -> Option<Result<()>> {
+        let runner = self.jobs.get(name)?;
+        info!("Running job {} immediately", name);
+        runner.run().await
+    }
+
     pub async fn start(&self) -> Result<()> {
         info!("Starting scheduler...");
         self.scheduler.start().await?;
@@ -49,16 +540,284 @@ impl DailyScheduler {
 
     pub async fn shutdown(&self) -> Result<()> {
         info!("Shutting down scheduler...");
-        self.scheduler.shutdown().await?;
+        self.scheduler.clone().shutdown().await?;
         Ok(())
     }
 
+    /// Stops new executions, waits up to `timeout` for in-flight jobs to
+    /// finish and returns the names of jobs that were still running.
+    pub async fn drain(&self, timeout: std::time::Duration) -> Result<Vec<String>> {
+        self.draining.store(true, Ordering::SeqCst);
+        self.shutdown().await?;
+
+        let jobs: Vec<(String, Arc<JobStats>)> = self
+            .jobs
+            .iter()
+            .map(|(name, runner)| (name.clone(), runner.stats.clone()))
+            .collect();
+        let in_flight: Vec<&str> = jobs
+            .iter()
+            .filter(|(_, stats)| stats.is_running())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if !in_flight.is_empty() {
+            info!(
+                "Waiting up to {}s for running jobs to finish: {}",
+                timeout.as_secs(),
+                in_flight.join(", ")
+            );
+        }
+
+        let aborted = wait_for_idle(&jobs, timeout).await;
+        if aborted.is_empty() {
+            info!("All jobs finished, scheduler drained");
+        } else {
+            warn!(
+                "Drain timed out after {}s, aborting running jobs: {}",
+                timeout.as_secs(),
+                aborted.join(", ")
+            );
+        }
+        Ok(aborted)
+    }
+
+    /// Keeps the process alive once [`start`](Self::start) has been called.
     pub async fn run_forever(&self) -> Result<()> {
-        self.start().await?;
-        
-        // Keep the scheduler running
+        // Keep the scheduler running; each tick proves the loop is alive to `/healthz`.
         loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+            if let Ok(mut status) = self.status.write() {
+                status.record_tick(Utc::now());
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn overlapping_trigger_is_skipped() {
+        let mut scheduler = DailyScheduler::new().await.unwrap();
+        let concurrent = Arc::new(AtomicU64::new(0));
+        let peak = Arc::new(AtomicU64::new(0));
+        let (running, most) = (concurrent.clone(), peak.clone());
+        scheduler
+            .add_cron_job("slow", "* * * * * *", move || -> JobFuture {
+                let (running, most) = (running.clone(), most.clone());
+                Box::pin(async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    // Runs past the next trigger.
+                    tokio::time::sleep(Duration::from_millis(2500)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                })
+            })
+            .await
+            .unwrap();
+        scheduler.start().await.unwrap();
+
+        let mut status = scheduler.status();
+        for _ in 0..50 {
+            if status[0].skipped_runs >= 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            status = scheduler.status();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert!(status[0].executions >= 1);
+        assert!(status[0].skipped_runs >= 1, "{:?}", status);
+        scheduler.shutdown().await.unwrap();
+    }
+
+    fn counting_job(counter: &Arc<AtomicU64>) -> impl Fn() -> JobFuture + Send + Sync + 'static {
+        let counter = counter.clone();
+        move || -> JobFuture {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Ok(()) })
         }
     }
-}
\ No newline at end of file
+
+    /// Waits (in real time) until `counter` reaches `min`, failing after a few seconds.
+    async fn wait_for(counter: &AtomicU64, min: u64) {
+        for _ in 0..50 {
+            if counter.load(Ordering::SeqCst) >= min {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("job did not fire {} time(s)", min);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn add_daily_job_validates_time_and_rejects_duplicate_names() {
+        let mut scheduler = DailyScheduler::new().await.unwrap();
+        let counter = Arc::new(AtomicU64::new(0));
+        assert!(scheduler
+            .add_daily_job("digest", 25, 90, counting_job(&counter))
+            .await
+            .is_err());
+        let handle = scheduler
+            .add_daily_job("digest", 9, 0, counting_job(&counter))
+            .await
+            .unwrap();
+        assert_eq!(handle.name, "digest");
+        let err = scheduler
+            .add_daily_job("digest", 10, 0, counting_job(&counter))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already registered"));
+        assert!(scheduler
+            .add_cron_job("digest", "0 0 9 * * *", counting_job(&counter))
+            .await
+            .is_err());
+
+        let jobs = scheduler.list_jobs();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(
+            (jobs[0].id, jobs[0].cron.as_str()),
+            (handle.id, "0 0 9 * * *")
+        );
+        assert!(jobs[0].next_fire.is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn add_cron_job_rejects_duplicate_names_and_add_trigger_extends_a_job() {
+        let mut scheduler = DailyScheduler::new().await.unwrap();
+        let counter = Arc::new(AtomicU64::new(0));
+        assert!(scheduler
+            .add_trigger("digest", "0 0 9 * * *")
+            .await
+            .is_err());
+        scheduler
+            .add_cron_job("digest", "0 0 9 * * *", counting_job(&counter))
+            .await
+            .unwrap();
+        let err = scheduler
+            .add_cron_job("digest", "0 0 21 * * *", counting_job(&counter))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already registered"));
+
+        scheduler
+            .add_trigger("digest", "0 0 21 * * *")
+            .await
+            .unwrap();
+        assert!(scheduler
+            .add_trigger("digest", "0 0 21 * * *")
+            .await
+            .is_err());
+        let crons: Vec<_> = scheduler.list_jobs().into_iter().map(|j| j.cron).collect();
+        assert_eq!(crons, vec!["0 0 21 * * *", "0 0 9 * * *"]);
+        assert_eq!(scheduler.status().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn removed_job_stops_firing() {
+        let mut scheduler = DailyScheduler::new().await.unwrap();
+        let counter = Arc::new(AtomicU64::new(0));
+        let handle = scheduler
+            .add_cron_job("tick", "* * * * * *", counting_job(&counter))
+            .await
+            .unwrap();
+        scheduler.start().await.unwrap();
+        wait_for(&counter, 1).await;
+
+        scheduler.remove_job(&handle).await.unwrap();
+        let fired = counter.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert_eq!(counter.load(Ordering::SeqCst), fired);
+        assert!(scheduler.list_jobs().is_empty());
+        assert!(scheduler.status().is_empty());
+        assert!(scheduler.remove_job(&handle).await.is_err());
+        scheduler.shutdown().await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replace_job_swaps_cron_and_body() {
+        let mut scheduler = DailyScheduler::new().await.unwrap();
+        let old = Arc::new(AtomicU64::new(0));
+        let new = Arc::new(AtomicU64::new(0));
+        scheduler
+            .add_cron_job("tick", "0 0 9 * * *", counting_job(&old))
+            .await
+            .unwrap();
+        scheduler.add_trigger("tick", "0 0 21 * * *").await.unwrap();
+
+        let handle = scheduler
+            .replace_job("tick", "* * * * * *", counting_job(&new))
+            .await
+            .unwrap();
+        let jobs = scheduler.list_jobs();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(
+            (jobs[0].id, jobs[0].cron.as_str()),
+            (handle.id, "* * * * * *")
+        );
+
+        scheduler.start().await.unwrap();
+        wait_for(&new, 1).await;
+        assert_eq!(old.load(Ordering::SeqCst), 0);
+        scheduler.shutdown().await.unwrap();
+    }
+
+    async fn slow_job(stats: Arc<JobStats>, secs: u64) {
+        run_exclusive("slow", &stats, || async move {
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            Ok(())
+        })
+        .await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drain_waits_for_job_finishing_within_timeout() {
+        let stats = Arc::new(JobStats::default());
+        let job = tokio::spawn(slow_job(stats.clone(), 30));
+        tokio::task::yield_now().await;
+        assert!(stats.is_running());
+
+        // Shutdown is requested while the job still has 30s to go.
+        let started = tokio::time::Instant::now();
+        let aborted = wait_for_idle(
+            &[("slow".to_string(), stats.clone())],
+            Duration::from_secs(300),
+        )
+        .await;
+        assert!(aborted.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(31));
+        job.await.unwrap();
+        assert_eq!(stats.executions(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drain_reports_jobs_still_running_at_timeout() {
+        let stats = Arc::new(JobStats::default());
+        let _job = tokio::spawn(slow_job(stats.clone(), 600));
+        tokio::task::yield_now().await;
+
+        let started = tokio::time::Instant::now();
+        let aborted = wait_for_idle(
+            &[("slow".to_string(), stats.clone())],
+            Duration::from_secs(300),
+        )
+        .await;
+        assert_eq!(aborted, vec!["slow".to_string()]);
+        assert_eq!(started.elapsed(), Duration::from_secs(300));
+    }
+
+    #[tokio::test]
+    async fn running_flag_is_cleared_after_failure() {
+        let stats = JobStats::default();
+        let result: Option<Result<()>> =
+            run_exclusive("failing", &stats, || async { anyhow::bail!("boom") }).await;
+        assert!(matches!(result, Some(Err(_))));
+        assert!(!stats.is_running());
+        assert!(run_exclusive("failing", &stats, || async { Ok(()) })
+            .await
+            .is_some());
+    }
+}
@@ -0,0 +1,172 @@
+//! The tail of a Ctrl+C or SIGTERM shutdown, after running jobs have drained: pending
+//! notifications get their chance to go out, a last heartbeat records the
+//! final status and the status listener stays up for one more scrape, all
+//! within `SHUTDOWN_GRACE_SECS`.
+
+use anyhow::Result;
+use notify::{Notification, Notifier};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::schedule;
+
+/// Completes on Ctrl+C or, on unix, SIGTERM (what `docker stop` and
+/// systemd send).
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => info!("Received Ctrl+C"),
+                    _ = sigterm.recv() => info!("Received SIGTERM"),
+                }
+                return;
+            }
+            Err(e) => warn!(
+                "Could not listen for SIGTERM, only Ctrl+C stops the scheduler: {}",
+                e
+            ),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+    info!("Received Ctrl+C");
+}
+
+/// Reads `SHUTDOWN_GRACE_SECS`: how long shutdown waits for notifications and
+/// keeps `/status` up after jobs have drained (default 10).
+pub fn grace_from_env() -> Result<Duration> {
+    schedule::parse_env_u64("SHUTDOWN_GRACE_SECS", 10).map(Duration::from_secs)
+}
+
+/// Notification sends running in the background, so a slow webhook never
+/// holds up a job, yet shutdown can still wait for them.
+#[derive(Clone, Default)]
+pub struct PendingSends {
+    tasks: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl PendingSends {
+    /// Sends `msg` through `notifier` in the background.
+    pub fn notify(&self, notifier: Arc<dyn Notifier>, msg: Notification) {
+        self.spawn(async move { notifier.send(&msg).await });
+    }
+
+    pub fn spawn(&self, send: impl Future<Output = ()> + Send + 'static) {
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        // Reap finished sends so the set only holds pending ones.
+        while tasks.try_join_next().is_some() {}
+        tasks.spawn(send);
+    }
+
+    /// Waits for every send started so far, giving up at `deadline`. Returns
+    /// how many were abandoned.
+    pub async fn wait(&self, deadline: Instant) -> usize {
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(|e| e.into_inner()));
+        while !tasks.is_empty() {
+            if tokio::time::timeout_at(deadline, tasks.join_next())
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+        tasks.len()
+    }
+}
+
+/// Spends up to `grace` flushing: waits for `pending` sends, then runs
+/// `final_heartbeat`, then (with a status listener) keeps serving until the
+/// grace period is over so a last scrape sees the final run.
+pub async fn flush<F>(
+    pending: &PendingSends,
+    grace: Duration,
+    final_heartbeat: Option<F>,
+    serving_status: bool,
+) where
+    F: Future<Output = Result<()>>,
+{
+    let deadline = Instant::now() + grace;
+    let abandoned = pending.wait(deadline).await;
+    if abandoned > 0 {
+        warn!(
+            "Gave up on {} notification(s) still sending after {}s",
+            abandoned,
+            grace.as_secs()
+        );
+    }
+    if let Some(heartbeat) = final_heartbeat {
+        match tokio::time::timeout_at(deadline, heartbeat).await {
+            Ok(Ok(())) => info!("Uploaded final heartbeat"),
+            Ok(Err(e)) => warn!("Final heartbeat upload failed: {}", e),
+            Err(_) => warn!("Final heartbeat upload timed out"),
+        }
+    }
+    if serving_status && Instant::now() < deadline {
+        info!(
+            "Serving final status for {}s before exiting",
+            (deadline - Instant::now()).as_secs()
+        );
+        tokio::time::sleep_until(deadline).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_waits_for_sends_until_the_deadline() {
+        let pending = PendingSends::default();
+        let sent = Arc::new(AtomicUsize::new(0));
+        for delay in [1, 5, 60] {
+            let sent = sent.clone();
+            pending.spawn(async move {
+                tokio::time::sleep(Duration::from_secs(delay)).await;
+                sent.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        let started = Instant::now();
+        let abandoned = pending.wait(started + Duration::from_secs(10)).await;
+        assert_eq!(abandoned, 1);
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+        assert_eq!(started.elapsed(), Duration::from_secs(10));
+
+        assert_eq!(
+            pending.wait(Instant::now()).await,
+            0,
+            "nothing left to wait for"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn status_stays_up_for_the_whole_grace_period() {
+        let started = Instant::now();
+        let heartbeat = async { Ok(()) };
+        flush(
+            &PendingSends::default(),
+            Duration::from_secs(10),
+            Some(heartbeat),
+            true,
+        )
+        .await;
+        assert_eq!(started.elapsed(), Duration::from_secs(10));
+
+        let started = Instant::now();
+        flush::<std::future::Ready<Result<()>>>(
+            &PendingSends::default(),
+            Duration::from_secs(10),
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(started.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use common::SupabaseStorageClient;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+use crate::schedule;
+
+/// What the scheduler does for each job right after it starts.
+#[derive(Debug, Clone)]
+pub struct StartupOptions {
+    /// Run every job once immediately (`CUTION_RUN_ON_START`).
+    pub run_on_start: bool,
+    /// Run a job immediately when a trigger was missed (`CUTION_CATCH_UP`).
+    pub catch_up: bool,
+    /// How far back a missed trigger still counts (`CUTION_CATCH_UP_WINDOW_HOURS`).
+    pub catch_up_window: Duration,
+}
+
+impl StartupOptions {
+    pub fn from_env() -> Result<Self> {
+        let window_hours = match env::var("CUTION_CATCH_UP_WINDOW_HOURS") {
+            Ok(v) => v
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .filter(|h| *h > 0)
+                .with_context(|| {
+                    format!(
+                        "CUTION_CATCH_UP_WINDOW_HOURS must be a positive integer, got '{}'",
+                        v
+                    )
+                })?,
+            Err(_) => 24,
+        };
+        Ok(Self {
+            run_on_start: env_flag("CUTION_RUN_ON_START"),
+            catch_up: env_flag("CUTION_CATCH_UP"),
+            catch_up_window: Duration::hours(window_hours),
+        })
+    }
+}
+
+fn env_flag(key: &str) -> bool {
+    env::var(key)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LastRun {
+    job: String,
+    finished_at: DateTime<Utc>,
+}
+
+/// Storage path holding the last successful run of `job`.
+pub fn last_run_path(job: &str) -> String {
+    format!("state/scheduler-last-run-{}.json", job)
+}
+
+/// Reads the last successful run of `job`; a missing or unreadable file means "never".
+pub async fn load_last_success(
+    storage: &SupabaseStorageClient,
+    job: &str,
+) -> Result<Option<DateTime<Utc>>> {
+    let Some(body) = storage.download_file(&last_run_path(job)).await? else {
+        return Ok(None);
+    };
+    Ok(serde_json::from_str::<LastRun>(&body)
+        .ok()
+        .map(|last| last.finished_at))
+}
+
+pub async fn save_last_success(
+    storage: &SupabaseStorageClient,
+    job: &str,
+    finished_at: DateTime<Utc>,
+) -> Result<()> {
+    let body = serde_json::to_string(&LastRun {
+        job: job.to_string(),
+        finished_at,
+    })?;
+    storage
+        .upload_file(&last_run_path(job), body, "application/json")
+        .await
+}
+
+/// Returns the most recent trigger of `crons` within `window` before `now` that
+/// has not been covered by a successful run since.
+pub fn missed_trigger(
+    crons: &[String],
+    last_success: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    window: Duration,
+) -> Result<Option<DateTime<Utc>>> {
+    let window_start = now - window;
+    let mut latest: Option<DateTime<Utc>> = None;
+    for cron in crons {
+        let schedule = schedule::validate_cron(cron)?;
+        // `after` is exclusive, so step back one second to include a trigger exactly at the window start.
+        let previous = schedule
+            .after(&(window_start - Duration::seconds(1)))
+            .take_while(|t| *t <= now)
+            .last();
+        latest = latest.max(previous);
+    }
+
+    Ok(latest.filter(|trigger| match last_success {
+        Some(last) => last < *trigger,
+        None => true,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 10, h, m, 0).unwrap()
+    }
+
+    fn daily_9() -> Vec<String> {
+        vec!["0 0 9 * * *".to_string()]
+    }
+
+    #[test]
+    fn missed_run_is_detected_after_restart() {
+        let last = Some(at(9, 0) - Duration::days(1));
+        let missed = missed_trigger(&daily_9(), last, at(9, 30), Duration::hours(6)).unwrap();
+        assert_eq!(missed, Some(at(9, 0)));
+    }
+
+    #[test]
+    fn no_catch_up_when_last_run_covers_trigger() {
+        let last = Some(at(9, 5));
+        assert_eq!(
+            missed_trigger(&daily_9(), last, at(9, 30), Duration::hours(6)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn trigger_outside_window_is_ignored() {
+        assert_eq!(
+            missed_trigger(&daily_9(), None, at(9, 30), Duration::minutes(29)).unwrap(),
+            None
+        );
+        assert_eq!(
+            missed_trigger(&daily_9(), None, at(9, 30), Duration::minutes(30)).unwrap(),
+            Some(at(9, 0))
+        );
+    }
+
+    #[test]
+    fn trigger_exactly_now_counts_as_missed() {
+        assert_eq!(
+            missed_trigger(&daily_9(), None, at(9, 0), Duration::hours(1)).unwrap(),
+            Some(at(9, 0))
+        );
+    }
+
+    #[test]
+    fn before_first_trigger_of_day_uses_previous_day() {
+        let yesterday = at(9, 0) - Duration::days(1);
+        assert_eq!(
+            missed_trigger(&daily_9(), None, at(8, 0), Duration::hours(24)).unwrap(),
+            Some(yesterday)
+        );
+        assert_eq!(
+            missed_trigger(&daily_9(), Some(yesterday), at(8, 0), Duration::hours(24)).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,377 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+use crate::schedule;
+
+/// Shared scheduler state read by the status listener.
+pub type SharedStatus = Arc<RwLock<SchedulerStatus>>;
+
+/// How many upcoming fire times `/status` lists per job.
+const NEXT_FIRE_TIMES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunOutcome {
+    Success,
+    Failed,
+}
+
+/// The most recent execution of a job.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LastRun {
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub outcome: Option<RunOutcome>,
+    pub error: Option<String>,
+}
+
+/// A retry waiting to fire after a failed run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingRetry {
+    pub attempt: u32,
+    pub of: u32,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct JobState {
+    crons: Vec<String>,
+    last_run: LastRun,
+    skipped_runs: u64,
+    pending_retry: Option<PendingRetry>,
+    jitter: Duration,
+}
+
+/// Liveness and per-job run history, updated by the scheduler's job wrapper.
+#[derive(Debug, Clone)]
+pub struct SchedulerStatus {
+    started_at: DateTime<Utc>,
+    last_tick: DateTime<Utc>,
+    jobs: BTreeMap<String, JobState>,
+}
+
+impl SchedulerStatus {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            started_at: now,
+            last_tick: now,
+            jobs: BTreeMap::new(),
+        }
+    }
+
+    pub fn shared(now: DateTime<Utc>) -> SharedStatus {
+        Arc::new(RwLock::new(Self::new(now)))
+    }
+
+    pub fn register_job(&mut self, name: &str, cron: &str) {
+        self.jobs
+            .entry(name.to_string())
+            .or_default()
+            .crons
+            .push(cron.to_string());
+    }
+
+    /// Offset added to the job's cron fire times.
+    pub fn set_jitter(&mut self, name: &str, jitter: std::time::Duration) {
+        self.jobs.entry(name.to_string()).or_default().jitter =
+            Duration::from_std(jitter).unwrap_or_else(|_| Duration::zero());
+    }
+
+    /// Drops `cron` from `name`; the job disappears with its last cron.
+    pub fn unregister_job(&mut self, name: &str, cron: &str) {
+        if let Some(job) = self.jobs.get_mut(name) {
+            job.crons.retain(|c| c != cron);
+            if job.crons.is_empty() {
+                self.jobs.remove(name);
+            }
+        }
+    }
+
+    /// Marks the scheduler loop as alive.
+    pub fn record_tick(&mut self, now: DateTime<Utc>) {
+        self.last_tick = now;
+    }
+
+    pub fn record_start(&mut self, name: &str, now: DateTime<Utc>) {
+        let job = self.jobs.entry(name.to_string()).or_default();
+        job.last_run = LastRun {
+            started_at: Some(now),
+            ..LastRun::default()
+        };
+    }
+
+    pub fn record_finish(&mut self, name: &str, now: DateTime<Utc>, result: &Result<()>) {
+        let job = self.jobs.entry(name.to_string()).or_default();
+        job.last_run.finished_at = Some(now);
+        match result {
+            Ok(()) => job.last_run.outcome = Some(RunOutcome::Success),
+            Err(e) => {
+                job.last_run.outcome = Some(RunOutcome::Failed);
+                job.last_run.error = Some(e.to_string());
+            }
+        }
+    }
+
+    pub fn record_skip(&mut self, name: &str) {
+        self.jobs.entry(name.to_string()).or_default().skipped_runs += 1;
+    }
+
+    pub fn record_retry_scheduled(&mut self, name: &str, attempt: u32, of: u32, at: DateTime<Utc>) {
+        self.jobs.entry(name.to_string()).or_default().pending_retry =
+            Some(PendingRetry { attempt, of, at });
+    }
+
+    pub fn clear_pending_retry(&mut self, name: &str) {
+        if let Some(job) = self.jobs.get_mut(name) {
+            job.pending_retry = None;
+        }
+    }
+
+    /// The loop is healthy while it has ticked within `stale_after`.
+    pub fn is_healthy(&self, now: DateTime<Utc>, stale_after: Duration) -> bool {
+        now - self.last_tick <= stale_after
+    }
+
+    /// Serializable view served at `/status`.
+    pub fn report(&self, now: DateTime<Utc>, stale_after: Duration) -> StatusReport {
+        let jobs = self
+            .jobs
+            .iter()
+            .map(|(name, job)| {
+                let mut next_fire_times: Vec<DateTime<Utc>> = job
+                    .crons
+                    .iter()
+                    .filter_map(|cron| schedule::next_fire_times(cron, now, NEXT_FIRE_TIMES).ok())
+                    .flatten()
+                    .map(|fire| fire + job.jitter)
+                    .collect();
+                next_fire_times.sort();
+                next_fire_times.dedup();
+                next_fire_times.truncate(NEXT_FIRE_TIMES);
+                JobReport {
+                    name: name.clone(),
+                    crons: job.crons.clone(),
+                    jitter_secs: job.jitter.num_seconds(),
+                    next_fire_times,
+                    last_run: job.last_run.clone(),
+                    skipped_runs: job.skipped_runs,
+                    pending_retry: job.pending_retry.clone(),
+                }
+            })
+            .collect();
+        StatusReport {
+            healthy: self.is_healthy(now, stale_after),
+            started_at: self.started_at,
+            last_tick: self.last_tick,
+            jobs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusReport {
+    pub healthy: bool,
+    pub started_at: DateTime<Utc>,
+    pub last_tick: DateTime<Utc>,
+    pub jobs: Vec<JobReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobReport {
+    pub name: String,
+    pub crons: Vec<String>,
+    pub jitter_secs: i64,
+    /// Cron fire times plus the job's jitter, i.e. when it will actually start.
+    pub next_fire_times: Vec<DateTime<Utc>>,
+    pub last_run: LastRun,
+    pub skipped_runs: u64,
+    pub pending_retry: Option<PendingRetry>,
+}
+
+/// Settings for the optional HTTP status listener.
+#[derive(Debug, Clone)]
+pub struct StatusServerOptions {
+    pub addr: SocketAddr,
+    /// `/healthz` turns unhealthy when the loop has not ticked for this long.
+    pub stale_after: Duration,
+}
+
+impl StatusServerOptions {
+    /// Reads `CUTION_STATUS_ADDR` and `CUTION_STATUS_STALE_MINUTES` (default 5).
+    /// Returns `None` when no address is configured.
+    pub fn from_env() -> Result<Option<Self>> {
+        let addr = match env::var("CUTION_STATUS_ADDR") {
+            Ok(v) if !v.trim().is_empty() => v
+                .trim()
+                .parse::<SocketAddr>()
+                .with_context(|| format!("CUTION_STATUS_ADDR must be host:port, got '{}'", v))?,
+            _ => return Ok(None),
+        };
+        let stale_minutes = match env::var("CUTION_STATUS_STALE_MINUTES") {
+            Ok(v) => v
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .filter(|m| *m > 0)
+                .with_context(|| {
+                    format!(
+                        "CUTION_STATUS_STALE_MINUTES must be a positive integer, got '{}'",
+                        v
+                    )
+                })?,
+            Err(_) => 5,
+        };
+        Ok(Some(Self {
+            addr,
+            stale_after: Duration::minutes(stale_minutes),
+        }))
+    }
+}
+
+/// Builds the response for `path`: status code, content type and body.
+pub fn respond(
+    path: &str,
+    status: &SchedulerStatus,
+    now: DateTime<Utc>,
+    stale_after: Duration,
+) -> (u16, &'static str, String) {
+    match path {
+        "/healthz" if status.is_healthy(now, stale_after) => {
+            (200, "text/plain", "ok\n".to_string())
+        }
+        "/healthz" => (503, "text/plain", "stale\n".to_string()),
+        "/status" => match serde_json::to_string_pretty(&status.report(now, stale_after)) {
+            Ok(body) => (200, "application/json", body),
+            Err(e) => (500, "text/plain", format!("{}\n", e)),
+        },
+        _ => (404, "text/plain", "not found\n".to_string()),
+    }
+}
+
+/// Serves `/healthz` and `/status` until the task is dropped.
+pub async fn serve(options: StatusServerOptions, status: SharedStatus) -> Result<()> {
+    let listener = TcpListener::bind(options.addr)
+        .await
+        .with_context(|| format!("Failed to bind status listener on {}", options.addr))?;
+    info!("Status listener on http://{}", listener.local_addr()?);
+
+    loop {
+        let (mut socket, _) = listener.accept().await?;
+        let status = status.clone();
+        let stale_after = options.stale_after;
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let n = match socket.read(&mut buf).await {
+                Ok(n) => n,
+                Err(e) => {
+                    warn!("Status listener read failed: {}", e);
+                    return;
+                }
+            };
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("/");
+            let (code, content_type, body) = match status.read() {
+                Ok(status) => respond(path, &status, Utc::now(), stale_after),
+                Err(_) => (500, "text/plain", "status lock poisoned\n".to_string()),
+            };
+            let reason = match code {
+                200 => "OK",
+                404 => "Not Found",
+                503 => "Service Unavailable",
+                _ => "Internal Server Error",
+            };
+            let response = format!(
+                "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                code,
+                reason,
+                content_type,
+                body.len(),
+                body
+            );
+            if let Err(e) = socket.write_all(response.as_bytes()).await {
+                warn!("Status listener write failed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 10, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn health_turns_stale_without_ticks() {
+        let mut status = SchedulerStatus::new(at(9, 0));
+        let stale_after = Duration::minutes(5);
+        assert!(status.is_healthy(at(9, 5), stale_after));
+        assert!(!status.is_healthy(at(9, 6), stale_after));
+        assert_eq!(respond("/healthz", &status, at(9, 6), stale_after).0, 503);
+
+        status.record_tick(at(9, 6));
+        assert_eq!(respond("/healthz", &status, at(9, 6), stale_after).0, 200);
+    }
+
+    #[test]
+    fn status_json_shape() {
+        let mut status = SchedulerStatus::new(at(8, 0));
+        status.register_job("crawlers", "0 0 9 * * *");
+        status.set_jitter("crawlers", std::time::Duration::from_secs(90));
+        status.record_start("crawlers", at(9, 0));
+        status.record_finish("crawlers", at(9, 2), &Err(anyhow::anyhow!("boom")));
+        status.record_skip("crawlers");
+        status.record_retry_scheduled("crawlers", 1, 2, at(9, 32));
+
+        let (code, content_type, body) = respond("/status", &status, at(9, 3), Duration::hours(2));
+        assert_eq!((code, content_type), (200, "application/json"));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "healthy": true,
+                "started_at": "2024-05-10T08:00:00Z",
+                "last_tick": "2024-05-10T08:00:00Z",
+                "jobs": [{
+                    "name": "crawlers",
+                    "crons": ["0 0 9 * * *"],
+                    "jitter_secs": 90,
+                    "next_fire_times": [
+                        "2024-05-11T09:01:30Z",
+                        "2024-05-12T09:01:30Z",
+                        "2024-05-13T09:01:30Z"
+                    ],
+                    "last_run": {
+                        "started_at": "2024-05-10T09:00:00Z",
+                        "finished_at": "2024-05-10T09:02:00Z",
+                        "outcome": "failed",
+                        "error": "boom"
+                    },
+                    "skipped_runs": 1,
+                    "pending_retry": {
+                        "attempt": 1,
+                        "of": 2,
+                        "at": "2024-05-10T09:32:00Z"
+                    }
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_path_is_not_found() {
+        let status = SchedulerStatus::new(at(9, 0));
+        assert_eq!(respond("/", &status, at(9, 0), Duration::minutes(5)).0, 404);
+    }
+}
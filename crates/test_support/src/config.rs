@@ -0,0 +1,154 @@
+use crate::supabase::{MockSupabase, BUCKET};
+use common::config::{SupabaseConfig, TrendingRange};
+use common::{Config, OutputFormat};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Builds a `Config` with the same defaults as `Config::from_env` and every
+/// endpoint pointed at the mocks, so nothing reaches the network.
+pub struct TestConfig {
+    config: Config,
+}
+
+impl TestConfig {
+    pub fn new(supabase: &MockSupabase) -> Self {
+        Self {
+            config: Config {
+                supabase: SupabaseConfig {
+                    url: supabase.url(),
+                    storage_url: supabase.storage_url(),
+                    key: "test-key".to_string(),
+                    bucket: BUCKET.to_string(),
+                },
+                gemini_api_key: None,
+                xai_api_key: None,
+                custom_site_url: None,
+                custom_site_urls: Vec::new(),
+                custom_site_combined: false,
+                languages: Vec::new(),
+                languages_set: false,
+                immutable_archive: false,
+                csv_bom: false,
+                output_formats: vec![OutputFormat::Markdown],
+                crawler_output_formats: HashMap::new(),
+                output_banners: HashMap::new(),
+                output_footers: HashMap::new(),
+                github_language_concurrency: 4,
+                github_requests_per_sec: 2.0,
+                github_fetch_readme: 0,
+                github_exclude_archived: false,
+                github_exclude_forks: false,
+                github_file_per_language: false,
+                github_combined_file: false,
+                github_confirm_flags_top: 0,
+                github_trending_since: TrendingRange::Daily,
+                ranking_min_description_len: 1,
+                openrouter_use_api: true,
+                hn_item_types: vec!["story".to_string()],
+                hn_seen_urls: Default::default(),
+                custom_site_seen_urls: Default::default(),
+                seen_urls_ttl_days: 30,
+                summary_cache_ttl_days: None,
+                partial_upload_every: None,
+                retention_days: None,
+                retention_dry_run: false,
+                llm_prices: HashMap::new(),
+                llm_budget_usd: None,
+                best_of_day: false,
+                best_of_day_weights: HashMap::new(),
+                lobsters_enabled: false,
+                lobsters_tags: Vec::new(),
+                lobsters_min_score: 5,
+                product_hunt_token: None,
+                product_hunt_top_n: 10,
+                reddit_subreddits: Vec::new(),
+                reddit_min_score: 10,
+                reddit_flairs: Vec::new(),
+                reddit_summarize: false,
+                feeds_opml: None,
+                feeds_max_age_hours: 24,
+                feeds_summarize: false,
+                devto_enabled: false,
+                devto_tags: Vec::new(),
+                devto_min_reactions: 10,
+                registries_enabled: false,
+                npm_watchlist: Vec::new(),
+                youtube_channels: Vec::new(),
+                arxiv_ids: Vec::new(),
+                youtube_summarize: false,
+                fediverse_enabled: false,
+                fediverse_keywords: Vec::new(),
+                fediverse_mastodon_instance: None,
+                fediverse_min_likes: 10,
+                fediverse_min_reposts: 0,
+                papers_with_code: false,
+                papers_with_code_top_n: 20,
+                weekly_rollup: false,
+                weekly_rollup_top_n: 5,
+                daily_digest: false,
+                digest_tldr: false,
+                generate_html_index: false,
+                daily_index: false,
+                stale_after_days: None,
+                fixture_dir: None,
+                local_storage_dir: None,
+                storage_upload_retries: 2,
+                storage_upload_retry_base_delay: std::time::Duration::from_millis(500),
+                http_timeout: common::http::DEFAULT_TIMEOUT,
+                crawler_timeout: Some(common::crawler::DEFAULT_CRAWLER_TIMEOUT),
+                http_tuning: Default::default(),
+                checksums: false,
+                checksums_key: None,
+                errors_file: Vec::new(),
+                audit_http: false,
+                archive: false,
+                state_db: None,
+                embeddings: false,
+                embedding_model: common::gemini::DEFAULT_EMBEDDING_MODEL.to_string(),
+                embedding_dimensions: 768,
+                translate_to: None,
+                translate_sources: ["github", "hacker_news", "openrouter", "mcp_rankings"]
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+                notify_slack_url: None,
+                notify_discord_url: None,
+                notify_webhook_url: None,
+                http_proxy: None,
+                http_user_agent: None,
+            },
+        }
+    }
+
+    pub fn languages(mut self, languages: &[&str]) -> Self {
+        self.config.languages = languages.iter().map(|l| l.to_string()).collect();
+        self.config.languages_set = true;
+        self
+    }
+
+    pub fn gemini_api_key(mut self, key: &str) -> Self {
+        self.config.gemini_api_key = Some(key.to_string());
+        self
+    }
+
+    /// Serves HTTP GETs from `dir` (see `FixtureDir`) instead of the network.
+    pub fn fixture_dir(mut self, dir: &Path) -> Self {
+        self.config.fixture_dir = Some(dir.to_path_buf());
+        self
+    }
+
+    pub fn output_formats(mut self, formats: &[OutputFormat]) -> Self {
+        self.config.output_formats = formats.to_vec();
+        self
+    }
+
+    /// Escape hatch for fields without a dedicated setter.
+    pub fn with(mut self, update: impl FnOnce(&mut Config)) -> Self {
+        update(&mut self.config);
+        self
+    }
+
+    pub fn build(self) -> Config {
+        self.config
+    }
+}
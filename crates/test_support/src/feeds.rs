@@ -0,0 +1,84 @@
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Blogs on a local port, serving feeds that honour `If-None-Match`.
+pub struct MockFeeds {
+    server: MockServer,
+}
+
+/// What the mock saw of one request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedRequest {
+    pub path: String,
+    pub if_none_match: Option<String>,
+}
+
+impl MockFeeds {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// The URL of `path` on the mock, e.g. `url("/rust.xml")`.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.server.uri(), path)
+    }
+
+    /// Serves `body` at `route` with `etag`, answering requests that send the
+    /// same tag back with 304 Not Modified.
+    pub async fn feed(&self, route: &str, body: &str, etag: &str) {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .and(header("if-none-match", etag))
+            .respond_with(ResponseTemplate::new(304).insert_header("etag", etag))
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/xml")
+                    .insert_header("etag", etag)
+                    .set_body_string(body),
+            )
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Serves `body` at `route` without validators.
+    pub async fn file(&self, route: &str, body: &str) {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answers every request for `route` with `status`.
+    pub async fn fail(&self, route: &str, status: u16) {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(status).set_body_string("feed unavailable"))
+            .mount(&self.server)
+            .await;
+    }
+
+    pub async fn requests(&self) -> Vec<FeedRequest> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .map(|request| FeedRequest {
+                path: request.url.path().to_string(),
+                if_none_match: request
+                    .headers
+                    .get("if-none-match")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,42 @@
+use common::http::fixture_name;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Contents of `fixtures/{name}` in this crate, e.g. `github/trending.html`.
+pub fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join(name);
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("cannot read fixture {}: {}", path.display(), e))
+}
+
+/// A temporary `FIXTURE_DIR` holding saved responses by URL. Removed on drop.
+pub struct FixtureDir {
+    dir: TempDir,
+}
+
+impl FixtureDir {
+    pub fn new() -> Self {
+        Self {
+            dir: TempDir::new().expect("create fixture directory"),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Saves `body` as the response for `url`.
+    pub fn add(&self, url: &str, body: &str) -> PathBuf {
+        let path = self.dir.path().join(fixture_name(url));
+        std::fs::write(&path, body).expect("write fixture");
+        path
+    }
+}
+
+impl Default for FixtureDir {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,142 @@
+use common::{GeminiClient, HttpClient};
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// The Gemini API on a local port; point a client at it with `client()`.
+pub struct MockGemini {
+    server: MockServer,
+}
+
+/// Answers `batchEmbedContents` with one constant vector per request entry.
+struct Embeddings {
+    dimensions: usize,
+}
+
+impl Respond for Embeddings {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let count = serde_json::from_slice::<Value>(&request.body)
+            .ok()
+            .and_then(|body| body["requests"].as_array().map(Vec::len))
+            .unwrap_or(0);
+        let embeddings: Vec<Value> = (0..count)
+            .map(|_| json!({ "values": vec![0.5; self.dimensions] }))
+            .collect();
+        ResponseTemplate::new(200).set_body_json(json!({ "embeddings": embeddings }))
+    }
+}
+
+impl MockGemini {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        self.server.uri()
+    }
+
+    pub fn client(&self) -> GeminiClient {
+        GeminiClient::new(HttpClient::new().unwrap(), "test-key").with_base_url(&self.base_url())
+    }
+
+    /// Answers every `generateContent` call with `text`.
+    pub async fn respond_with_text(&self, text: &str) {
+        let body = json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": text }] } }],
+            "usageMetadata": { "promptTokenCount": 12, "candidatesTokenCount": 3 }
+        });
+        Mock::given(method("POST"))
+            .and(path_regex(":generateContent$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answers every `batchEmbedContents` call with `dimensions`-long vectors.
+    pub async fn respond_with_embeddings(&self, dimensions: usize) {
+        Mock::given(method("POST"))
+            .and(path_regex(":batchEmbedContents$"))
+            .respond_with(Embeddings { dimensions })
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answers the next `times` `batchEmbedContents` calls with `status`;
+    /// later ones get what `respond_with_embeddings` set up.
+    pub async fn fail_embeddings(&self, status: u16, times: u64) {
+        Mock::given(method("POST"))
+            .and(path_regex(":batchEmbedContents$"))
+            .respond_with(ResponseTemplate::new(status).set_body_json(json!({ "error": "failed" })))
+            .up_to_n_times(times)
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// JSON bodies of the requests received so far.
+    pub async fn requests(&self) -> Vec<Value> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|request| serde_json::from_slice(&request.body).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::usage::{self, UsageTracker};
+    use common::CrawlerError;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn serves_text_and_embeddings() {
+        let gemini = MockGemini::start().await;
+        gemini.respond_with_text("こんにちは").await;
+        gemini.respond_with_embeddings(4).await;
+        let client = gemini.client();
+
+        assert_eq!(client.generate("hello").await.unwrap(), "こんにちは");
+        let vectors = client
+            .embed(&["a".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(vectors, vec![vec![0.5; 4], vec![0.5; 4]]);
+        assert_eq!(gemini.requests().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn usage_is_reported_until_the_budget_runs_out() {
+        let gemini = MockGemini::start().await;
+        gemini.respond_with_text("ok").await;
+        let usage = Arc::new(UsageTracker::new(
+            usage::parse_prices("gemini-2.0-flash=1000000/0").unwrap(),
+        ));
+        let client = gemini.client().with_usage(usage.clone());
+
+        usage::attribute("translate", client.generate("hello"))
+            .await
+            .unwrap();
+        let row = &usage.summary().rows[0];
+        assert_eq!(
+            (row.source.as_str(), row.input_tokens, row.output_tokens),
+            ("translate", 12, 3)
+        );
+
+        usage.set_budget(12.0, 0.0);
+        assert!(matches!(
+            client.generate("again").await,
+            Err(CrawlerError::BudgetExceeded(_))
+        ));
+        assert_eq!(
+            gemini.requests().await.len(),
+            1,
+            "no request once over budget"
+        );
+    }
+}
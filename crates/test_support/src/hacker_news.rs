@@ -0,0 +1,59 @@
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// The Hacker News Firebase API on a local port. Items that were not set up
+/// answer `null`, as the real API does for unknown ids.
+pub struct MockHackerNews {
+    server: MockServer,
+}
+
+impl MockHackerNews {
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("null"))
+            .with_priority(10)
+            .mount(&server)
+            .await;
+        Self { server }
+    }
+
+    /// Replaces `https://hacker-news.firebaseio.com/v0`.
+    pub fn base_url(&self) -> String {
+        self.server.uri()
+    }
+
+    pub async fn top_stories(&self, ids: &[u64]) {
+        Mock::given(method("GET"))
+            .and(path("/topstories.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ids))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Serves `item` as `/item/{id}.json`.
+    pub async fn item(&self, id: u64, item: Value) {
+        Mock::given(method("GET"))
+            .and(path(format!("/item/{}.json", id)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(item))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Serves a link story with `title` and `score`.
+    pub async fn story(&self, id: u64, title: &str, score: u32) {
+        self.item(
+            id,
+            json!({
+                "id": id,
+                "type": "story",
+                "by": "tester",
+                "title": title,
+                "score": score,
+                "url": format!("https://example.com/{}", id),
+            }),
+        )
+        .await;
+    }
+}
@@ -0,0 +1,26 @@
+//! Shared scaffolding for crawler tests: mock Supabase, Gemini, Hacker
+//! News, Product Hunt, Reddit and feed servers, sample items and outcomes,
+//! saved fixtures, golden-file snapshots, and a `Config` pointing at the mocks.
+//!
+//! Only ever a dev-dependency.
+
+pub mod config;
+pub mod feeds;
+pub mod fixtures;
+pub mod gemini;
+pub mod hacker_news;
+pub mod models;
+pub mod product_hunt;
+pub mod reddit;
+pub mod snapshot;
+pub mod supabase;
+
+pub use config::TestConfig;
+pub use feeds::{FeedRequest, MockFeeds};
+pub use fixtures::{fixture, FixtureDir};
+pub use gemini::MockGemini;
+pub use hacker_news::MockHackerNews;
+pub use models::{outcome, repo, story};
+pub use product_hunt::MockProductHunt;
+pub use reddit::MockReddit;
+pub use supabase::{MockSupabase, Upload, Upsert};
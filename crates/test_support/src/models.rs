@@ -0,0 +1,51 @@
+//! Items the crawlers publish, for tests that need a few of them.
+//!
+//! `github` and `hacker_news` cannot use these in their own unit tests: there
+//! the crate under test is a different build from the one linked here.
+
+use common::CrawlerOutcome;
+use github::Repository;
+use hacker_news::models::StoryData;
+use std::time::Duration;
+
+/// A trending repository with `stars` and nothing else set.
+pub fn repo(name: &str, stars: u32) -> Repository {
+    Repository {
+        name: name.to_string(),
+        description: None,
+        link: format!("https://github.com/{}", name),
+        stars,
+        stars_today: None,
+        language: None,
+        trending_in: vec![],
+        archived: false,
+        is_fork: false,
+        readme_excerpt: None,
+    }
+}
+
+/// A story with `score` and no link, text or summary.
+pub fn story(id: u64, title: &str, score: i64) -> StoryData {
+    StoryData {
+        story_id: id,
+        title: title.to_string(),
+        score,
+        url: None,
+        text: None,
+        summary: None,
+        first_seen: None,
+    }
+}
+
+/// A successful, instant run of the crawler `name` that recorded nothing.
+pub fn outcome(name: &str) -> CrawlerOutcome {
+    CrawlerOutcome {
+        name: name.to_string(),
+        duration: Duration::ZERO,
+        error: None,
+        uploaded: vec![],
+        unchanged: vec![],
+        checksums: vec![],
+        item_errors: vec![],
+    }
+}
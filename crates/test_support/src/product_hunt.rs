@@ -0,0 +1,85 @@
+use serde_json::{json, Value};
+use wiremock::matchers::{body_partial_json, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// The Product Hunt GraphQL endpoint on a local port, serving pages of the
+/// `posts` query by cursor.
+pub struct MockProductHunt {
+    server: MockServer,
+}
+
+impl MockProductHunt {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// Replaces `https://api.producthunt.com/v2/api/graphql`.
+    pub fn url(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Answers the query with `after` as its cursor with `posts` as
+    /// `(id, votes)` pairs, pointing at `next` when it is set.
+    pub async fn page(&self, after: Option<&str>, posts: &[(&str, u64)], next: Option<&str>) {
+        let edges: Vec<Value> = posts
+            .iter()
+            .map(|(id, votes)| {
+                json!({ "node": {
+                    "id": id,
+                    "name": format!("Launch {}", id),
+                    "tagline": format!("Tagline of launch {}", id),
+                    "votesCount": votes,
+                    "url": format!("https://www.producthunt.com/posts/launch-{}", id),
+                    "topics": { "edges": [{ "node": { "name": "Developer Tools" } }] },
+                }})
+            })
+            .collect();
+        let body = json!({ "data": { "posts": {
+            "edges": edges,
+            "pageInfo": { "hasNextPage": next.is_some(), "endCursor": next },
+        }}});
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                json!({ "variables": { "after": after } }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answers every request as the API does for a missing or revoked token.
+    pub async fn reject_token(&self) {
+        let body = json!({
+            "data": null,
+            "errors": [{ "error": "invalid_oauth_token", "error_description": "Please supply a valid access token." }]
+        });
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(body))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answers every request with a GraphQL error carrying `message`.
+    pub async fn graphql_error(&self, message: &str) {
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "errors": [{ "message": message }] })),
+            )
+            .mount(&self.server)
+            .await;
+    }
+
+    /// JSON bodies of the requests received so far.
+    pub async fn requests(&self) -> Vec<Value> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|request| serde_json::from_slice(&request.body).ok())
+            .collect()
+    }
+}
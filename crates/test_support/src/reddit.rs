@@ -0,0 +1,77 @@
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// `www.reddit.com` on a local port, serving subreddit listings.
+pub struct MockReddit {
+    server: MockServer,
+}
+
+/// What the mock saw of one request.
+#[derive(Debug, Clone)]
+pub struct RedditRequest {
+    pub path_and_query: String,
+    pub user_agent: String,
+}
+
+impl MockReddit {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// Replaces `https://www.reddit.com`.
+    pub fn base_url(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Serves `listing` as `/r/{subreddit}/top.json`.
+    pub async fn top(&self, subreddit: &str, listing: &str) {
+        Mock::given(method("GET"))
+            .and(path(format!("/r/{}/top.json", subreddit)))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/json")
+                    .set_body_string(listing),
+            )
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answers the next `times` requests for the subreddit with 429, ahead of
+    /// any listing mounted with `top`.
+    pub async fn rate_limit(&self, subreddit: &str, times: u64) {
+        Mock::given(method("GET"))
+            .and(path(format!("/r/{}/top.json", subreddit)))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "1")
+                    .set_body_string(r#"{"message": "Too Many Requests", "error": 429}"#),
+            )
+            .up_to_n_times(times)
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    pub async fn requests(&self) -> Vec<RedditRequest> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .map(|request| RedditRequest {
+                path_and_query: match request.url.query() {
+                    Some(query) => format!("{}?{}", request.url.path(), query),
+                    None => request.url.path().to_string(),
+                },
+                user_agent: request
+                    .headers
+                    .get("user-agent")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,65 @@
+//! Golden-file assertions for rendered output. Snapshots live in the calling
+//! crate's `tests/snapshots/`; run with `UPDATE_SNAPSHOTS=1` to (re)write them
+//! after an intended formatting change, and review the diff like any other.
+
+use std::path::Path;
+
+/// Compares `actual` with `tests/snapshots/{name}` in the calling crate.
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($name:expr, $actual:expr) => {
+        $crate::snapshot::assert_snapshot_in(
+            ::std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots"),
+            $name,
+            &$actual,
+        )
+    };
+}
+
+pub fn assert_snapshot_in(dir: impl AsRef<Path>, name: &str, actual: &str) {
+    let path = dir.as_ref().join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(dir.as_ref()).expect("create snapshot dir");
+        std::fs::write(&path, actual).expect("write snapshot");
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {}; run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    if expected != actual {
+        let line = expected
+            .lines()
+            .zip(actual.lines())
+            .position(|(e, a)| e != a)
+            .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+        panic!(
+            "{} differs from the snapshot, first at line {}:\n--- expected\n{}\n--- actual\n{}\n\
+             (run with UPDATE_SNAPSHOTS=1 if the change is intended)",
+            path.display(),
+            line + 1,
+            expected,
+            actual
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_output_passes_and_differences_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "one\ntwo\n").unwrap();
+        assert_snapshot_in(dir.path(), "a.md", "one\ntwo\n");
+
+        let changed =
+            std::panic::catch_unwind(|| assert_snapshot_in(dir.path(), "a.md", "one\n2\n"));
+        let message = *changed.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains("first at line 2"), "{}", message);
+        assert!(std::panic::catch_unwind(|| assert_snapshot_in(dir.path(), "b.md", "")).is_err());
+    }
+}
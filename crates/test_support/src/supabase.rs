@@ -0,0 +1,433 @@
+use common::SupabaseStorageClient;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::{body_partial_json, header, method, path, path_regex};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Bucket every `MockSupabase` serves.
+pub const BUCKET: &str = "test-bucket";
+/// Priority of the catch-all responses; anything mounted later wins.
+const FALLBACK: u8 = 10;
+/// Priority of `keep_uploads`: above the fallbacks, below `serve_file`.
+const STORED: u8 = 8;
+
+/// An object written through the storage API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upload {
+    /// Path within the bucket.
+    pub path: String,
+    pub content_type: String,
+    pub body: String,
+}
+
+/// Rows written through the PostgREST API in one request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upsert {
+    pub table: String,
+    /// The `on_conflict` column, when the request upserts.
+    pub on_conflict: Option<String>,
+    /// The `Prefer` header.
+    pub prefer: String,
+    pub rows: Value,
+}
+
+/// Objects uploaded since `keep_uploads`, by path within the bucket.
+type Store = Arc<Mutex<HashMap<String, String>>>;
+
+fn object_path(request: &Request) -> Option<String> {
+    let prefix = format!("/storage/v1/object/{}/", BUCKET);
+    request.url.path().strip_prefix(&prefix).map(str::to_string)
+}
+
+fn not_found() -> ResponseTemplate {
+    ResponseTemplate::new(404).set_body_json(json!({
+        "statusCode": "404",
+        "error": "not_found",
+        "message": "Object not found",
+    }))
+}
+
+/// Stores each uploaded body under its path.
+struct StoreUpload(Store);
+
+impl Respond for StoreUpload {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        if let Some(object) = object_path(request) {
+            let body = String::from_utf8_lossy(&request.body).into_owned();
+            self.0.lock().unwrap().insert(object, body);
+        }
+        ResponseTemplate::new(200).set_body_json(json!({ "Key": "ok" }))
+    }
+}
+
+/// Serves what `StoreUpload` stored, 404 for anything else.
+struct ServeStored(Store);
+
+impl Respond for ServeStored {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let stored = object_path(request).and_then(|o| self.0.lock().unwrap().get(&o).cloned());
+        match stored {
+            Some(body) => ResponseTemplate::new(200).set_body_string(body),
+            None => not_found(),
+        }
+    }
+}
+
+/// Supabase Storage on a local port. Uploads succeed and are recorded,
+/// unknown objects are 404s and listings are empty unless set up otherwise.
+pub struct MockSupabase {
+    server: MockServer,
+}
+
+impl MockSupabase {
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path_regex(format!("^/storage/v1/object/{}/", BUCKET)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "Key": "ok" })))
+            .with_priority(FALLBACK)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex("^/storage/v1/object/"))
+            .respond_with(not_found())
+            .with_priority(FALLBACK)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/storage/v1/object/list/{}", BUCKET)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .with_priority(FALLBACK)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path(format!("/storage/v1/object/{}", BUCKET)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .with_priority(FALLBACK)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex("^/rest/v1/"))
+            .respond_with(ResponseTemplate::new(201))
+            .with_priority(FALLBACK)
+            .mount(&server)
+            .await;
+        Self { server }
+    }
+
+    /// The project URL, as in `SUPABASE_URL`.
+    pub fn url(&self) -> String {
+        self.server.uri()
+    }
+
+    pub fn storage_url(&self) -> String {
+        format!("{}/storage/v1", self.server.uri())
+    }
+
+    pub fn client(&self) -> SupabaseStorageClient {
+        SupabaseStorageClient::new(&self.storage_url(), "test-key", BUCKET)
+    }
+
+    /// Serves `body` for downloads of `path`.
+    pub async fn serve_file(&self, object: &str, body: &str) {
+        Mock::given(method("GET"))
+            .and(path(format!("/storage/v1/object/{}/{}", BUCKET, object)))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Makes uploads downloadable afterwards, like a real bucket. Downloads
+    /// only see them when they carry the client's auth headers.
+    pub async fn keep_uploads(&self) {
+        let store = Store::default();
+        Mock::given(method("POST"))
+            .and(path_regex(format!("^/storage/v1/object/{}/", BUCKET)))
+            .respond_with(StoreUpload(store.clone()))
+            .with_priority(STORED)
+            .mount(&self.server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(format!("^/storage/v1/object/{}/", BUCKET)))
+            .and(header("apikey", "test-key"))
+            .and(header("authorization", "Bearer test-key"))
+            .respond_with(ServeStored(store))
+            .with_priority(STORED)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Lists `files` (with their sizes) under `prefix`.
+    pub async fn serve_list(&self, prefix: &str, files: &[(&str, u64)]) {
+        let objects: Vec<Value> = files
+            .iter()
+            .map(|(name, size)| json!({ "name": name, "metadata": { "size": size } }))
+            .collect();
+        Mock::given(method("POST"))
+            .and(path(format!("/storage/v1/object/list/{}", BUCKET)))
+            .and(body_partial_json(
+                json!({ "prefix": prefix.trim_matches('/') }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(objects))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Answers the next `times` uploads with `status`; later ones succeed.
+    pub async fn fail_uploads(&self, status: u16, times: u64) {
+        Mock::given(method("POST"))
+            .and(path_regex(format!("^/storage/v1/object/{}/", BUCKET)))
+            .respond_with(ResponseTemplate::new(status).set_body_string("upload failed"))
+            .up_to_n_times(times)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Every upload so far, in order.
+    pub async fn uploads(&self) -> Vec<Upload> {
+        let prefix = format!("/storage/v1/object/{}/", BUCKET);
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| request.method.as_str() == "POST")
+            .filter_map(|request| {
+                let object = request.url.path().strip_prefix(&prefix)?.to_string();
+                Some(Upload {
+                    path: object,
+                    content_type: request
+                        .headers
+                        .get("content-type")
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string(),
+                    body: String::from_utf8_lossy(&request.body).into_owned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Paths of every upload so far, in order.
+    pub async fn uploaded_paths(&self) -> Vec<String> {
+        self.uploads().await.into_iter().map(|u| u.path).collect()
+    }
+
+    /// Paths sent to the batch-delete API so far, in order.
+    pub async fn deleted_paths(&self) -> Vec<String> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| request.method.as_str() == "DELETE")
+            .filter_map(|request| serde_json::from_slice::<Value>(&request.body).ok())
+            .flat_map(|body| {
+                body["prefixes"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|p| p.as_str().map(str::to_string))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Every table write so far, in order.
+    pub async fn upserts(&self) -> Vec<Upsert> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| request.method.as_str() == "POST")
+            .filter_map(|request| {
+                let table = request.url.path().strip_prefix("/rest/v1/")?.to_string();
+                Some(Upsert {
+                    table,
+                    on_conflict: request
+                        .url
+                        .query_pairs()
+                        .find(|(name, _)| name == "on_conflict")
+                        .map(|(_, column)| column.into_owned()),
+                    prefer: request
+                        .headers
+                        .get("prefer")
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string(),
+                    rows: serde_json::from_slice(&request.body).unwrap_or(Value::Null),
+                })
+            })
+            .collect()
+    }
+
+    /// The body last uploaded to `path`, panicking with the paths that were
+    /// uploaded when there is none.
+    pub async fn uploaded(&self, object: &str) -> String {
+        let uploads = self.uploads().await;
+        match uploads.iter().rev().find(|u| u.path == object) {
+            Some(upload) => upload.body.clone(),
+            None => panic!(
+                "nothing uploaded to {}; uploads: {:?}",
+                object,
+                uploads.iter().map(|u| &u.path).collect::<Vec<_>>()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn records_uploads_and_serves_files() {
+        let supabase = MockSupabase::start().await;
+        let client = supabase.client();
+        supabase.serve_file("2024-05-10/a.md", "saved").await;
+        supabase.serve_list("2024-05-10", &[("a.md", 5)]).await;
+
+        assert_eq!(
+            client
+                .download_file("2024-05-10/a.md")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("saved")
+        );
+        assert_eq!(client.download_file("missing.md").await.unwrap(), None);
+        assert_eq!(
+            client.list_files("2024-05-10/").await.unwrap(),
+            vec!["a.md"]
+        );
+        assert!(client.list_files("2024-05-11").await.unwrap().is_empty());
+
+        client
+            .upload_file("2024-05-10/b.md", "new".to_string(), "text/markdown")
+            .await
+            .unwrap();
+        assert_eq!(
+            supabase.uploads().await,
+            vec![Upload {
+                path: "2024-05-10/b.md".to_string(),
+                content_type: "text/markdown".to_string(),
+                body: "new".to_string(),
+            }]
+        );
+        assert_eq!(supabase.uploaded("2024-05-10/b.md").await, "new");
+    }
+
+    #[tokio::test]
+    async fn downloads_what_was_uploaded() {
+        let supabase = MockSupabase::start().await;
+        supabase.keep_uploads().await;
+        let client = supabase.client();
+        assert_eq!(
+            client
+                .download_file("2024-05-10/hacker-news.md")
+                .await
+                .unwrap(),
+            None
+        );
+
+        client
+            .upload_file(
+                "2024-05-10/hacker-news.md",
+                "# Story".to_string(),
+                "text/markdown",
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            client
+                .download_file("2024-05-10/hacker-news.md")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("# Story")
+        );
+        let unauthorized = SupabaseStorageClient::new(&supabase.storage_url(), "other", BUCKET);
+        assert_eq!(
+            unauthorized
+                .download_file("2024-05-10/hacker-news.md")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn other_download_failures_are_errors() {
+        let supabase = MockSupabase::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/storage/v1/object/{}/broken.md", BUCKET)))
+            .respond_with(ResponseTemplate::new(500).set_body_string("database timeout"))
+            .mount(&supabase.server)
+            .await;
+        let err = supabase
+            .client()
+            .download_file("broken.md")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("500"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn lists_past_the_first_page() {
+        let supabase = MockSupabase::start().await;
+        let names: Vec<String> = (0..1002).map(|i| format!("{:04}.md", i)).collect();
+        for (offset, page) in [(0, &names[..1000]), (1000, &names[1000..])] {
+            let objects: Vec<Value> = page.iter().map(|name| json!({ "name": name })).collect();
+            Mock::given(method("POST"))
+                .and(path(format!("/storage/v1/object/list/{}", BUCKET)))
+                .and(body_partial_json(
+                    json!({ "prefix": "2024-05-10", "limit": 1000, "offset": offset }),
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(objects))
+                .expect(1)
+                .mount(&supabase.server)
+                .await;
+        }
+        assert_eq!(
+            supabase.client().list_files("2024-05-10/").await.unwrap(),
+            names
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_uploads_failing_with_server_errors() {
+        let supabase = MockSupabase::start().await;
+        supabase.fail_uploads(503, 2).await;
+        let client = supabase.client().with_retry(2, Duration::from_millis(1));
+        client
+            .upload_file("2024-05-10/a.md", "body".to_string(), "text/markdown")
+            .await
+            .unwrap();
+        assert_eq!(supabase.uploaded_paths().await.len(), 3);
+
+        supabase.fail_uploads(502, 2).await;
+        let err = supabase
+            .client()
+            .with_retry(1, Duration::from_millis(1))
+            .upload_file("2024-05-10/b.md", "body".to_string(), "text/markdown")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("upload failed"), "{}", err);
+        assert_eq!(supabase.uploaded_paths().await.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let supabase = MockSupabase::start().await;
+        supabase.fail_uploads(401, 1).await;
+        let client = supabase.client().with_retry(3, Duration::from_millis(1));
+        assert!(client
+            .upload_file("2024-05-10/a.md", "body".to_string(), "text/markdown")
+            .await
+            .is_err());
+        assert_eq!(supabase.uploaded_paths().await.len(), 1);
+    }
+}
@@ -1,14 +1,32 @@
 use anyhow::Result;
-use reqwest::Client;
+use async_trait::async_trait;
+use common::http::{parse_json, send_text};
+use common::usage::{self, UsageTracker};
+use common::{
+    ClientFactory, Config, Crawler, CrawlerContext, CrawlerError, CrawlerResult, HttpClient,
+    SupabaseStorageClient,
+};
 use serde::Deserialize;
-use std::env;
-use time::OffsetDateTime;
+use std::sync::Arc;
+use time::Date;
 use tracing::{info, warn};
-use common::SupabaseStorageClient;
+
+const COMPLETIONS_URL: &str = "https://api.x.ai/v1/chat/completions";
+const MODEL: &str = "grok-3-latest";
 
 #[derive(Deserialize)]
 struct ChatCompletionResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Deserialize)]
+struct TokenUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
 }
 
 #[derive(Deserialize)]
@@ -21,95 +39,163 @@ struct Message {
     content: String,
 }
 
+/// Asks Grok for a digest of the last day's news and uploads it to
+/// `{date}/xai-news.md`.
 pub struct XaiClient {
-    http_client: Client,
+    http_client: HttpClient,
     api_key: String,
     supabase_client: SupabaseStorageClient,
+    usage: Arc<UsageTracker>,
 }
 
 impl XaiClient {
-    pub fn new(api_key: &str, supabase_url: &str, supabase_key: &str, supabase_bucket: &str) -> Self {
-        let http_client = Client::new();
-        let supabase_client = SupabaseStorageClient::new(supabase_url, supabase_key, supabase_bucket);
-        Self {
-            http_client,
-            api_key: api_key.to_string(),
-            supabase_client,
-        }
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        Ok(Self {
+            http_client: ClientFactory::from_config(config).build()?,
+            api_key: config.require_xai_api_key()?.clone(),
+            supabase_client: SupabaseStorageClient::from_config(config)?,
+            usage: usage::global(),
+        })
     }
 
-    async fn fetch_news_digest(&self) -> Result<String> {
-        let url = "https://api.x.ai/v1/chat/completions";
+    /// Replaces the client built from the config.
+    pub fn with_http_client(mut self, http_client: HttpClient) -> Self {
+        self.http_client = http_client;
+        self
+    }
+
+    /// Reports token usage to `usage` instead of the process-wide tracker.
+    pub fn with_usage(mut self, usage: Arc<UsageTracker>) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    async fn fetch_news_digest(&self, ctx: &CrawlerContext) -> CrawlerResult<String> {
         let body = serde_json::json!({
             "messages": [{"role": "user", "content": "Provide me a digest of world news in the last 24 hours."}],
             "search_parameters": {"mode": "auto"},
-            "model": "grok-3-latest"
+            "model": MODEL
         });
 
-        let res = self
-            .http_client
-            .post(url)
+        let request = ctx
+            .http_client(&self.http_client)
+            .inner()
+            .post(COMPLETIONS_URL)
             .header("Content-Type", "application/json")
             .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&body)
-            .send()
-            .await?;
-
-        if !res.status().is_success() {
-            let status = res.status();
-            let text = res.text().await.unwrap_or_default();
-            anyhow::bail!("Request failed: {} - {}", status, text);
-        }
-
-        let resp: ChatCompletionResponse = res.json().await?;
+            .json(&body);
+        let text = send_text(request, COMPLETIONS_URL).await?;
+
+        let resp: ChatCompletionResponse = parse_json(COMPLETIONS_URL, &text)?;
+        let (input, output) = resp
+            .usage
+            .as_ref()
+            .map_or((0, 0), |u| (u.prompt_tokens, u.completion_tokens));
+        self.usage.record(MODEL, input, output);
         let content = resp
             .choices
-            .get(0)
+            .first()
             .map(|c| c.message.content.clone())
             .unwrap_or_default();
         Ok(content)
     }
+}
 
-    pub async fn run(&self) -> Result<()> {
+#[async_trait]
+impl Crawler for XaiClient {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
         info!("Fetching news digest from xAI");
-        let digest = self.fetch_news_digest().await?;
+        if let Err(CrawlerError::BudgetExceeded(reason)) = self.usage.check_budget() {
+            warn!("Skipping the xAI news digest: {}", reason);
+            return Ok(());
+        }
+        let digest = self.fetch_news_digest(ctx).await?;
 
         if digest.is_empty() {
             warn!("Received empty digest from xAI");
             return Ok(());
         }
 
-        let today = OffsetDateTime::now_utc().date().to_string();
-        let file_path = format!("{}/xai-news.md", today);
-        self
-            .supabase_client
+        let file_path = digest_path(ctx.date);
+        ctx.storage_client(&self.supabase_client)
             .upload_file(&file_path, digest, "text/markdown")
             .await?;
         info!("Uploaded xAI news digest to {}", file_path);
         Ok(())
     }
+
+    fn name(&self) -> &'static str {
+        "xAI Search"
+    }
+
+    fn slug(&self) -> &'static str {
+        "xai_search"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if config.xai_api_key.is_none() {
+            return Err("XAI_API_KEY not set".to_string());
+        }
+        Ok(())
+    }
 }
 
+common::register_crawler!(XaiClient {
+    name: "xAI search crawler",
+    slug: "xai_search",
+    required_env: ["XAI_API_KEY"],
+    new: XaiClient::new,
+});
+
+/// Where the digest for `date` is uploaded.
+fn digest_path(date: Date) -> String {
+    format!("{}/xai-news.md", date)
+}
 
+/// Runs the crawler on its own, outside the orchestrator.
 pub async fn run_xai_search() -> Result<()> {
     let _ = dotenv::dotenv();
-    let api_key = match env::var("XAI_API_KEY") {
-        Ok(v) => v,
-        Err(_) => {
-            warn!("XAI_API_KEY not set; skipping xAI search");
-            return Ok(());
-        }
-    };
-    let supabase_url = env::var("SUPABASE_URL").expect("SUPABASE_URL must be set");
-    let supabase_key = env::var("SUPABASE_SERVICE_ROLE_KEY").expect("SUPABASE_SERVICE_ROLE_KEY must be set");
-    let supabase_bucket = env::var("SUPABASE_BUCKET_NAME").expect("SUPABASE_BUCKET_NAME must be set");
-
-    let client = XaiClient::new(
-        &api_key,
-        &format!("{}/storage/v1", supabase_url.trim_end_matches('/')),
-        &supabase_key,
-        &supabase_bucket,
-    );
-
-    client.run().await
+    let config = Config::from_env()?;
+    if let Err(reason) = XaiClient::precheck(&config) {
+        warn!("{}; skipping xAI search", reason);
+        return Ok(());
+    }
+    usage::global().set_prices(config.llm_prices.clone());
+    let client = XaiClient::new(&config)?;
+
+    usage::attribute(
+        "xai_search",
+        client.run_with_context(&CrawlerContext::default()),
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::{MockSupabase, TestConfig};
+
+    #[test]
+    fn digest_is_filed_under_the_run_date() {
+        assert_eq!(
+            digest_path(time::macros::date!(2024 - 05 - 10)),
+            "2024-05-10/xai-news.md"
+        );
+    }
+
+    #[tokio::test]
+    async fn gated_on_the_api_key() {
+        let supabase = MockSupabase::start().await;
+        let config = TestConfig::new(&supabase).build();
+        assert_eq!(
+            XaiClient::precheck(&config),
+            Err("XAI_API_KEY not set".to_string())
+        );
+        let config = TestConfig::new(&supabase)
+            .with(|c| c.xai_api_key = Some("xai-key".to_string()))
+            .build();
+        assert!(XaiClient::precheck(&config).is_ok());
+        assert!(XaiClient::new(&config).is_ok());
+    }
 }
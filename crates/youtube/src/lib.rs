@@ -0,0 +1,165 @@
+use async_trait::async_trait;
+use common::{Config, Crawler, CrawlerContext, CrawlerResult, Summarizer};
+use feeds::opml::OpmlFeed;
+use feeds::FeedsCrawler;
+use std::sync::Arc;
+use time::Duration;
+use tracing::info;
+
+/// Videos published longer ago than this are left out.
+const MAX_AGE: Duration = Duration::hours(24);
+
+/// A channel's public uploads feed; no API key needed.
+pub fn feed_url(channel_id: &str) -> String {
+    format!(
+        "https://www.youtube.com/feeds/videos.xml?channel_id={}",
+        channel_id
+    )
+}
+
+/// Publishes the last day's uploads of the `YOUTUBE_CHANNELS` channels to
+/// `{date}/youtube.md`, one section per channel. Each channel's uploads feed
+/// is read like any other feed, under the channel's name from the feed.
+pub struct YoutubeCrawler {
+    feeds: FeedsCrawler,
+}
+
+impl YoutubeCrawler {
+    pub fn new(config: &Config) -> CrawlerResult<Self> {
+        let channels = config
+            .youtube_channels
+            .iter()
+            .map(|channel_id| OpmlFeed {
+                title: feed_url(channel_id),
+                xml_url: feed_url(channel_id),
+                html_url: None,
+            })
+            .collect();
+        let mut feeds =
+            FeedsCrawler::for_feeds(config, "youtube", "YouTube", channels)?.with_max_age(MAX_AGE);
+        if let Some(summarizer) = config.summarizer(config.youtube_summarize)? {
+            feeds = feeds.with_summarizer(summarizer);
+        }
+        Ok(Self { feeds })
+    }
+
+    pub fn with_summarizer(self, summarizer: Arc<dyn Summarizer>) -> Self {
+        Self {
+            feeds: self.feeds.with_summarizer(summarizer),
+        }
+    }
+}
+
+#[async_trait]
+impl Crawler for YoutubeCrawler {
+    async fn run_with_context(&self, ctx: &CrawlerContext) -> CrawlerResult<()> {
+        info!("YouTube crawler starting up");
+        self.feeds.publish(ctx).await
+    }
+
+    fn name(&self) -> &'static str {
+        "YouTube"
+    }
+
+    fn slug(&self) -> &'static str {
+        "youtube"
+    }
+
+    fn precheck(config: &Config) -> Result<(), String> {
+        if config.youtube_channels.is_empty() {
+            return Err("YOUTUBE_CHANNELS not set".to_string());
+        }
+        Ok(())
+    }
+}
+
+common::register_crawler!(YoutubeCrawler {
+    name: "YouTube crawler",
+    slug: "youtube",
+    required_env: ["YOUTUBE_CHANNELS"],
+    digest: ("youtube", "YouTube"),
+    new: YoutubeCrawler::new,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{FixedClock, OutputFormat, TruncatingSummarizer};
+    use feeds::models::FeedItem;
+    use test_support::{assert_snapshot, fixture, FixtureDir, MockSupabase, TestConfig};
+    use time::macros::datetime;
+
+    const RUSTCONF: &str = "UCrustconf000000000000aa";
+    const TEACHER: &str = "UCteacher00000000000000b";
+    const MISSING: &str = "UCmissing00000000000000c";
+
+    fn channel_fixtures() -> FixtureDir {
+        let fixtures = FixtureDir::new();
+        fixtures.add(&feed_url(RUSTCONF), &fixture("youtube/rustconf.xml"));
+        fixtures.add(&feed_url(TEACHER), &fixture("youtube/teacher.xml"));
+        fixtures
+    }
+
+    fn context() -> CrawlerContext {
+        CrawlerContext::with_clock(Arc::new(FixedClock(datetime!(2024-05-10 12:00 UTC))))
+    }
+
+    fn crawler(
+        supabase: &MockSupabase,
+        fixtures: &FixtureDir,
+        channels: &[&str],
+    ) -> YoutubeCrawler {
+        let config = TestConfig::new(supabase)
+            .fixture_dir(fixtures.path())
+            .output_formats(&[OutputFormat::Markdown, OutputFormat::Json])
+            .with(|c| c.youtube_channels = channels.iter().map(|c| c.to_string()).collect())
+            .build();
+        YoutubeCrawler::new(&config)
+            .unwrap()
+            .with_summarizer(Arc::new(TruncatingSummarizer { max_chars: 60 }))
+    }
+
+    #[tokio::test]
+    async fn publishes_the_last_day_of_uploads_per_channel() {
+        let fixtures = channel_fixtures();
+        let supabase = MockSupabase::start().await;
+        let ctx = context();
+        crawler(&supabase, &fixtures, &[RUSTCONF, MISSING, TEACHER])
+            .run_with_context(&ctx)
+            .await
+            .unwrap();
+
+        let failed: Vec<String> = ctx.item_errors.take().into_iter().map(|e| e.item).collect();
+        assert_eq!(failed, [feed_url(MISSING)]);
+        let json: Vec<FeedItem> =
+            serde_json::from_str(&supabase.uploaded("2024-05-10/youtube.json").await).unwrap();
+        let titles: Vec<_> = json
+            .iter()
+            .map(|v| (v.feed.as_str(), v.title.as_str()))
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                ("RustConf", "Keynote: The state of async Rust"),
+                ("RustConf", "Zero-copy parsing with nom"),
+                ("Teaching Tech", "Ownership explained in 10 minutes"),
+            ],
+            "uploads older than a day are left out"
+        );
+        assert_snapshot!(
+            "youtube.md",
+            supabase.uploaded("2024-05-10/youtube.md").await
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_when_no_channel_can_be_read() {
+        let fixtures = FixtureDir::new();
+        let supabase = MockSupabase::start().await;
+        let result = crawler(&supabase, &fixtures, &[MISSING])
+            .run_with_context(&context())
+            .await;
+        assert!(result.is_err());
+        assert!(supabase.uploaded_paths().await.is_empty());
+    }
+}